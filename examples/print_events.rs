@@ -0,0 +1,66 @@
+//! Prints what the parsers make of an NMEA log file or a receiver on a
+//! serial port, without the GUI:
+//!
+//! ```text
+//! cargo run --example print_events -- drive.nmea
+//! cargo run --example print_events -- /dev/ttyACM0 9600
+//! ```
+
+use std::fs::File;
+use std::io;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use rust_nmea_parser::pipeline::{self, GpsEvent};
+
+fn print(event: GpsEvent) {
+    match event {
+        GpsEvent::Fix(fix) if fix.has_position() => println!(
+            "fix      {:.6}, {:.6}  {}, {} satellites",
+            fix.latitude,
+            fix.longitude,
+            fix.quality_name(),
+            fix.satellites_used
+        ),
+        GpsEvent::Fix(fix) => println!("fix      none ({})", fix.quality_name()),
+        // The combined GN fix of the same epoch is printed instead
+        GpsEvent::SupersededFix(_) => {}
+        GpsEvent::Motion(rmc) => println!(
+            "motion   {} kn, course {}",
+            rmc.speed_knots.map_or("—".to_string(), |s| format!("{s:.1}")),
+            rmc.course.map_or("—".to_string(), |c| format!("{c:.1}°"))
+        ),
+        GpsEvent::FixMode(mode) => println!("mode     {}", mode.name()),
+        GpsEvent::Satellites(satellites) => {
+            let used = satellites.iter().filter(|s| s.used_in_fix).count();
+            println!("sky      {} in view, {used} used", satellites.len());
+        }
+        GpsEvent::BadChecksum(line) => println!("checksum {line}"),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.as_slice() {
+        [path] => File::open(path).and_then(|file| pipeline::run(file, print)),
+        [port, baud] => match baud.parse() {
+            Ok(baud) => serialport::new(port, baud)
+                .timeout(Duration::from_secs(1))
+                .open()
+                .map_err(io::Error::from)
+                .and_then(|port| pipeline::run(port, print)),
+            Err(_) => Err(io::Error::other(format!("not a baud rate: {baud}"))),
+        },
+        _ => {
+            eprintln!("usage: print_events <log file> | <serial port> <baud rate>");
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Quality alarms for unattended monitoring: each enabled threshold
+//! raises a timestamped alarm while it is violated and marks it cleared
+//! once the receiver recovers, leaving a history of the dips. Raising an
+//! alarm also asks for the user's attention, throttled per alarm kind and
+//! held back during quiet hours; the history records every alarm anyway.
+
+use std::collections::{HashMap, VecDeque};
+
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+
+use crate::conditions;
+use crate::parser::GgaFix;
+use crate::satellite::Satellite;
+
+/// Alarms kept per source; the oldest cleared ones are dropped past this.
+pub const MAX_ALARMS: usize = 500;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlarmThresholds {
+    pub fix_required: bool,
+    pub few_satellites: bool,
+    pub min_satellites: u8,
+    pub high_hdop: bool,
+    pub max_hdop: f64,
+    pub weak_signal: bool,
+    /// dB-Hz the strongest satellite must reach
+    pub min_best_snr: u8,
+    /// RTK fixed lost again once reached, to float or worse
+    pub rtk_fixed_lost: bool,
+    pub stale_rtk: bool,
+    /// Seconds the RTK correction age may reach
+    pub max_rtk_age: f64,
+}
+
+impl Default for AlarmThresholds {
+    fn default() -> Self {
+        Self {
+            fix_required: false,
+            few_satellites: false,
+            min_satellites: 4,
+            high_hdop: false,
+            max_hdop: 5.0,
+            weak_signal: false,
+            min_best_snr: 30,
+            rtk_fixed_lost: false,
+            stale_rtk: false,
+            max_rtk_age: 10.0,
+        }
+    }
+}
+
+/// When a raised alarm calls for attention.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyPolicy {
+    pub enabled: bool,
+    /// Seconds before an alarm of the same kind notifies again
+    pub min_interval_secs: u32,
+    pub quiet_hours: bool,
+    /// Local hours the quiet window starts and ends; it may span midnight
+    pub quiet_start: u8,
+    pub quiet_end: u8,
+}
+
+impl Default for NotifyPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_interval_secs: 60,
+            quiet_hours: false,
+            quiet_start: 22,
+            quiet_end: 7,
+        }
+    }
+}
+
+impl NotifyPolicy {
+    /// Whether the local `hour` falls in the quiet window.
+    pub fn is_quiet(&self, hour: u8) -> bool {
+        let (start, end) = (self.quiet_start, self.quiet_end);
+        self.quiet_hours
+            && if start <= end {
+                (start..end).contains(&hour)
+            } else {
+                hour >= start || hour < end
+            }
+    }
+}
+
+fn local_hour(time: f64) -> u8 {
+    chrono::DateTime::from_timestamp(time as i64, 0)
+        .map_or(0, |utc| utc.with_timezone(&chrono::Local).hour() as u8)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AlarmKind {
+    NoFix,
+    FewSatellites,
+    HighHdop,
+    WeakSignal,
+    RtkFixedLost,
+    StaleRtk,
+}
+
+impl AlarmKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlarmKind::NoFix => "No fix",
+            AlarmKind::FewSatellites => "Few satellites",
+            AlarmKind::HighHdop => "High HDOP",
+            AlarmKind::WeakSignal => "Weak signal",
+            AlarmKind::RtkFixedLost => "RTK fixed lost",
+            AlarmKind::StaleRtk => "Stale RTK corrections",
+        }
+    }
+}
+
+impl AlarmThresholds {
+    /// The enabled thresholds violated by `fix` and the satellites in
+    /// view, each with a description; `rtk_fixed_seen` tells whether the
+    /// source has reached RTK fixed before.
+    fn violated(
+        &self,
+        fix: &GgaFix,
+        satellites: &[Satellite],
+        rtk_fixed_seen: bool,
+    ) -> Vec<(AlarmKind, String)> {
+        let mut violated = Vec::new();
+        if self.fix_required && !fix.has_position() {
+            violated.push((AlarmKind::NoFix, fix.quality_name().to_string()));
+        }
+        let few = conditions::few_satellites(fix, self.min_satellites);
+        if let Some(detail) = few.filter(|_| self.few_satellites) {
+            violated.push((AlarmKind::FewSatellites, detail));
+        }
+        if let Some(detail) = conditions::high_hdop(fix, self.max_hdop).filter(|_| self.high_hdop) {
+            violated.push((AlarmKind::HighHdop, detail));
+        }
+        let best = satellites.iter().map(|s| s.strength).max().unwrap_or(0);
+        if self.weak_signal && best < self.min_best_snr {
+            let detail = format!("best SNR {best} dB-Hz < {}", self.min_best_snr);
+            violated.push((AlarmKind::WeakSignal, detail));
+        }
+        if self.rtk_fixed_lost && rtk_fixed_seen && fix.quality != 4 {
+            let detail = format!("RTK fixed lost, now {}", fix.quality_name());
+            violated.push((AlarmKind::RtkFixedLost, detail));
+        }
+        let age = fix.dgps_age.filter(|_| fix.is_rtk());
+        if let Some(age) = age.filter(|a| self.stale_rtk && *a > self.max_rtk_age) {
+            let detail = format!("RTK correction age {age:.1} s > {:.1}", self.max_rtk_age);
+            violated.push((AlarmKind::StaleRtk, detail));
+        }
+        violated
+    }
+}
+
+pub struct Alarm {
+    pub kind: AlarmKind,
+    /// Unix times the threshold was first violated and met again
+    pub raised: f64,
+    pub cleared: Option<f64>,
+    /// The violation when the alarm was raised
+    pub detail: String,
+}
+
+impl Alarm {
+    pub fn is_active(&self) -> bool {
+        self.cleared.is_none()
+    }
+}
+
+/// Checks one source's fixes against the alarm thresholds.
+#[derive(Default)]
+pub struct AlarmMonitor {
+    /// Copied from the settings each frame
+    pub thresholds: AlarmThresholds,
+    pub notify: NotifyPolicy,
+    pub alarms: VecDeque<Alarm>,
+    /// When each kind last notified
+    notified: HashMap<AlarmKind, f64>,
+    /// The latest alarm to notify, until the UI takes it
+    pub notification: Option<String>,
+    /// Raised alarms that were throttled or fell in the quiet hours
+    pub held_back: u64,
+    /// Whether the source has had an RTK fixed solution
+    rtk_fixed_seen: bool,
+}
+
+impl AlarmMonitor {
+    /// Raises an alarm for each threshold newly violated and clears those
+    /// no longer violated (or no longer enabled), returning a log line
+    /// for every change.
+    pub fn check(&mut self, time: f64, fix: &GgaFix, satellites: &[Satellite]) -> Vec<String> {
+        let violated = self.thresholds.violated(fix, satellites, self.rtk_fixed_seen);
+        self.rtk_fixed_seen |= fix.quality == 4;
+        let mut changes = Vec::new();
+        for alarm in self.alarms.iter_mut().filter(|a| a.is_active()) {
+            if !violated.iter().any(|(kind, _)| *kind == alarm.kind) {
+                alarm.cleared = Some(time);
+                changes.push(format!("[alarm] cleared: {}", alarm.kind.name()));
+            }
+        }
+        for (kind, detail) in violated {
+            if self.active().any(|a| a.kind == kind) {
+                continue;
+            }
+            changes.push(format!("[alarm] raised: {detail}"));
+            if self.should_notify(kind, time, local_hour(time)) {
+                self.notification = Some(detail.clone());
+            } else {
+                self.held_back += 1;
+            }
+            self.alarms.push_back(Alarm {
+                kind,
+                raised: time,
+                cleared: None,
+                detail,
+            });
+            if self.alarms.len() > MAX_ALARMS {
+                // Active alarms stay so that their recovery is still seen
+                if let Some(oldest) = self.alarms.iter().position(|a| !a.is_active()) {
+                    self.alarms.remove(oldest);
+                }
+            }
+        }
+        changes
+    }
+
+    /// Whether an alarm of `kind` raised at `time`, in local `hour`, may
+    /// notify, noting it if so.
+    fn should_notify(&mut self, kind: AlarmKind, time: f64, hour: u8) -> bool {
+        if !self.notify.enabled || self.notify.is_quiet(hour) {
+            return false;
+        }
+        let interval = f64::from(self.notify.min_interval_secs);
+        if self.notified.get(&kind).is_some_and(|last| time - last < interval) {
+            return false;
+        }
+        self.notified.insert(kind, time);
+        true
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &Alarm> {
+        self.alarms.iter().filter(|a| a.is_active())
+    }
+
+    /// Forgets the cleared alarms, keeping those still active.
+    pub fn clear_history(&mut self) {
+        self.alarms.retain(Alarm::is_active);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conditions::fix;
+
+    fn satellite(strength: u8) -> Satellite {
+        Satellite {
+            strength,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn raises_and_clears_with_the_violation() {
+        let mut monitor = AlarmMonitor {
+            thresholds: AlarmThresholds {
+                high_hdop: true,
+                weak_signal: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let strong = [satellite(42)];
+
+        assert!(monitor.check(1.0, &fix(1, 8, 0.9), &strong).is_empty());
+        let raised = monitor.check(2.0, &fix(1, 8, 6.5), &[satellite(25)]);
+        assert_eq!(
+            raised,
+            ["[alarm] raised: HDOP 6.5 > 5.0", "[alarm] raised: best SNR 25 dB-Hz < 30"]
+        );
+        // Still violated: no new alarms
+        assert!(monitor.check(3.0, &fix(1, 8, 7.0), &[satellite(20)]).is_empty());
+        assert_eq!(monitor.active().count(), 2);
+
+        assert_eq!(
+            monitor.check(4.0, &fix(1, 8, 1.0), &[satellite(20)]),
+            ["[alarm] cleared: High HDOP"]
+        );
+        assert_eq!(monitor.alarms[0].cleared, Some(4.0));
+        assert_eq!(monitor.alarms[0].raised, 2.0);
+
+        // Turning a threshold off clears its alarm
+        monitor.thresholds.weak_signal = false;
+        assert_eq!(
+            monitor.check(5.0, &fix(1, 8, 1.0), &[]),
+            ["[alarm] cleared: Weak signal"]
+        );
+        assert_eq!(monitor.active().count(), 0);
+        monitor.clear_history();
+        assert!(monitor.alarms.is_empty());
+    }
+
+    #[test]
+    fn rtk_fixed_lost_and_stale_corrections() {
+        let mut monitor = AlarmMonitor {
+            thresholds: AlarmThresholds {
+                rtk_fixed_lost: true,
+                stale_rtk: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let rtk = |quality: u8, age: f64| GgaFix {
+            dgps_age: Some(age),
+            ..fix(quality, 12, 0.7)
+        };
+        // Float before any fixed solution is only converging
+        assert!(monitor.check(1.0, &rtk(5, 1.0), &[]).is_empty());
+        assert!(monitor.check(2.0, &rtk(4, 1.0), &[]).is_empty());
+        assert_eq!(
+            monitor.check(3.0, &rtk(5, 12.5), &[]),
+            [
+                "[alarm] raised: RTK fixed lost, now RTK float",
+                "[alarm] raised: RTK correction age 12.5 s > 10.0"
+            ]
+        );
+        assert_eq!(
+            monitor.check(4.0, &rtk(4, 2.0), &[]),
+            ["[alarm] cleared: RTK fixed lost", "[alarm] cleared: Stale RTK corrections"]
+        );
+        // The age of plain DGPS corrections is not an RTK matter
+        assert!(monitor.check(5.0, &rtk(4, 2.0), &[]).is_empty());
+        monitor.thresholds.rtk_fixed_lost = false;
+        assert!(monitor.check(6.0, &rtk(2, 30.0), &[]).is_empty());
+    }
+
+    #[test]
+    fn notifications_are_throttled_and_muted_in_quiet_hours() {
+        let mut monitor = AlarmMonitor::default();
+        assert!(monitor.should_notify(AlarmKind::NoFix, 0.0, 12));
+        assert!(!monitor.should_notify(AlarmKind::NoFix, 30.0, 12));
+        assert!(monitor.should_notify(AlarmKind::HighHdop, 30.0, 12));
+        assert!(monitor.should_notify(AlarmKind::NoFix, 60.0, 12));
+
+        monitor.notify.quiet_hours = true;
+        assert!(monitor.notify.is_quiet(23) && monitor.notify.is_quiet(0));
+        assert!(!monitor.notify.is_quiet(7) && !monitor.notify.is_quiet(21));
+        assert!(!monitor.should_notify(AlarmKind::WeakSignal, 200.0, 3));
+        monitor.notify.quiet_start = 9;
+        monitor.notify.quiet_end = 17;
+        assert!(monitor.notify.is_quiet(9) && !monitor.notify.is_quiet(17));
+
+        // Every flapping alarm is still recorded
+        let mut monitor = AlarmMonitor {
+            thresholds: AlarmThresholds {
+                fix_required: true,
+                ..Default::default()
+            },
+            notify: NotifyPolicy {
+                min_interval_secs: 3600,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for n in 0..3 {
+            monitor.check(1000.0 + n as f64, &fix(0, 8, 1.0), &[]);
+            monitor.check(1000.5 + n as f64, &fix(1, 8, 1.0), &[]);
+        }
+        assert_eq!(monitor.alarms.len(), 3);
+        assert_eq!(monitor.held_back, 2);
+        assert_eq!(monitor.notification.as_deref(), Some("No fix"));
+    }
+
+    #[test]
+    fn keeps_a_bounded_history_without_losing_active_alarms() {
+        let mut monitor = AlarmMonitor {
+            thresholds: AlarmThresholds {
+                fix_required: true,
+                few_satellites: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // Held through the whole run
+        monitor.check(0.0, &fix(1, 3, 1.0), &[]);
+        for n in 1..MAX_ALARMS + 3 {
+            monitor.check(n as f64, &fix(0, 3, 1.0), &[]);
+            monitor.check(n as f64 + 0.5, &fix(1, 3, 1.0), &[]);
+        }
+        assert_eq!(monitor.alarms.len(), MAX_ALARMS);
+        assert_eq!(monitor.alarms[0].kind, AlarmKind::FewSatellites);
+        assert!(monitor.alarms[0].is_active());
+        assert_eq!(monitor.alarms[1].raised, 4.0);
+    }
+}
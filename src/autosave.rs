@@ -0,0 +1,76 @@
+//! The auto-save log: every NMEA sentence a source receives, and the
+//! user's notes, appended to one file per reading session as they arrive. Writes are buffered and
+//! synced to disk once per flush interval, so a power cut on an
+//! unattended run loses at most that interval of data. Both happen on the
+//! file's own thread.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::logfile::{self, LogFile};
+
+pub struct AutosaveLog {
+    pub path: PathBuf,
+    file: LogFile,
+    pub lines: u64,
+    /// When the file was last synced to disk
+    pub synced: Instant,
+}
+
+impl AutosaveLog {
+    /// Creates `nmea_<label>_<unix time>.log` in `dir`.
+    pub fn create(dir: &Path, label: &str, now: f64) -> Result<Self, String> {
+        let label = logfile::file_name_part(label);
+        let path = dir.join(format!("nmea_{label}_{}.log", now as u64));
+        let file = LogFile::create(path.clone())?;
+        Ok(Self {
+            path,
+            file,
+            lines: 0,
+            synced: Instant::now(),
+        })
+    }
+
+    /// Buffers one sentence or note; it reaches the disk at the next sync.
+    pub fn append(&mut self, line: &str) -> Result<(), String> {
+        self.file.append(format!("{line}\n"))?;
+        self.lines += 1;
+        Ok(())
+    }
+
+    /// Writes out the buffer and syncs the file once `interval` has
+    /// passed since the last sync, returning whether it did.
+    pub fn sync_if_due(&mut self, interval: Duration) -> Result<bool, String> {
+        if self.synced.elapsed() < interval {
+            return Ok(false);
+        }
+        self.sync()?;
+        Ok(true)
+    }
+
+    pub fn sync(&mut self) -> Result<(), String> {
+        self.synced = Instant::now();
+        self.file.sync()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_reach_the_file_when_synced() {
+        let dir = std::env::temp_dir();
+        let mut log = AutosaveLog::create(&dir, "autosave/test", 1_700_000_000.0).unwrap();
+        assert!(log.path.ends_with("nmea_autosave_test_1700000000.log"));
+        log.append("$GPGGA,1").unwrap();
+        log.append("$GPRMC,1").unwrap();
+        assert!(!log.sync_if_due(Duration::from_secs(60)).unwrap());
+        assert!(log.sync_if_due(Duration::ZERO).unwrap());
+        log.file.wait();
+        let text = std::fs::read_to_string(&log.path).unwrap();
+        assert_eq!(text, "$GPGGA,1\n$GPRMC,1\n");
+        assert_eq!(log.lines, 2);
+        std::fs::remove_file(&log.path).unwrap();
+    }
+}
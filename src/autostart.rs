@@ -0,0 +1,215 @@
+//! Hands-free starts for kiosk setups: when a serial port matching the
+//! user's VID:PID or name pattern appears in the periodic port scan, it
+//! is selected and read. A device that keeps dropping out is only
+//! restarted a few times a minute.
+
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Seconds between port scans while auto-start is on.
+pub const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+/// Auto-starts allowed within `FLAP_WINDOW` before holding off.
+pub const MAX_STARTS: usize = 3;
+pub const FLAP_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoStart {
+    pub enabled: bool,
+    /// Comma separated `VID:PID` hex pairs (`*` for any PID) or port name
+    /// patterns with `*` and `?`, e.g. `1546:01A7, ttyACM*`. Names match
+    /// in full or without their directory.
+    pub pattern: String,
+}
+
+/// A listed serial port and, for USB devices, its vendor and product.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PortEntry {
+    pub name: String,
+    pub usb_id: Option<(u16, u16)>,
+}
+
+impl PortEntry {
+    pub fn describe(&self) -> String {
+        match self.usb_id {
+            Some((vid, pid)) => format!("{} ({vid:04X}:{pid:04X})", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl AutoStart {
+    pub fn matches(&self, port: &PortEntry) -> bool {
+        self.pattern
+            .split(',')
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .any(|term| match usb_term(term) {
+                Some((vid, pid)) => port
+                    .usb_id
+                    .is_some_and(|(v, p)| v == vid && pid.is_none_or(|pid| p == pid)),
+                None => {
+                    let name = port.name.to_lowercase();
+                    let file = name.rsplit('/').next().unwrap_or(&name);
+                    let term = term.to_lowercase();
+                    glob(&term, &name) || glob(&term, file)
+                }
+            })
+    }
+}
+
+/// `1546:01A7` or `1546:*` as a vendor and optional product id.
+fn usb_term(term: &str) -> Option<(u16, Option<u16>)> {
+    let (vid, pid) = term.split_once(':')?;
+    let hex = |text: &str| {
+        let valid = text.len() == 4 && text.chars().all(|c| c.is_ascii_hexdigit());
+        valid.then(|| u16::from_str_radix(text, 16).ok()).flatten()
+    };
+    let pid = if pid == "*" { None } else { Some(hex(pid)?) };
+    Some((hex(vid)?, pid))
+}
+
+/// Whether `text` matches `pattern`, where `*` is any run of characters
+/// and `?` any one.
+fn glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // Position after the last `*`, and where in the text it resumes
+    let (mut p, mut t, mut star) = (0, 0, None);
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, t));
+            p += 1;
+        } else if let Some((after_star, resume)) = star {
+            p = after_star;
+            t = resume + 1;
+            star = Some((after_star, resume + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// What a scan asks of the source.
+#[derive(PartialEq, Debug)]
+pub enum AutoStartAction {
+    Start(String),
+    /// The device came back too often lately; it starts once the window
+    /// has passed if it is still there
+    HoldOff(String),
+}
+
+/// Remembers which ports were present and when auto-starts happened.
+#[derive(Default)]
+pub struct AutoStarter {
+    seen: HashSet<String>,
+    starts: VecDeque<Instant>,
+    // A matching port held off by the flap guard, started once allowed
+    pending: Option<String>,
+}
+
+impl AutoStarter {
+    /// Compares a scan with the last, returning a matching port to start
+    /// if one has appeared (or was held off and may now start). `ready`
+    /// is whether a source is free to take it.
+    pub fn scan(
+        &mut self,
+        config: &AutoStart,
+        ports: &[PortEntry],
+        ready: bool,
+        now: Instant,
+    ) -> Option<AutoStartAction> {
+        let appeared = ports
+            .iter()
+            .find(|p| !self.seen.contains(&p.name) && config.matches(p))
+            .map(|p| p.name.clone());
+        self.seen = ports.iter().map(|p| p.name.clone()).collect();
+        if self.pending.as_ref().is_some_and(|name| !self.seen.contains(name)) {
+            self.pending = None;
+        }
+        if !ready {
+            return None;
+        }
+        let port = appeared.or_else(|| self.pending.clone())?;
+
+        while self.starts.front().is_some_and(|t| now.duration_since(*t) > FLAP_WINDOW) {
+            self.starts.pop_front();
+        }
+        if self.starts.len() >= MAX_STARTS {
+            let first_hold = self.pending.replace(port.clone()).is_none();
+            return first_hold.then_some(AutoStartAction::HoldOff(port));
+        }
+        self.pending = None;
+        self.starts.push_back(now);
+        Some(AutoStartAction::Start(port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(name: &str, usb_id: Option<(u16, u16)>) -> PortEntry {
+        PortEntry {
+            name: name.to_string(),
+            usb_id,
+        }
+    }
+
+    #[test]
+    fn matches_usb_ids_and_name_patterns() {
+        let config = AutoStart {
+            enabled: true,
+            pattern: "1546:01a7, 067B:*, /dev/ttyACM?, cu.usb*".to_string(),
+        };
+        assert!(config.matches(&port("COM7", Some((0x1546, 0x01A7)))));
+        assert!(config.matches(&port("COM8", Some((0x067B, 0x2303)))));
+        assert!(!config.matches(&port("COM9", Some((0x1546, 0x01A8)))));
+        assert!(config.matches(&port("/dev/ttyACM0", None)));
+        assert!(!config.matches(&port("/dev/ttyACM10", None)));
+        assert!(config.matches(&port("/dev/cu.usbmodem1101", None)));
+        assert!(!config.matches(&port("/dev/ttyS0", None)));
+        assert!(!AutoStart::default().matches(&port("COM1", None)));
+        assert!(glob("a*b*c", "axxbyyc") && !glob("a*b*c", "axxbyy"));
+    }
+
+    #[test]
+    fn starts_on_appearance_and_holds_off_a_flapping_device() {
+        let config = AutoStart {
+            enabled: true,
+            pattern: "ttyACM*".to_string(),
+        };
+        let present = [port("ttyACM0", None), port("ttyS0", None)];
+        let absent = [port("ttyS0", None)];
+        let mut starter = AutoStarter::default();
+        let t0 = Instant::now();
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+
+        let start = Some(AutoStartAction::Start("ttyACM0".to_string()));
+        assert_eq!(starter.scan(&config, &present, true, at(0)), start);
+        // Still there: nothing more to do
+        assert_eq!(starter.scan(&config, &present, true, at(2)), None);
+
+        for n in 1..MAX_STARTS as u64 {
+            starter.scan(&config, &absent, true, at(10 * n));
+            assert_eq!(starter.scan(&config, &present, true, at(10 * n + 2)), start);
+        }
+        starter.scan(&config, &absent, true, at(40));
+        let held = Some(AutoStartAction::HoldOff("ttyACM0".to_string()));
+        assert_eq!(starter.scan(&config, &present, true, at(42)), held);
+        assert_eq!(starter.scan(&config, &present, true, at(44)), None);
+        // Once the first start is out of the window
+        assert_eq!(starter.scan(&config, &present, true, at(61)), start);
+
+        // A busy source lets the appearance pass
+        starter.scan(&config, &absent, true, at(200));
+        assert_eq!(starter.scan(&config, &present, false, at(202)), None);
+        assert_eq!(starter.scan(&config, &present, true, at(204)), None);
+    }
+}
@@ -0,0 +1,112 @@
+//! Caps on the buffers that grow with a session: the log, the track, the
+//! fix history behind the scrubber, the per-satellite SNR histories and
+//! sky trails, and the satellites-in-view history. Low-memory mode swaps
+//! in tighter caps so a long unattended run on a small machine stays
+//! within its RAM.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BufferCaps {
+    pub log_lines: usize,
+    /// Breadcrumb trail, in GGA fixes
+    pub track_fixes: usize,
+    /// Full fixes kept to scrub back through; none turns the scrubber off
+    pub snapshots: usize,
+    /// SNR samples per satellite for the detail sparkline
+    pub snr_samples: usize,
+    /// Satellites-in-view history, minutes
+    pub view_history_mins: u32,
+    /// Sky trail points per satellite; none turns the trails off
+    pub trail_points: usize,
+}
+
+impl BufferCaps {
+    pub const LOW_MEMORY: BufferCaps = BufferCaps {
+        log_lines: 200,
+        track_fixes: 1_000,
+        snapshots: 0,
+        snr_samples: 30,
+        view_history_mins: 5,
+        trail_points: 0,
+    };
+
+    /// The caps of a normal session, keeping `log_lines` of log.
+    pub fn full(log_lines: usize) -> Self {
+        Self {
+            log_lines,
+            track_fixes: crate::TRACK_LEN,
+            snapshots: crate::SNAPSHOT_HISTORY_LEN,
+            snr_samples: crate::SNR_HISTORY_LEN,
+            view_history_mins: (crate::VIEW_HISTORY_SECS / 60.0) as u32,
+            trail_points: crate::SKY_TRAIL_LEN,
+        }
+    }
+
+    pub fn view_history_secs(&self) -> f64 {
+        f64::from(self.view_history_mins) * 60.0
+    }
+}
+
+impl Default for BufferCaps {
+    fn default() -> Self {
+        Self::full(crate::LOG_LEN)
+    }
+}
+
+/// Estimated bytes held by each buffer, counting their elements and the
+/// strings they own but not allocator overhead.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct BufferUsage {
+    pub log: usize,
+    pub track: usize,
+    pub snapshots: usize,
+    pub snr: usize,
+    pub view_history: usize,
+    pub trails: usize,
+}
+
+impl BufferUsage {
+    pub fn total(&self) -> usize {
+        self.log + self.track + self.snapshots + self.snr + self.view_history + self.trails
+    }
+
+    /// e.g. "1.3 MB (log 0.6, track 0.7, fix history 0.0, ...)".
+    pub fn describe(&self) -> String {
+        let mb = |bytes: usize| bytes as f64 / 1_000_000.0;
+        format!(
+            "{:.1} MB (log {:.1}, track {:.1}, fix history {:.1}, SNR {:.1}, in view {:.1}, \
+             trails {:.1})",
+            mb(self.total()),
+            mb(self.log),
+            mb(self.track),
+            mb(self.snapshots),
+            mb(self.snr),
+            mb(self.view_history),
+            mb(self.trails)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usage_adds_up() {
+        let usage = BufferUsage {
+            log: 600_000,
+            track: 650_000,
+            snr: 20_000,
+            ..Default::default()
+        };
+        assert_eq!(usage.total(), 1_270_000);
+        assert_eq!(
+            usage.describe(),
+            "1.3 MB (log 0.6, track 0.7, fix history 0.0, SNR 0.0, in view 0.0, trails 0.0)"
+        );
+        assert_eq!(BufferCaps::full(500).view_history_secs(), crate::VIEW_HISTORY_SECS);
+        assert_eq!(BufferCaps::default().log_lines, crate::LOG_LEN);
+    }
+}
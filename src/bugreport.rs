@@ -0,0 +1,90 @@
+//! The diagnostics bundle for bug reports: version, settings, serial
+//! ports, each source's reader state, sentence inventory and log tail in
+//! one block of text. Nothing is redacted; the user reviews, and may
+//! edit, the text before copying or saving it.
+
+use crate::autostart::PortEntry;
+
+/// Log lines included per source.
+pub const LOG_TAIL: usize = 50;
+
+/// What one source contributes to the bundle.
+pub struct SourceReport {
+    pub label: String,
+    /// The Reader health rows
+    pub health: Vec<(&'static str, String)>,
+    /// Sentence types and how often they came, as a finished inventory
+    /// scan or the counters since the start
+    pub inventory: String,
+    /// The newest log lines, oldest first
+    pub log_tail: Vec<String>,
+}
+
+/// The bundle as text, in sections headed `== Name ==`.
+pub fn bundle(
+    settings_json: &str,
+    ports: &Result<Vec<PortEntry>, String>,
+    sources: &[SourceReport],
+) -> String {
+    let mut text = format!(
+        "== Version ==\n{} {} ({} {})\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    text += "\n== Serial ports ==\n";
+    match ports {
+        Ok(ports) if ports.is_empty() => text += "none found\n",
+        Ok(ports) => {
+            for port in ports {
+                text += &format!("{}\n", port.describe());
+            }
+        }
+        Err(e) => text += &format!("{e}\n"),
+    }
+    for source in sources {
+        text += &format!("\n== Source {} ==\n", source.label);
+        for (name, value) in &source.health {
+            text += &format!("{name}: {value}\n");
+        }
+        text += &format!("\n-- Sentences --\n{}", source.inventory);
+        text += &format!("\n-- Last {} log lines --\n", source.log_tail.len());
+        for line in &source.log_tail {
+            text += &format!("{line}\n");
+        }
+    }
+    text += &format!("\n== Settings ==\n{settings_json}\n");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_in_order() {
+        let ports = Ok(vec![PortEntry {
+            name: "COM7".to_string(),
+            usb_id: Some((0x1546, 0x01A7)),
+        }]);
+        let source = SourceReport {
+            label: "A".to_string(),
+            health: vec![("State", "Reading".to_string())],
+            inventory: "GPGGA 10\n".to_string(),
+            log_tail: vec!["$GPGGA,1".to_string(), "$GPGGA,2".to_string()],
+        };
+        let text = bundle("{}", &ports, &[source]);
+        let headings: Vec<&str> = text.lines().filter(|l| l.starts_with("==")).collect();
+        assert_eq!(
+            headings,
+            ["== Version ==", "== Serial ports ==", "== Source A ==", "== Settings =="]
+        );
+        assert!(text.contains("COM7 (1546:01A7)\n"));
+        assert!(text.contains("State: Reading\n"));
+        assert!(text.contains("-- Last 2 log lines --\n$GPGGA,1\n$GPGGA,2\n"));
+
+        let text = bundle("{}", &Err("Could not enumerate ports".to_string()), &[]);
+        assert!(text.contains("== Serial ports ==\nCould not enumerate ports\n"));
+    }
+}
@@ -0,0 +1,441 @@
+//! Receiver configuration commands: NMEA output rate presets and restarts
+//! for common receiver families, encoded as PMTK sentences or UBX frames.
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::checksum;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiverFamily {
+    #[default]
+    Generic,
+    Ublox,
+    MediaTek,
+}
+
+impl ReceiverFamily {
+    pub const ALL: [ReceiverFamily; 3] = [
+        ReceiverFamily::Generic,
+        ReceiverFamily::Ublox,
+        ReceiverFamily::MediaTek,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReceiverFamily::Generic => "Generic",
+            ReceiverFamily::Ublox => "u-blox",
+            ReceiverFamily::MediaTek => "MediaTek",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RatePreset {
+    FastPositionOnly,
+    FullOneHz,
+    GsvOff,
+}
+
+impl RatePreset {
+    pub const ALL: [RatePreset; 3] = [
+        RatePreset::FastPositionOnly,
+        RatePreset::FullOneHz,
+        RatePreset::GsvOff,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RatePreset::FastPositionOnly => "10 Hz GGA+RMC only",
+            RatePreset::FullOneHz => "1 Hz full",
+            RatePreset::GsvOff => "GSV off",
+        }
+    }
+
+    /// The fix rate the preset sets, if it changes it.
+    pub fn fix_rate_hz(&self) -> Option<f64> {
+        match self {
+            RatePreset::FastPositionOnly => Some(10.0),
+            RatePreset::FullOneHz => Some(1.0),
+            RatePreset::GsvOff => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ResetKind {
+    Hot,
+    Warm,
+    Cold,
+    Factory,
+}
+
+impl ResetKind {
+    pub const ALL: [ResetKind; 4] = [
+        ResetKind::Hot,
+        ResetKind::Warm,
+        ResetKind::Cold,
+        ResetKind::Factory,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResetKind::Hot => "Hot start",
+            ResetKind::Warm => "Warm start",
+            ResetKind::Cold => "Cold start",
+            ResetKind::Factory => "Factory reset",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ResetKind::Hot => "Restart keeping ephemeris, almanac, time and position",
+            ResetKind::Warm => "Restart without ephemeris, keeping the almanac",
+            ResetKind::Cold => "Restart from nothing, for timing acquisition (TTFF)",
+            ResetKind::Factory => "Wipe the saved configuration and cold start",
+        }
+    }
+}
+
+/// UBX-CFG-RST navBbrMask values: what battery-backed data is cleared.
+const UBX_BBR_HOT: u16 = 0x0000;
+const UBX_BBR_WARM: u16 = 0x0001;
+const UBX_BBR_COLD: u16 = 0xFFFF;
+/// UBX-CFG-RST resetMode: controlled software reset of GNSS only, which
+/// leaves the serial port up
+const UBX_RESET_GNSS: u8 = 0x02;
+/// ...and a controlled software reset of the whole receiver
+const UBX_RESET_SOFTWARE: u8 = 0x01;
+
+/// The byte sequences restarting the receiver, or nothing for receivers
+/// without a known command set.
+pub fn reset_commands(family: ReceiverFamily, kind: ResetKind) -> Vec<Vec<u8>> {
+    match family {
+        ReceiverFamily::Generic => Vec::new(),
+        ReceiverFamily::MediaTek => {
+            // PMTK104 is a full cold start that also clears system/user config
+            let body = match kind {
+                ResetKind::Hot => "PMTK101",
+                ResetKind::Warm => "PMTK102",
+                ResetKind::Cold => "PMTK103",
+                ResetKind::Factory => "PMTK104",
+            };
+            vec![nmea_sentence(body)]
+        }
+        ReceiverFamily::Ublox => {
+            let rst = |mask: u16, mode: u8| {
+                let [lo, hi] = mask.to_le_bytes();
+                ubx_frame(0x06, 0x04, &[lo, hi, mode, 0x00])
+            };
+            match kind {
+                ResetKind::Hot => vec![rst(UBX_BBR_HOT, UBX_RESET_GNSS)],
+                ResetKind::Warm => vec![rst(UBX_BBR_WARM, UBX_RESET_GNSS)],
+                ResetKind::Cold => vec![rst(UBX_BBR_COLD, UBX_RESET_GNSS)],
+                ResetKind::Factory => {
+                    // UBX-CFG-CFG: clear all sections, save none, load the
+                    // defaults, on BBR, flash, EEPROM and SPI flash
+                    let mut cfg = Vec::new();
+                    cfg.extend(0xFFFFu32.to_le_bytes());
+                    cfg.extend(0u32.to_le_bytes());
+                    cfg.extend(0xFFFFu32.to_le_bytes());
+                    cfg.push(0x17);
+                    vec![
+                        ubx_frame(0x06, 0x09, &cfg),
+                        rst(UBX_BBR_COLD, UBX_RESET_SOFTWARE),
+                    ]
+                }
+            }
+        }
+    }
+}
+
+/// Polls asking the receiver what it is: UBX-MON-VER and `$PMTK605`.
+/// Generic receivers get both, as each is ignored by the other family.
+pub fn identify_commands(family: ReceiverFamily) -> Vec<Vec<u8>> {
+    let ublox = || ubx_frame(0x0A, crate::ubx::MON_VER, &[]);
+    let mediatek = || nmea_sentence("PMTK605");
+    match family {
+        ReceiverFamily::Generic => vec![ublox(), mediatek()],
+        ReceiverFamily::Ublox => vec![ublox()],
+        ReceiverFamily::MediaTek => vec![mediatek()],
+    }
+}
+
+/// u-blox navigation dynamic models, the UBX-CFG-NAV5 `dynModel`: what
+/// motion the position filter expects.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DynamicModel {
+    #[default]
+    Portable,
+    Stationary,
+    Pedestrian,
+    Automotive,
+    Sea,
+    Airborne1g,
+    Airborne2g,
+    Airborne4g,
+}
+
+impl DynamicModel {
+    pub const ALL: [DynamicModel; 8] = [
+        DynamicModel::Portable,
+        DynamicModel::Stationary,
+        DynamicModel::Pedestrian,
+        DynamicModel::Automotive,
+        DynamicModel::Sea,
+        DynamicModel::Airborne1g,
+        DynamicModel::Airborne2g,
+        DynamicModel::Airborne4g,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DynamicModel::Portable => "Portable",
+            DynamicModel::Stationary => "Stationary",
+            DynamicModel::Pedestrian => "Pedestrian",
+            DynamicModel::Automotive => "Automotive",
+            DynamicModel::Sea => "Sea",
+            DynamicModel::Airborne1g => "Airborne <1g",
+            DynamicModel::Airborne2g => "Airborne <2g",
+            DynamicModel::Airborne4g => "Airborne <4g",
+        }
+    }
+
+    pub fn code(&self) -> u8 {
+        match self {
+            DynamicModel::Portable => 0,
+            DynamicModel::Stationary => 2,
+            DynamicModel::Pedestrian => 3,
+            DynamicModel::Automotive => 4,
+            DynamicModel::Sea => 5,
+            DynamicModel::Airborne1g => 6,
+            DynamicModel::Airborne2g => 7,
+            DynamicModel::Airborne4g => 8,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.code() == code)
+    }
+}
+
+/// UBX-CFG-NAV5 payload length.
+pub const UBX_NAV5_LEN: usize = 36;
+/// UBX-CFG-NAV5 mask bit applying only the dynamic model.
+const UBX_NAV5_MASK_DYN: u16 = 0x0001;
+
+/// UBX-CFG-NAV5 setting the dynamic model; the mask leaves every other
+/// navigation setting as it is.
+pub fn dynamic_model_command(model: DynamicModel) -> Vec<u8> {
+    let mut payload = vec![0; UBX_NAV5_LEN];
+    payload[..2].copy_from_slice(&UBX_NAV5_MASK_DYN.to_le_bytes());
+    payload[2] = model.code();
+    ubx_frame(0x06, crate::ubx::CFG_NAV5, &payload)
+}
+
+/// Polls UBX-CFG-NAV5; the reply carries the current dynamic model.
+pub fn dynamic_model_poll() -> Vec<u8> {
+    ubx_frame(0x06, crate::ubx::CFG_NAV5, &[])
+}
+
+/// u-blox NMEA message IDs (class 0xF0) switched by the presets.
+const UBX_NMEA_GGA: u8 = 0x00;
+const UBX_NMEA_GLL: u8 = 0x01;
+const UBX_NMEA_GSA: u8 = 0x02;
+const UBX_NMEA_GSV: u8 = 0x03;
+const UBX_NMEA_RMC: u8 = 0x04;
+const UBX_NMEA_VTG: u8 = 0x05;
+
+/// UBX-CFG-PRT port IDs: the first UART and USB.
+const UBX_PORT_UART1: u8 = 1;
+const UBX_PORT_USB: u8 = 3;
+/// UBX-CFG-PRT UART mode: 8 data bits, no parity, 1 stop bit.
+const UBX_MODE_8N1: u32 = 0x0000_08D0;
+/// UBX-CFG-PRT protocol mask: UBX and NMEA.
+const UBX_PROTO_UBX_NMEA: u16 = 0x0003;
+
+/// Turns NMEA back on for a u-blox receiver sending only UBX: UBX-CFG-PRT
+/// letting NMEA in and out on UART1, kept at `baud_rate`, and on USB,
+/// then UBX-CFG-MSG sending GGA, RMC, GSA and GSV every fix.
+pub fn enable_nmea_commands(baud_rate: u32) -> Vec<Vec<u8>> {
+    let prt = |port: u8, mode: u32, baud: u32| {
+        let mut payload = vec![port, 0x00, 0x00, 0x00];
+        payload.extend(mode.to_le_bytes());
+        payload.extend(baud.to_le_bytes());
+        payload.extend(UBX_PROTO_UBX_NMEA.to_le_bytes());
+        payload.extend(UBX_PROTO_UBX_NMEA.to_le_bytes());
+        payload.extend([0x00; 4]);
+        ubx_frame(0x06, 0x00, &payload)
+    };
+    let mut frames = vec![
+        prt(UBX_PORT_UART1, UBX_MODE_8N1, baud_rate),
+        // USB has no mode or baud rate
+        prt(UBX_PORT_USB, 0, 0),
+    ];
+    for id in [UBX_NMEA_GGA, UBX_NMEA_RMC, UBX_NMEA_GSA, UBX_NMEA_GSV] {
+        frames.push(ubx_frame(0x06, 0x01, &[0xF0, id, 1]));
+    }
+    frames
+}
+
+/// The byte sequences implementing `preset`, or nothing for receivers
+/// without a known command set.
+pub fn preset_commands(family: ReceiverFamily, preset: RatePreset) -> Vec<Vec<u8>> {
+    match family {
+        ReceiverFamily::Generic => Vec::new(),
+        ReceiverFamily::MediaTek => {
+            // PMTK314 rates: GLL, RMC, VTG, GGA, GSA, GSV, then reserved
+            let sentences: &[&str] = match preset {
+                RatePreset::FastPositionOnly => &[
+                    "PMTK220,100",
+                    "PMTK314,0,1,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0",
+                ],
+                RatePreset::FullOneHz => &["PMTK220,1000", "PMTK314,-1"],
+                RatePreset::GsvOff => &["PMTK314,1,1,1,1,1,0,0,0,0,0,0,0,0,0,0,0,0,0,0"],
+            };
+            sentences.iter().map(|s| nmea_sentence(s)).collect()
+        }
+        ReceiverFamily::Ublox => {
+            let (rate_ms, messages): (Option<u16>, &[(u8, u8)]) = match preset {
+                RatePreset::FastPositionOnly => (
+                    Some(100),
+                    &[
+                        (UBX_NMEA_GGA, 1),
+                        (UBX_NMEA_RMC, 1),
+                        (UBX_NMEA_GLL, 0),
+                        (UBX_NMEA_GSA, 0),
+                        (UBX_NMEA_GSV, 0),
+                        (UBX_NMEA_VTG, 0),
+                    ],
+                ),
+                RatePreset::FullOneHz => (
+                    Some(1000),
+                    &[
+                        (UBX_NMEA_GGA, 1),
+                        (UBX_NMEA_RMC, 1),
+                        (UBX_NMEA_GLL, 1),
+                        (UBX_NMEA_GSA, 1),
+                        (UBX_NMEA_GSV, 1),
+                        (UBX_NMEA_VTG, 1),
+                    ],
+                ),
+                RatePreset::GsvOff => (None, &[(UBX_NMEA_GSV, 0)]),
+            };
+
+            let mut frames = Vec::new();
+            if let Some(ms) = rate_ms {
+                // UBX-CFG-RATE: measurement period, one fix per measurement, GPS time
+                let [lo, hi] = ms.to_le_bytes();
+                frames.push(ubx_frame(0x06, 0x08, &[lo, hi, 0x01, 0x00, 0x01, 0x00]));
+            }
+            for &(id, rate) in messages {
+                // UBX-CFG-MSG: output rate of one NMEA message on the current port
+                frames.push(ubx_frame(0x06, 0x01, &[0xF0, id, rate]));
+            }
+            frames
+        }
+    }
+}
+
+/// `$body*CS\r\n` for a free-form or PMTK command.
+pub fn nmea_sentence(body: &str) -> Vec<u8> {
+    format!("${body}*{:02X}\r\n", checksum(body)).into_bytes()
+}
+
+/// Frames a UBX message with its sync characters and Fletcher checksum.
+pub fn ubx_frame(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0xB5, 0x62, class, id];
+    frame.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    frame.extend_from_slice(payload);
+
+    let (mut ck_a, mut ck_b) = (0u8, 0u8);
+    for byte in &frame[2..] {
+        ck_a = ck_a.wrapping_add(*byte);
+        ck_b = ck_b.wrapping_add(ck_a);
+    }
+    frame.extend([ck_a, ck_b]);
+    frame
+}
+
+/// How a sent command appears in the log: text when it is printable,
+/// otherwise a hex dump of the exact bytes.
+pub fn describe(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) if text.trim_end().bytes().all(|b| b.is_ascii_graphic() || b == b' ') => {
+            text.trim_end().to_string()
+        }
+        _ => {
+            let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02X}")).collect();
+            hex.join(" ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ubx_cfg_rate_frame() {
+        let frames = preset_commands(ReceiverFamily::Ublox, RatePreset::FastPositionOnly);
+        assert_eq!(
+            describe(&frames[0]),
+            "B5 62 06 08 06 00 64 00 01 00 01 00 7A 12"
+        );
+        assert_eq!(frames.len(), 7);
+    }
+
+    #[test]
+    fn reset_sequences() {
+        let cold = reset_commands(ReceiverFamily::Ublox, ResetKind::Cold);
+        assert_eq!(describe(&cold[0]), "B5 62 06 04 04 00 FF FF 02 00 0E 61");
+        let hot = reset_commands(ReceiverFamily::Ublox, ResetKind::Hot);
+        assert_eq!(describe(&hot[0]), "B5 62 06 04 04 00 00 00 02 00 10 68");
+        let factory = reset_commands(ReceiverFamily::Ublox, ResetKind::Factory);
+        assert_eq!(factory.len(), 2);
+        assert_eq!(&factory[0][2..6], [0x06, 0x09, 13, 0]);
+
+        let cold = reset_commands(ReceiverFamily::MediaTek, ResetKind::Cold);
+        assert_eq!(cold, [b"$PMTK103*30\r\n".to_vec()]);
+        let factory = reset_commands(ReceiverFamily::MediaTek, ResetKind::Factory);
+        assert_eq!(describe(&factory[0]), "$PMTK104*37");
+        assert!(reset_commands(ReceiverFamily::Generic, ResetKind::Warm).is_empty());
+    }
+
+    #[test]
+    fn identification_polls() {
+        let polls = identify_commands(ReceiverFamily::Generic);
+        assert_eq!(describe(&polls[0]), "B5 62 0A 04 00 00 0E 34");
+        assert_eq!(describe(&polls[1]), "$PMTK605*31");
+        assert_eq!(identify_commands(ReceiverFamily::MediaTek).len(), 1);
+    }
+
+    #[test]
+    fn dynamic_model_frames() {
+        let poll = dynamic_model_poll();
+        assert_eq!(describe(&poll), "B5 62 06 24 00 00 2A 84");
+        let set = dynamic_model_command(DynamicModel::Automotive);
+        assert_eq!(&set[2..9], [0x06, 0x24, 36, 0, 0x01, 0x00, 4]);
+        assert!(set[9..6 + UBX_NAV5_LEN].iter().all(|b| *b == 0));
+        assert_eq!(DynamicModel::from_code(8), Some(DynamicModel::Airborne4g));
+        assert_eq!(DynamicModel::from_code(1), None);
+    }
+
+    #[test]
+    fn enabling_nmea_output() {
+        let frames = enable_nmea_commands(9600);
+        assert_eq!(frames.len(), 6);
+        // UART1, 8N1, 9600 baud (0x2580), UBX+NMEA in and out
+        assert_eq!(describe(&frames[0][..14]), "B5 62 06 00 14 00 01 00 00 00 D0 08 00 00");
+        assert_eq!(describe(&frames[0][14..]), "80 25 00 00 03 00 03 00 00 00 00 00 9E 95");
+        assert_eq!(frames[1][6], UBX_PORT_USB);
+        assert_eq!(&frames[2][2..9], [0x06, 0x01, 3, 0, 0xF0, UBX_NMEA_GGA, 1]);
+    }
+
+    #[test]
+    fn pmtk_sentences_carry_checksums() {
+        let sentences = preset_commands(ReceiverFamily::MediaTek, RatePreset::FullOneHz);
+        assert_eq!(sentences[0], b"$PMTK220,1000*1F\r\n");
+        assert_eq!(describe(&sentences[1]), "$PMTK314,-1*04");
+        assert!(preset_commands(ReceiverFamily::Generic, RatePreset::GsvOff).is_empty());
+    }
+}
@@ -0,0 +1,29 @@
+//! Fix conditions checked by both the event triggers and the alarms, so
+//! the two judge a fix alike and describe it in the same words.
+
+use crate::parser::GgaFix;
+
+/// Describes `fix` using fewer than `min` satellites.
+pub fn few_satellites(fix: &GgaFix, min: u8) -> Option<String> {
+    let used = fix.satellites_used;
+    (used < min).then(|| format!("{used} satellites used < {min}"))
+}
+
+/// Describes `fix`'s HDOP exceeding `limit`; a fix without one passes.
+pub fn high_hdop(fix: &GgaFix, limit: f64) -> Option<String> {
+    fix.hdop.filter(|h| *h > limit).map(|h| format!("HDOP {h:.1} > {limit:.1}"))
+}
+
+/// A positioned fix with the given quality, satellites used and HDOP,
+/// for the modules checking fixes to test with.
+#[cfg(test)]
+pub fn fix(quality: u8, satellites_used: u8, hdop: f64) -> GgaFix {
+    GgaFix {
+        latitude: 48.1,
+        longitude: 11.5,
+        quality,
+        satellites_used,
+        hdop: Some(hdop),
+        ..Default::default()
+    }
+}
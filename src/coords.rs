@@ -0,0 +1,324 @@
+//! Positions typed or pasted by hand, in whichever common notation they
+//! come: `lat, lon` decimal degrees, degrees and decimal minutes, degrees
+//! minutes seconds, or NMEA `ddmm.mmmm` fields. The notation is guessed
+//! from the number of values per coordinate and the hemisphere letters.
+//!
+//! Also the WGS84 geodetic to local East/North/Up transform: both points
+//! go to Earth-centred Earth-fixed (ECEF) coordinates,
+//! `X = (N+h)·cos φ·cos λ`, `Y = (N+h)·cos φ·sin λ`, `Z = (N(1−e²)+h)·sin φ`
+//! with `N = a/√(1 − e²·sin² φ)`, and their difference is rotated into
+//! the tangent plane at the reference.
+
+/// WGS84 semi-major axis, metres, and flattening.
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CoordFormat {
+    Decimal,
+    DegreesMinutes,
+    Dms,
+    /// `4807.038,N,01131.000,E` as in GGA/RMC fields
+    Nmea,
+}
+
+impl CoordFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CoordFormat::Decimal => "decimal degrees",
+            CoordFormat::DegreesMinutes => "degrees and minutes",
+            CoordFormat::Dms => "degrees, minutes and seconds",
+            CoordFormat::Nmea => "NMEA ddmm.mmmm",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ParsedPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub format: CoordFormat,
+}
+
+const EXAMPLE: &str = "e.g. 48.1173, 11.5167 or 48°07'02\"N 11°31'00\"E";
+
+enum Token {
+    Number(f64),
+    Hemisphere(char),
+}
+
+/// Parses a latitude/longitude pair, converting it to decimal degrees.
+pub fn parse_position(text: &str) -> Result<ParsedPosition, String> {
+    let tokens = tokenize(text)?;
+    let has_letters = tokens.iter().any(|t| matches!(t, Token::Hemisphere(_)));
+    let (lat, lon) = if has_letters {
+        split_by_hemisphere(&tokens)?
+    } else {
+        split_in_half(&tokens)?
+    };
+
+    let nmea = has_letters
+        && lat.1.len() == 1
+        && lon.1.len() == 1
+        && (lat.1[0] > 90.0 || lon.1[0] > 180.0);
+    let format = match (nmea, lat.1.len()) {
+        (true, _) => CoordFormat::Nmea,
+        (false, 1) => CoordFormat::Decimal,
+        (false, 2) => CoordFormat::DegreesMinutes,
+        (false, 3) => CoordFormat::Dms,
+        _ => return Err(format!("Too many numbers for one coordinate; {EXAMPLE}")),
+    };
+    if lat.1.len() != lon.1.len() {
+        return Err("Latitude and longitude are written differently".to_string());
+    }
+
+    let latitude = to_degrees(&lat.1, lat.0, nmea)?;
+    let longitude = to_degrees(&lon.1, lon.0, nmea)?;
+    if latitude.abs() > 90.0 {
+        return Err(format!("Latitude {latitude} is beyond ±90°"));
+    }
+    if longitude.abs() > 180.0 {
+        return Err(format!("Longitude {longitude} is beyond ±180°"));
+    }
+    Ok(ParsedPosition {
+        latitude,
+        longitude,
+        format,
+    })
+}
+
+/// Numbers and hemisphere letters, with degree/minute/second marks and
+/// commas treated as separators.
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut number = String::new();
+    let flush = |number: &mut String, tokens: &mut Vec<Token>| -> Result<(), String> {
+        if !number.is_empty() {
+            let value = number
+                .parse()
+                .map_err(|_| format!("\"{number}\" is not a number; {EXAMPLE}"))?;
+            tokens.push(Token::Number(value));
+            number.clear();
+        }
+        Ok(())
+    };
+
+    for c in text.trim().chars() {
+        match c.to_ascii_uppercase() {
+            '0'..='9' | '.' => number.push(c),
+            '-' | '+' if number.is_empty() => number.push(c),
+            h @ ('N' | 'S' | 'E' | 'W') => {
+                flush(&mut number, &mut tokens)?;
+                tokens.push(Token::Hemisphere(h));
+            }
+            ' ' | '\t' | ',' | ';' | '°' | 'º' | '\'' | '"' | '′' | '″' => {
+                flush(&mut number, &mut tokens)?;
+            }
+            other => return Err(format!("Unexpected '{other}'; {EXAMPLE}")),
+        }
+    }
+    flush(&mut number, &mut tokens)?;
+    if tokens.is_empty() {
+        return Err(format!("Enter a latitude and longitude, {EXAMPLE}"));
+    }
+    Ok(tokens)
+}
+
+/// A coordinate's hemisphere sign and its degree/minute/second values.
+type Part = (f64, Vec<f64>);
+
+/// Without letters the first half of the numbers is the latitude.
+fn split_in_half(tokens: &[Token]) -> Result<(Part, Part), String> {
+    let numbers: Vec<f64> = tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Number(n) => Some(*n),
+            Token::Hemisphere(_) => None,
+        })
+        .collect();
+    if !numbers.len().is_multiple_of(2) || numbers.len() > 6 {
+        return Err(format!("Expected a latitude and longitude, {EXAMPLE}"));
+    }
+    let (lat, lon) = numbers.split_at(numbers.len() / 2);
+    Ok(((1.0, lat.to_vec()), (1.0, lon.to_vec())))
+}
+
+/// Groups the numbers by the N/S and E/W letter written before or after
+/// them, in either order.
+fn split_by_hemisphere(tokens: &[Token]) -> Result<(Part, Part), String> {
+    let prefix = matches!(tokens.first(), Some(Token::Hemisphere(_)));
+    let mut groups: Vec<(char, Vec<f64>)> = Vec::new();
+    let mut pending = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Number(n) if prefix => match groups.last_mut() {
+                Some((_, values)) => values.push(*n),
+                None => pending.push(*n),
+            },
+            Token::Number(n) => pending.push(*n),
+            Token::Hemisphere(h) if prefix => groups.push((*h, Vec::new())),
+            Token::Hemisphere(h) => groups.push((*h, std::mem::take(&mut pending))),
+        }
+    }
+    if !pending.is_empty() || groups.len() != 2 || groups.iter().any(|(_, v)| v.is_empty()) {
+        return Err(format!("Give one N/S and one E/W letter per coordinate, {EXAMPLE}"));
+    }
+
+    let lat = groups.iter().position(|(h, _)| matches!(h, 'N' | 'S'));
+    let lon = groups.iter().position(|(h, _)| matches!(h, 'E' | 'W'));
+    let (Some(lat), Some(lon)) = (lat, lon) else {
+        return Err("Needs one N/S and one E/W coordinate".to_string());
+    };
+    let part = |(h, values): &(char, Vec<f64>)| -> Result<Part, String> {
+        if values[0].is_sign_negative() {
+            return Err("Use either a minus sign or a hemisphere letter, not both".to_string());
+        }
+        let sign = if matches!(h, 'S' | 'W') { -1.0 } else { 1.0 };
+        Ok((sign, values.clone()))
+    };
+    Ok((part(&groups[lat])?, part(&groups[lon])?))
+}
+
+/// Combines degrees, minutes and seconds (or an NMEA `dddmm.mmmm` value)
+/// into signed decimal degrees.
+fn to_degrees(values: &[f64], sign: f64, nmea: bool) -> Result<f64, String> {
+    let (degrees, minutes, seconds) = match values {
+        [v] if nmea => ((v / 100.0).trunc(), v % 100.0, 0.0),
+        [d] => (*d, 0.0, 0.0),
+        [d, m] => (*d, *m, 0.0),
+        [d, m, s] => (*d, *m, *s),
+        _ => return Err(format!("Expected a latitude and longitude, {EXAMPLE}")),
+    };
+    if values.len() > 1 && degrees.fract() != 0.0 {
+        return Err("Degrees must be whole when minutes follow".to_string());
+    }
+    if !(0.0..60.0).contains(&minutes) {
+        return Err(format!("Minutes {minutes} must be from 0 to under 60"));
+    }
+    if !(0.0..60.0).contains(&seconds) {
+        return Err(format!("Seconds {seconds} must be from 0 to under 60"));
+    }
+    // A leading minus applies to the whole coordinate
+    let magnitude = degrees.abs() + minutes / 60.0 + seconds / 3600.0;
+    Ok(sign * magnitude.copysign(degrees))
+}
+
+/// Earth-centred Earth-fixed metres of `[latitude, longitude, height]`,
+/// degrees and metres above the ellipsoid.
+pub fn geodetic_to_ecef([lat, lon, height]: [f64; 3]) -> [f64; 3] {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let (phi, lambda) = (lat.to_radians(), lon.to_radians());
+    let n = WGS84_A / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+    [
+        (n + height) * phi.cos() * lambda.cos(),
+        (n + height) * phi.cos() * lambda.sin(),
+        (n * (1.0 - e2) + height) * phi.sin(),
+    ]
+}
+
+/// East, north and up metres of `point` from `reference`, both
+/// `[latitude, longitude, height]`.
+pub fn geodetic_to_enu(reference: [f64; 3], point: [f64; 3]) -> [f64; 3] {
+    let origin = geodetic_to_ecef(reference);
+    let target = geodetic_to_ecef(point);
+    let [dx, dy, dz] = [0, 1, 2].map(|axis| target[axis] - origin[axis]);
+    let (phi, lambda) = (reference[0].to_radians(), reference[1].to_radians());
+    let (sin_phi, cos_phi, sin_lambda, cos_lambda) =
+        (phi.sin(), phi.cos(), lambda.sin(), lambda.cos());
+    [
+        -sin_lambda * dx + cos_lambda * dy,
+        -sin_phi * cos_lambda * dx - sin_phi * sin_lambda * dy + cos_phi * dz,
+        cos_phi * cos_lambda * dx + cos_phi * sin_lambda * dy + sin_phi * dz,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> ParsedPosition {
+        parse_position(text).unwrap_or_else(|e| panic!("{text}: {e}"))
+    }
+
+    fn assert_near(position: ParsedPosition, latitude: f64, longitude: f64) {
+        assert!((position.latitude - latitude).abs() < 1e-6, "{position:?}");
+        assert!((position.longitude - longitude).abs() < 1e-6, "{position:?}");
+    }
+
+    #[test]
+    fn decimal_pairs() {
+        let p = parse("48.1173, 11.5167");
+        assert_eq!(p.format, CoordFormat::Decimal);
+        assert_near(p, 48.1173, 11.5167);
+        assert_near(parse("-33.8568 151.2153"), -33.8568, 151.2153);
+        assert_near(parse("33.8568S,151.2153E"), -33.8568, 151.2153);
+        // Hemisphere letters in front, longitude first
+        assert_near(parse("W 74.25 N 40.5"), 40.5, -74.25);
+    }
+
+    #[test]
+    fn degrees_minutes_seconds() {
+        let p = parse("48°07'02\"N 11°31'00\"E");
+        assert_eq!(p.format, CoordFormat::Dms);
+        assert_near(p, 48.0 + 7.0 / 60.0 + 2.0 / 3600.0, 11.0 + 31.0 / 60.0);
+        assert_near(parse("33 51 24.5 S 151 12 55 E"), -33.856_806, 151.215_278);
+        assert_near(parse("-33 51 24.5, 151 12 55"), -33.856_806, 151.215_278);
+    }
+
+    #[test]
+    fn degrees_decimal_minutes() {
+        let p = parse("48°07.038'N, 011°31.000'E");
+        assert_eq!(p.format, CoordFormat::DegreesMinutes);
+        assert_near(p, 48.1173, 11.516_667);
+    }
+
+    #[test]
+    fn nmea_fields() {
+        let p = parse("4807.038,N,01131.000,E");
+        assert_eq!(p.format, CoordFormat::Nmea);
+        assert_near(p, 48.1173, 11.516_667);
+        assert_near(parse("3351.408,S,15112.917,W"), -33.8568, -151.215_283);
+    }
+
+    #[test]
+    fn ecef_of_known_points() {
+        let equator = geodetic_to_ecef([0.0, 0.0, 0.0]);
+        assert!((equator[0] - WGS84_A).abs() < 1e-6 && equator[1].abs() < 1e-6);
+        // The pole sits at the semi-minor axis
+        let pole = geodetic_to_ecef([90.0, 0.0, 0.0]);
+        assert!((pole[2] - 6_356_752.314_245).abs() < 1e-3);
+    }
+
+    #[test]
+    fn enu_offsets() {
+        let assert_enu = |enu: [f64; 3], expected: [f64; 3]| {
+            let close = enu.iter().zip(expected).all(|(a, b)| (a - b).abs() < 0.01);
+            assert!(close, "{enu:?} != {expected:?}");
+        };
+        assert_enu(geodetic_to_enu([48.0, 11.0, 500.0], [48.0, 11.0, 512.5]), [0.0, 0.0, 12.5]);
+        // A millidegree at the equator: the meridian and equatorial radii
+        assert_enu(geodetic_to_enu([0.0, 0.0, 0.0], [0.001, 0.0, 0.0]), [0.0, 110.574, 0.0]);
+        assert_enu(geodetic_to_enu([0.0, 0.0, 0.0], [0.0, 0.001, 0.0]), [111.319, 0.0, 0.0]);
+        // Across the antimeridian and south and west of the reference
+        let enu = geodetic_to_enu([-33.9, 179.9999, 20.0], [-33.9001, -179.9999, 20.0]);
+        assert!(enu[0] > 18.4 && enu[0] < 18.5 && enu[1] < -11.0 && enu[1] > -11.1, "{enu:?}");
+    }
+
+    #[test]
+    fn rejects_unparseable_input() {
+        for text in [
+            "",
+            "48.1173",
+            "hello",
+            "95, 10",
+            "10, 190",
+            "48 75 N 11 0 E",
+            "48.5 N 11.5 N",
+            "-48.5 N 11.5 E",
+            "1 2 3",
+            "48.1173, 11.5167, 3",
+        ] {
+            assert!(parse_position(text).is_err(), "accepted {text:?}");
+        }
+    }
+}
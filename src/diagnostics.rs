@@ -0,0 +1,94 @@
+//! Consistency checks on receiver time, shown when diagnostics are on.
+
+use crate::parser::{seconds_of_day, GgaFix, RmcData};
+
+/// GGA and RMC of one cycle should carry the same time.
+const SENTENCE_TOLERANCE_SECS: f64 = 1.0;
+/// Allowance for serial latency and an unsynchronised system clock.
+const CLOCK_TOLERANCE_SECS: f64 = 5.0;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Warnings about the latest GGA/RMC times disagreeing with each other or
+/// with the system clock at `now` (Unix seconds). Recorded or simulated
+/// data passes no `now`, its time being unrelated to the clock.
+pub fn time_warnings(
+    fix: Option<&GgaFix>,
+    rmc: Option<&RmcData>,
+    now: Option<f64>,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let gga_time = fix.and_then(|f| seconds_of_day(f.utc_time.as_deref()?));
+    let rmc_time = rmc.and_then(|r| seconds_of_day(r.utc_time.as_deref()?));
+
+    if let (Some(gga), Some(rmc)) = (gga_time, rmc_time) {
+        let diff = day_difference(gga, rmc);
+        if diff.abs() > SENTENCE_TOLERANCE_SECS {
+            warnings.push(format!("GGA and RMC times differ by {diff:+.1} s"));
+        }
+    }
+
+    let Some(now) = now else {
+        return warnings;
+    };
+    // A full RMC date/time gives an absolute comparison, else time of day
+    let clock_diff = match rmc.and_then(RmcData::unix_time) {
+        Some(unix) => Some(unix - now),
+        None => gga_time
+            .or(rmc_time)
+            .map(|t| day_difference(t, now.rem_euclid(SECONDS_PER_DAY))),
+    };
+    if let Some(diff) = clock_diff.filter(|d| d.abs() > CLOCK_TOLERANCE_SECS) {
+        warnings.push(format!("Receiver UTC is {diff:+.1} s from the system clock"));
+    }
+    warnings
+}
+
+/// `a - b` for two times of day, taking the shorter way round midnight.
+fn day_difference(a: f64, b: f64) -> f64 {
+    let diff = (a - b).rem_euclid(SECONDS_PER_DAY);
+    if diff > SECONDS_PER_DAY / 2.0 {
+        diff - SECONDS_PER_DAY
+    } else {
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(time: &str) -> GgaFix {
+        GgaFix {
+            utc_time: Some(time.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn rmc(time: &str, date: &str) -> RmcData {
+        RmcData {
+            utc_time: Some(time.to_string()),
+            date: Some(date.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flags_diverging_sentences() {
+        // 2024-01-01 12:00:00 UTC
+        let now = Some(1_704_110_400.0);
+        let rmc = rmc("12:00:00", "010124");
+        assert!(time_warnings(Some(&fix("12:00:00")), Some(&rmc), now).is_empty());
+
+        let warnings = time_warnings(Some(&fix("12:00:03")), Some(&rmc), now);
+        assert_eq!(warnings, ["GGA and RMC times differ by +3.0 s"]);
+    }
+
+    #[test]
+    fn flags_clock_offset_across_midnight() {
+        // 2024-01-01 00:00:02 UTC against a GGA-only receiver at 23:59:50
+        let now = 1_704_067_202.0;
+        let warnings = time_warnings(Some(&fix("23:59:50")), None, Some(now));
+        assert_eq!(warnings, ["Receiver UTC is -12.0 s from the system clock"]);
+        assert!(time_warnings(Some(&fix("23:59:50")), None, None).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! Geometry-only dilution of precision and how much each satellite adds
+//! to it, from the azimuths and elevations a receiver reports.
+//!
+//! Each satellite gives a row `[−cos el·sin az, −cos el·cos az, −sin el, 1]`
+//! of the design matrix `H`: the unit line of sight from the receiver, in
+//! east/north/up, negated, plus one for the receiver clock. With
+//! `Q = (HᵀH)⁻¹`, `PDOP = √(Q₁₁ + Q₂₂ + Q₃₃)`. A satellite's contribution is
+//! how far PDOP rises when it is left out: a satellite far from the others
+//! pins down a direction none of them can and costs a lot to lose, while
+//! one of a tight cluster mostly repeats its neighbours. With four or fewer
+//! satellites every one is essential. No weighting by SNR or elevation is
+//! applied, so the figures show geometry alone and differ a little from the
+//! receiver's own DOP.
+
+/// Pivots smaller than this make `HᵀH` singular: no position solution.
+const SINGULAR: f64 = 1e-9;
+
+/// PDOP of satellites at `(azimuth, elevation)` in degrees, or `None` when
+/// fewer than four or placed so they fix no position.
+pub fn pdop(directions: &[(f64, f64)]) -> Option<f64> {
+    if directions.len() < 4 {
+        return None;
+    }
+    let mut normal = [[0.0; 4]; 4];
+    for &(azimuth, elevation) in directions {
+        let row = design_row(azimuth, elevation);
+        for (i, a) in row.iter().enumerate() {
+            for (j, b) in row.iter().enumerate() {
+                normal[i][j] += a * b;
+            }
+        }
+    }
+    let cofactor = invert(normal)?;
+    Some((cofactor[0][0] + cofactor[1][1] + cofactor[2][2]).sqrt())
+}
+
+fn design_row(azimuth: f64, elevation: f64) -> [f64; 4] {
+    let (az, el) = (azimuth.to_radians(), elevation.to_radians());
+    [-el.cos() * az.sin(), -el.cos() * az.cos(), -el.sin(), 1.0]
+}
+
+/// Gauss-Jordan inverse with partial pivoting.
+fn invert(mut m: [[f64; 4]; 4]) -> Option<[[f64; 4]; 4]> {
+    let mut inverse = [[0.0; 4]; 4];
+    for (i, row) in inverse.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+    for col in 0..4 {
+        let pivot = (col..4).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+        if m[pivot][col].abs() < SINGULAR {
+            return None;
+        }
+        m.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let scale = m[col][col];
+        for j in 0..4 {
+            m[col][j] /= scale;
+            inverse[col][j] /= scale;
+        }
+        for row in 0..4 {
+            let factor = m[row][col];
+            if row == col || factor == 0.0 {
+                continue;
+            }
+            for j in 0..4 {
+                m[row][j] -= factor * m[col][j];
+                inverse[row][j] -= factor * inverse[col][j];
+            }
+        }
+    }
+    Some(inverse)
+}
+
+pub struct Geometry {
+    pub pdop: f64,
+    /// Rise in PDOP without each satellite, in the order given; infinite
+    /// for one the rest can't fix a position without
+    pub increase: Vec<f64>,
+}
+
+impl Geometry {
+    /// `None` when the satellites fix no position between them.
+    pub fn new(directions: &[(f64, f64)]) -> Option<Self> {
+        let all = pdop(directions)?;
+        let increase = (0..directions.len())
+            .map(|skip| {
+                let rest: Vec<(f64, f64)> = directions
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != skip)
+                    .map(|(_, d)| *d)
+                    .collect();
+                pdop(&rest).map_or(f64::INFINITY, |without| (without - all).max(0.0))
+            })
+            .collect();
+        Some(Self { pdop: all, increase })
+    }
+
+    /// The `index`th satellite's contribution from 0 to 1, relative to the
+    /// largest; essential satellites are 1.
+    pub fn share(&self, index: usize) -> f32 {
+        let increase = self.increase[index];
+        if increase.is_infinite() {
+            return 1.0;
+        }
+        let largest = self.increase.iter().copied().filter(|i| i.is_finite()).fold(0.0, f64::max);
+        if largest > 0.0 {
+            (increase / largest) as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zenith_and_three_on_the_horizon() {
+        // HᵀH has 1.5 east and north, and [[1, 1], [1, 4]] up and clock
+        let directions = [(0.0, 90.0), (0.0, 0.0), (120.0, 0.0), (240.0, 0.0)];
+        let expected = (1.0 / 1.5 + 1.0 / 1.5 + 4.0 / 3.0_f64).sqrt();
+        assert!((pdop(&directions).unwrap() - expected).abs() < 1e-9);
+
+        assert_eq!(pdop(&directions[..3]), None);
+        // All in one direction fix nothing
+        assert_eq!(pdop(&[(10.0, 45.0); 5]), None);
+        let geometry = Geometry::new(&directions).unwrap();
+        assert!(geometry.increase.iter().all(|i| i.is_infinite()));
+        assert_eq!(geometry.share(2), 1.0);
+    }
+
+    #[test]
+    fn clustered_satellites_contribute_least() {
+        let directions = [
+            (0.0, 80.0),
+            (0.0, 15.0),
+            (120.0, 15.0),
+            (240.0, 15.0),
+            (122.0, 17.0),
+            (118.0, 14.0),
+        ];
+        let geometry = Geometry::new(&directions).unwrap();
+        // Three near 120° do the work of one; each of the rest is alone
+        for lone in [0, 1, 3] {
+            assert!(geometry.share(lone) > 0.4, "share of {lone}");
+        }
+        for clustered in [2, 4, 5] {
+            assert!(geometry.share(clustered) < 0.01, "share of {clustered}");
+        }
+        assert!(geometry.pdop < 10.0);
+    }
+}
@@ -0,0 +1,286 @@
+//! Freeze-frame captures: when a trigger condition starts to hold, the
+//! satellites, fix and log lines around that moment are kept for review,
+//! so transient problems can be looked at after they have cleared.
+
+use std::collections::{BTreeSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::conditions;
+use crate::parser::GgaFix;
+use crate::satellite::Satellite;
+use crate::LogEntry;
+
+/// Events kept per source; the oldest are dropped past this.
+pub const MAX_EVENTS: usize = 50;
+/// Log lines kept from before and after the triggering fix.
+const LOG_BEFORE: usize = 20;
+const LOG_AFTER: usize = 10;
+/// Seconds the receiver's time may step back between sentences, as those
+/// of one epoch can lag each other, before it counts as a jump.
+const BACKWARD_SLACK: f64 = 0.5;
+/// Seconds the receiver's time may run ahead of the system clock between
+/// two sentences before it counts as a leap.
+const FORWARD_SLACK: f64 = 60.0;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventTriggers {
+    pub fix_lost: bool,
+    pub high_hdop: bool,
+    pub hdop_limit: f64,
+    pub few_satellites: bool,
+    pub min_satellites: u8,
+    /// The receiver's UTC stepping back or leaping ahead
+    pub clock_jump: bool,
+    /// Restart the time-series graphs after a clock jump
+    pub clock_jump_resets_graphs: bool,
+}
+
+impl Default for EventTriggers {
+    fn default() -> Self {
+        Self {
+            fix_lost: false,
+            high_hdop: false,
+            hdop_limit: 5.0,
+            few_satellites: false,
+            min_satellites: 4,
+            clock_jump: true,
+            clock_jump_resets_graphs: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Trigger {
+    FixLost,
+    HighHdop,
+    FewSatellites,
+}
+
+impl EventTriggers {
+    /// The enabled conditions `fix` meets, each with a description.
+    fn firing(&self, fix: &GgaFix) -> Vec<(Trigger, String)> {
+        let mut firing = Vec::new();
+        if self.fix_lost && !fix.has_position() {
+            firing.push((Trigger::FixLost, "Fix lost".to_string()));
+        }
+        let hdop = conditions::high_hdop(fix, self.hdop_limit);
+        if let Some(reason) = hdop.filter(|_| self.high_hdop) {
+            firing.push((Trigger::HighHdop, reason));
+        }
+        let few = conditions::few_satellites(fix, self.min_satellites);
+        if let Some(reason) = few.filter(|_| self.few_satellites) {
+            firing.push((Trigger::FewSatellites, reason));
+        }
+        firing
+    }
+}
+
+pub struct CapturedEvent {
+    /// Unix time of the triggering fix
+    pub time: f64,
+    pub reason: String,
+    pub fix: GgaFix,
+    pub satellites: Vec<Satellite>,
+    pub log: Vec<String>,
+    /// Lines still to be added from after the trigger
+    lines_after: usize,
+}
+
+/// A step in the receiver's UTC that real time doesn't explain.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ClockJump {
+    /// Seconds since UTC midnight before and after
+    pub from: f64,
+    pub to: f64,
+    /// Seconds moved, negative when going back
+    pub step: f64,
+}
+
+impl ClockJump {
+    /// e.g. "Clock jumped back 35.0 s (12:00:05 → 11:59:30)"
+    pub fn describe(&self) -> String {
+        let direction = if self.step < 0.0 { "back" } else { "ahead" };
+        let hms = |secs: f64| {
+            let secs = secs as u64;
+            format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        };
+        format!(
+            "Clock jumped {direction} {:.1} s ({} → {})",
+            self.step.abs(),
+            hms(self.from),
+            hms(self.to)
+        )
+    }
+}
+
+/// Follows the UTC time of day in one source's sentences, as spliced
+/// logs and receiver resets make it go backwards or leap ahead. Steps
+/// across midnight count by the shorter way round.
+#[derive(Default)]
+pub struct ClockWatch {
+    // Time of day of the last timed sentence, and the system time then
+    last: Option<(f64, f64)>,
+}
+
+impl ClockWatch {
+    pub fn check(&mut self, time_of_day: f64, system_time: f64) -> Option<ClockJump> {
+        let (from, then) = self.last.replace((time_of_day, system_time))?;
+        let step = (time_of_day - from + 43_200.0).rem_euclid(86_400.0) - 43_200.0;
+        let ahead = step - (system_time - then).max(0.0);
+        (step < -BACKWARD_SLACK || ahead > FORWARD_SLACK).then_some(ClockJump {
+            from,
+            to: time_of_day,
+            step,
+        })
+    }
+}
+
+impl CapturedEvent {
+    /// Still collecting the log lines that follow the trigger.
+    pub fn is_filling(&self) -> bool {
+        self.lines_after > 0
+    }
+}
+
+/// Watches one source's fixes for trigger conditions.
+#[derive(Default)]
+pub struct EventRecorder {
+    /// Copied from the settings each frame
+    pub triggers: EventTriggers,
+    // Conditions that held at the previous fix; each fires on its edge
+    active: BTreeSet<Trigger>,
+    pub events: VecDeque<CapturedEvent>,
+}
+
+impl EventRecorder {
+    /// Checks a new fix, capturing an event for each condition that has
+    /// just started to hold.
+    pub fn check(&mut self, time: f64, fix: &GgaFix, satellites: &[Satellite], log: &[LogEntry]) {
+        let firing = self.triggers.firing(fix);
+        let before = std::mem::take(&mut self.active);
+        for (trigger, reason) in firing {
+            self.active.insert(trigger);
+            if before.contains(&trigger) {
+                continue;
+            }
+            self.capture(time, reason, fix, satellites, log);
+        }
+    }
+
+    /// Captures an event for `reason` whatever the triggers.
+    pub fn capture(
+        &mut self,
+        time: f64,
+        reason: String,
+        fix: &GgaFix,
+        satellites: &[Satellite],
+        log: &[LogEntry],
+    ) {
+        let start = log.len().saturating_sub(LOG_BEFORE);
+        self.events.push_back(CapturedEvent {
+            time,
+            reason,
+            fix: fix.clone(),
+            satellites: satellites.to_vec(),
+            log: log[start..].iter().map(|e| e.line.clone()).collect(),
+            lines_after: LOG_AFTER,
+        });
+        if self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    /// Adds a newly logged line to the events still collecting lines.
+    pub fn push_line(&mut self, line: &str) {
+        for event in self.events.iter_mut().rev().take_while(|e| e.is_filling()) {
+            event.log.push(line.to_string());
+            event.lines_after -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conditions::fix;
+
+    #[test]
+    fn fires_once_per_condition_onset() {
+        let mut recorder = EventRecorder {
+            triggers: EventTriggers {
+                fix_lost: true,
+                high_hdop: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let log: Vec<LogEntry> = (0..30)
+            .map(|n| LogEntry {
+                time: n as f64,
+                gps_time: None,
+                line: format!("line {n}"),
+            })
+            .collect();
+
+        recorder.check(1.0, &fix(1, 8, 0.9), &[], &log);
+        assert!(recorder.events.is_empty());
+        recorder.check(2.0, &fix(1, 8, 6.5), &[], &log);
+        recorder.check(3.0, &fix(1, 8, 7.0), &[], &log);
+        assert_eq!(recorder.events.len(), 1);
+        assert_eq!(recorder.events[0].reason, "HDOP 6.5 > 5.0");
+        assert_eq!(recorder.events[0].log.len(), LOG_BEFORE);
+        assert_eq!(recorder.events[0].log[0], "line 10");
+
+        for n in 0..LOG_AFTER + 5 {
+            recorder.push_line(&format!("after {n}"));
+        }
+        assert_eq!(recorder.events[0].log.len(), LOG_BEFORE + LOG_AFTER);
+        assert!(!recorder.events[0].is_filling());
+
+        // Back to normal, then lost: the HDOP trigger can fire again
+        recorder.check(4.0, &fix(1, 8, 1.0), &[], &log);
+        recorder.check(5.0, &fix(0, 0, 99.9), &[], &log);
+        let reasons: Vec<&str> = recorder.events.iter().map(|e| e.reason.as_str()).collect();
+        assert_eq!(reasons, ["HDOP 6.5 > 5.0", "Fix lost", "HDOP 99.9 > 5.0"]);
+    }
+
+    #[test]
+    fn flags_backward_steps_and_unexplained_leaps() {
+        let mut watch = ClockWatch::default();
+        assert_eq!(watch.check(43_200.0, 0.0), None);
+        assert_eq!(watch.check(43_201.0, 1.0), None);
+        // RMC a little behind the GGA of the same epoch
+        assert_eq!(watch.check(43_200.8, 1.0), None);
+        let jump = watch.check(43_166.0, 2.0).unwrap();
+        assert!((jump.step + 34.8).abs() < 1e-6);
+        assert_eq!(jump.describe(), "Clock jumped back 34.8 s (12:00:00 → 11:59:26)");
+
+        // Across midnight, and a quiet spell the system clock also saw
+        let mut watch = ClockWatch::default();
+        assert_eq!(watch.check(86_399.0, 10.0), None);
+        assert_eq!(watch.check(0.5, 11.0), None);
+        assert_eq!(watch.check(600.5, 611.0), None);
+        // Ten minutes ahead in one second
+        let jump = watch.check(1_200.5, 612.0).unwrap();
+        assert_eq!(jump.describe(), "Clock jumped ahead 600.0 s (00:10:00 → 00:20:00)");
+    }
+
+    #[test]
+    fn keeps_a_bounded_list() {
+        let mut recorder = EventRecorder {
+            triggers: EventTriggers {
+                fix_lost: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for n in 0..MAX_EVENTS + 3 {
+            recorder.check(n as f64, &fix(0, 0, 1.0), &[], &[]);
+            recorder.check(n as f64 + 0.5, &fix(1, 8, 1.0), &[], &[]);
+        }
+        assert_eq!(recorder.events.len(), MAX_EVENTS);
+        assert_eq!(recorder.events[0].time, 3.0);
+    }
+}
@@ -0,0 +1,828 @@
+//! Track export to GPX or CSV, picked by the file extension, waypoints
+//! on their own as GPX, saving the NMEA log in one of the `LogFormat`s,
+//! the alarm history and graph time series as CSV and the satellites in
+//! view as JSON. Logs and tracks can be thinned by a `Decimation` first.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alarms::Alarm;
+use crate::parser::GgaFix;
+use crate::satellite::Satellite;
+use crate::{FixRecord, LogEntry, Waypoint};
+
+/// How a saved log is written out.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogFormat {
+    #[default]
+    Raw,
+    Timestamped,
+    Csv,
+}
+
+impl LogFormat {
+    pub const ALL: [LogFormat; 3] = [LogFormat::Raw, LogFormat::Timestamped, LogFormat::Csv];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogFormat::Raw => "Raw NMEA",
+            LogFormat::Timestamped => "Timestamped NMEA",
+            LogFormat::Csv => "CSV of fixes",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            LogFormat::Raw => "Lines exactly as received, for replay or other tools",
+            LogFormat::Timestamped => "Each line prefixed with its arrival time",
+            LogFormat::Csv => "One row of parsed values per position fix",
+        }
+    }
+}
+
+/// Writes one source's log in `format`, timestamped from `clock`. The
+/// CSV is built from the fixes rather than the log lines.
+pub fn write_log(
+    path: &Path,
+    format: LogFormat,
+    clock: LogClock,
+    track: &Track,
+    log: &[LogEntry],
+    decimals: usize,
+) -> Result<(), String> {
+    let text = match format {
+        LogFormat::Raw => log_raw(log),
+        LogFormat::Timestamped => log_timestamped(log, clock),
+        LogFormat::Csv => to_csv(std::slice::from_ref(track), decimals),
+    };
+    fs::write(path, text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn log_raw(log: &[LogEntry]) -> String {
+    log.iter().map(|entry| format!("{}\n", entry.line)).collect()
+}
+
+fn log_timestamped(log: &[LogEntry], clock: LogClock) -> String {
+    log.iter()
+        .map(|entry| format!("{} {}\n", clock.stamp(entry), entry.line))
+        .collect()
+}
+
+/// Clock the Timestamped log format takes each line's time from.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogClock {
+    #[default]
+    System,
+    Local,
+    Utc,
+    Gps,
+}
+
+impl LogClock {
+    pub const ALL: [LogClock; 4] = [
+        LogClock::System,
+        LogClock::Local,
+        LogClock::Utc,
+        LogClock::Gps,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogClock::System => "Unix seconds",
+            LogClock::Local => "Local time",
+            LogClock::Utc => "UTC",
+            LogClock::Gps => "GPS UTC",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            LogClock::System => "Seconds since 1970 from the system clock",
+            LogClock::Local => "Date and time of the system clock in the local time zone",
+            LogClock::Utc => "Date and time of the system clock in UTC",
+            LogClock::Gps => {
+                "UTC as reported by the receiver's GGA/RMC/ZDA sentences, more accurate for \
+                 timing; system UTC until the first of them arrives"
+            }
+        }
+    }
+
+    /// The time written ahead of a log line.
+    fn stamp(&self, entry: &LogEntry) -> String {
+        let time = match self {
+            LogClock::System => return format!("{:.3}", entry.time),
+            LogClock::Gps => entry.gps_time.unwrap_or(entry.time),
+            LogClock::Local | LogClock::Utc => entry.time,
+        };
+        let Some(utc) = chrono::DateTime::from_timestamp_millis((time * 1e3).round() as i64) else {
+            return format!("{time:.3}");
+        };
+        match self {
+            LogClock::Local => {
+                let local = utc.with_timezone(&chrono::Local);
+                local.format("%Y-%m-%d %H:%M:%S%.3f%:z").to_string()
+            }
+            _ => iso_utc(utc),
+        }
+    }
+}
+
+/// Which position cycles of a high-rate stream saved files keep.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecimationMode {
+    #[default]
+    Full,
+    EveryNth,
+    PerSecond,
+}
+
+impl DecimationMode {
+    pub const ALL: [DecimationMode; 3] = [
+        DecimationMode::Full,
+        DecimationMode::EveryNth,
+        DecimationMode::PerSecond,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DecimationMode::Full => "Every cycle",
+            DecimationMode::EveryNth => "1 in N cycles",
+            DecimationMode::PerSecond => "1 per second",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Decimation {
+    pub mode: DecimationMode,
+    /// N of `EveryNth`
+    pub every: u32,
+}
+
+impl Default for Decimation {
+    fn default() -> Self {
+        Self {
+            mode: DecimationMode::Full,
+            every: 10,
+        }
+    }
+}
+
+impl Decimation {
+    pub fn decimator(&self) -> Decimator {
+        Decimator {
+            decimation: *self,
+            seen: 0,
+            last_second: None,
+            cycle: None,
+        }
+    }
+
+    /// e.g. "1 in 10 cycles"; `None` when every cycle is kept.
+    pub fn describe(&self) -> Option<String> {
+        match self.mode {
+            DecimationMode::Full => None,
+            DecimationMode::EveryNth => Some(format!("1 in {} cycles", self.every.max(1))),
+            DecimationMode::PerSecond => Some("1 per second".to_string()),
+        }
+    }
+
+    pub fn fixes<'a>(&self, fixes: impl IntoIterator<Item = &'a FixRecord>) -> Vec<&'a FixRecord> {
+        let mut decimator = self.decimator();
+        fixes
+            .into_iter()
+            .filter(|r| decimator.keep_fix(r.time, r.fix.utc_time.as_deref()))
+            .collect()
+    }
+
+    /// The log lines of the kept cycles. A cycle starts at the first GGA
+    /// of a fix time, or RMC for receivers sending no GGA, so the GGA of
+    /// each system and the combined one share a cycle. With
+    /// `prefer_combined`, only `GN` sentences start cycles in a log that
+    /// has them. Lines ahead of the first cycle are kept, and user notes
+    /// always.
+    pub fn log(&self, log: &[LogEntry], prefer_combined: bool) -> Vec<LogEntry> {
+        fn kind(line: &str) -> Option<&str> {
+            line.strip_prefix('$').and_then(|l| l.get(2..5))
+        }
+        let has_gga = log.iter().any(|e| kind(&e.line) == Some("GGA"));
+        let starts = if has_gga { "GGA" } else { "RMC" };
+        let combined = format!("$GN{starts}");
+        let combined_only = prefer_combined && log.iter().any(|e| e.line.starts_with(&combined));
+        let mut decimator = self.decimator();
+        let mut keep = true;
+        let mut kept = Vec::new();
+        for entry in log {
+            let line = &entry.line;
+            if kind(line) == Some(starts) && (!combined_only || line.starts_with(&combined)) {
+                let utc = line.split(',').nth(1);
+                keep = decimator.keep_fix(entry.time, utc);
+            }
+            if keep || line.starts_with(crate::NOTE_PREFIX) {
+                kept.push(entry.clone());
+            }
+        }
+        kept
+    }
+}
+
+/// Decides cycle by cycle, in order, which a `Decimation` keeps.
+pub struct Decimator {
+    decimation: Decimation,
+    seen: u64,
+    last_second: Option<i64>,
+    // UTC time of the last fix and whether its cycle was kept
+    cycle: Option<(String, bool)>,
+}
+
+impl Decimator {
+    /// Whether the cycle starting at Unix `time` is kept.
+    pub fn keep(&mut self, time: f64) -> bool {
+        match self.decimation.mode {
+            DecimationMode::Full => true,
+            DecimationMode::EveryNth => {
+                let keep = self.seen.is_multiple_of(u64::from(self.decimation.every.max(1)));
+                self.seen += 1;
+                keep
+            }
+            DecimationMode::PerSecond => {
+                let second = time.floor() as i64;
+                let keep = self.last_second != Some(second);
+                self.last_second = Some(second);
+                keep
+            }
+        }
+    }
+
+    /// Whether the cycle of the fix at UTC `utc`, as the sentence gives
+    /// it, is kept. Fixes repeating the last one's time are in its cycle;
+    /// those without a time each start their own.
+    pub fn keep_fix(&mut self, time: f64, utc: Option<&str>) -> bool {
+        let utc = utc.filter(|t| !t.is_empty());
+        let same = self.cycle.as_ref().filter(|(last, _)| Some(last.as_str()) == utc);
+        if let Some((_, keep)) = same {
+            return *keep;
+        }
+        let keep = self.keep(time);
+        self.cycle = utc.map(|t| (t.to_string(), keep));
+        keep
+    }
+}
+
+fn iso_utc(utc: chrono::DateTime<chrono::Utc>) -> String {
+    utc.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+/// Writes each source's alarms as CSV rows, times in UTC; alarms still
+/// active have no cleared time or duration.
+pub fn write_alarms(path: &Path, sources: &[(&str, Vec<&Alarm>)]) -> Result<(), String> {
+    fs::write(path, alarms_csv(sources)).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// `text` as one quoted CSV field, whatever commas or quotes it holds.
+fn csv_text(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// Unix `time` in UTC, or as seconds if out of chrono's range.
+fn utc(time: f64) -> String {
+    chrono::DateTime::from_timestamp_millis((time * 1e3).round() as i64)
+        .map_or(format!("{time:.3}"), iso_utc)
+}
+
+fn alarms_csv(sources: &[(&str, Vec<&Alarm>)]) -> String {
+    let mut out = String::from("source,alarm,raised,cleared,duration_s,detail\n");
+    for (label, alarms) in sources {
+        for alarm in alarms {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                csv_text(label),
+                alarm.kind.name(),
+                utc(alarm.raised),
+                alarm.cleared.map_or(String::new(), utc),
+                alarm.cleared.map_or(String::new(), |c| format!("{:.1}", c - alarm.raised)),
+                csv_text(&alarm.detail)
+            );
+        }
+    }
+    out
+}
+
+/// One source's fixes to export under its label.
+pub struct Track<'a> {
+    pub label: &'a str,
+    pub fixes: Vec<&'a FixRecord>,
+}
+
+/// Writes the tracks as GPX (`.gpx`) or CSV (anything else), with
+/// latitude and longitude rounded to `decimals` places. Waypoints only
+/// have a place in GPX.
+pub fn write_tracks(
+    path: &Path,
+    tracks: &[Track],
+    waypoints: &[Waypoint],
+    decimals: usize,
+) -> Result<(), String> {
+    let is_gpx = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gpx"));
+    let text = if is_gpx {
+        to_gpx(tracks, waypoints, decimals)
+    } else {
+        to_csv(tracks, decimals)
+    };
+    fs::write(path, text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Writes just the waypoints as GPX, whatever the file extension.
+pub fn write_waypoints(path: &Path, waypoints: &[Waypoint], decimals: usize) -> Result<(), String> {
+    let text = to_gpx(&[], waypoints, decimals);
+    fs::write(path, text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn to_gpx(tracks: &[Track], waypoints: &[Waypoint], decimals: usize) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <gpx version=\"1.1\" creator=\"RUST_NMEA_PARSER\">\n",
+    );
+    // GPX requires waypoints ahead of tracks
+    for wpt in waypoints {
+        let _ = write!(
+            out,
+            "  <wpt lat=\"{:.p$}\" lon=\"{:.p$}\">",
+            wpt.latitude,
+            wpt.longitude,
+            p = decimals
+        );
+        if let Some(alt) = wpt.altitude {
+            let _ = write!(out, "<ele>{alt:.1}</ele>");
+        }
+        let _ = write!(out, "<name>{}</name>", escape(&wpt.name));
+        if !wpt.note.is_empty() {
+            let _ = write!(out, "<desc>{}</desc>", escape(&wpt.note));
+        }
+        out.push_str("</wpt>\n");
+    }
+    for track in tracks {
+        let _ = writeln!(out, "  <trk><name>{}</name><trkseg>", track.label);
+        for record in &track.fixes {
+            let fix = &record.fix;
+            let _ = write!(
+                out,
+                "    <trkpt lat=\"{:.p$}\" lon=\"{:.p$}\">",
+                fix.latitude,
+                fix.longitude,
+                p = decimals
+            );
+            if let Some(alt) = fix.altitude {
+                let _ = write!(out, "<ele>{alt:.1}</ele>");
+            }
+            out.push_str("</trkpt>\n");
+        }
+        out.push_str("  </trkseg></trk>\n");
+    }
+    out.push_str("</gpx>\n");
+    out
+}
+
+/// Escapes text for an XML element.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn to_csv(tracks: &[Track], decimals: usize) -> String {
+    let mut out = String::from("source,time,latitude,longitude,altitude,quality,satellites,hdop\n");
+    for track in tracks {
+        for record in &track.fixes {
+            let fix = &record.fix;
+            let _ = writeln!(
+                out,
+                "{},{:.3},{:.p$},{:.p$},{},{},{},{}",
+                csv_text(track.label),
+                record.time,
+                fix.latitude,
+                fix.longitude,
+                fix.altitude.map_or(String::new(), |a| format!("{a:.1}")),
+                fix.quality,
+                fix.satellites_used,
+                fix.hdop.map_or(String::new(), |h| format!("{h:.1}")),
+                p = decimals
+            );
+        }
+    }
+    out
+}
+
+/// One source's graphed samples to export under its label: its fixes and
+/// `(time, satellites in view)` samples.
+pub struct SeriesExport<'a> {
+    pub label: &'a str,
+    pub fixes: Vec<&'a FixRecord>,
+    pub in_view: Vec<(f64, usize)>,
+}
+
+/// Metrics sampled at one timestamp; those not sampled then are blank.
+#[derive(Default)]
+struct SeriesRow {
+    altitude: Option<f64>,
+    speed_knots: Option<f64>,
+    hdop: Option<f64>,
+    satellites_used: Option<u8>,
+    satellites_in_view: Option<usize>,
+}
+
+/// Writes each source's graphed metrics as CSV, one row per timestamp
+/// in UTC. Fixes and satellite counts are sampled at different times, so
+/// a row has blanks for whichever wasn't sampled then; nothing is
+/// interpolated.
+pub fn write_time_series(path: &Path, sources: &[SeriesExport]) -> Result<(), String> {
+    fs::write(path, time_series_csv(sources)).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn time_series_csv(sources: &[SeriesExport]) -> String {
+    let mut out = String::from(
+        "source,time,altitude_m,speed_kn,hdop,satellites_used,satellites_in_view\n",
+    );
+    let blank = |value: Option<String>| value.unwrap_or_default();
+    for source in sources {
+        // Keyed by millisecond, so samples of the same moment share a row
+        let mut rows: BTreeMap<i64, SeriesRow> = BTreeMap::new();
+        let key = |time: f64| (time * 1e3).round() as i64;
+        for record in &source.fixes {
+            let row = rows.entry(key(record.time)).or_default();
+            row.altitude = record.fix.altitude;
+            row.speed_knots = record.speed_knots;
+            row.hdop = record.fix.hdop;
+            row.satellites_used = Some(record.fix.satellites_used);
+        }
+        for &(time, count) in &source.in_view {
+            rows.entry(key(time)).or_default().satellites_in_view = Some(count);
+        }
+        for (millis, row) in rows {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                csv_text(source.label),
+                utc(millis as f64 / 1e3),
+                blank(row.altitude.map(|a| format!("{a:.1}"))),
+                blank(row.speed_knots.map(|s| format!("{s:.2}"))),
+                blank(row.hdop.map(|h| format!("{h:.1}"))),
+                blank(row.satellites_used.map(|n| n.to_string())),
+                blank(row.satellites_in_view.map(|n| n.to_string())),
+            );
+        }
+    }
+    out
+}
+
+/// One source's sky as handed to `write_sky`: its label, current fix if
+/// it has a position, and satellites in view.
+pub struct SkySource<'a> {
+    pub label: &'a str,
+    pub fix: Option<&'a GgaFix>,
+    pub satellites: &'a [Satellite],
+}
+
+#[derive(Serialize)]
+struct SkyJson<'a> {
+    time: String,
+    sources: Vec<SkySourceJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct SkySourceJson<'a> {
+    label: &'a str,
+    position: Option<PositionJson>,
+    satellites: Vec<SatelliteJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct PositionJson {
+    latitude: f64,
+    longitude: f64,
+    altitude: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct SatelliteJson<'a> {
+    prn: &'a str,
+    constellation: &'static str,
+    band: Option<&'a str>,
+    azimuth: f64,
+    elevation: f64,
+    snr: u8,
+    used: bool,
+}
+
+/// Writes the satellites in view, with a UTC timestamp and each source's
+/// position when known, as JSON for plotting elsewhere. Angles are in
+/// degrees and SNR in dB-Hz.
+pub fn write_sky(path: &Path, time: f64, sources: &[SkySource]) -> Result<(), String> {
+    fs::write(path, sky_json(time, sources)).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn sky_json(time: f64, sources: &[SkySource]) -> String {
+    let utc = chrono::DateTime::from_timestamp_millis((time * 1000.0).round() as i64);
+    let sky = SkyJson {
+        time: utc.map(iso_utc).unwrap_or_default(),
+        sources: sources
+            .iter()
+            .map(|source| SkySourceJson {
+                label: source.label,
+                position: source.fix.filter(|f| f.has_position()).map(|f| PositionJson {
+                    latitude: f.latitude,
+                    longitude: f.longitude,
+                    altitude: f.altitude,
+                }),
+                satellites: source
+                    .satellites
+                    .iter()
+                    .map(|sat| SatelliteJson {
+                        prn: &sat.id,
+                        constellation: sat.constellation.name(),
+                        band: sat.band.as_deref(),
+                        azimuth: sat.longitude,
+                        elevation: sat.latitude,
+                        snr: sat.strength,
+                        used: sat.used_in_fix,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&sky).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::satellite::Constellation;
+
+    #[test]
+    fn rounds_coordinates_to_precision() {
+        let record = FixRecord {
+            time: 1.0,
+            fix: GgaFix {
+                latitude: 40.679_512_34,
+                longitude: -74.234_756_78,
+                quality: 1,
+                ..Default::default()
+            },
+            segment_start: false,
+            speed_knots: None,
+        };
+        let tracks = [Track {
+            label: "A",
+            fixes: vec![&record],
+        }];
+
+        assert!(to_csv(&tracks, 5).contains("\"A\",1.000,40.67951,-74.23476,,1,0,"));
+        assert!(to_gpx(&tracks, &[], 7).contains("lat=\"40.6795123\" lon=\"-74.2347568\""));
+        // Stored values keep full precision
+        assert_eq!(record.fix.latitude, 40.679_512_34);
+    }
+
+    #[test]
+    fn time_series_rows_align_by_timestamp() {
+        let record = |time: f64, altitude: f64| FixRecord {
+            time,
+            fix: GgaFix {
+                altitude: Some(altitude),
+                hdop: Some(0.9),
+                satellites_used: 8,
+                ..Default::default()
+            },
+            segment_start: false,
+            speed_knots: Some(1.25),
+        };
+        let fixes = [record(1_700_000_000.0, 512.34), record(1_700_000_001.0, 513.0)];
+        let series = [SeriesExport {
+            label: "A, rover",
+            fixes: fixes.iter().collect(),
+            in_view: vec![(1_700_000_000.0, 11), (1_700_000_000.5, 12)],
+        }];
+        assert_eq!(
+            time_series_csv(&series),
+            "source,time,altitude_m,speed_kn,hdop,satellites_used,satellites_in_view\n\
+             \"A, rover\",2023-11-14T22:13:20.000Z,512.3,1.25,0.9,8,11\n\
+             \"A, rover\",2023-11-14T22:13:20.500Z,,,,,12\n\
+             \"A, rover\",2023-11-14T22:13:21.000Z,513.0,1.25,0.9,8,\n"
+        );
+    }
+
+    #[test]
+    fn log_formats() {
+        let log = [
+            LogEntry {
+                time: 1_700_000_000.25,
+                gps_time: None,
+                line: "$GPGGA,1".to_string(),
+            },
+            LogEntry {
+                time: 1_700_000_001.0,
+                gps_time: Some(1_700_000_000.5),
+                line: "$GPRMC,2".to_string(),
+            },
+        ];
+        assert_eq!(log_raw(&log), "$GPGGA,1\n$GPRMC,2\n");
+        assert_eq!(
+            log_timestamped(&log, LogClock::System),
+            "1700000000.250 $GPGGA,1\n1700000001.000 $GPRMC,2\n"
+        );
+        assert_eq!(
+            log_timestamped(&log, LogClock::Utc),
+            "2023-11-14T22:13:20.250Z $GPGGA,1\n2023-11-14T22:13:21.000Z $GPRMC,2\n"
+        );
+        // Receiver time where known, the system clock before it
+        assert_eq!(
+            log_timestamped(&log, LogClock::Gps),
+            "2023-11-14T22:13:20.250Z $GPGGA,1\n2023-11-14T22:13:20.500Z $GPRMC,2\n"
+        );
+
+        let path = std::env::temp_dir().join("log_formats_test.csv");
+        let track = Track {
+            label: "A",
+            fixes: Vec::new(),
+        };
+        write_log(&path, LogFormat::Csv, LogClock::Utc, &track, &log, 6).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(csv, to_csv(&[track], 6));
+        assert!(LogFormat::default() == LogFormat::Raw);
+    }
+
+    #[test]
+    fn decimation_keeps_whole_cycles() {
+        // 10 Hz: a GGA and a GSV every 0.1 s
+        let mut log = Vec::new();
+        log.push(LogEntry {
+            time: 999.95,
+            gps_time: None,
+            line: "$GPTXT,startup".to_string(),
+        });
+        for n in 0..25 {
+            for kind in ["GGA", "GSV"] {
+                log.push(LogEntry {
+                    time: 1000.0 + f64::from(n) * 0.1,
+                    gps_time: None,
+                    line: format!("$GP{kind},{n}"),
+                });
+            }
+        }
+        log.insert(8, LogEntry {
+            time: 1000.35,
+            gps_time: None,
+            line: "[note] moved antenna to window".to_string(),
+        });
+        let lines = |decimation: Decimation| -> Vec<String> {
+            decimation.log(&log, true).into_iter().map(|e| e.line).collect()
+        };
+        let per_second = Decimation {
+            mode: DecimationMode::PerSecond,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(per_second),
+            [
+                "$GPTXT,startup",
+                "$GPGGA,0",
+                "$GPGSV,0",
+                "[note] moved antenna to window",
+                "$GPGGA,10",
+                "$GPGSV,10",
+                "$GPGGA,20",
+                "$GPGSV,20",
+            ]
+        );
+        let every_5th = Decimation {
+            mode: DecimationMode::EveryNth,
+            every: 5,
+        };
+        assert_eq!(lines(every_5th).len(), 1 + 5 * 2 + 1);
+        assert_eq!(lines(Decimation::default()).len(), log.len());
+        assert_eq!(every_5th.describe().as_deref(), Some("1 in 5 cycles"));
+
+        // A GPS and a combined GGA per fix are one cycle, by their time or,
+        // without one, by the preferred talker
+        for timed in [true, false] {
+            let prefer_combined = !timed;
+            let mut log = Vec::new();
+            for n in 0..10 {
+                for kind in ["GNGGA", "GPGGA", "GPGSV"] {
+                    log.push(LogEntry {
+                        time: 1000.0 + f64::from(n) * 0.1,
+                        gps_time: None,
+                        line: format!("${kind},{}", if timed { n.to_string() } else { "".into() }),
+                    });
+                }
+            }
+            assert_eq!(every_5th.log(&log, prefer_combined).len(), 2 * 3, "{timed}");
+            assert_eq!(per_second.log(&log, prefer_combined).len(), 3, "{timed}");
+        }
+    }
+
+    #[test]
+    fn alarm_rows() {
+        use crate::alarms::AlarmKind;
+        let alarms = [
+            Alarm {
+                kind: AlarmKind::HighHdop,
+                raised: 1_700_000_000.0,
+                cleared: Some(1_700_000_042.5),
+                detail: "HDOP 6.5 > 5.0".to_string(),
+            },
+            Alarm {
+                kind: AlarmKind::NoFix,
+                raised: 1_700_000_100.0,
+                cleared: None,
+                detail: "No fix".to_string(),
+            },
+        ];
+        let csv = alarms_csv(&[("Rover, \"north\"", alarms.iter().collect())]);
+        assert_eq!(
+            csv,
+            "source,alarm,raised,cleared,duration_s,detail\n\
+             \"Rover, \"\"north\"\"\",High HDOP,2023-11-14T22:13:20.000Z,\
+             2023-11-14T22:14:02.500Z,42.5,\"HDOP 6.5 > 5.0\"\n\
+             \"Rover, \"\"north\"\"\",No fix,2023-11-14T22:15:00.000Z,,,\"No fix\"\n"
+        );
+    }
+
+    #[test]
+    fn sky_json_lists_satellites_and_position() {
+        let fix = GgaFix {
+            latitude: 48.1,
+            longitude: 11.5,
+            altitude: Some(520.0),
+            quality: 1,
+            ..Default::default()
+        };
+        let satellites = [Satellite {
+            id: "07".to_string(),
+            latitude: 45.0,
+            longitude: 270.0,
+            strength: 38,
+            constellation: Constellation::Gps,
+            used_in_fix: true,
+            ..Default::default()
+        }];
+        let sources = [
+            SkySource {
+                label: "A",
+                fix: Some(&fix),
+                satellites: &satellites,
+            },
+            SkySource {
+                label: "B",
+                fix: None,
+                satellites: &[],
+            },
+        ];
+        let json: serde_json::Value =
+            serde_json::from_str(&sky_json(1_700_000_000.0, &sources)).unwrap();
+
+        assert_eq!(json["time"], "2023-11-14T22:13:20.000Z");
+        assert_eq!(
+            json["sources"][0]["position"],
+            serde_json::json!({"latitude": 48.1, "longitude": 11.5, "altitude": 520.0})
+        );
+        assert_eq!(
+            json["sources"][0]["satellites"][0],
+            serde_json::json!({
+                "prn": "07", "constellation": "GPS", "band": null,
+                "azimuth": 270.0, "elevation": 45.0, "snr": 38, "used": true
+            })
+        );
+        assert!(json["sources"][1]["position"].is_null());
+    }
+
+    #[test]
+    fn waypoints_precede_tracks() {
+        let wpt = Waypoint {
+            time: 1.0,
+            source: "A".to_string(),
+            name: "Corner <post> & gate".to_string(),
+            latitude: 40.5,
+            longitude: -74.25,
+            altitude: Some(12.0),
+            note: String::new(),
+        };
+        let noted = Waypoint {
+            note: "north side".to_string(),
+            ..wpt.clone()
+        };
+        let gpx = to_gpx(&[], &[wpt, noted], 2);
+
+        assert!(gpx.contains(
+            "<wpt lat=\"40.50\" lon=\"-74.25\"><ele>12.0</ele>\
+             <name>Corner &lt;post&gt; &amp; gate</name></wpt>"
+        ));
+        assert!(gpx.contains("&amp; gate</name><desc>north side</desc></wpt>"));
+    }
+}
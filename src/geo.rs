@@ -0,0 +1,275 @@
+//! Distances and bearings between positions on a spherical Earth.
+//!
+//! Great-circle distance uses the haversine formula
+//! `d = 2R·asin(√(sin²(Δφ/2) + cos φ1·cos φ2·sin²(Δλ/2)))`, the shortest
+//! path. A rhumb line keeps a constant bearing `θ = atan2(Δλ, Δψ)`, with
+//! `Δψ = ln(tan(π/4 + φ2/2) / tan(π/4 + φ1/2))` the stretched latitude
+//! difference on a Mercator chart, and runs `d = R·√(Δφ² + q²·Δλ²)` where
+//! `q = Δφ/Δψ` (or `cos φ` along a parallel). The rhumb line is never
+//! shorter but is what a fixed compass course steers.
+
+use serde::{Deserialize, Serialize};
+
+/// Mean Earth radius (IUGG), metres.
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DistanceMode {
+    #[default]
+    GreatCircle,
+    RhumbLine,
+}
+
+impl DistanceMode {
+    pub const ALL: [DistanceMode; 2] = [DistanceMode::GreatCircle, DistanceMode::RhumbLine];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DistanceMode::GreatCircle => "Great circle",
+            DistanceMode::RhumbLine => "Rhumb line",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            DistanceMode::GreatCircle => "Shortest path over the Earth's surface",
+            DistanceMode::RhumbLine => "Constant-bearing path, as steered on a fixed course",
+        }
+    }
+
+    /// Metres from `from` to `to`, each `[latitude, longitude]` in degrees.
+    pub fn distance_m(&self, from: [f64; 2], to: [f64; 2]) -> f64 {
+        match self {
+            DistanceMode::GreatCircle => great_circle(from, to).0,
+            DistanceMode::RhumbLine => rhumb_line(from, to).0,
+        }
+    }
+
+    /// True bearing from `from` to `to`, degrees: the initial bearing of
+    /// a great circle or the constant one of a rhumb line.
+    pub fn bearing_deg(&self, from: [f64; 2], to: [f64; 2]) -> f64 {
+        match self {
+            DistanceMode::GreatCircle => great_circle(from, to).1,
+            DistanceMode::RhumbLine => rhumb_line(from, to).1,
+        }
+    }
+}
+
+/// How bearings and courses are shown.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BearingUnit {
+    #[default]
+    True,
+    /// Corrected by the RMC magnetic variation, when the receiver sends it
+    Magnetic,
+    /// The nearest of 16 compass points, N, NNE, NE, ...
+    Compass,
+}
+
+const COMPASS_POINTS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+    "NNW",
+];
+
+impl BearingUnit {
+    pub const ALL: [BearingUnit; 3] =
+        [BearingUnit::True, BearingUnit::Magnetic, BearingUnit::Compass];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BearingUnit::True => "Degrees true",
+            BearingUnit::Magnetic => "Degrees magnetic",
+            BearingUnit::Compass => "Compass points",
+        }
+    }
+
+    /// A true bearing in this unit, e.g. "84.4°", "87.5° M" or "E".
+    /// `variation` is degrees east; without it magnetic bearings are
+    /// shown true, marked "T".
+    pub fn format(&self, true_deg: f64, variation: Option<f64>, decimals: usize) -> String {
+        match (self, variation) {
+            (BearingUnit::True, _) => format!("{:.decimals$}°", true_deg.rem_euclid(360.0)),
+            (BearingUnit::Magnetic, Some(variation)) => {
+                format!("{:.decimals$}° M", magnetic_deg(true_deg, variation))
+            }
+            (BearingUnit::Magnetic, None) => {
+                format!("{:.decimals$}° T", true_deg.rem_euclid(360.0))
+            }
+            (BearingUnit::Compass, _) => compass_point(true_deg).to_string(),
+        }
+    }
+}
+
+/// Magnetic bearing from a true one and the variation, degrees east.
+pub fn magnetic_deg(true_deg: f64, variation: f64) -> f64 {
+    (true_deg - variation).rem_euclid(360.0)
+}
+
+/// The nearest of the 16 compass points to a bearing.
+pub fn compass_point(deg: f64) -> &'static str {
+    let sector = (deg.rem_euclid(360.0) / 22.5).round() as usize;
+    COMPASS_POINTS[sector % 16]
+}
+
+/// Where a bearing lies from a heading, degrees clockwise, -180 to 180:
+/// negative to port, positive to starboard.
+pub fn relative_bearing_deg(bearing: f64, heading: f64) -> f64 {
+    let relative = (bearing - heading).rem_euclid(360.0);
+    if relative >= 180.0 {
+        relative - 360.0
+    } else {
+        relative
+    }
+}
+
+/// Moves a smoothed bearing `weight` of the way towards `bearing` along
+/// the shorter arc, so 350° and 10° average to north rather than south.
+pub fn smooth_bearing_deg(smoothed: f64, bearing: f64, weight: f64) -> f64 {
+    (smoothed + weight * relative_bearing_deg(bearing, smoothed)).rem_euclid(360.0)
+}
+
+/// East and north metres of `point` from `origin`, on the plane tangent
+/// at the origin; plenty accurate over the metres a stationary receiver
+/// wanders.
+pub fn local_offset_m(origin: [f64; 2], point: [f64; 2]) -> [f64; 2] {
+    let ((p0, l0), (p1, l1)) = (radians(origin), radians(point));
+    [EARTH_RADIUS_M * delta_lon(l0, l1) * p0.cos(), EARTH_RADIUS_M * (p1 - p0)]
+}
+
+/// Spread of east/north offsets about their mean, metres.
+pub struct Scatter {
+    pub mean: [f64; 2],
+    pub std_east: f64,
+    pub std_north: f64,
+}
+
+impl Scatter {
+    /// Sample statistics of `points`; needs at least two.
+    pub fn of(points: &[[f64; 2]]) -> Option<Scatter> {
+        if points.len() < 2 {
+            return None;
+        }
+        let n = points.len() as f64;
+        let mean = [0, 1].map(|axis| points.iter().map(|p| p[axis]).sum::<f64>() / n);
+        let std = |axis: usize| {
+            let squares: f64 = points.iter().map(|p| (p[axis] - mean[axis]).powi(2)).sum();
+            (squares / (n - 1.0)).sqrt()
+        };
+        Some(Scatter {
+            mean,
+            std_east: std(0),
+            std_north: std(1),
+        })
+    }
+
+    /// Horizontal standard deviation (DRMS), `√(σE² + σN²)`.
+    pub fn horizontal(&self) -> f64 {
+        self.std_east.hypot(self.std_north)
+    }
+}
+
+fn radians([lat, lon]: [f64; 2]) -> (f64, f64) {
+    (lat.to_radians(), lon.to_radians())
+}
+
+/// Longitude difference the short way round, radians.
+fn delta_lon(from: f64, to: f64) -> f64 {
+    let d = to - from;
+    (d + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI
+}
+
+fn great_circle(from: [f64; 2], to: [f64; 2]) -> (f64, f64) {
+    let ((p1, l1), (p2, l2)) = (radians(from), radians(to));
+    let dl = delta_lon(l1, l2);
+    let h = ((p2 - p1) / 2.0).sin().powi(2) + p1.cos() * p2.cos() * (dl / 2.0).sin().powi(2);
+    let distance = 2.0 * EARTH_RADIUS_M * h.sqrt().min(1.0).asin();
+    let bearing = (dl.sin() * p2.cos()).atan2(p1.cos() * p2.sin() - p1.sin() * p2.cos() * dl.cos());
+    (distance, bearing.to_degrees().rem_euclid(360.0))
+}
+
+fn rhumb_line(from: [f64; 2], to: [f64; 2]) -> (f64, f64) {
+    let ((p1, l1), (p2, l2)) = (radians(from), radians(to));
+    let (dp, dl) = (p2 - p1, delta_lon(l1, l2));
+    let quarter = std::f64::consts::FRAC_PI_4;
+    let dpsi = ((quarter + p2 / 2.0).tan() / (quarter + p1 / 2.0).tan()).ln();
+    let q = if dpsi.abs() > 1e-12 { dp / dpsi } else { p1.cos() };
+    let distance = EARTH_RADIUS_M * (dp * dp + q * q * dl * dl).sqrt();
+    (distance, dl.atan2(dpsi).to_degrees().rem_euclid(360.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Land's End to John o' Groats
+    const LANDS_END: [f64; 2] = [50.066_389, -5.714_722];
+    const JOHN_O_GROATS: [f64; 2] = [58.643_889, -3.07];
+
+    #[test]
+    fn great_circle_known_pairs() {
+        let mode = DistanceMode::GreatCircle;
+        assert!((mode.distance_m(LANDS_END, JOHN_O_GROATS) - 968_855.0).abs() < 10.0);
+        assert!((mode.bearing_deg(LANDS_END, JOHN_O_GROATS) - 9.12).abs() < 0.01);
+        // One degree of longitude on the equator
+        assert!((mode.distance_m([0.0, 0.0], [0.0, 1.0]) - 111_195.0).abs() < 1.0);
+        // Across the antimeridian the short way
+        assert!((mode.distance_m([0.0, 179.5], [0.0, -179.5]) - 111_195.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn rhumb_line_known_pairs() {
+        let mode = DistanceMode::RhumbLine;
+        assert!((mode.distance_m(LANDS_END, JOHN_O_GROATS) - 968_912.0).abs() < 10.0);
+        assert!((mode.bearing_deg(LANDS_END, JOHN_O_GROATS) - 10.14).abs() < 0.01);
+        // Along the 60th parallel a rhumb line follows it, the great circle cuts north
+        let (a, b) = ([60.0, 0.0], [60.0, 90.0]);
+        assert!((mode.distance_m(a, b) - 5_003_779.0).abs() < 1.0);
+        assert!((mode.bearing_deg(a, b) - 90.0).abs() < 1e-9);
+        assert!((DistanceMode::GreatCircle.distance_m(a, b) - 4_604_546.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn relative_bearings() {
+        assert_eq!(relative_bearing_deg(90.0, 0.0), 90.0);
+        assert_eq!(relative_bearing_deg(10.0, 350.0), 20.0);
+        assert_eq!(relative_bearing_deg(350.0, 10.0), -20.0);
+        assert_eq!(relative_bearing_deg(180.0, 0.0), -180.0);
+        // John o' Groats seen while heading due east lies off to port
+        let bearing = DistanceMode::GreatCircle.bearing_deg(LANDS_END, JOHN_O_GROATS);
+        assert!((relative_bearing_deg(bearing, 90.0) + 80.88).abs() < 0.01);
+
+        assert_eq!(smooth_bearing_deg(350.0, 10.0, 0.5), 0.0);
+        assert_eq!(smooth_bearing_deg(10.0, 350.0, 0.25), 5.0);
+        assert_eq!(smooth_bearing_deg(90.0, 180.0, 1.0), 180.0);
+    }
+
+    #[test]
+    fn bearing_units() {
+        assert_eq!(BearingUnit::True.format(84.44, Some(-3.1), 1), "84.4°");
+        // West variation: magnetic north lies west, so bearings grow
+        assert_eq!(BearingUnit::Magnetic.format(84.4, Some(-3.1), 1), "87.5° M");
+        assert_eq!(BearingUnit::Magnetic.format(2.0, Some(5.0), 0), "357° M");
+        assert_eq!(BearingUnit::Magnetic.format(84.4, None, 0), "84° T");
+        let points = [0.0, 11.2, 11.3, 45.0, 191.0, 348.7, 348.8, 359.9, -90.0];
+        assert_eq!(
+            points.map(compass_point),
+            ["N", "N", "NNE", "NE", "S", "NNW", "N", "N", "W"]
+        );
+    }
+
+    #[test]
+    fn local_offsets_and_scatter() {
+        let origin = [60.0, 10.0];
+        let [east, north] = local_offset_m(origin, [60.0 + 1e-5, 10.0 + 2e-5]);
+        assert!((north - 1.112).abs() < 1e-3);
+        // A degree of longitude is half as long at 60°
+        assert!((east - 1.112).abs() < 1e-3);
+
+        let points = [[1.0, -1.0], [-1.0, 1.0], [1.0, 1.0], [-1.0, -1.0]];
+        let scatter = Scatter::of(&points).unwrap();
+        assert_eq!(scatter.mean, [0.0, 0.0]);
+        assert!((scatter.std_east - (4.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert!((scatter.horizontal() - (8.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert!(Scatter::of(&points[..1]).is_none());
+    }
+}
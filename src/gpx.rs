@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+
+use crate::Waypoint;
+
+/// Reads the `<trkpt lat=".." lon="..">` points of a GPX file as
+/// `[longitude, latitude]` pairs, ready for plotting.
+pub fn load_track(path: &Path) -> Result<Vec<[f64; 2]>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let points = parse_track(&text);
+
+    if points.is_empty() {
+        return Err(format!("{}: no <trkpt> points found", path.display()));
+    }
+    Ok(points)
+}
+
+fn parse_track(text: &str) -> Vec<[f64; 2]> {
+    text.split("<trkpt")
+        .skip(1)
+        .filter_map(|chunk| {
+            let tag = &chunk[..chunk.find('>')?];
+            let lat = attribute(tag, "lat")?;
+            let lon = attribute(tag, "lon")?;
+            Some([lon, lat])
+        })
+        .collect()
+}
+
+/// Reads the `<wpt>` points of a GPX file, with their name, elevation and
+/// description as the note. Tracks in the same file are skipped.
+pub fn load_waypoints(path: &Path) -> Result<Vec<Waypoint>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let source = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    );
+    let waypoints = parse_waypoints(&text, &source, crate::unix_now());
+
+    if waypoints.is_empty() {
+        return Err(format!("{}: no <wpt> points found", path.display()));
+    }
+    Ok(waypoints)
+}
+
+fn parse_waypoints(text: &str, source: &str, time: f64) -> Vec<Waypoint> {
+    text.split("<wpt")
+        .skip(1)
+        .filter_map(|chunk| {
+            let end = chunk.find('>')?;
+            // A start tag left unclosed runs on into the next tag
+            let tag = Some(&chunk[..end]).filter(|tag| !tag.contains('<'))?;
+            // A self-closing `<wpt .../>` has no elements
+            let body = if tag.ends_with('/') {
+                ""
+            } else {
+                let close = chunk.find("</wpt>").unwrap_or(chunk.len());
+                chunk.get(end + 1..close).unwrap_or_default()
+            };
+            let name = text_of(body, "name").unwrap_or_default();
+            Some(Waypoint {
+                time,
+                source: source.to_string(),
+                name,
+                latitude: attribute(tag, "lat")?,
+                longitude: attribute(tag, "lon")?,
+                altitude: text_of(body, "ele").and_then(|e| e.trim().parse().ok()),
+                note: text_of(body, "desc").or_else(|| text_of(body, "cmt")).unwrap_or_default(),
+            })
+        })
+        .enumerate()
+        .map(|(n, mut wpt)| {
+            if wpt.name.trim().is_empty() {
+                wpt.name = format!("WP{}", n + 1);
+            }
+            wpt
+        })
+        .collect()
+}
+
+/// The unescaped text of the first `<name>..</name>` element in `body`.
+fn text_of(body: &str, name: &str) -> Option<String> {
+    let start = body.find(&format!("<{name}>"))? + name.len() + 2;
+    let end = start + body[start..].find(&format!("</{name}>"))?;
+    Some(unescape(body[start..end].trim()))
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Finds `name="value"` (or single-quoted) in a tag and parses it.
+fn attribute(tag: &str, name: &str) -> Option<f64> {
+    tag.split_whitespace().find_map(|token| {
+        let value = token.strip_prefix(name)?.strip_prefix('=')?;
+        let quote = value.chars().next()?;
+        value[quote.len_utf8()..].split(quote).next()?.parse().ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_track_points_in_order() {
+        let gpx = r#"<gpx><trk><trkseg>
+            <trkpt lat="40.6795" lon="-74.2347"><ele>10</ele></trkpt>
+            <trkpt lon='-74.2350' lat='40.6801'/>
+        </trkseg></trk></gpx>"#;
+
+        assert_eq!(
+            parse_track(gpx),
+            [[-74.2347, 40.6795], [-74.2350, 40.6801]]
+        );
+    }
+
+    #[test]
+    fn reads_waypoints_beside_a_track() {
+        let gpx = r#"<gpx>
+            <wpt lat="40.5" lon="-74.25"><ele>12.0</ele>
+                <name>Corner &lt;post&gt; &amp; gate</name><desc>north side</desc></wpt>
+            <wpt lat='40.6' lon='-74.3'/>
+            <trk><trkseg><trkpt lat="40.6795" lon="-74.2347"/></trkseg></trk>
+        </gpx>"#;
+
+        let waypoints = parse_waypoints(gpx, "field.gpx", 5.0);
+        assert_eq!(waypoints.len(), 2);
+        let corner = &waypoints[0];
+        assert_eq!(corner.name, "Corner <post> & gate");
+        assert_eq!(corner.note, "north side");
+        let position = (corner.latitude, corner.longitude, corner.altitude);
+        assert_eq!(position, (40.5, -74.25, Some(12.0)));
+        assert_eq!((waypoints[1].name.as_str(), waypoints[1].note.as_str()), ("WP2", ""));
+        assert_eq!(waypoints[1].source, "field.gpx");
+        assert_eq!(parse_track(gpx).len(), 1);
+    }
+
+    #[test]
+    fn skips_malformed_waypoints() {
+        assert!(parse_waypoints(r#"<wpt lat="1" lon="2"</wpt>"#, "bad.gpx", 0.0).is_empty());
+        let gpx = r#"<wpt lat="1" lon="2"</wpt>
+            <wpt lat=€1€ lon=€2€/><wpt lat="3" lon="4"/>"#;
+        let waypoints = parse_waypoints(gpx, "bad.gpx", 0.0);
+        let positions: Vec<_> = waypoints.iter().map(|w| (w.latitude, w.longitude)).collect();
+        assert_eq!(positions, [(1.0, 2.0), (3.0, 4.0)]);
+    }
+}
@@ -0,0 +1,173 @@
+//! GSV cycle reassembly. Receivers send their GPGSV, GLGSV, GAGSV, ...
+//! sequences back to back, each numbered against its own total (and from
+//! NMEA 4.10 one sequence per signal band), so each is collected on its
+//! own and only replaces that constellation's satellites once its final
+//! message has arrived.
+
+use crate::parser::GsvMessage;
+use crate::satellite::Satellite;
+
+/// Talker and signal ID of one message sequence.
+type SequenceKey = (String, Option<String>);
+
+#[derive(Clone)]
+struct Sequence {
+    key: SequenceKey,
+    /// Message number expected next
+    next: usize,
+    satellites: Vec<Satellite>,
+}
+
+#[derive(Default, Clone)]
+pub struct GsvAssembler {
+    // Sequences waiting for their remaining messages
+    pending: Vec<Sequence>,
+    // The latest finished set of each sequence, in the order first seen
+    complete: Vec<(SequenceKey, Vec<Satellite>)>,
+    // Sequences finished since the current cycle began
+    fresh: Vec<SequenceKey>,
+}
+
+impl GsvAssembler {
+    /// Adds one message. Returns true once every known sequence has
+    /// finished in the current cycle, so the merged list is up to date.
+    pub fn push(&mut self, message: GsvMessage) -> bool {
+        let key = (message.talker, message.signal);
+        let pending = self
+            .pending
+            .iter()
+            .position(|s| s.key == key)
+            .map(|i| self.pending.swap_remove(i));
+        let mut sequence = if message.number <= 1 {
+            Sequence {
+                key,
+                next: 1,
+                satellites: Vec::new(),
+            }
+        } else {
+            // A missed or repeated message spoils the sequence until its
+            // next first message
+            match pending.filter(|s| s.next == message.number) {
+                Some(sequence) => sequence,
+                None => return false,
+            }
+        };
+
+        sequence.next = message.number + 1;
+        sequence.satellites.extend(message.satellites);
+        if message.number < message.total {
+            self.pending.push(sequence);
+            return false;
+        }
+        self.finish(sequence.key, sequence.satellites);
+        self.complete.iter().all(|(key, _)| self.fresh.contains(key))
+    }
+
+    fn finish(&mut self, key: SequenceKey, satellites: Vec<Satellite>) {
+        // Finishing a second time starts a new cycle; sequences missing
+        // from the one just ended are no longer sent
+        if self.fresh.contains(&key) {
+            let fresh = std::mem::take(&mut self.fresh);
+            self.complete.retain(|(key, _)| fresh.contains(key));
+        }
+        self.fresh.push(key.clone());
+        match self.complete.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, set)) => *set = satellites,
+            None => self.complete.push((key, satellites)),
+        }
+    }
+
+    /// The satellites of every finished sequence.
+    pub fn satellites(&self) -> Vec<Satellite> {
+        self.complete
+            .iter()
+            .flat_map(|(_, set)| set.iter().cloned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{checksum, parse_gsv_message};
+
+    fn push(assembler: &mut GsvAssembler, body: &str) -> bool {
+        let line = format!("${body}*{:02X}", checksum(body));
+        assembler.push(parse_gsv_message(&line).unwrap())
+    }
+
+    fn keys(assembler: &GsvAssembler) -> Vec<String> {
+        assembler.satellites().iter().map(Satellite::key).collect()
+    }
+
+    #[test]
+    fn interleaved_talkers_finish_independently() {
+        let mut gsv = GsvAssembler::default();
+        // GPS sends three messages, GLONASS and Galileo one or two, mixed
+        assert!(!push(
+            &mut gsv,
+            "GPGSV,3,1,09,01,40,083,46,02,17,308,41,03,07,344,39,04,22,228,45"
+        ));
+        assert!(push(&mut gsv, "GLGSV,1,1,02,65,10,045,30,66,80,300,35"));
+        assert_eq!(keys(&gsv), ["GLONASS-65", "GLONASS-66"]);
+        assert!(!push(
+            &mut gsv,
+            "GPGSV,3,2,09,05,40,083,46,06,17,308,41,07,07,344,39,08,22,228,45"
+        ));
+        assert!(!push(
+            &mut gsv,
+            "GAGSV,2,1,05,02,57,061,45,07,33,287,41,08,40,175,39,13,14,320,33"
+        ));
+        assert!(push(&mut gsv, "GPGSV,3,3,09,09,20,100,30"));
+        assert!(push(&mut gsv, "GAGSV,2,2,05,15,10,120,28"));
+
+        let mut all = keys(&gsv);
+        assert_eq!(all.len(), 9 + 2 + 5);
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), 16, "duplicated satellites");
+
+        // The next cycle replaces rather than adds, and is only reported
+        // once every talker has finished again
+        assert!(!push(&mut gsv, "GLGSV,1,1,01,65,10,045,31"));
+        assert!(!push(&mut gsv, "GPGSV,1,1,01,01,40,083,46"));
+        assert!(push(&mut gsv, "GAGSV,1,1,01,02,57,061,45"));
+        assert_eq!(keys(&gsv), ["GLONASS-65", "GPS-01", "Galileo-02"]);
+    }
+
+    #[test]
+    fn signal_bands_are_separate_sequences() {
+        let mut gsv = GsvAssembler::default();
+        assert!(!push(
+            &mut gsv,
+            "GPGSV,2,1,05,01,40,083,46,02,17,308,41,03,07,344,39,04,22,228,45,1"
+        ));
+        assert!(push(&mut gsv, "GPGSV,1,1,02,01,40,083,40,03,07,344,33,8"));
+        assert!(push(&mut gsv, "GPGSV,2,2,05,05,40,083,46,1"));
+        let satellites = gsv.satellites();
+        assert_eq!(satellites.len(), 7);
+        let l5 = satellites.iter().filter(|s| s.band.as_deref() == Some("L5-Q"));
+        assert_eq!(l5.count(), 2);
+    }
+
+    #[test]
+    fn broken_sequences_and_silent_talkers_drop_out() {
+        let mut gsv = GsvAssembler::default();
+        push(&mut gsv, "GPGSV,1,1,01,01,40,083,46");
+        push(&mut gsv, "GLGSV,1,1,01,65,10,045,30");
+
+        // Message 2 of 3 lost: the partial GPS set is never merged
+        assert!(!push(
+            &mut gsv,
+            "GPGSV,3,1,09,01,40,083,46,02,17,308,41,03,07,344,39,04,22,228,45"
+        ));
+        assert!(!push(&mut gsv, "GPGSV,3,3,09,09,20,100,30"));
+        assert_eq!(keys(&gsv), ["GPS-01", "GLONASS-65"]);
+
+        // GLONASS stops: after a full cycle without it its satellites go
+        assert!(!push(&mut gsv, "GPGSV,1,1,01,02,17,308,41"));
+        assert_eq!(keys(&gsv), ["GPS-02", "GLONASS-65"]);
+        assert!(push(&mut gsv, "GPGSV,1,1,01,03,07,344,39"));
+        assert_eq!(keys(&gsv), ["GPS-03"]);
+    }
+}
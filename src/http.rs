@@ -0,0 +1,189 @@
+//! Optional local HTTP endpoint serving the current fix and satellites as
+//! JSON, built with the `http` feature.
+//!
+//! `GET /status` answers:
+//!
+//! ```json
+//! {
+//!   "sources": [{
+//!     "label": "A",
+//!     "connection": "Reading",
+//!     "fix": { "latitude": 40.67, "longitude": -74.23, "quality": 1,
+//!              "satellites_used": 8, "hdop": 0.9, "altitude": 15.0,
+//!              "dgps_age": null, "dgps_station": null },
+//!     "satellites": [{ "prn": "12", "constellation": "GPS", "elevation": 45.0,
+//!                      "azimuth": 120.0, "snr": 38, "used": true }]
+//!   }]
+//! }
+//! ```
+//!
+//! `fix` is `null` until the first GGA sentence arrives.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::parser::GgaFix;
+use crate::{AppState, SourceState};
+
+/// How often the accept loop checks the stop flag.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Serialize)]
+struct Status {
+    sources: Vec<SourceStatus>,
+}
+
+#[derive(Serialize)]
+struct SourceStatus {
+    label: String,
+    connection: &'static str,
+    fix: Option<GgaFix>,
+    satellites: Vec<SatelliteStatus>,
+}
+
+#[derive(Serialize)]
+struct SatelliteStatus {
+    prn: String,
+    constellation: &'static str,
+    elevation: f64,
+    azimuth: f64,
+    snr: u8,
+    used: bool,
+}
+
+impl SourceStatus {
+    fn capture(source: &SourceState) -> Self {
+        Self {
+            label: source.label.clone(),
+            connection: source.connection_state(),
+            fix: source.fix.clone(),
+            satellites: source
+                .satellites
+                .iter()
+                .map(|sat| SatelliteStatus {
+                    prn: sat.id.clone(),
+                    constellation: sat.constellation.name(),
+                    elevation: sat.latitude,
+                    azimuth: sat.longitude,
+                    snr: sat.strength,
+                    used: sat.used_in_fix,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A running server; dropping it stops the accept loop.
+pub struct Server {
+    pub port: u16,
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Binds `127.0.0.1:port` and serves `/status` from `state` on a
+/// background thread.
+pub fn start(state: Arc<Mutex<AppState>>, port: u16) -> Result<Server, String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .and_then(|l| l.set_nonblocking(true).map(|()| l))
+        .map_err(|e| format!("HTTP port {port}: {e}"))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = Arc::clone(&stop);
+
+    thread::spawn(move || {
+        while !stop_flag.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = handle(stream, &state);
+                }
+                Err(_) => thread::sleep(POLL_INTERVAL),
+            }
+        }
+    });
+
+    Ok(Server { port, stop })
+}
+
+fn handle(mut stream: TcpStream, state: &Mutex<AppState>) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let response = if request_line.starts_with("GET /status ") {
+        let body = status_json(state);
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes())
+}
+
+/// Locks the app state only long enough to copy out the source handles,
+/// matching the UI's app-then-source locking order. Like the UI, it reads
+/// on from a lock a panicking thread left poisoned, so the server keeps
+/// answering.
+fn status_json(state: &Mutex<AppState>) -> String {
+    let sources = state.lock().unwrap_or_else(PoisonError::into_inner).sources.clone();
+    let status = Status {
+        sources: sources
+            .iter()
+            .map(|s| SourceStatus::capture(&s.lock().unwrap_or_else(PoisonError::into_inner)))
+            .collect(),
+    };
+    serde_json::to_string(&status).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_lists_each_source() {
+        let state = AppState {
+            sources: vec![Arc::new(Mutex::new(SourceState::new("A")))],
+            ..Default::default()
+        };
+        let body = status_json(&Mutex::new(state));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(json["sources"][0]["label"], "A");
+        assert_eq!(json["sources"][0]["connection"], "Idle");
+        assert!(json["sources"][0]["fix"].is_null());
+        assert_eq!(json["sources"][0]["satellites"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn status_survives_a_poisoned_source() {
+        let source = Arc::new(Mutex::new(SourceState::new("A")));
+        let reader = Arc::clone(&source);
+        let _ = thread::spawn(move || {
+            let _guard = reader.lock().unwrap();
+            panic!("reader panicked mid-update");
+        })
+        .join();
+        let state = AppState {
+            sources: vec![source],
+            ..Default::default()
+        };
+        let body = status_json(&Mutex::new(state));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["sources"][0]["label"], "A");
+    }
+}
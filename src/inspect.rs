@@ -0,0 +1,282 @@
+//! The sentence inspector: pasted sentences run through the same parsers
+//! the readers use, with what each parser made of them, for finding out
+//! why a line doesn't show up as expected.
+
+use std::fmt::Display;
+
+use crate::parser::{self, FieldCountAnomaly, GsvMapping, GsvMessage};
+
+#[derive(Debug, PartialEq)]
+pub enum Checksum {
+    Missing,
+    Valid(u8),
+    Invalid { sent: String, computed: u8 },
+}
+
+impl Checksum {
+    pub fn describe(&self) -> String {
+        match self {
+            Checksum::Missing => "none, so the sentence can't be verified".to_string(),
+            Checksum::Valid(sum) => format!("{sum:02X}, valid"),
+            Checksum::Invalid { sent, computed } => {
+                format!("{sent} sent, {computed:02X} computed")
+            }
+        }
+    }
+}
+
+/// What one parser decoded, as named fields.
+pub struct Decoded {
+    /// e.g. "GGA"
+    pub parser: &'static str,
+    pub fields: Vec<(&'static str, String)>,
+}
+
+pub struct Inspection {
+    pub line: String,
+    /// The address field, e.g. "GPGGA"
+    pub kind: Option<String>,
+    pub checksum: Checksum,
+    pub anomaly: Option<FieldCountAnomaly>,
+    /// One entry per parser that accepted the sentence
+    pub decoded: Vec<Decoded>,
+    /// Why the sentence would not be used, as far as can be told
+    pub problems: Vec<String>,
+}
+
+fn opt<T: Display>(value: Option<T>) -> String {
+    value.map_or("—".to_string(), |v| v.to_string())
+}
+
+fn checksum(line: &str) -> Checksum {
+    let Some((data, sent)) = line.trim().get(1..).and_then(|body| body.rsplit_once('*')) else {
+        return Checksum::Missing;
+    };
+    let computed = parser::checksum(data);
+    if parser::verify_checksum(line) {
+        Checksum::Valid(computed)
+    } else {
+        Checksum::Invalid {
+            sent: sent.to_string(),
+            computed,
+        }
+    }
+}
+
+fn gsv_fields(message: GsvMessage) -> Vec<(&'static str, String)> {
+    let mut fields = vec![
+        ("Talker", message.talker),
+        ("Message", format!("{} of {}", message.number, message.total)),
+        ("Signal ID", opt(message.signal)),
+    ];
+    for sat in &message.satellites {
+        let position = format!(
+            "{} elevation {:.0}°, azimuth {:.0}°, SNR {}",
+            sat.key(),
+            sat.latitude,
+            sat.longitude,
+            sat.strength
+        );
+        fields.push(("Satellite", position));
+    }
+    fields
+}
+
+/// Runs every sentence parser of the live path over `line`. With a
+/// non-standard GSV mapping, GSV is decoded both ways to compare.
+pub fn inspect(line: &str, gsv: &GsvMapping) -> Inspection {
+    let line = line.trim();
+    let kind = parser::sentence_type(line).map(str::to_string);
+    let mut decoded = Vec::new();
+    let mut problems = Vec::new();
+
+    if !line.starts_with(['$', '!']) {
+        problems.push("Not an NMEA sentence: it must start with $ or !".to_string());
+    }
+    let checksum = checksum(line);
+    if let Checksum::Invalid { .. } = checksum {
+        problems.push("Checksum mismatch: dropped when logging valid lines only".to_string());
+    }
+
+    if let Some(fix) = parser::parse_gga(line) {
+        decoded.push(Decoded {
+            parser: "GGA",
+            fields: vec![
+                ("UTC time", opt(fix.utc_time.as_deref())),
+                ("Latitude", format!("{:.7}", fix.latitude)),
+                ("Longitude", format!("{:.7}", fix.longitude)),
+                ("Quality", format!("{} ({})", fix.quality, fix.quality_name())),
+                ("Satellites used", fix.satellites_used.to_string()),
+                ("HDOP", opt(fix.hdop)),
+                ("Altitude", opt(fix.altitude)),
+                ("Geoid separation", opt(fix.geoid_separation)),
+                ("DGPS age", opt(fix.dgps_age)),
+                ("DGPS station", opt(fix.dgps_station.as_deref())),
+            ],
+        });
+        if !fix.has_position() {
+            problems.push("No position: not plotted or counted as a fix".to_string());
+        }
+        if let Some(warning) = &fix.unit_warning {
+            problems.push(format!("The GGA {warning}, so it is dropped"));
+        }
+    }
+    if let Some(rmc) = parser::parse_rmc(line) {
+        decoded.push(Decoded {
+            parser: "RMC",
+            fields: vec![
+                ("Status", if rmc.valid { "A (valid)" } else { "V (warning)" }.to_string()),
+                ("UTC time", opt(rmc.utc_time.as_deref())),
+                ("Date", opt(rmc.date.as_deref())),
+                ("Speed", opt(rmc.speed_knots.map(|k| format!("{k} kn")))),
+                ("Course", opt(rmc.course.map(|c| format!("{c}°")))),
+                ("Variation", opt(rmc.variation.map(|v| format!("{v}°")))),
+                ("Mode", opt(rmc.nav_status.map(|s| s.name()))),
+            ],
+        });
+    }
+    if let Some(message) = parser::parse_gsv_message(line) {
+        decoded.push(Decoded {
+            parser: "GSV",
+            fields: gsv_fields(message),
+        });
+    }
+    let mapped = (!gsv.is_standard()).then(|| parser::parse_gsv_message_with(line, gsv));
+    if let Some(message) = mapped.flatten() {
+        decoded.push(Decoded {
+            parser: "GSV (mapped)",
+            fields: gsv_fields(message),
+        });
+    }
+    if let Some(mode) = parser::parse_gsa_mode(line) {
+        let prns = parser::parse_gsa_prns(line).unwrap_or_default();
+        decoded.push(Decoded {
+            parser: "GSA",
+            fields: vec![
+                ("Fix mode", mode.name().to_string()),
+                ("System", opt(parser::parse_gsa_system(line).map(|c| c.name()))),
+                ("Used PRNs", prns.join(" ")),
+            ],
+        });
+    }
+    if let Some(time) = parser::parse_pubx_time(line) {
+        decoded.push(Decoded {
+            parser: "PUBX,04",
+            fields: vec![
+                ("GPS week", opt(time.week)),
+                ("Time of week", opt(time.tow_secs)),
+                ("Leap seconds", opt(time.leap_seconds)),
+            ],
+        });
+    }
+    if let Some(status) = parser::parse_antenna_status(line) {
+        decoded.push(Decoded {
+            parser: "Antenna",
+            fields: vec![("Status", status.name().to_string())],
+        });
+    }
+    if let Some(info) = parser::parse_pmtk_release(line) {
+        decoded.push(Decoded {
+            parser: "PMTK705",
+            fields: vec![("Model", info.model), ("Firmware", info.firmware)],
+        });
+    }
+
+    if decoded.is_empty() && line.starts_with(['$', '!']) {
+        let known = ["GGA", "RMC", "GSV", "GSA"];
+        let rejected = kind.as_deref().and_then(|k| known.iter().find(|s| k.ends_with(**s)));
+        problems.push(match rejected {
+            Some(sentence) => format!("The {sentence} parser rejected it: a field is malformed"),
+            None => "No parser handles this sentence type; it is only logged".to_string(),
+        });
+    }
+    Inspection {
+        line: line.to_string(),
+        kind,
+        checksum,
+        anomaly: parser::field_count_anomaly(line, gsv),
+        decoded,
+        problems,
+    }
+}
+
+/// The comma-separated fields of a sentence with their indices, the
+/// address first and the checksum left off: `(0, "$PXYZ"), (1, "A"), ...`.
+pub fn indexed_fields(line: &str) -> Vec<(usize, &str)> {
+    let line = line.trim();
+    let body = line.rsplit_once('*').map_or(line, |(body, _)| body);
+    body.split(',').enumerate().collect()
+}
+
+impl Inspection {
+    /// Whether no parser of the live path handles the sentence.
+    pub fn is_unknown(&self) -> bool {
+        self.decoded.is_empty()
+    }
+}
+
+/// Inspects each non-blank line of `text`.
+pub fn inspect_all(text: &str, gsv: &GsvMapping) -> Vec<Inspection> {
+    text.lines().filter(|l| !l.trim().is_empty()).map(|l| inspect(l, gsv)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_and_explains() {
+        let text = "\n$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\n\
+                    $GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48\n\
+                    $GPZDA,201530.00,04,07,2002,00,00*60\n\
+                    GPGGA,1";
+        let inspections = inspect_all(text, &GsvMapping::STANDARD);
+        assert_eq!(inspections.len(), 4);
+
+        let good = &inspections[0];
+        assert_eq!(good.kind.as_deref(), Some("GPGGA"));
+        assert_eq!(good.checksum, Checksum::Valid(0x47));
+        assert!(good.problems.is_empty());
+        assert_eq!(good.decoded[0].parser, "GGA");
+        assert_eq!(good.decoded[0].fields[3].1, "1 (GPS)");
+
+        let corrupted = &inspections[1];
+        assert_eq!(corrupted.checksum.describe(), "48 sent, 47 computed");
+        assert!(corrupted.problems[0].starts_with("Checksum mismatch"));
+
+        assert!(inspections[2].decoded.is_empty());
+        assert_eq!(
+            inspections[2].problems,
+            ["No parser handles this sentence type; it is only logged"]
+        );
+        assert!(inspections[2].is_unknown() && !good.is_unknown());
+        assert_eq!(
+            indexed_fields(&inspections[2].line)[..3],
+            [(0, "$GPZDA"), (1, "201530.00"), (2, "04")]
+        );
+        assert_eq!(indexed_fields("$PXYZ,,7*1F"), [(0, "$PXYZ"), (1, ""), (2, "7")]);
+        assert_eq!(inspections[3].checksum, Checksum::Missing);
+        assert!(inspections[3].problems[0].starts_with("Not an NMEA sentence"));
+    }
+
+    #[test]
+    fn previews_a_gsv_mapping() {
+        let line = "$GPGSV,1,1,01,12,45,38,270";
+        let standard = inspect(line, &GsvMapping::STANDARD);
+        assert_eq!(standard.decoded.len(), 1);
+        let swapped = GsvMapping {
+            azimuth: 3,
+            snr: 2,
+            ..GsvMapping::STANDARD
+        };
+        let preview = inspect(line, &swapped);
+        assert_eq!(preview.decoded[1].parser, "GSV (mapped)");
+        assert_eq!(preview.decoded[1].fields[3].1, "GPS-12 elevation 45°, azimuth 270°, SNR 38");
+
+        // A wider block is only an anomaly under the standard mapping
+        let wide = "$GPGSV,1,1,02,12,45,38,270,X,07,10,12,090,Y";
+        let widened = GsvMapping { block: 5, ..swapped };
+        assert!(inspect(wide, &GsvMapping::STANDARD).anomaly.is_some());
+        assert_eq!(inspect(wide, &widened).anomaly, None);
+    }
+}
@@ -0,0 +1,130 @@
+//! Sentence inventory: a timed scan of which sentence types a receiver
+//! sends and how often, taken from the difference in the per-type
+//! counters over the window. Helps document an unfamiliar receiver's
+//! output without watching the log.
+
+use std::collections::BTreeMap;
+
+/// Length of the scan window, seconds.
+pub const SCAN_SECS: f64 = 10.0;
+
+pub struct InventoryRow {
+    /// e.g. "GPGSV"
+    pub kind: String,
+    /// Sentences received during the window
+    pub count: u64,
+    pub rate_hz: f64,
+}
+
+impl InventoryRow {
+    /// "1.0 Hz", or for slow sentences "every 30 s".
+    pub fn rate_text(&self) -> String {
+        rate_text(self.rate_hz)
+    }
+}
+
+/// Formats a rate the way the inventory rows show it.
+pub fn rate_text(hz: f64) -> String {
+    if hz >= 1.0 {
+        format!("{hz:.1} Hz")
+    } else {
+        format!("every {:.0} s", 1.0 / hz)
+    }
+}
+
+pub struct Inventory {
+    /// Unix time the scan started
+    started: f64,
+    baseline: BTreeMap<String, u64>,
+    /// Length of the finished window; longer than `SCAN_SECS` when the
+    /// scan was not looked at right as it ended
+    secs: f64,
+    /// Set once the window has passed, fastest sentences first
+    pub rows: Option<Vec<InventoryRow>>,
+}
+
+impl Inventory {
+    pub fn start(counts: &BTreeMap<String, u64>, time: f64) -> Self {
+        Self {
+            started: time,
+            baseline: counts.clone(),
+            secs: SCAN_SECS,
+            rows: None,
+        }
+    }
+
+    /// How far through the window, 0 to 1.
+    pub fn progress(&self, time: f64) -> f32 {
+        ((time - self.started) / SCAN_SECS).clamp(0.0, 1.0) as f32
+    }
+
+    /// Completes the inventory once the window has passed, returning
+    /// whether it is done. Counters reset meanwhile count from zero.
+    pub fn update(&mut self, counts: &BTreeMap<String, u64>, time: f64) -> bool {
+        if self.rows.is_some() {
+            return true;
+        }
+        let elapsed = time - self.started;
+        if elapsed < SCAN_SECS {
+            return false;
+        }
+        let mut rows: Vec<InventoryRow> = counts
+            .iter()
+            .filter_map(|(kind, &count)| {
+                let before = self.baseline.get(kind).copied().unwrap_or(0);
+                let count = count.checked_sub(before).unwrap_or(count);
+                (count > 0).then(|| InventoryRow {
+                    kind: kind.clone(),
+                    count,
+                    rate_hz: count as f64 / elapsed,
+                })
+            })
+            .collect();
+        rows.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.kind.cmp(&b.kind)));
+        self.rows = Some(rows);
+        self.secs = elapsed;
+        true
+    }
+
+    /// The finished inventory as plain text, one sentence type a line.
+    pub fn to_text(&self, source: &str) -> String {
+        let mut text = format!("Sentence inventory of source {source} over {:.0} s\n", self.secs);
+        for row in self.rows.iter().flatten() {
+            text += &format!("{:<8} {:>6}  {}\n", row.kind, row.count, row.rate_text());
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(pairs: &[(&str, u64)]) -> BTreeMap<String, u64> {
+        pairs.iter().map(|(kind, count)| (kind.to_string(), *count)).collect()
+    }
+
+    #[test]
+    fn rates_over_the_window() {
+        let mut inventory = Inventory::start(&counts(&[("GPGGA", 50), ("GPGSV", 90)]), 100.0);
+        assert!(!inventory.update(&counts(&[("GPGGA", 55)]), 105.0));
+        assert_eq!(inventory.progress(105.0), 0.5);
+
+        let after = counts(&[("GPGGA", 60), ("GPGSV", 120), ("GPTXT", 1), ("GPZDA", 0)]);
+        assert!(inventory.update(&after, 110.0));
+        let rows = inventory.rows.as_ref().unwrap();
+        let summary: Vec<(&str, u64)> = rows.iter().map(|r| (r.kind.as_str(), r.count)).collect();
+        assert_eq!(summary, [("GPGSV", 30), ("GPGGA", 10), ("GPTXT", 1)]);
+        assert_eq!(rows[0].rate_text(), "3.0 Hz");
+        assert_eq!(rows[2].rate_text(), "every 10 s");
+        assert_eq!(
+            inventory.to_text("A").lines().nth(2),
+            Some("GPGGA        10  1.0 Hz")
+        );
+
+        // A counter reset mid-scan, as a new replay does
+        let mut inventory = Inventory::start(&counts(&[("GPGGA", 50)]), 0.0);
+        inventory.update(&counts(&[("GPGGA", 8)]), 10.0);
+        assert_eq!(inventory.rows.unwrap()[0].count, 8);
+    }
+}
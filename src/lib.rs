@@ -0,0 +1,31 @@
+//! NMEA parsing for GNSS receivers, usable without the GUI. Besides the
+//! sentence parsers and GSV reassembly, [`pipeline`] turns the bytes of
+//! a log file or serial port into [`pipeline::GpsEvent`]s handed to a
+//! callback:
+//!
+//! ```no_run
+//! use rust_nmea_parser::pipeline::{self, GpsEvent};
+//!
+//! let file = std::fs::File::open("drive.nmea").unwrap();
+//! pipeline::run(file, |event| {
+//!     if let GpsEvent::Fix(fix) = event {
+//!         println!("{:.6}, {:.6}", fix.latitude, fix.longitude);
+//!     }
+//! })
+//! .unwrap();
+//! ```
+//!
+//! The `RUST_NMEA_PARSER` binary is a GUI built on these modules.
+
+pub mod commands;
+pub mod coords;
+pub mod dop;
+pub mod geo;
+pub mod gsv;
+pub mod inspect;
+pub mod parser;
+pub mod pipeline;
+pub mod quality;
+pub mod satellite;
+pub mod simulator;
+pub mod ubx;
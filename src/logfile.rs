@@ -0,0 +1,97 @@
+//! Append-only files written on a thread of their own, so the reader and
+//! UI threads feeding them never wait on the disk while holding a lock.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+enum Op {
+    Append(String),
+    /// Hand the buffered text to the OS, so the file can be followed
+    Flush,
+    /// Flush, then sync the file to disk
+    Sync,
+    /// Answer once everything queued before it is written
+    #[cfg(test)]
+    Barrier(Sender<()>),
+}
+
+/// `text` with everything but ASCII letters and digits replaced by `_`,
+/// so a source label or port name can go in a file name on any system.
+pub fn file_name_part(text: &str) -> String {
+    text.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+pub struct LogFile {
+    pub path: PathBuf,
+    ops: Sender<Op>,
+    // The first write that failed; the thread stops there
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl LogFile {
+    /// Creates the file, so a bad directory is reported at once, and
+    /// starts the thread writing it.
+    pub fn create(path: PathBuf) -> Result<Self, String> {
+        let file = File::create(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let (ops, rx) = mpsc::channel();
+        let error = Arc::new(Mutex::new(None));
+        let thread_error = Arc::clone(&error);
+        let name = path.display().to_string();
+
+        thread::spawn(move || {
+            let mut file = BufWriter::new(file);
+            for op in rx {
+                let written = match op {
+                    Op::Append(text) => file.write_all(text.as_bytes()),
+                    Op::Flush => file.flush(),
+                    Op::Sync => file.flush().and_then(|()| file.get_ref().sync_data()),
+                    #[cfg(test)]
+                    Op::Barrier(done) => {
+                        let _ = done.send(());
+                        Ok(())
+                    }
+                };
+                if let Err(e) = written {
+                    *thread_error.lock().unwrap() = Some(format!("{name}: {e}"));
+                    return;
+                }
+            }
+            let _ = file.flush();
+        });
+
+        Ok(Self { path, ops, error })
+    }
+
+    /// Queues text to be written, failing if an earlier write did.
+    pub fn append(&self, text: String) -> Result<(), String> {
+        self.queue(Op::Append(text))
+    }
+
+    pub fn flush(&self) -> Result<(), String> {
+        self.queue(Op::Flush)
+    }
+
+    pub fn sync(&self) -> Result<(), String> {
+        self.queue(Op::Sync)
+    }
+
+    /// Blocks until everything queued so far has been written.
+    #[cfg(test)]
+    pub fn wait(&self) {
+        let (done, written) = mpsc::channel();
+        if self.ops.send(Op::Barrier(done)).is_ok() {
+            let _ = written.recv();
+        }
+    }
+
+    fn queue(&self, op: Op) -> Result<(), String> {
+        if let Some(e) = self.error.lock().unwrap().clone() {
+            return Err(e);
+        }
+        self.ops.send(op).map_err(|_| format!("{}: the writer stopped", self.path.display()))
+    }
+}
@@ -1,190 +1,7481 @@
+mod alarms;
+mod autosave;
+mod autostart;
+mod bugreport;
+mod buffers;
+mod conditions;
+mod diagnostics;
+mod events;
+mod export;
+mod gpx;
+#[cfg(feature = "http")]
+mod http;
+mod inventory;
+mod logfile;
+mod palette;
+mod profiles;
+mod rate;
+mod records;
+mod reader;
+mod repeater;
+mod satlog;
+mod score;
+mod series;
+mod session;
+mod settings;
+mod survey;
+mod tiles;
+
+// The parsers live in the library so other programs can use them too
+use rust_nmea_parser::{
+    commands, coords, dop, geo, inspect, parser, pipeline, quality, satellite, simulator, ubx,
+};
 use eframe::egui;
 use serialport::available_ports;
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use egui_plot::{
+    Bar, BarChart, GridInput, GridMark, HLine, Line, LineStyle, MarkerShape, Plot, PlotBounds,
+    PlotImage, PlotPoint, PlotPoints, Points, Polygon, Text, VLine,
+};
+use serde::{Deserialize, Serialize};
+
+use commands::{DynamicModel, RatePreset, ReceiverFamily, ResetKind};
+use alarms::{AlarmMonitor, AlarmThresholds, NotifyPolicy};
+use autosave::AutosaveLog;
+use autostart::{AutoStartAction, AutoStarter, PortEntry};
+use buffers::{BufferCaps, BufferUsage};
+use events::{CapturedEvent, ClockWatch, EventRecorder, EventTriggers};
+use export::{Decimation, DecimationMode, LogClock, LogFormat};
+use geo::{BearingUnit, DistanceMode};
+use inventory::Inventory;
+use parser::{
+    fix_quality_name, AntennaStatus, DeviceInfo, FixMode, GgaFix, GpsTime, GsvMapping, NavStatus,
+    RmcData,
+};
+use palette::Palette;
+use profiles::Profile;
+use pipeline::Decoder;
+use quality::QualityTracker;
+use rate::RateMeter;
+use records::SessionRecords;
+use repeater::{Advert, Repeater};
+use reader::{
+    PortFault, PortWait, Protocol, ReaderHealth, ReplayProgress, TalkerConflicts, WaitStep,
+};
+use satellite::{Constellation, Satellite, SnrHistogram, SNR_BIN_DB};
+use satlog::SatelliteLog;
+use series::TimeSeries;
+use session::{Session, SourceSession};
+use simulator::SimConfig;
+use survey::{Survey, SurveyConfig, SurveyStop};
+use settings::{
+    AltitudeUnit, ElevationMask, FlowControl, MaskSector, PanelCorner, ReplayAlign, Settings,
+    SkyPanelView, SkyProjection, SnrDisplay, SnrUnit,
+};
+
+/// How many SNR samples are kept per satellite for the detail sparkline.
+const SNR_HISTORY_LEN: usize = 120;
+/// Breadcrumb trail length, in GGA fixes.
+const TRACK_LEN: usize = 10_000;
+/// Full fixes kept to scrub back through, ten minutes at 1 Hz.
+const SNAPSHOT_HISTORY_LEN: usize = 600;
+/// Sky trail points kept per satellite, one per whole-degree move: more
+/// than a pass from horizon to horizon.
+const SKY_TRAIL_LEN: usize = 360;
+/// How long a newly acquired satellite is highlighted.
+const ACQUIRE_FLASH: Duration = Duration::from_secs(2);
+/// How long a lost satellite fades out before it is removed from the map.
+const LOSS_FADE: Duration = Duration::from_millis(1500);
+/// Azimuth resolution of the elevation mask shading.
+const MASK_STEP_DEG: usize = 5;
+/// Weight of the newest sample in the smoothed SNR average.
+const SNR_SMOOTHING: f32 = 0.3;
+/// Recent fixes inspected for a falling used-satellite count.
+const FIX_TREND_LEN: usize = 10;
+/// Half the visible width of the sky map when zoomed onto a satellite.
+const SKY_FOCUS_HALF_WIDTH: f64 = 0.35;
+/// Starts the log lines holding user notes.
+const NOTE_PREFIX: &str = "[note] ";
+/// Status warnings and notices.
+const WARNING_COLOR: egui::Color32 = egui::Color32::from_rgb(240, 160, 60);
+/// Sky snapshots kept at once, one ghost color each.
+const MAX_SNAPSHOTS: usize = 2;
+/// How long a receiver has to answer the identification query.
+const DEVICE_ID_TIMEOUT: Duration = Duration::from_secs(3);
+/// How long NMEA has to start after asking a UBX-only receiver for it,
+/// and how long the confirmation shows once it has.
+const NMEA_ENABLE_WAIT: Duration = Duration::from_secs(5);
+const NMEA_ENABLE_NOTICE: Duration = Duration::from_secs(30);
+/// Default for the lines kept in each source's NMEA log.
+const LOG_LEN: usize = 500;
+/// Upper bound of the log lines setting.
+const MAX_LOG_LINES: usize = 20_000;
+/// Span of the satellites-in-view history graph.
+const VIEW_HISTORY_SECS: f64 = 30.0 * 60.0;
+/// Repaint interval while any source is active, for the clocks and
+/// timeouts shown; new data wakes the UI sooner.
+const LIVE_REPAINT: Duration = Duration::from_secs(1);
+/// Span over which the UI frame rate is measured.
+const FRAME_RATE_WINDOW: Duration = Duration::from_secs(5);
+/// Repaints are spaced at least this many frame times apart, so a UI that
+/// gets expensive to draw slows down instead of saturating a core.
+const FRAME_BACKOFF: f32 = 4.0;
+/// Weight of the newest frame in the smoothed frame time.
+const FRAME_TIME_SMOOTHING: f32 = 0.1;
+/// Least time between writes of the recovery file, which holds the whole
+/// session rather than the few lines the logs gained since their last sync.
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(30);
+/// Below this speed, knots, the RMC course is noise: the smoothed course
+/// holds its last value and the go-to arrow points relative to north.
+const MOVING_KNOTS: f64 = 0.5;
+/// Weight of the newest RMC course in the smoothed course.
+const COURSE_SMOOTHING: f64 = 0.2;
+/// The heading-up sky map only turns once the smoothed course has moved
+/// this far from its rotation, degrees.
+const HEADING_UP_HYSTERESIS_DEG: f64 = 3.0;
+/// Switches between the full interface and the big readout.
+const BIG_READOUT_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::B);
+/// Width of a big readout character relative to its height.
+const BIG_READOUT_CHAR_WIDTH: f32 = 0.62;
+/// Side of the floating sky panel's map and bar chart.
+const SKY_PANEL_SIZE: f32 = 300.0;
+/// Dashboard width from which the stats and bars sit beside the sky map
+/// instead of under it.
+const DASHBOARD_WIDE: f32 = 560.0;
+/// Upper bound on simultaneously configured data sources.
+const MAX_SOURCES: usize = 2;
+/// Band group for satellites from receivers that report no signal ID.
+const SINGLE_BAND: &str = "Single band";
+/// Baud rates offered in the source selector.
+const BAUD_RATES: [u32; 8] = [4800, 9600, 19200, 38400, 57600, 115200, 230400, 460800];
+/// Sky map marker per source, so overlapping sources stay distinguishable.
+const SOURCE_MARKERS: [MarkerShape; MAX_SOURCES] = [MarkerShape::Circle, MarkerShape::Square];
+
+/// Seconds since the Unix epoch, used to timestamp log lines and fixes.
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct LogEntry {
+    time: f64,
+    /// The receiver's UTC at arrival, once a sentence has reported it
+    #[serde(default)]
+    gps_time: Option<f64>,
+    line: String,
+}
 
-use egui_plot::{Plot, PlotPoints, Points, Text, Line};
+#[derive(Clone, Serialize, Deserialize)]
+struct FixRecord {
+    time: f64,
+    fix: GgaFix,
+    /// First fix after a loss of fix, where the track has a gap
+    #[serde(default)]
+    segment_start: bool,
+    /// Ground speed from the latest valid RMC
+    #[serde(default)]
+    speed_knots: Option<f64>,
+}
+
+/// A complete fix as it was shown: the position, its DOP, and the
+/// satellites in view and in use at the time.
+#[derive(Clone)]
+struct FixSnapshot {
+    time: f64,
+    fix: GgaFix,
+    fix_mode: Option<FixMode>,
+    satellites: Vec<Satellite>,
+}
+
+/// Where the position scatter measures from, and the time from which
+/// fixes count.
+#[derive(Clone, Copy)]
+struct EnuReference {
+    since: f64,
+    position: [f64; 2],
+    /// Height Up is measured from; the first counted fix's when unknown
+    altitude: Option<f64>,
+    /// Typed in rather than taken from a fix
+    typed: bool,
+}
+
+/// A fix in metres east and north of the scatter reference, and up from
+/// it when the fix has an altitude.
+struct EnuPoint {
+    time: f64,
+    east_north: [f64; 2],
+    up: Option<f64>,
+}
 
-#[derive(Default, Clone)]
-struct Satellite {
-    id: String,
+/// A user-marked point on the track, exported as a GPX `<wpt>`.
+#[derive(Clone, Serialize, Deserialize)]
+struct Waypoint {
+    time: f64,
+    /// Source label the position was taken from
+    source: String,
+    name: String,
     latitude: f64,
     longitude: f64,
-    strength: u8,
+    altitude: Option<f64>,
+    /// Free text, a GPX `<desc>`
+    #[serde(default)]
+    note: String,
 }
 
-#[derive(Default)]
-struct AppState {
-    ports: Vec<String>,
-    selected_port: Option<String>,
-    satellites: Vec<Satellite>,
-    is_reading: bool,
+/// Satellites in view at the end of one GSV cycle, with how many were
+/// acquired and lost since the previous cycle.
+#[derive(Clone)]
+struct ViewSample {
+    count: usize,
+    acquired: usize,
+    lost: usize,
+}
 
-    // 🔵 NEW: live NMEA data buffer
-    nmea_log: Vec<String>,
+/// Where a source's data comes from.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum SourceKind {
+    #[default]
+    Serial,
+    Replay,
+    Tail,
+    Simulator,
+    /// Another instance's repeater, read over TCP
+    Tcp,
 }
 
-pub struct MyApp {
-    state: Arc<Mutex<AppState>>,
+impl SourceKind {
+    fn name(&self) -> &'static str {
+        match self {
+            SourceKind::Serial => "Serial",
+            SourceKind::Replay => "Replay",
+            SourceKind::Tail => "Tail",
+            SourceKind::Simulator => "Simulator",
+            SourceKind::Tcp => "TCP",
+        }
+    }
 }
 
-impl Default for MyApp {
-    fn default() -> Self {
-        let ports = available_ports()
-            .map(|ps| ps.into_iter().map(|p| p.port_name).collect())
-            .unwrap_or_default();
+/// The settings a serial reader opens its port with; changing any of
+/// them only takes effect when it is restarted.
+#[derive(Clone, Copy, PartialEq)]
+struct SerialParams {
+    baud_rate: u32,
+    flow_control: FlowControl,
+    read_timeout_ms: u64,
+    skip_leading_garbage: bool,
+}
 
+impl SerialParams {
+    fn new(baud_rate: u32, settings: &Settings) -> Self {
         Self {
-            state: Arc::new(Mutex::new(AppState {
-                ports,
-                ..Default::default()
-            })),
+            baud_rate,
+            flow_control: settings.flow_control,
+            read_timeout_ms: settings.read_timeout_ms,
+            skip_leading_garbage: settings.skip_leading_garbage,
         }
     }
 }
 
-// =====================================================================
-// Satellite Map Drawing Method
-// =====================================================================
-impl MyApp {
-    fn draw_satellite_map(&self, ui: &mut egui::Ui, sats: &[Satellite]) {
-        Plot::new("satellite_map")
-            .width(300.0)
-            .height(300.0)
-            .view_aspect(1.0)
-            .show(ui, |plot_ui| {
-                // Draw outline circle
-                let circle: PlotPoints = (0..360)
-                    .map(|deg| {
-                        let rad = (deg as f64).to_radians();
-                        [rad.cos(), rad.sin()]
-                    })
-                    .collect::<Vec<_>>()
-                    .into();
+/// Where a source's reader is in its lifecycle. Buttons, labels and the
+/// reader threads all go by this rather than separate flags.
+#[derive(Default, Clone, PartialEq, Debug)]
+enum AppMode {
+    #[default]
+    Idle,
+    /// Between Start and the port's first read or failure to open
+    Connecting,
+    Reading,
+    /// Connected, with incoming data ignored (or a replay held) until resumed
+    Paused,
+    /// The port was lost and the watchdog is bringing it back
+    Reconnecting,
+    /// Stopped by a failure that retrying automatically can't fix
+    Error(String),
+    /// Stop requested; the thread exits after its current read
+    Stopping,
+}
 
-                plot_ui.line(Line::new(circle));
+impl AppMode {
+    /// Whether a reader owns the source, so another may not be started.
+    fn is_active(&self) -> bool {
+        matches!(
+            self,
+            AppMode::Connecting | AppMode::Reading | AppMode::Paused | AppMode::Reconnecting
+        )
+    }
 
-                // Draw satellites
-                for sat in sats {
-                    let az = sat.longitude.to_radians();
-                    let el = sat.latitude.to_radians();
+    /// Whether nothing holds the source, so a reader may be started. A
+    /// stopping thread still owns the port until it exits.
+    fn is_idle(&self) -> bool {
+        !self.is_active() && *self != AppMode::Stopping
+    }
+}
+
+/// Everything belonging to one receiver: its port settings, the reader
+/// thread that feeds it and the data parsed from it.
+#[derive(Default)]
+struct SourceState {
+    // What the user calls the source, e.g. "Rover"; "A" or "B" until
+    // renamed. The text being typed for it
+    label: String,
+    label_text: String,
+    kind: SourceKind,
+    selected_port: Option<String>,
+    baud_rate: u32,
+    satellites: Vec<Satellite>,
+    mode: AppMode,
+
+    // 🔵 NEW: live NMEA data buffer. It and the fix history are shared
+    // with the frame's view copies rather than cloned into them, and
+    // copied only if the reader adds to them while a frame still draws
+    nmea_log: Arc<Vec<LogEntry>>,
+    // Copied from the settings each frame; lines dropped to stay within it
+    log_limit: usize,
+    // Caps on the other session buffers, likewise
+    caps: BufferCaps,
+    log_dropped: u64,
+    // User notes as logged, kept whatever the log cap drops
+    notes: Vec<LogEntry>,
+    // Drop checksum failures instead of logging them
+    log_valid_only: bool,
+    // Turns the sentences into fixes and satellites, choosing between
+    // GP-only and combined GN solutions; its filter and GSV mapping are
+    // copied from the settings each frame for the reader to consult
+    decoder: Decoder,
+    talker_conflicts: TalkerConflicts,
+    discarded_lines: u64,
+    // Arrival times of the sentences received in the last second
+    recent_sentences: VecDeque<Instant>,
+    // Sentences seen per type, e.g. "GPGSV", and those of them with a
+    // field count no version of the type has
+    sentence_counts: BTreeMap<String, u64>,
+    field_anomalies: BTreeMap<String, u64>,
+    // GSV and GSA counted without and with the NMEA 4.10 signal/system ID
+    nmea_versions: BTreeMap<&'static str, [u64; 2]>,
+
+    // File replay in place of a serial port
+    replay_path: String,
+    replay: Option<ReplayProgress>,
+    replay_error: Option<String>,
+    // Pace the replay by the file's timestamps
+    replay_timed: bool,
+    // Seconds the replay starts late (or, negative, skips), and whether it
+    // takes part when both sources replay together
+    replay_offset_secs: f64,
+    // The log as captured before "Replay buffer" fed it back through the
+    // parser, kept until restored
+    captured_log: Option<Arc<Vec<LogEntry>>>,
+    in_comparison: bool,
+    // Log file written by another program, followed as it grows
+    tail_path: String,
+    tail_error: Option<String>,
+
+    sim_config: SimConfig,
+
+    // Fix mode from GSA; the decoder keeps the satellites each system's
+    // latest GSA lists as used
+    fix_mode: Option<FixMode>,
+    snr_history: HashMap<String, TimeSeries<u8>>,
+    // Copied from the settings each frame; always visible, and their SNR
+    // history outlives a spell out of view
+    favorites: BTreeSet<String>,
+    // Exponential moving average of each satellite's SNR
+    snr_smoothed: HashMap<String, f32>,
+
+    // When each satellite key was first/last seen, and recently lost
+    // satellites with the moment they disappeared
+    first_seen: HashMap<String, Instant>,
+    last_seen: HashMap<String, Instant>,
+    // When the last GSV cycle completed
+    last_gsv: Option<Instant>,
+    lost_satellites: Vec<(Satellite, Instant)>,
+    // Satellites missing from the latest cycles but still within the grace
+    // period, drawn dimmed, with how many cycles they have missed
+    held_satellites: HashMap<String, u32>,
+    // Copied from the settings each frame; zero removes satellites at once
+    loss_grace_secs: f32,
+    loss_grace_cycles: u32,
+    // Satellite keys in the order they were acquired, for stable table
+    // rows; lost satellites stay while they fade
+    row_order: Vec<String>,
+    // Keys of the table's rows as last drawn, filtered and sorted, which
+    // the arrow keys step through; and whether to scroll to the selection
+    list_order: Vec<String>,
+    scroll_to_selected: bool,
+    // Satellites-in-view count per GSV cycle over the last half hour
+    view_history: TimeSeries<ViewSample>,
+    // Azimuth and elevation each satellite has passed through, for the
+    // sky map's trails
+    sky_trails: HashMap<String, TimeSeries<[f64; 2]>>,
+    // Per-satellite CSV time series, open while logging is on and the
+    // source is reading
+    sat_log: Option<SatelliteLog>,
+    sat_log_error: Option<String>,
+    // Every received sentence and user note, likewise
+    autosave: Option<AutosaveLog>,
+    autosave_error: Option<String>,
+
+    // Latest GGA position and the timestamped fixes forming the trail
+    fix: Option<GgaFix>,
+    // Latest RMC status, speed and course
+    rmc: Option<RmcData>,
+    // RMC course smoothed over the fixes made while moving, and the
+    // rotation of the heading-up sky map that follows it
+    smoothed_course: Option<f64>,
+    heading_up_rotation: Option<f64>,
+    // GPS week/TOW/leap seconds, only from receivers that report them
+    gps_time: Option<GpsTime>,
+    // Receiver UTC minus the system clock, from the latest sentence
+    // carrying the time
+    gps_clock_offset: Option<f64>,
+    // Antenna supervisor state, from receivers that report it
+    antenna: Option<AntennaStatus>,
+    // Receiver identity, and when the query for it was sent on connect
+    device: Option<DeviceInfo>,
+    device_query: Option<Instant>,
+    // u-blox dynamic model as last reported, and the one picked to set
+    dynamic_model: Option<DynamicModel>,
+    dynamic_model_choice: DynamicModel,
+    fix_history: Arc<VecDeque<FixRecord>>,
+    // Altitude (m), speed (kn) and HDOP of the positioned fixes, for the
+    // fix graphs, as many as the track keeps
+    altitude_history: TimeSeries,
+    speed_history: TimeSeries,
+    hdop_history: TimeSeries,
+    // Recent full fixes, and the time of the one being looked back at
+    // instead of the live fix
+    snapshots: VecDeque<FixSnapshot>,
+    scrub: Option<f64>,
+    // Position scatter reference set by the user; otherwise the oldest
+    // fix kept. A coordinate typed for it, and why it was rejected
+    scatter_reference: Option<EnuReference>,
+    enu_reference_text: String,
+    enu_height_text: String,
+    enu_reference_error: Option<String>,
+    // Freeze-frame captures of the moments a trigger condition set in
+    events: EventRecorder,
+    // Threshold violations raised and cleared over the session
+    alarms: AlarmMonitor,
+    // Debounced GGA quality changes, e.g. GPS to RTK
+    quality: QualityTracker,
+    // The receiver's time of day, watched for jumps, and the time from
+    // which the time-series graphs draw after one reset them
+    clock_watch: ClockWatch,
+    graphs_since: Option<f64>,
+    // GGA/RMC epochs over the last few seconds, and the rate of the last
+    // preset sent to the receiver
+    fix_rate: RateMeter,
+    expected_rate_hz: Option<f64>,
+    // Extremes since the session started
+    records: SessionRecords,
+    // Survey-in averaging, running or finished with its result
+    survey: Option<Survey>,
 
-                    let x = el.cos() * az.cos();
-                    let y = el.cos() * az.sin();
+    // Channel to the serial reader's writer, open while the port is, and
+    // the last command that could not be sent
+    commands: Option<Sender<Vec<u8>>>,
+    command_error: Option<String>,
+    receiver_family: ReceiverFamily,
+    command_text: String,
+    // Where the reader hands the bytes it reads for the repeater, when it
+    // repeats this source
+    repeater: Option<Sender<Vec<u8>>>,
+    // Connection profile name being saved or last loaded, the file
+    // profiles are shared through, and how the last load or save went
+    profile_name: String,
+    profile_file: String,
+    profile_status: Option<Result<String, String>>,
+    // Factory reset asked for, awaiting confirmation
+    confirm_factory_reset: bool,
+    // A reset went out; the reader skips the restart noise that follows
+    resync: bool,
+    // Time to first fix: when timing started and what from, and the
+    // result once a position arrives
+    ttff_start: Option<(Instant, &'static str)>,
+    ttff: Option<(Duration, &'static str)>,
+    // Receiver seconds of day when timing started, if its clock is known;
+    // a fix stamped earlier was computed before the reset
+    ttff_after: Option<f64>,
 
-                    plot_ui.points(Points::new(vec![[x, y]]).radius(3.0));
-                    plot_ui.text(Text::new([x, y].into(), sat.id.clone()));
+    // Satellite table rows and SNR text held while the pointer is over it
+    frozen_rows: Option<Vec<(Satellite, String)>>,
+    // Satellite keys whose table rows show the GSV sentences behind them
+    expanded_rows: HashSet<String>,
+
+    // Protocol sniffed from a serial device's first bytes, and when NMEA
+    // output was last asked for because it was UBX
+    protocol: Option<Protocol>,
+    nmea_enable_sent: Option<Instant>,
+    // Mostly-binary chunks kept out of the NMEA log, optionally hex dumped
+    binary_chunks: u64,
+    binary_as_hex: bool,
+
+    // Reader thread control, used by the watchdog to replace a hung reader
+    health: ReaderHealth,
+    reader_abort: Option<Arc<AtomicBool>>,
+    last_data: Option<Instant>,
+    // Set while a replaced reader is still letting go of the port
+    respawn_pending: bool,
+    // Retries of a port another program holds, and the TCP port another
+    // instance advertises repeating it on
+    port_wait: Option<PortWait>,
+    handoff_port: Option<u16>,
+    // What the serial reader was started with, and whether it was last
+    // restarted to apply changes to them
+    serial_params: Option<SerialParams>,
+    // Set when the port refused those and was opened at 9600 8N1 instead
+    open_fallback: Option<String>,
+    applying_settings: bool,
+    // Lets the reader wake the UI when data arrives
+    repaint: Option<egui::Context>,
+}
+
+impl SourceState {
+    fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            baud_rate: 9600,
+            log_limit: LOG_LEN,
+            in_comparison: true,
+            ..Default::default()
+        }
+    }
+
+    fn push_log(&mut self, line: String) {
+        self.events.push_line(&line);
+        if line.starts_with(['$', '!']) || line.starts_with(NOTE_PREFIX) {
+            if let Some(log) = self.autosave.as_mut() {
+                if let Err(e) = log.append(&line) {
+                    self.autosave = None;
+                    self.autosave_error = Some(e);
                 }
-            });
+            }
+        }
+        let time = unix_now();
+        Arc::make_mut(&mut self.nmea_log).push(LogEntry {
+            time,
+            gps_time: self.gps_clock_offset.map(|offset| time + offset),
+            line,
+        });
+
+        self.trim_log();
     }
-}
 
-// =====================================================================
-// Main App UI
-// =====================================================================
-impl eframe::App for MyApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let mut state = self.state.lock().unwrap();
+    /// Holds the buffers to new caps, dropping what no longer fits.
+    fn set_caps(&mut self, caps: BufferCaps) {
+        self.caps = caps;
+        self.log_limit = caps.log_lines;
+        self.trim_log();
+        let excess = self.fix_history.len().saturating_sub(caps.track_fixes);
+        if excess > 0 {
+            Arc::make_mut(&mut self.fix_history).drain(..excess);
+        }
+        let excess = self.snapshots.len().saturating_sub(caps.snapshots);
+        self.snapshots.drain(..excess);
+        for history in self.snr_history.values_mut() {
+            history.set_cap(caps.snr_samples);
+        }
+        let graphs = [&mut self.altitude_history, &mut self.speed_history, &mut self.hdop_history];
+        for series in graphs {
+            series.set_cap(caps.track_fixes);
+        }
+        for trail in self.sky_trails.values_mut() {
+            trail.set_cap(caps.trail_points);
+        }
+        self.view_history.trim_to_span(caps.view_history_secs());
+    }
+
+    /// Rough bytes held by the capped buffers.
+    fn measure_buffers(&self) -> BufferUsage {
+        use std::mem::size_of;
+        let fix_bytes = |fix: &GgaFix| {
+            fix.sentence.capacity() + fix.utc_time.as_ref().map_or(0, String::capacity)
+        };
+        let satellite_bytes = |sat: &Satellite| {
+            size_of::<Satellite>()
+                + sat.id.capacity()
+                + sat.sentence.capacity()
+                + sat.band.as_ref().map_or(0, String::capacity)
+        };
+        BufferUsage {
+            log: self
+                .nmea_log
+                .iter()
+                .map(|e| size_of::<LogEntry>() + e.line.capacity())
+                .sum(),
+            track: self
+                .fix_history
+                .iter()
+                .map(|r| size_of::<FixRecord>() + fix_bytes(&r.fix))
+                .sum::<usize>()
+                + [&self.altitude_history, &self.speed_history, &self.hdop_history]
+                    .iter()
+                    .map(|series| series.len() * size_of::<(f64, f64)>())
+                    .sum::<usize>(),
+            snapshots: self
+                .snapshots
+                .iter()
+                .map(|s| {
+                    size_of::<FixSnapshot>()
+                        + fix_bytes(&s.fix)
+                        + s.satellites.iter().map(satellite_bytes).sum::<usize>()
+                })
+                .sum(),
+            snr: self
+                .snr_history
+                .iter()
+                .map(|(key, history)| {
+                    size_of::<TimeSeries<u8>>()
+                        + key.capacity()
+                        + history.len() * size_of::<(f64, u8)>()
+                })
+                .sum(),
+            view_history: self.view_history.len() * size_of::<(f64, ViewSample)>(),
+            trails: self
+                .sky_trails
+                .iter()
+                .map(|(key, trail)| {
+                    size_of::<TimeSeries<[f64; 2]>>()
+                        + key.capacity()
+                        + trail.len() * size_of::<(f64, [f64; 2])>()
+                })
+                .sum(),
+        }
+    }
+
+    /// Drops the oldest lines beyond `log_limit`, counting them.
+    fn trim_log(&mut self) {
+        let excess = self.nmea_log.len().saturating_sub(self.log_limit);
+        if excess > 0 {
+            Arc::make_mut(&mut self.nmea_log).drain(..excess);
+            self.log_dropped += excess as u64;
+        }
+    }
+
+    /// Records a GGA fix. Fixes without a position only update `fix`;
+    /// the next good one starts a new track segment.
+    fn push_fix(&mut self, fix: GgaFix) {
+        let last_warning = self.fix.as_ref().and_then(|f| f.unit_warning.as_ref());
+        if let Some(warning) = fix.unit_warning.as_ref().filter(|w| last_warning != Some(*w)) {
+            self.push_log(format!("[units] GGA {warning}; that length is dropped"));
+        }
+        self.note_fix_epoch(fix.utc_time.as_deref());
+        if let Some(log) = self.sat_log.as_mut() {
+            log.fix(unix_now(), fix.utc_time.as_deref());
+        }
+        self.records.push_fix(unix_now(), &fix);
+        self.events.check(unix_now(), &fix, &self.satellites, &self.nmea_log);
+        for change in self.alarms.check(unix_now(), &fix, &self.satellites) {
+            self.push_log(change);
+        }
+        if let Some(change) = self.quality.check(unix_now(), fix.quality) {
+            let line = format!("[fix] {}", change.describe());
+            self.push_log(line);
+        }
+        if self.survey.as_mut().is_some_and(|s| s.push(unix_now(), &fix)) {
+            let line = match self.survey.as_ref().and_then(Survey::estimate) {
+                Some(result) => format!(
+                    "[survey] averaged {} fixes: {:.8}, {:.8}, σ {:.2} m",
+                    result.fixes, result.latitude, result.longitude, result.std_horizontal
+                ),
+                None => "[survey] finished with too few fixes to average".to_string(),
+            };
+            self.push_log(line);
+        }
+        if !fix.has_position() {
+            self.fix = Some(fix);
+            return;
+        }
+        if self.ttff_start.is_some() && self.fix_follows_ttff_start(&fix) {
+            if let Some((start, from)) = self.ttff_start.take() {
+                self.ttff = Some((start.elapsed(), from));
+            }
+        }
+        let segment_start = self.fix.as_ref().is_some_and(|f| !f.has_position());
+        let speed_knots = self.rmc.as_ref().filter(|r| r.valid).and_then(|r| r.speed_knots);
+        let history = Arc::make_mut(&mut self.fix_history);
+        history.push_back(FixRecord {
+            time: unix_now(),
+            fix: fix.clone(),
+            segment_start,
+            speed_knots,
+        });
+        if history.len() > self.caps.track_fixes {
+            history.pop_front();
+        }
+        let graphs = [
+            (&mut self.altitude_history, fix.altitude),
+            (&mut self.speed_history, speed_knots),
+            (&mut self.hdop_history, fix.hdop),
+        ];
+        for (series, value) in graphs {
+            if let Some(value) = value {
+                series.set_cap(self.caps.track_fixes);
+                series.push(unix_now(), value);
+            }
+        }
+        self.snapshots.push_back(FixSnapshot {
+            time: unix_now(),
+            fix: fix.clone(),
+            fix_mode: self.fix_mode,
+            satellites: self.satellites.clone(),
+        });
+        if self.snapshots.len() > self.caps.snapshots {
+            self.snapshots.pop_front();
+        }
+        self.fix = Some(fix);
+    }
+
+    /// The snapshot being looked back at, if it is still kept.
+    fn scrubbed(&self) -> Option<&FixSnapshot> {
+        let time = self.scrub?;
+        let index = self.snapshots.partition_point(|s| s.time < time);
+        self.snapshots.get(index).filter(|s| s.time == time)
+    }
+
+    /// Folds the course of a valid RMC made while moving into the smoothed
+    /// course; when stationary the course keeps its last value.
+    fn note_course(&mut self, rmc: &RmcData) {
+        let moving = rmc.valid && rmc.speed_knots.is_some_and(|k| k >= MOVING_KNOTS);
+        let Some(course) = rmc.course.filter(|c| moving && c.is_finite()) else {
+            return;
+        };
+        let smoothed = match self.smoothed_course {
+            Some(smoothed) => geo::smooth_bearing_deg(smoothed, course, COURSE_SMOOTHING),
+            None => course,
+        };
+        self.smoothed_course = Some(smoothed);
+        let turned = self.heading_up_rotation.is_none_or(|rotation| {
+            geo::relative_bearing_deg(smoothed, rotation).abs() >= HEADING_UP_HYSTERESIS_DEG
+        });
+        if turned {
+            self.heading_up_rotation = Some(smoothed);
+        }
+    }
+
+    /// The smoothed course while moving, for pointing relative to it.
+    fn moving_course(&self) -> Option<f64> {
+        self.rmc
+            .as_ref()
+            .filter(|r| r.valid && r.speed_knots.is_some_and(|k| k >= MOVING_KNOTS))
+            .and(self.smoothed_course)
+    }
+
+    /// Counts a GGA or RMC stamped `utc` towards the fix update rate.
+    fn note_fix_epoch(&mut self, utc: Option<&str>) {
+        if let Some(time) = utc.and_then(parser::seconds_of_day) {
+            self.fix_rate.push(unix_now(), time);
+        }
+    }
+
+    /// Watches the receiver's time of day for jumps, capturing an event
+    /// for each and, when set, restarting the time-series graphs.
+    fn check_clock(&mut self, line: &str, system_time: f64) {
+        let time = parser::sentence_time(line);
+        let Some(jump) = time.and_then(|t| self.clock_watch.check(t, system_time)) else {
+            return;
+        };
+        let triggers = self.events.triggers;
+        if !triggers.clock_jump {
+            return;
+        }
+        let reason = jump.describe();
+        self.push_log(format!("[clock] {reason}"));
+        let fix = self.fix.clone().unwrap_or_default();
+        self.events.capture(system_time, reason, &fix, &self.satellites, &self.nmea_log);
+        if triggers.clock_jump_resets_graphs {
+            self.graphs_since = Some(system_time);
+        }
+    }
+
+    /// Logs a user note and captures the moment as an event, so what the
+    /// user did lines up with what the receiver reported.
+    fn add_note(&mut self, note: &str) {
+        let note = note.trim();
+        self.push_log(format!("{NOTE_PREFIX}{note}"));
+        self.notes.extend(self.nmea_log.last().cloned());
+        let fix = self.fix.clone().unwrap_or_default();
+        let reason = format!("Note: {note}");
+        self.events.capture(unix_now(), reason, &fix, &self.satellites, &self.nmea_log);
+    }
+
+    /// Replaces the satellite set with a fresh GSV cycle, keeping the
+    /// per-satellite SNR history and acquisition/loss times up to date.
+    /// Satellites missing from the cycle are held over while within the
+    /// loss grace period.
+    fn update_satellites(&mut self, satellites: Vec<Satellite>) {
+        self.replace_satellites(satellites, true);
+    }
+
+    fn replace_satellites(&mut self, mut satellites: Vec<Satellite>, grace: bool) {
+        let now = Instant::now();
+        self.last_gsv = Some(now);
+        let fresh: HashSet<String> = satellites.iter().map(Satellite::key).collect();
+
+        // Missing for no more than the grace cycles and seconds: keep it
+        self.held_satellites.retain(|key, _| !fresh.contains(key));
+        let mut held = Vec::new();
+        for sat in &self.satellites {
+            let key = sat.key();
+            if fresh.contains(&key) {
+                continue;
+            }
+            let missed = self.held_satellites.get(&key).copied().unwrap_or(0) + 1;
+            let recent = self
+                .last_seen
+                .get(&key)
+                .is_some_and(|t| t.elapsed().as_secs_f32() <= self.loss_grace_secs);
+            if grace && missed <= self.loss_grace_cycles && recent {
+                self.held_satellites.insert(key, missed);
+                held.push(sat.clone());
+            } else {
+                self.held_satellites.remove(&key);
+            }
+        }
+        let keys: HashSet<String> =
+            fresh.iter().cloned().chain(held.iter().map(Satellite::key)).collect();
+
+        let mut lost = 0;
+        for sat in &self.satellites {
+            let key = sat.key();
+            if !keys.contains(&key) {
+                self.first_seen.remove(&key);
+                self.lost_satellites.push((sat.clone(), now));
+                lost += 1;
+            }
+        }
+        let acquired = keys.iter().filter(|k| !self.first_seen.contains_key(*k)).count();
+        self.push_view_sample(fresh.len(), acquired, lost);
+        if let Some(log) = self.sat_log.as_mut() {
+            if let Err(e) = log.append(unix_now(), &satellites) {
+                self.sat_log = None;
+                self.sat_log_error = Some(e);
+            }
+        }
+        self.records.push_satellites(unix_now(), &satellites);
+        satellites.extend(held);
+        self.lost_satellites
+            .retain(|(sat, lost)| lost.elapsed() < LOSS_FADE && !keys.contains(&sat.key()));
+        let fading: HashSet<String> = self.lost_satellites.iter().map(|(s, _)| s.key()).collect();
+        self.row_order.retain(|key| keys.contains(key) || fading.contains(key));
+        for sat in &satellites {
+            let key = sat.key();
+            if !self.row_order.contains(&key) {
+                self.row_order.push(key);
+            }
+        }
+
+        self.satellites = satellites;
+        self.snr_history
+            .retain(|key, _| keys.contains(key) || self.favorites.contains(key));
+        // A reacquired satellite starts smoothing afresh, and its trail too
+        self.snr_smoothed.retain(|key, _| keys.contains(key));
+        self.sky_trails.retain(|key, _| keys.contains(key));
+        for sat in &self.satellites {
+            let key = sat.key();
+            if self.held_satellites.contains_key(&key) {
+                continue;
+            }
+            self.first_seen.entry(key.clone()).or_insert(now);
+            self.last_seen.insert(key.clone(), now);
+
+            let raw = sat.strength as f32;
+            self.snr_smoothed
+                .entry(key.clone())
+                .and_modify(|avg| *avg += SNR_SMOOTHING * (raw - *avg))
+                .or_insert(raw);
+
+            let cap = self.caps.snr_samples;
+            let history =
+                self.snr_history.entry(key.clone()).or_insert_with(|| TimeSeries::new(cap));
+            history.push(unix_now(), sat.strength);
+
+            // GSV positions are whole degrees, so only changes are kept
+            let position = [sat.longitude, sat.latitude];
+            let cap = self.caps.trail_points;
+            let trail = self.sky_trails.entry(key).or_insert_with(|| TimeSeries::new(cap));
+            if trail.last().map(|(_, p)| p) != Some(&position) {
+                trail.push(unix_now(), position);
+            }
+        }
+    }
+
+    /// The position scatter's reference as latitude, longitude and height,
+    /// and the fixes since it was set in local East/North/Up metres.
+    fn scatter_points(&self) -> Option<([f64; 3], Vec<EnuPoint>)> {
+        let reference = match self.scatter_reference {
+            Some(reference) => reference,
+            None => {
+                let first = self.fix_history.front()?;
+                EnuReference {
+                    since: first.time,
+                    position: [first.fix.latitude, first.fix.longitude],
+                    altitude: first.fix.altitude,
+                    typed: false,
+                }
+            }
+        };
+        let records: Vec<&FixRecord> =
+            self.fix_history.iter().filter(|r| r.time >= reference.since).collect();
+        let height = reference
+            .altitude
+            .or_else(|| records.iter().find_map(|r| r.fix.altitude))
+            .unwrap_or(0.0);
+        let origin = [reference.position[0], reference.position[1], height];
+        let points = records
+            .iter()
+            .map(|r| {
+                let point = [r.fix.latitude, r.fix.longitude, r.fix.altitude.unwrap_or(height)];
+                let [east, north, up] = coords::geodetic_to_enu(origin, point);
+                EnuPoint {
+                    time: r.time,
+                    east_north: [east, north],
+                    up: r.fix.altitude.map(|_| up),
+                }
+            })
+            .collect();
+        Some((origin, points))
+    }
+
+    /// Clears the satellites once no GSV has arrived for `timeout`, rather
+    /// than showing a frozen sky. Returns whether they were cleared.
+    fn expire_satellites(&mut self, timeout: Duration) -> bool {
+        let silent = self.last_gsv.is_some_and(|t| t.elapsed() > timeout);
+        if !silent || self.mode == AppMode::Paused {
+            return false;
+        }
+        self.replace_satellites(Vec::new(), false);
+        self.decoder.reset_satellites();
+        self.last_gsv = None;
+        self.push_log(format!(
+            "[gsv] no satellites reported for {:.0} s, cleared",
+            timeout.as_secs_f32()
+        ));
+        true
+    }
+
+    fn push_view_sample(&mut self, count: usize, acquired: usize, lost: usize) {
+        let time = unix_now();
+        let sample = ViewSample {
+            count,
+            acquired,
+            lost,
+        };
+        self.view_history.push(time, sample);
+        self.view_history.trim_before(time - self.caps.view_history_secs());
+    }
+
+    /// Systems with satellites in the current solution, per the last GSA
+    /// cycle, as e.g. "GPS + Galileo".
+    fn fix_constellations(&self) -> Option<String> {
+        let systems: BTreeSet<Constellation> = self
+            .satellites
+            .iter()
+            .filter(|s| s.used_in_fix)
+            .map(|s| s.constellation)
+            .collect();
+        let names: Vec<&str> = systems.iter().map(Constellation::name).collect();
+        (!names.is_empty()).then(|| names.join(" + "))
+    }
+
+    /// Satellites passing the minimum SNR filter (0 shows all), plus the
+    /// favorites whatever their SNR; in used-only view, just those the
+    /// last GSA put in the fix.
+    fn visible_satellites(&self, settings: &Settings) -> impl Iterator<Item = &Satellite> {
+        let (min_snr, used_only) = (settings.min_snr, settings.used_only);
+        let satellites = self.scrubbed().map_or(&self.satellites, |s| &s.satellites);
+        satellites
+            .iter()
+            .filter(move |s| !used_only || s.used_in_fix)
+            .filter(move |s| s.strength >= min_snr || self.favorites.contains(&s.key()))
+    }
+
+    /// The fix to show, and whether it is a stale last-known position held
+    /// through a loss of fix.
+    fn displayed_fix(&self, hold_last_fix: bool) -> Option<(&GgaFix, bool)> {
+        match &self.fix {
+            Some(fix) if fix.has_position() => Some((fix, false)),
+            Some(_) if hold_last_fix => self.fix_history.back().map(|r| (&r.fix, true)),
+            _ => None,
+        }
+    }
+
+    /// SNR to display: the moving average when smoothing, else the raw value.
+    fn display_snr(&self, sat: &Satellite, smooth: bool, display: SnrDisplay) -> String {
+        match self.snr_smoothed.get(&sat.key()) {
+            Some(avg) if smooth => display.number(f64::from(*avg)),
+            _ => display.number(f64::from(sat.strength)),
+        }
+    }
+
+    /// True while the satellite is within its acquisition highlight.
+    fn is_new(&self, key: &str) -> bool {
+        self.first_seen
+            .get(key)
+            .is_some_and(|t| t.elapsed() < ACQUIRE_FLASH)
+    }
+
+    /// The Reader health rows: connection, thread and port state.
+    fn health_rows(&self) -> Vec<(&'static str, String)> {
+        let health = &self.health;
+        vec![
+            ("Source type", self.kind.name().to_string()),
+            ("State", self.connection_state().to_string()),
+            ("Thread", if health.thread_alive { "alive" } else { "stopped" }.to_string()),
+            ("Last read", format!("{} bytes", health.last_read_size)),
+            (
+                "Driver buffer",
+                health.buffered_bytes.map_or("—".to_string(), |b| format!("{b} bytes")),
+            ),
+            ("Device", self.device_text().unwrap_or("—".to_string())),
+            ("Port settings", health.port_settings.map_or("—".to_string(), |p| p.summary())),
+            ("Log lines", format!("{}/{}", self.nmea_log.len(), self.log_limit)),
+            (
+                "Buffer memory",
+                // Walks every buffer, so only while the panel asks
+                self.measure_buffers().describe(),
+            ),
+            (
+                "Command channel",
+                if self.commands.is_some() { "open" } else { "closed" }.to_string(),
+            ),
+            ("Reconnects", health.reconnects.to_string()),
+            (
+                "Fix rate (received)",
+                self.fix_rate.received_hz().map_or("—".to_string(), inventory::rate_text),
+            ),
+            ("GP/GN conflicts", self.talker_conflicts.count.to_string()),
+            (
+                "Since last byte",
+                self.last_data.map_or("—".to_string(), |t| {
+                    format!("{:.1} s", t.elapsed().as_secs_f32())
+                }),
+            ),
+            ("Last error", health.last_error.clone().unwrap_or("—".to_string())),
+        ]
+    }
+
+    /// True while the satellite is missing from GSV but within the grace
+    /// period.
+    fn is_held(&self, key: &str) -> bool {
+        self.held_satellites.contains_key(key)
+    }
+
+    /// Geometry of the satellites in the fix, or of all in view when the
+    /// receiver marks none used, with their keys in the same order.
+    fn geometry(&self) -> Option<(Vec<String>, dop::Geometry)> {
+        let current = |s: &&Satellite| s.implausible().is_none() && !self.is_held(&s.key());
+        let mut satellites: Vec<&Satellite> =
+            self.satellites.iter().filter(current).filter(|s| s.used_in_fix).collect();
+        if satellites.is_empty() {
+            satellites = self.satellites.iter().filter(current).collect();
+        }
+        let directions: Vec<(f64, f64)> =
+            satellites.iter().map(|s| (s.longitude, s.latitude)).collect();
+        let geometry = dop::Geometry::new(&directions)?;
+        Some((satellites.iter().map(|s| s.key()).collect(), geometry))
+    }
+
+    fn has_animations(&self) -> bool {
+        self.lost_satellites.iter().any(|(_, lost)| lost.elapsed() < LOSS_FADE)
+            || self.first_seen.keys().any(|k| self.is_new(k))
+    }
+
+    /// Warns when the used-satellite count is falling toward the minimum
+    /// for the current fix type (4 for 3D, 3 for 2D).
+    fn fix_loss_warning(&self) -> Option<String> {
+        let minimum = match self.fix_mode {
+            Some(FixMode::TwoD) => 3,
+            Some(FixMode::ThreeD) | None => 4,
+            Some(FixMode::NoFix) => return None,
+        };
+        let counts: Vec<u8> = self
+            .fix_history
+            .iter()
+            .rev()
+            .take(FIX_TREND_LEN)
+            .map(|r| r.fix.satellites_used)
+            .collect();
+        let (&latest, &oldest) = (counts.first()?, counts.last()?);
+        let falling = latest < oldest && counts.windows(2).all(|w| w[0] <= w[1]);
+
+        (falling && latest > 0 && latest <= minimum + 1).then(|| {
+            format!("About to lose fix: {latest} satellites used, {minimum} needed")
+        })
+    }
+
+    /// The errors, then the warnings, the panels show for this source, for
+    /// the big readout that hides them.
+    fn alerts(&self) -> Vec<(egui::Color32, String)> {
+        let error = egui::Color32::RED;
+        let mut alerts = Vec::new();
+        if let Some(fault) = self.health.fault {
+            alerts.push((error, fault.message().to_string()));
+        } else if let AppMode::Error(err) = &self.mode {
+            alerts.push((error, err.clone()));
+        } else if self.mode == AppMode::Reconnecting {
+            alerts.extend(self.health.last_error.iter().map(|e| (error, e.clone())));
+        }
+        let errors = [
+            &self.replay_error,
+            &self.tail_error,
+            &self.sat_log_error,
+            &self.autosave_error,
+        ];
+        alerts.extend(errors.into_iter().flatten().map(|e| (error, e.clone())));
+        let live = self.connection_state() == "Reading";
+        if let Some(status) = self.antenna.filter(|a| live && a.is_fault()) {
+            alerts.push((error, format!("Antenna {}", status.name())));
+        }
+        alerts.extend(self.alarms.active().map(|a| (WARNING_COLOR, a.detail.clone())));
+        alerts.extend(self.fix_loss_warning().map(|w| (WARNING_COLOR, w)));
+        alerts
+    }
+
+    /// Counts a sentence with too few or too many fields, noting the first
+    /// of each type in the log.
+    fn note_field_anomaly(&mut self, anomaly: parser::FieldCountAnomaly) {
+        let count = self.field_anomalies.entry(anomaly.kind.clone()).or_default();
+        *count += 1;
+        if *count == 1 {
+            self.push_log(format!(
+                "[fields] {}; any more are counted in Statistics",
+                anomaly.describe()
+            ));
+        }
+    }
+
+    /// Counts a GSV or GSA with or without the NMEA 4.10 ID, noting in the
+    /// log when a type first turns up both ways.
+    fn note_nmea_version(&mut self, sentence: &'static str, with_id: bool) {
+        let counts = self.nmea_versions.entry(sentence).or_default();
+        counts[usize::from(with_id)] += 1;
+        if counts[usize::from(with_id)] == 1 && counts[usize::from(!with_id)] > 0 {
+            let id = if sentence == "GSV" { "signal" } else { "system" };
+            self.push_log(format!(
+                "[versions] {sentence} arrives both with and without the NMEA 4.10 {id} ID: \
+                 a mixed receiver or merged logs; counts are in Statistics"
+            ));
+        }
+    }
+
+    /// The sentence types that have arrived both with and without their
+    /// NMEA 4.10 ID, with the counts without and with.
+    fn mixed_nmea_versions(&self) -> impl Iterator<Item = (&'static str, [u64; 2])> + '_ {
+        self.nmea_versions
+            .iter()
+            .filter(|(_, counts)| counts.iter().all(|c| *c > 0))
+            .map(|(sentence, counts)| (*sentence, *counts))
+    }
+
+    fn count_sentence(&mut self, line: &str) {
+        let kind = parser::sentence_type(line).unwrap_or("(other)");
+        *self.sentence_counts.entry(kind.to_string()).or_default() += 1;
+
+        let now = Instant::now();
+        self.recent_sentences.push_back(now);
+        while self
+            .recent_sentences
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > Duration::from_secs(1))
+        {
+            self.recent_sentences.pop_front();
+        }
+    }
+
+    fn sentences_per_second(&self) -> usize {
+        self.recent_sentences
+            .iter()
+            .filter(|t| t.elapsed() <= Duration::from_secs(1))
+            .count()
+    }
+
+    fn connection_state(&self) -> &'static str {
+        match self.mode {
+            AppMode::Idle => "Idle",
+            AppMode::Connecting => "Connecting",
+            AppMode::Paused => "Paused",
+            AppMode::Reconnecting => "Reconnecting",
+            AppMode::Error(_) => "Error",
+            AppMode::Stopping => "Stopping",
+            AppMode::Reading => match self.last_data {
+                Some(t) if t.elapsed() < Duration::from_secs(2) => "Reading",
+                _ => "Waiting for data",
+            },
+        }
+    }
+
+    /// Distance along the trail, not counting the gaps left by fix losses.
+    fn trip_distance_m(&self, mode: DistanceMode) -> f64 {
+        let point = |r: &FixRecord| [r.fix.latitude, r.fix.longitude];
+        self.fix_history
+            .iter()
+            .zip(self.fix_history.iter().skip(1))
+            .filter(|(_, b)| !b.segment_start)
+            .map(|(a, b)| mode.distance_m(point(a), point(b)))
+            .sum()
+    }
+
+    /// Records the antenna state, logging changes to and from a fault.
+    fn set_antenna(&mut self, status: AntennaStatus) {
+        let was_fault = self.antenna.is_some_and(|a| a.is_fault());
+        if status.is_fault() && self.antenna != Some(status) {
+            self.push_log(format!("⚠ Antenna {} detected", status.name()));
+        } else if was_fault && !status.is_fault() {
+            self.push_log("Antenna OK again".to_string());
+        }
+        self.antenna = Some(status);
+    }
+
+    fn set_device(&mut self, info: DeviceInfo) {
+        if self.device.as_ref() != Some(&info) {
+            self.push_log(format!("Receiver: {}, firmware {}", info.model, info.firmware));
+        }
+        self.device = Some(info);
+    }
+
+    fn set_dynamic_model(&mut self, model: DynamicModel) {
+        if self.dynamic_model != Some(model) {
+            self.push_log(format!("[UBX] Dynamic model: {}", model.name()));
+        }
+        self.dynamic_model = Some(model);
+        self.dynamic_model_choice = model;
+    }
+
+    /// The identified receiver, or whether it is still being asked.
+    fn device_text(&self) -> Option<String> {
+        match (&self.device, self.device_query) {
+            (Some(info), _) => Some(format!("{}, firmware {}", info.model, info.firmware)),
+            (None, Some(t)) if t.elapsed() < DEVICE_ID_TIMEOUT => Some("Identifying…".into()),
+            (None, Some(_)) => Some("Unknown device".to_string()),
+            (None, None) => None,
+        }
+    }
+
+    /// Starts reading the selected serial port afresh.
+    fn start_serial(&mut self, self_arc: &Arc<Mutex<SourceState>>, settings: &Settings) {
+        let Some(port_name) = self.selected_port.clone() else {
+            return;
+        };
+        self.kind = SourceKind::Serial;
+        self.replay = None;
+        self.health = ReaderHealth::default();
+        self.applying_settings = false;
+        self.spawn_serial(self_arc, settings, port_name);
+        self.mode = AppMode::Connecting;
+        self.start_ttff("connect");
+    }
+
+    /// Hands the port to a new reader thread with the current settings.
+    fn spawn_serial(
+        &mut self,
+        self_arc: &Arc<Mutex<SourceState>>,
+        settings: &Settings,
+        port_name: String,
+    ) {
+        self.serial_params = Some(SerialParams::new(self.baud_rate, settings));
+        self.open_fallback = None;
+        self.reader_abort = Some(reader::spawn(
+            Arc::clone(self_arc),
+            port_name,
+            self.baud_rate,
+            settings.read_timeout(),
+            settings.flow_control,
+            settings.skip_leading_garbage,
+            settings.open_fallback,
+        ));
+        self.last_data = Some(Instant::now());
+    }
+
+    /// Starts timing acquisition afresh, e.g. after a cold start.
+    fn start_ttff(&mut self, from: &'static str) {
+        self.ttff_start = Some((Instant::now(), from));
+        self.ttff = None;
+        self.ttff_after = self
+            .gps_clock_offset
+            .map(|offset| (unix_now() + offset).rem_euclid(86_400.0));
+    }
+
+    /// Whether a fix was stamped after timing started, so one still queued
+    /// from before a reset doesn't end it. Fixes without a time, or from a
+    /// receiver whose clock isn't known yet, are taken as they come.
+    fn fix_follows_ttff_start(&self, fix: &GgaFix) -> bool {
+        let stamped = fix.utc_time.as_deref().and_then(parser::seconds_of_day);
+        match (self.ttff_after, stamped) {
+            // Half a day either way, so a reset just before midnight works
+            (Some(after), Some(time)) => {
+                let since = (time - after).rem_euclid(86_400.0);
+                since > 0.0 && since < 43_200.0
+            }
+            _ => true,
+        }
+    }
+
+    /// Magnetic variation from the latest RMC, degrees east.
+    fn variation(&self) -> Option<f64> {
+        self.rmc.as_ref().and_then(|r| r.variation)
+    }
+
+    /// TTFF once measured, or the time spent acquiring so far.
+    fn ttff_text(&self) -> Option<String> {
+        match (self.ttff, self.ttff_start) {
+            (Some((ttff, from)), _) => Some(format!("{:.1} s from {from}", ttff.as_secs_f32())),
+            (None, Some((start, from))) => Some(format!(
+                "acquiring, {:.0} s since {from}",
+                start.elapsed().as_secs_f32()
+            )),
+            (None, None) => None,
+        }
+    }
+
+    /// Asks a receiver sending only UBX to send NMEA too; the reader takes
+    /// it up as NMEA once the sentences arrive.
+    fn enable_nmea_output(&mut self) {
+        for command in commands::enable_nmea_commands(self.baud_rate) {
+            self.send_command(command);
+        }
+        if self.command_error.is_none() {
+            self.push_log("[protocol] NMEA output requested with UBX-CFG-PRT/CFG-MSG".to_string());
+            self.nmea_enable_sent = Some(Instant::now());
+        }
+    }
+
+    /// Sends a restart and, as it drops the fix, times the reacquisition.
+    fn send_reset(&mut self, kind: ResetKind) {
+        let sequence = commands::reset_commands(self.receiver_family, kind);
+        for command in sequence {
+            self.send_command(command);
+        }
+        if self.command_error.is_none() {
+            self.push_log(format!("[reset] {} sent, timing first fix", kind.name()));
+            self.start_ttff(kind.name());
+            self.resync = true;
+        }
+    }
+
+    /// A reset copy keeping only the user's connection choices.
+    fn fresh(&self) -> Self {
+        Self {
+            selected_port: self.selected_port.clone(),
+            baud_rate: self.baud_rate,
+            log_valid_only: self.log_valid_only,
+            replay_path: self.replay_path.clone(),
+            replay_timed: self.replay_timed,
+            replay_offset_secs: self.replay_offset_secs,
+            in_comparison: self.in_comparison,
+            tail_path: self.tail_path.clone(),
+            sim_config: self.sim_config.clone(),
+            receiver_family: self.receiver_family,
+            binary_as_hex: self.binary_as_hex,
+            profile_name: self.profile_name.clone(),
+            profile_file: self.profile_file.clone(),
+            ..Self::new(&self.label)
+        }
+    }
+
+    /// A copy of what the read-only windows draw, so that they render
+    /// without holding the source lock and stalling its reader. Files,
+    /// channels and parser state stay behind. While scrubbing back, the
+    /// snapshot stands in for the live fix and satellites.
+    fn view(&self) -> Self {
+        let mut view = Self {
+            kind: self.kind,
+            selected_port: self.selected_port.clone(),
+            baud_rate: self.baud_rate,
+            satellites: self.satellites.clone(),
+            mode: self.mode.clone(),
+            nmea_log: self.nmea_log.clone(),
+            log_limit: self.log_limit,
+            caps: self.caps,
+            log_dropped: self.log_dropped,
+            notes: self.notes.clone(),
+            log_valid_only: self.log_valid_only,
+            decoder: self.decoder.clone(),
+            talker_conflicts: self.talker_conflicts.clone(),
+            discarded_lines: self.discarded_lines,
+            recent_sentences: self.recent_sentences.clone(),
+            sentence_counts: self.sentence_counts.clone(),
+            field_anomalies: self.field_anomalies.clone(),
+            nmea_versions: self.nmea_versions.clone(),
+            replay_path: self.replay_path.clone(),
+            replay_error: self.replay_error.clone(),
+            in_comparison: self.in_comparison,
+            tail_path: self.tail_path.clone(),
+            tail_error: self.tail_error.clone(),
+            fix_mode: self.fix_mode,
+            snr_history: self.snr_history.clone(),
+            favorites: self.favorites.clone(),
+            snr_smoothed: self.snr_smoothed.clone(),
+            first_seen: self.first_seen.clone(),
+            last_seen: self.last_seen.clone(),
+            last_gsv: self.last_gsv,
+            lost_satellites: self.lost_satellites.clone(),
+            held_satellites: self.held_satellites.clone(),
+            row_order: self.row_order.clone(),
+            view_history: self.view_history.clone(),
+            sky_trails: self.sky_trails.clone(),
+            sat_log_error: self.sat_log_error.clone(),
+            fix: self.fix.clone(),
+            rmc: self.rmc.clone(),
+            smoothed_course: self.smoothed_course,
+            heading_up_rotation: self.heading_up_rotation,
+            gps_time: self.gps_time.clone(),
+            gps_clock_offset: self.gps_clock_offset,
+            antenna: self.antenna,
+            device: self.device.clone(),
+            device_query: self.device_query,
+            dynamic_model: self.dynamic_model,
+            fix_history: self.fix_history.clone(),
+            altitude_history: self.altitude_history.clone(),
+            speed_history: self.speed_history.clone(),
+            hdop_history: self.hdop_history.clone(),
+            scrub: self.scrub,
+            scatter_reference: self.scatter_reference,
+            quality: self.quality.clone(),
+            graphs_since: self.graphs_since,
+            fix_rate: self.fix_rate.clone(),
+            records: self.records.clone(),
+            expected_rate_hz: self.expected_rate_hz,
+            commands: self.commands.clone(),
+            command_error: self.command_error.clone(),
+            receiver_family: self.receiver_family,
+            ttff_start: self.ttff_start,
+            ttff: self.ttff,
+            protocol: self.protocol,
+            binary_chunks: self.binary_chunks,
+            binary_as_hex: self.binary_as_hex,
+            health: self.health.clone(),
+            last_data: self.last_data,
+            handoff_port: self.handoff_port,
+            ..Self::new(&self.label)
+        };
+        match self.scrubbed() {
+            Some(snapshot) => {
+                view.fix = Some(snapshot.fix.clone());
+                view.fix_mode = snapshot.fix_mode;
+                view.satellites = snapshot.satellites.clone();
+            }
+            None => view.scrub = None,
+        }
+        view
+    }
+
+    fn start_simulator(&mut self, self_arc: &Arc<Mutex<SourceState>>) {
+        self.kind = SourceKind::Simulator;
+        self.replay = None;
+        self.reader_abort = Some(reader::spawn_simulator(
+            Arc::clone(self_arc),
+            self.sim_config.clone(),
+        ));
+        self.last_data = Some(Instant::now());
+        self.mode = AppMode::Reading;
+    }
+
+    /// Starts replaying `replay_path`, its offset moved by `align_secs`.
+    fn start_replay(&mut self, self_arc: &Arc<Mutex<SourceState>>, timed: bool, align_secs: f64) {
+        let path = PathBuf::from(self.replay_path.trim());
+        let offset = self.replay_offset_secs + align_secs;
+        // Counters restart so the summary describes this file only
+        self.sentence_counts.clear();
+        self.field_anomalies.clear();
+        self.nmea_versions.clear();
+        match reader::spawn_replay(Arc::clone(self_arc), path, timed, offset) {
+            Ok(abort) => {
+                self.kind = SourceKind::Replay;
+                self.reader_abort = Some(abort);
+                self.last_data = Some(Instant::now());
+                self.mode = AppMode::Reading;
+                self.replay_error = None;
+            }
+            Err(e) => self.replay_error = Some(e),
+        }
+    }
+
+    /// Replays the sentences in the log from the start into a fresh
+    /// analysis, as if from a file, setting the captured log aside. A
+    /// second buffer replay starts again from the same capture.
+    fn replay_buffer(&mut self, self_arc: &Arc<Mutex<SourceState>>) {
+        let captured = match self.captured_log.take() {
+            Some(captured) => captured,
+            None => std::mem::take(&mut self.nmea_log),
+        };
+        let text: String = captured
+            .iter()
+            .filter(|entry| entry.line.starts_with(['$', '!']))
+            .map(|entry| format!("{}\n", entry.line))
+            .collect();
+        let name = PathBuf::from(format!("{} log buffer", self.label));
+        let mut fresh = self.fresh();
+        // The settings the reader consults before the next frame copies them
+        fresh.log_limit = self.log_limit;
+        fresh.caps = self.caps;
+        fresh.decoder.filter = self.decoder.filter;
+        fresh.decoder.gsv_mapping = self.decoder.gsv_mapping;
+        fresh.events.triggers = self.events.triggers;
+        fresh.alarms.thresholds = self.alarms.thresholds;
+        fresh.alarms.notify = self.alarms.notify;
+        fresh.favorites = std::mem::take(&mut self.favorites);
+        fresh.captured_log = Some(captured);
+        *self = fresh;
+
+        let timed = self.replay_timed;
+        let abort = reader::spawn_replay_text(Arc::clone(self_arc), name, text, timed, 0.0);
+        self.kind = SourceKind::Replay;
+        self.reader_abort = Some(abort);
+        self.last_data = Some(Instant::now());
+        self.mode = AppMode::Reading;
+    }
+
+    /// Reads the stream another instance repeats the selected port on,
+    /// instead of the port it holds.
+    fn start_tcp(&mut self, self_arc: &Arc<Mutex<SourceState>>, port: u16) {
+        self.port_wait = None;
+        self.kind = SourceKind::Tcp;
+        self.replay = None;
+        self.health = ReaderHealth::default();
+        self.handoff_port = Some(port);
+        self.reader_abort = Some(reader::spawn_tcp(Arc::clone(self_arc), port));
+        self.last_data = Some(Instant::now());
+        self.mode = AppMode::Reading;
+    }
+
+    /// Asks the reader thread, if any, to stop.
+    fn stop_reader(&mut self) {
+        self.port_wait = None;
+        self.respawn_pending = false;
+        if let Some(abort) = &self.reader_abort {
+            abort.store(true, Ordering::Relaxed);
+        }
+        self.commands = None;
+        self.health.thread_alive = false;
+        self.mode = if self.reader_abort.is_some() { AppMode::Stopping } else { AppMode::Idle };
+    }
+
+    /// Whether the baud rate or serial settings differ from those the
+    /// running serial reader opened its port with.
+    fn serial_params_changed(&self, settings: &Settings) -> bool {
+        let running = self.serial_params.filter(|_| {
+            self.kind == SourceKind::Serial && self.mode.is_active()
+        });
+        running.is_some_and(|params| params != SerialParams::new(self.baud_rate, settings))
+    }
+
+    /// Reopens the port with the current settings, keeping the log and
+    /// statistics.
+    /// The new reader is started by `settle_stop` once the old one has let
+    /// go of the port.
+    fn restart_serial(&mut self) {
+        self.stop_reader();
+        self.respawn_pending = true;
+    }
+
+    /// Finishes a stop once the thread has dropped its handle on the flag.
+    /// Returns whether a restart was waiting on that.
+    fn settle_stop(&mut self) -> bool {
+        let exited = self.reader_abort.as_ref().is_none_or(|a| Arc::strong_count(a) == 1);
+        if !exited {
+            return false;
+        }
+        if self.respawn_pending {
+            self.respawn_pending = false;
+            self.reader_abort = None;
+            return true;
+        }
+        if self.mode == AppMode::Stopping {
+            self.reader_abort = None;
+            self.mode = AppMode::Idle;
+        }
+        false
+    }
+
+    /// Opens the port again after `settle_stop` saw the old reader exit:
+    /// afresh to apply new settings, or keeping the health counters when
+    /// the watchdog replaced a stalled reader.
+    fn respawn_serial(&mut self, self_arc: &Arc<Mutex<SourceState>>, settings: &Settings) {
+        match (&self.mode, self.selected_port.clone()) {
+            (AppMode::Reconnecting, Some(port_name)) => {
+                self.spawn_serial(self_arc, settings, port_name)
+            }
+            (AppMode::Reconnecting, None) => self.mode = AppMode::Idle,
+            _ => {
+                self.start_serial(self_arc, settings);
+                self.applying_settings = true;
+            }
+        }
+    }
+
+    /// Hands a command to the reader for writing; it is echoed in the log
+    /// once written.
+    fn send_command(&mut self, bytes: Vec<u8>) {
+        let sent = match &self.commands {
+            Some(commands) => commands
+                .send(bytes)
+                .map_err(|_| "The reader has stopped; restart it to send commands".to_string()),
+            None => Err("The port is not open for writing".to_string()),
+        };
+        self.command_error = sent.err();
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum SortColumn {
+    #[default]
+    Prn,
+    Constellation,
+    Elevation,
+    Azimuth,
+    Snr,
+    Used,
+}
+
+impl SortColumn {
+    const ALL: [SortColumn; 6] = [
+        SortColumn::Prn,
+        SortColumn::Constellation,
+        SortColumn::Elevation,
+        SortColumn::Azimuth,
+        SortColumn::Snr,
+        SortColumn::Used,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            SortColumn::Prn => "PRN",
+            SortColumn::Constellation => "System",
+            SortColumn::Elevation => "Elv",
+            SortColumn::Azimuth => "Azm",
+            SortColumn::Snr => "SNR",
+            SortColumn::Used => "Used",
+        }
+    }
+}
+
+/// Satellite table ordering, chosen by clicking a column header.
+#[derive(Default, Clone, Copy)]
+struct SatelliteSort {
+    column: SortColumn,
+    descending: bool,
+}
+
+impl SatelliteSort {
+    /// Clicking the active column flips direction, another column sorts ascending.
+    fn toggle(&mut self, column: SortColumn) {
+        if self.column == column {
+            self.descending = !self.descending;
+        } else {
+            *self = SatelliteSort {
+                column,
+                descending: false,
+            };
+        }
+    }
+
+    fn compare(&self, a: &Satellite, b: &Satellite) -> std::cmp::Ordering {
+        // Numeric PRNs sort as numbers; anything odd falls back to text
+        let prn = |s: &Satellite| s.id.parse::<u32>().unwrap_or(u32::MAX);
+        let ordering = match self.column {
+            SortColumn::Prn => prn(a).cmp(&prn(b)).then_with(|| a.id.cmp(&b.id)),
+            SortColumn::Constellation => a.constellation.name().cmp(b.constellation.name()),
+            SortColumn::Elevation => a.latitude.total_cmp(&b.latitude),
+            SortColumn::Azimuth => a.longitude.total_cmp(&b.longitude),
+            SortColumn::Snr => a.strength.cmp(&b.strength),
+            SortColumn::Used => a.used_in_fix.cmp(&b.used_in_fix),
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+#[derive(Default)]
+struct AppState {
+    ports: Vec<String>,
+    port_error: Option<String>,
+    // Periodic rescans while auto-start is on: when the last ran, the
+    // one still enumerating in the background, what it found, and the
+    // ports already seen
+    port_scan: Option<Instant>,
+    port_scan_thread: Option<std::thread::JoinHandle<Result<Vec<PortEntry>, String>>>,
+    detected_ports: Vec<PortEntry>,
+    auto_starter: AutoStarter,
+    sources: Vec<Arc<Mutex<SourceState>>>,
+
+    // Source index and satellite key pinned in the detail panel
+    selected_satellite: Option<(usize, String)>,
+    satellite_sort: SatelliteSort,
+    // Source shown in the GPS Stream window
+    stream_source: usize,
+    // Set once the user scrolls away from the newest log line
+    log_scroll_locked: bool,
+    log_save_path: String,
+    log_save_status: Option<String>,
+
+    // Reference GPX track drawn behind the live trail
+    gpx_path: String,
+    reference_track: Vec<[f64; 2]>,
+    gpx_error: Option<String>,
+    track_fit_all: bool,
+    tile_layer: Option<tiles::TileLayer>,
+    // Tile URL being typed, applied with Enter or Apply so partial URLs
+    // aren't fetched from
+    tile_url_edit: Option<String>,
+    export_path: String,
+    export_status: Option<String>,
+    alarm_export_path: String,
+    alarm_export_status: Option<String>,
+    series_export_path: String,
+    series_export_status: Option<String>,
+    waypoints: Vec<Waypoint>,
+    waypoint_name: String,
+    // Note being typed for the session log
+    note_text: String,
+    // GPX file waypoints are loaded from and saved to
+    waypoint_file: String,
+    waypoint_file_status: Option<String>,
+    // Position typed or pasted for a waypoint, and why it was rejected
+    waypoint_position: String,
+    waypoint_position_error: Option<String>,
+    // Go-to target as typed, and why it was rejected
+    go_to_text: String,
+    go_to_error: Option<String>,
+
+    settings: Settings,
+    sky_focus: Option<SkyFocus>,
+    sky_snapshots: Vec<SkySnapshot>,
+    sky_export_path: String,
+    sky_export_status: Option<String>,
+    snapshot_name: String,
+    // Sentence inventory scan per source, running or finished
+    inventories: [Option<Inventory>; MAX_SOURCES],
+    // Diagnostics bundle under review for a bug report, and where it is
+    // saved
+    diagnostics_bundle: Option<String>,
+    diagnostics_path: String,
+    diagnostics_status: Option<String>,
+    // Sentences pasted into the inspector
+    inspector_text: String,
+
+    repeater: Option<Repeater>,
+    repeater_error: Option<String>,
+    // Ports being edited, applied to the settings (and the repeater) on
+    // Apply
+    repeater_port_edit: Option<u16>,
+    repeater_serial_edit: Option<String>,
+    // The repeated serial port's TCP port, advertised to other instances
+    repeater_advert: Option<Advert>,
+    // Page of the first-run guide being shown
+    onboarding_step: Option<usize>,
+
+    #[cfg(feature = "http")]
+    http_server: Option<http::Server>,
+    #[cfg(feature = "http")]
+    http_error: Option<String>,
+    // Port being edited, applied to the settings (and the server) on Apply
+    #[cfg(feature = "http")]
+    http_port_edit: Option<u16>,
+
+    session_path: String,
+    session_status: Option<String>,
+    // Session left behind by a run that did not exit cleanly, offered for
+    // restore, and when the live session was last mirrored for recovery
+    recovered_session: Option<(Session, PathBuf)>,
+    recovery_lock: Option<session::RecoveryLock>,
+    recovery_saved: Option<Instant>,
+    // The recovery file being written in the background
+    recovery_write: Option<std::thread::JoinHandle<Result<(), String>>>,
+    // New Session asked for while recording, awaiting confirmation
+    confirm_new_session: bool,
+    // Smoothed cost of one UI frame, milliseconds, and when the frames in
+    // the last `FRAME_RATE_WINDOW` started
+    frame_ms: f32,
+    frame_starts: VecDeque<Instant>,
+    // Smoothed time each frame holds the source locks, milliseconds
+    lock_ms: f32,
+}
+
+impl AppState {
+    /// Replaces settings, waypoints and sources with a loaded session.
+    /// Readers must already have been stopped.
+    fn apply_session(&mut self, session: Session) {
+        self.settings = session.settings;
+        self.waypoints = session.waypoints;
+        self.sources = session
+            .sources
+            .into_iter()
+            .take(MAX_SOURCES)
+            .map(|s| Arc::new(Mutex::new(s.restore())))
+            .collect();
+        if self.sources.is_empty() {
+            self.sources.push(Arc::new(Mutex::new(SourceState::new("A"))));
+        }
+        self.selected_satellite = None;
+    }
+
+    /// Stops every reader and discards the collected data, keeping the
+    /// settings and each source's port and replay choices.
+    fn new_session(&mut self, sources: &mut [MutexGuard<SourceState>]) {
+        for source in sources.iter_mut() {
+            source.stop_reader();
+        }
+        // Fresh state objects, so a reader still winding down writes into
+        // the discarded ones
+        self.sources = sources
+            .iter()
+            .map(|s| Arc::new(Mutex::new(s.fresh())))
+            .collect();
+        self.selected_satellite = None;
+        self.log_scroll_locked = false;
+        self.waypoints.clear();
+        self.sky_focus = None;
+        self.sky_snapshots.clear();
+        self.export_status = None;
+        self.log_save_status = None;
+        self.confirm_new_session = false;
+        self.session_status = Some("Started a new session".to_string());
+    }
+
+    /// Folds how long this frame held the source locks, taken when they
+    /// were, into the smoothed figure.
+    fn record_lock_time(&mut self, locked: Instant) {
+        let lock_ms = locked.elapsed().as_secs_f32() * 1000.0;
+        self.lock_ms += FRAME_TIME_SMOOTHING * (lock_ms - self.lock_ms);
+    }
+
+    /// Folds this frame's time, from `frame_start`, into the smoothed figure.
+    fn record_frame_time(&mut self, frame_start: Instant) {
+        let elapsed = frame_start.elapsed().as_secs_f32() * 1000.0;
+        self.frame_ms += FRAME_TIME_SMOOTHING * (elapsed - self.frame_ms);
+    }
+}
+
+pub struct MyApp {
+    state: Arc<Mutex<AppState>>,
+}
+
+/// Adds or removes a satellite key from the favorites.
+fn toggle_favorite(favorites: &mut BTreeSet<String>, key: &str) {
+    if !favorites.remove(key) {
+        favorites.insert(key.to_string());
+    }
+}
+
+/// The satellite after (or before) `current` in `order`, wrapping at the
+/// ends; the first (or last) when `current` isn't in it.
+fn step_selection(order: &[String], current: Option<&str>, forward: bool) -> Option<String> {
+    let len = order.len();
+    let at = current.and_then(|key| order.iter().position(|k| k == key));
+    let next = match (at, forward) {
+        (Some(i), true) => (i + 1) % len,
+        (Some(i), false) => (i + len - 1) % len,
+        (None, true) => 0,
+        (None, false) => len.checked_sub(1)?,
+    };
+    order.get(next).cloned()
+}
+
+/// Which way a key press steps the satellite selection: down arrow or Tab
+/// forward, up arrow or Shift+Tab back. Not while typing, nor Tab while a
+/// widget has the focus it moves between.
+fn satellite_step(ctx: &egui::Context) -> Option<bool> {
+    if ctx.wants_keyboard_input() {
+        return None;
+    }
+    let tab = ctx.memory(|m| m.focused().is_none());
+    ctx.input_mut(|i| {
+        use egui::{Key, Modifiers};
+        if i.consume_key(Modifiers::NONE, Key::ArrowDown)
+            || (tab && i.consume_key(Modifiers::NONE, Key::Tab))
+        {
+            Some(true)
+        } else if i.consume_key(Modifiers::NONE, Key::ArrowUp)
+            || (tab && i.consume_key(Modifiers::SHIFT, Key::Tab))
+        {
+            Some(false)
+        } else {
+            None
+        }
+    })
+}
+
+/// Lists serial port names, keeping the reason if enumeration fails.
+fn enumerate_ports() -> Result<Vec<String>, String> {
+    enumerate_port_entries().map(|ps| ps.into_iter().map(|p| p.name).collect())
+}
+
+/// Lists serial ports with the USB vendor and product of those that are
+/// USB devices.
+fn enumerate_port_entries() -> Result<Vec<PortEntry>, String> {
+    available_ports()
+        .map(|ps| {
+            ps.into_iter()
+                .map(|p| PortEntry {
+                    usb_id: match p.port_type {
+                        serialport::SerialPortType::UsbPort(usb) => Some((usb.vid, usb.pid)),
+                        _ => None,
+                    },
+                    name: p.port_name,
+                })
+                .collect()
+        })
+        .map_err(|e| format!("Could not enumerate ports: {e}"))
+}
+
+/// Why a connected receiver might not be listed, for the empty port list.
+fn missing_port_hint() -> &'static str {
+    if cfg!(target_os = "linux") {
+        "On Linux, serial devices need the dialout group: \
+         `sudo usermod -aG dialout $USER`, then log out and back in."
+    } else if cfg!(target_os = "macos") {
+        "On macOS, receivers appear as /dev/cu.* once their USB driver is installed."
+    } else if cfg!(windows) {
+        "On Windows, check Device Manager under \"Ports (COM & LPT)\" and install \
+         the receiver's USB driver if it is missing."
+    } else {
+        "Check that the receiver's USB serial driver is installed."
+    }
+}
+
+impl Default for MyApp {
+    fn default() -> Self {
+        let (ports, port_error) = match enumerate_ports() {
+            Ok(ports) => (ports, None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+
+        Self {
+            state: Arc::new(Mutex::new(AppState {
+                ports,
+                port_error,
+                sources: vec![Arc::new(Mutex::new(SourceState::new("A")))],
+                ..Default::default()
+            })),
+        }
+    }
+}
+
+impl MyApp {
+    /// Restores saved settings, if any, on top of the defaults, and picks
+    /// up the recovery file a crashed run left behind.
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let app = Self::default();
+        let mut state = app.state.lock().unwrap();
+        if let Some(settings) = cc
+            .storage
+            .and_then(|storage| eframe::get_value::<Settings>(storage, eframe::APP_KEY))
+        {
+            state.settings = settings;
+        }
+        if !state.settings.onboarding_done {
+            state.onboarding_step = Some(0);
+        }
+
+        state.recovery_lock = session::RecoveryLock::acquire();
+        if let Some(recovery) = session::orphaned_recovery() {
+            match Session::load(&recovery) {
+                Ok(session) => state.recovered_session = Some((session, recovery)),
+                Err(_) => session::remove_recovery(&recovery),
+            }
+        }
+        drop(state);
+        app
+    }
+}
+
+/// Pages of the first-run guide.
+const ONBOARDING_STEPS: usize = 3;
+
+/// Step between the Signal chart's gridlines and axis labels, dB-Hz.
+const SNR_GRID_STEP: f64 = 10.0;
+
+/// A mark every `SNR_GRID_STEP` within the visible range.
+fn snr_grid_marks(input: GridInput) -> Vec<GridMark> {
+    let (low, high) = input.bounds;
+    let first = (low / SNR_GRID_STEP).ceil() as i64;
+    let last = (high / SNR_GRID_STEP).floor() as i64;
+    (first..=last)
+        .map(|n| GridMark {
+            value: n as f64 * SNR_GRID_STEP,
+            step_size: SNR_GRID_STEP,
+        })
+        .collect()
+}
+
+/// Frames per second over the recorded frame starts, the latest being
+/// the current frame.
+fn frame_rate(starts: &VecDeque<Instant>) -> f32 {
+    match (starts.front(), starts.back()) {
+        (Some(first), Some(last)) if starts.len() > 1 => {
+            let span = last.duration_since(*first).as_secs_f32();
+            if span > 0.0 {
+                (starts.len() - 1) as f32 / span
+            } else {
+                0.0
+            }
+        }
+        _ => 0.0,
+    }
+}
+
+/// GPS Stream color for a log line, by sentence type; checksum failures
+/// are flagged red whatever their type, and sent commands and user notes
+/// stand out.
+fn log_line_color(line: &str, palette: Palette) -> egui::Color32 {
+    if line.starts_with(NOTE_PREFIX) {
+        return palette.note();
+    }
+    if line.starts_with("→ ") {
+        return egui::Color32::WHITE;
+    }
+    if line.starts_with('⚠') {
+        return egui::Color32::RED;
+    }
+    let Some(kind) = parser::sentence_type(line) else {
+        return egui::Color32::GRAY;
+    };
+    if !parser::verify_checksum(line) {
+        return palette.checksum_failure();
+    }
+    palette.sentence(kind.get(2..).unwrap_or(""))
+}
+
+/// Anchor for a panel pinned `offset` points in from `corner`.
+fn corner_anchor(corner: PanelCorner, [x, y]: [f32; 2]) -> (egui::Align2, [f32; 2]) {
+    match corner {
+        PanelCorner::TopLeft => (egui::Align2::LEFT_TOP, [x, y]),
+        PanelCorner::TopRight => (egui::Align2::RIGHT_TOP, [-x, y]),
+        PanelCorner::BottomLeft => (egui::Align2::LEFT_BOTTOM, [x, -y]),
+        PanelCorner::BottomRight => (egui::Align2::RIGHT_BOTTOM, [-x, -y]),
+    }
+}
+
+/// How the dashboard fits in `available`: the side of its sky map, and
+/// whether the numbers and bars go beside the map rather than under it.
+fn dashboard_layout(available: egui::Vec2) -> (f32, bool) {
+    let wide = available.x >= DASHBOARD_WIDE;
+    let side = if wide {
+        (available.x / 2.0).min(available.y)
+    } else {
+        // Half the height stays for the numbers and bars
+        available.x.min(available.y / 2.0)
+    };
+    (side.max(120.0), wide)
+}
+
+/// White-on-red text for faults the user must not miss.
+fn alert_badge(text: &str) -> egui::RichText {
+    egui::RichText::new(text)
+        .strong()
+        .color(egui::Color32::WHITE)
+        .background_color(egui::Color32::RED)
+}
+
+/// `h:mm:ss` of a duration in seconds.
+fn format_hms(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// The color telling the `index`th source apart, on the shared sky map,
+/// track and status bar; `None` while it is the only one.
+fn source_accent(palette: Palette, index: usize, count: usize) -> Option<egui::Color32> {
+    (count > 1).then(|| palette.series(index))
+}
+
+/// Local date and time of day of a Unix time, to the second.
+fn clock_text(time: f64) -> String {
+    chrono::DateTime::from_timestamp(time as i64, 0).map_or(format!("{time:.0}"), |utc| {
+        utc.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string()
+    })
+}
+
+/// The signal score as a bar colored red, amber or green, with the points
+/// of each component on hover.
+fn draw_score_gauge(ui: &mut egui::Ui, score: &score::Score, palette: Palette) {
+    let color = palette.score(score.total);
+    ui.horizontal(|ui| {
+        ui.label("Signal score");
+        ui.add(
+            egui::ProgressBar::new(score.total / 100.0)
+                .desired_width(160.0)
+                .fill(color)
+                .text(format!("{:.0} / 100", score.total)),
+        )
+        .on_hover_text(score.breakdown());
+    });
+}
+
+/// Weights of the signal score components, each with how it is rated.
+fn draw_score_weights(ui: &mut egui::Ui, weights: &mut score::ScoreWeights) {
+    let snr_rule = format!(
+        "Of the used satellites; nothing at {} dB-Hz, full at {}",
+        score::SNR_FLOOR,
+        score::SNR_FULL
+    );
+    let hdop_rule = format!(
+        "Full at {} or better, nothing at {}",
+        score::HDOP_BEST,
+        score::HDOP_WORST
+    );
+    let rows = [
+        ("Satellites used", &mut weights.satellites, format!("Full at {}", score::FULL_SATELLITES)),
+        ("Mean SNR", &mut weights.snr, snr_rule),
+        ("HDOP", &mut weights.hdop, hdop_rule),
+        ("Fix type", &mut weights.fix, "Full for RTK fixed".to_string()),
+    ];
+    egui::Grid::new("score_weights").num_columns(2).show(ui, |ui| {
+        for (name, weight, rule) in rows {
+            ui.label(name).on_hover_text(rule);
+            ui.add(egui::DragValue::new(weight).clamp_range(0.0..=10.0).speed(0.05));
+            ui.end_row();
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Only the proportions matter.");
+        if ui.button("Equal").clicked() {
+            *weights = score::ScoreWeights::default();
+        }
+    });
+}
+
+/// Alarm threshold checkboxes, each with its limit.
+fn draw_alarm_thresholds(ui: &mut egui::Ui, thresholds: &mut AlarmThresholds) {
+    ui.horizontal(|ui| {
+        ui.label("Alarm when:");
+        ui.checkbox(&mut thresholds.fix_required, "No fix");
+        ui.checkbox(&mut thresholds.few_satellites, "Satellites used <");
+        ui.add(egui::DragValue::new(&mut thresholds.min_satellites).clamp_range(1..=12));
+        ui.checkbox(&mut thresholds.high_hdop, "HDOP >");
+        ui.add(
+            egui::DragValue::new(&mut thresholds.max_hdop)
+                .clamp_range(0.5..=50.0)
+                .speed(0.1),
+        );
+        ui.checkbox(&mut thresholds.weak_signal, "Best SNR <")
+            .on_hover_text("Strongest satellite in view, in dB-Hz");
+        ui.add(egui::DragValue::new(&mut thresholds.min_best_snr).clamp_range(0..=60));
+    });
+    ui.horizontal(|ui| {
+        ui.label("RTK:");
+        ui.checkbox(&mut thresholds.rtk_fixed_lost, "Fixed lost")
+            .on_hover_text("RTK fixed dropping to float or worse once it has been reached");
+        ui.checkbox(&mut thresholds.stale_rtk, "Correction age >");
+        ui.add(
+            egui::DragValue::new(&mut thresholds.max_rtk_age)
+                .clamp_range(1.0..=120.0)
+                .speed(0.5)
+                .suffix(" s"),
+        );
+    });
+    ui.label(format!(
+        "Checked on every fix; the last {} alarms per source are kept, active ones always.",
+        alarms::MAX_ALARMS
+    ));
+}
+
+/// When raised alarms flash the window: a per-kind interval and an
+/// optional quiet window.
+fn draw_notify_policy(ui: &mut egui::Ui, notify: &mut NotifyPolicy) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut notify.enabled, "Notify")
+            .on_hover_text("Ask for attention when an alarm is raised");
+        ui.add_enabled_ui(notify.enabled, |ui| {
+            ui.label("at most once per");
+            ui.add(
+                egui::DragValue::new(&mut notify.min_interval_secs)
+                    .clamp_range(0..=3600)
+                    .suffix(" s"),
+            );
+            ui.label("per alarm type");
+            ui.separator();
+            ui.checkbox(&mut notify.quiet_hours, "Quiet from");
+            ui.add(egui::DragValue::new(&mut notify.quiet_start).clamp_range(0..=23).suffix(":00"));
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut notify.quiet_end).clamp_range(0..=23).suffix(":00"));
+        });
+    });
+}
+
+/// The survey-in window and the HDOP screening of its fixes.
+fn draw_survey_config(ui: &mut egui::Ui, config: &mut SurveyConfig) {
+    ui.horizontal(|ui| {
+        ui.label("Average over");
+        match config.stop {
+            SurveyStop::Duration => {
+                ui.add(egui::DragValue::new(&mut config.duration_secs).clamp_range(10..=86_400))
+            }
+            SurveyStop::Count => {
+                ui.add(egui::DragValue::new(&mut config.count).clamp_range(2..=100_000))
+            }
+        };
+        egui::ComboBox::from_id_source("survey_stop")
+            .selected_text(config.stop.name())
+            .show_ui(ui, |ui| {
+                for option in SurveyStop::ALL {
+                    ui.selectable_value(&mut config.stop, option, option.name());
+                }
+            });
+        ui.label("Reject HDOP >");
+        ui.add(
+            egui::DragValue::new(&mut config.max_hdop)
+                .clamp_range(0.5..=50.0)
+                .speed(0.1),
+        );
+        ui.checkbox(&mut config.weight_by_hdop, "Weight by 1/HDOP²")
+            .on_hover_text("Fixes with better geometry count for more in the average");
+    });
+    ui.label("Settings apply to the next survey started; keep the antenna still meanwhile.");
+}
+
+/// Metres, kilometres past 1 km, with nautical miles alongside.
+fn format_distance(metres: f64) -> String {
+    let nautical_miles = metres / 1852.0;
+    if metres < 1000.0 {
+        format!("{metres:.0} m ({nautical_miles:.2} nmi)")
+    } else {
+        format!("{:.2} km ({nautical_miles:.2} nmi)", metres / 1000.0)
+    }
+}
+
+/// The low-memory caps, each no higher than the normal one.
+fn draw_buffer_caps(ui: &mut egui::Ui, caps: &mut BufferCaps) {
+    ui.horizontal(|ui| {
+        ui.label("Low-memory caps:");
+        let cap = |ui: &mut egui::Ui, name: &str, value: &mut usize, max: usize| {
+            ui.label(name);
+            ui.add(egui::DragValue::new(value).clamp_range(0..=max).speed(10));
+        };
+        cap(ui, "log lines", &mut caps.log_lines, MAX_LOG_LINES);
+        cap(ui, "track fixes", &mut caps.track_fixes, TRACK_LEN);
+        cap(ui, "fix history", &mut caps.snapshots, SNAPSHOT_HISTORY_LEN);
+        cap(ui, "SNR samples", &mut caps.snr_samples, SNR_HISTORY_LEN);
+        cap(ui, "trail points", &mut caps.trail_points, SKY_TRAIL_LEN);
+        ui.label("in-view history");
+        let max_mins = (VIEW_HISTORY_SECS / 60.0) as u32;
+        ui.add(egui::DragValue::new(&mut caps.view_history_mins).clamp_range(1..=max_mins))
+            .on_hover_text("Minutes");
+        if ui.button("Defaults").clicked() {
+            *caps = BufferCaps::LOW_MEMORY;
+        }
+    })
+    .response
+    .on_hover_text("A fix history of 0 turns the scrubber off, and 0 trail points the sky \
+                    trails");
+}
+
+/// A small arrow pointing `deg` clockwise from straight up.
+fn draw_arrow(ui: &mut egui::Ui, deg: f64) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(20.0, 20.0), egui::Sense::hover());
+    let (sin, cos) = (deg.to_radians() as f32).sin_cos();
+    let direction = egui::vec2(sin, -cos) * 16.0;
+    let stroke = egui::Stroke::new(2.0, ui.visuals().strong_text_color());
+    ui.painter().arrow(rect.center() - direction / 2.0, direction, stroke);
+    response
+}
+
+/// NaN or infinite coordinates, e.g. from a malformed field, break
+/// egui_plot's bounds and tessellation, so plot points are checked.
+fn is_finite_point([x, y]: [f64; 2]) -> bool {
+    x.is_finite() && y.is_finite()
+}
+
+/// Bounds around `[x, y]` points with a small margin, if there are any.
+fn fit_bounds(points: impl Iterator<Item = [f64; 2]>) -> Option<PlotBounds> {
+    let points = points.filter(|p| is_finite_point(*p));
+    let (min, max) = points.fold(None, |acc: Option<([f64; 2], [f64; 2])>, [x, y]| {
+        let (min, max) = acc.unwrap_or(([x, y], [x, y]));
+        Some(([min[0].min(x), min[1].min(y)], [max[0].max(x), max[1].max(y)]))
+    })?;
+    let margin = ((max[0] - min[0]).max(max[1] - min[1]) * 0.05).max(1e-4);
+    Some(PlotBounds::from_min_max(
+        [min[0] - margin, min[1] - margin],
+        [max[0] + margin, max[1] + margin],
+    ))
+}
+
+/// A frozen satellite set drawn as ghost markers on the sky map.
+struct SkySnapshot {
+    name: String,
+    satellites: Vec<Satellite>,
+    visible: bool,
+}
+
+/// One-shot sky map view change, applied on the next map draw.
+#[derive(Clone, Copy)]
+enum SkyFocus {
+    /// Zoom onto the selected satellite
+    Selected,
+    /// Back to the full sky
+    Reset,
+}
+
+/// Sky map orientation: north-up or rotated by the current heading,
+/// optionally mirrored east/west for a looking-up view.
+struct SkyView {
+    rotation: f64,
+    flip_east_west: bool,
+    projection: SkyProjection,
+}
+
+impl SkyView {
+    fn new(settings: &Settings, sources: &[&SourceState]) -> Self {
+        let heading = sources
+            .iter()
+            .find_map(|s| s.heading_up_rotation)
+            .filter(|_| settings.sky_heading_up);
+        Self {
+            rotation: heading.unwrap_or(0.0),
+            flip_east_west: settings.sky_flip_east_west,
+            projection: settings.sky_projection,
+        }
+    }
+
+    /// Projects azimuth/elevation (degrees) onto the unit sky map, or
+    /// `None` when either isn't a finite number.
+    fn project(&self, azimuth: f64, elevation: f64) -> Option<(f64, f64)> {
+        let az = (azimuth - self.rotation).to_radians();
+        let r = self.projection.radius(elevation);
+        let x = r * az.sin();
+        let point = [if self.flip_east_west { -x } else { x }, r * az.cos()];
+        is_finite_point(point).then_some((point[0], point[1]))
+    }
+
+    /// The satellites that can be drawn and where; those that project
+    /// nowhere are left off the map.
+    fn place<'a>(
+        &'a self,
+        satellites: impl Iterator<Item = &'a Satellite> + 'a,
+    ) -> impl Iterator<Item = (&'a Satellite, f64, f64)> + 'a {
+        satellites.filter_map(|sat| {
+            let (x, y) = self.project(sat.longitude, sat.latitude)?;
+            Some((sat, x, y))
+        })
+    }
+
+    /// Inverse of [`project`](Self::project): the azimuth/elevation under
+    /// a map point, or `None` outside the horizon.
+    fn unproject(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let x = if self.flip_east_west { -x } else { x };
+        let elevation = self.projection.elevation(x.hypot(y))?;
+        let azimuth = (x.atan2(y).to_degrees() + self.rotation).rem_euclid(360.0);
+        Some((azimuth, elevation))
+    }
+}
+
+// =====================================================================
+// Satellite Map Drawing Method
+// =====================================================================
+impl MyApp {
+    /// Draws the sky map for every source, as a square filling the space
+    /// given, and returns the source index and key of a satellite the user
+    /// clicked.
+    fn draw_satellite_map(
+        &self,
+        ui: &mut egui::Ui,
+        sources: &[&SourceState],
+        selected: Option<&(usize, String)>,
+        settings: &Settings,
+        focus: Option<SkyFocus>,
+        snapshots: &[SkySnapshot],
+    ) -> Option<(usize, String)> {
+        let mask = &settings.elevation_mask;
+        let view = &SkyView::new(settings, sources);
+        let radius = settings.sky_marker_radius.unwrap_or(3.0);
+        let label_scale = settings.sky_label_scale.unwrap_or(1.0);
+        let label_size = egui::TextStyle::Body.resolve(ui.style()).size * label_scale;
+        let label = |text: &str| egui::RichText::new(text).size(label_size);
+        let ring_color = ui.visuals().text_color();
+        let side = ui.available_size().min_elem();
+        Plot::new("satellite_map")
+            .width(side)
+            .height(side)
+            .view_aspect(1.0)
+            .include_x(-1.2)
+            .include_x(1.2)
+            .include_y(-1.2)
+            .include_y(1.2)
+            .show(ui, |plot_ui| {
+                // Draw outline circle
+                let circle: PlotPoints = (0..360)
+                    .map(|deg| {
+                        let rad = (deg as f64).to_radians();
+                        [rad.cos(), rad.sin()]
+                    })
+                    .collect::<Vec<_>>()
+                    .into();
+
+                plot_ui.line(Line::new(circle));
+
+                for (azimuth, name) in [(0.0, "N"), (90.0, "E"), (180.0, "S"), (270.0, "W")] {
+                    let Some((x, y)) = view.project(azimuth, 0.0) else {
+                        continue;
+                    };
+                    plot_ui.text(
+                        Text::new([x * 1.12, y * 1.12].into(), name).color(egui::Color32::GRAY),
+                    );
+                }
+
+                // Shade the masked band between the horizon and the mask limit
+                let shade = egui::Color32::from_gray(128).gamma_multiply(0.35);
+                for step in (0..360).step_by(MASK_STEP_DEG) {
+                    let (a0, a1) = (step as f64, (step + MASK_STEP_DEG) as f64);
+                    let (l0, l1) = (mask.limit_at(a0), mask.limit_at(a1));
+                    if l0 <= 0.0 && l1 <= 0.0 {
+                        continue;
+                    }
+                    let corners = [
+                        view.project(a0, 0.0),
+                        view.project(a1, 0.0),
+                        view.project(a1, l1.max(0.0)),
+                        view.project(a0, l0.max(0.0)),
+                    ];
+                    let Some(quad) = corners
+                        .into_iter()
+                        .map(|corner| corner.map(|(x, y)| [x, y]))
+                        .collect::<Option<Vec<_>>>()
+                    else {
+                        continue;
+                    };
+                    let quad: PlotPoints = quad.into();
+                    plot_ui.polygon(
+                        Polygon::new(quad)
+                            .fill_color(shade)
+                            .stroke(egui::Stroke::NONE),
+                    );
+                }
+
+                // Ghosts of snapshot skies, under the live satellites
+                for (index, snapshot) in snapshots.iter().enumerate() {
+                    if !snapshot.visible {
+                        continue;
+                    }
+                    let color = settings.palette.snapshot(index).gamma_multiply(0.6);
+                    for (_, x, y) in view.place(snapshot.satellites.iter()) {
+                        plot_ui.points(
+                            Points::new(vec![[x, y]])
+                                .radius(radius * 5.0 / 3.0)
+                                .filled(false)
+                                .shape(MarkerShape::Diamond)
+                                .color(color)
+                                .name(&snapshot.name),
+                        );
+                    }
+                }
+
+                // Draw satellites
+                let mut positions = Vec::new();
+                let mut pdops = Vec::new();
+                for (index, source) in sources.iter().enumerate() {
+                    let accent = source_accent(settings.palette, index, sources.len());
+                    let geometry = settings.sky_geometry.then(|| source.geometry()).flatten();
+                    if let Some((_, geometry)) = &geometry {
+                        pdops.push(format!("{} PDOP {:.1}", source.label, geometry.pdop));
+                    }
+                    if settings.sky_trails {
+                        for sat in source.visible_satellites(settings) {
+                            let Some(trail) = source.sky_trails.get(&sat.key()) else {
+                                continue;
+                            };
+                            let color = accent
+                                .unwrap_or(settings.palette.constellation(sat.constellation));
+                            let samples: Vec<[f64; 2]> = trail.values().copied().collect();
+                            for segment in satellite::trail_segments(&samples) {
+                                let points: Option<Vec<[f64; 2]>> = segment
+                                    .iter()
+                                    .map(|&[az, el]| view.project(az, el).map(|(x, y)| [x, y]))
+                                    .collect();
+                                if let Some(points) = points {
+                                    let line = Line::new(PlotPoints::from(points));
+                                    plot_ui.line(line.color(color.gamma_multiply(0.5)));
+                                }
+                            }
+                        }
+                    }
+                    for (sat, x, y) in view.place(source.visible_satellites(settings)) {
+                        let key = sat.key();
+                        // Wider, stronger rings for the satellites the
+                        // position would suffer most without
+                        let share = geometry.as_ref().and_then(|(keys, geometry)| {
+                            let index = keys.iter().position(|k| *k == key)?;
+                            Some(geometry.share(index))
+                        });
+                        if let Some(share) = share {
+                            plot_ui.points(
+                                Points::new(vec![[x, y]])
+                                    .radius(radius * (1.4 + 1.6 * share))
+                                    .filled(false)
+                                    .color(ring_color.gamma_multiply(0.2 + 0.8 * share)),
+                            );
+                        }
+                        let mut marker = Points::new(vec![[x, y]])
+                            .radius(radius)
+                            .shape(SOURCE_MARKERS[index]);
+                        let mut text = Text::new([x, y].into(), label(&sat.id));
+                        if let Some(accent) = accent {
+                            marker = marker.color(accent);
+                            text = text.color(accent);
+                        }
+                        if source.is_new(&key) {
+                            let flash = radius * 5.0 / 3.0;
+                            marker = marker.radius(flash).color(settings.palette.acquired());
+                        } else if mask.is_masked(sat.longitude, sat.latitude) {
+                            marker = marker.color(settings.palette.masked());
+                        }
+                        if source.is_held(&key) {
+                            marker = marker.color(settings.palette.held());
+                            text = text.color(settings.palette.held());
+                        }
+                        plot_ui.points(marker);
+                        plot_ui.text(text);
+
+                        if source.favorites.contains(&key) {
+                            plot_ui.points(
+                                Points::new(vec![[x, y]])
+                                    .radius(radius * 2.0)
+                                    .filled(false)
+                                    .shape(MarkerShape::Asterisk)
+                                    .color(settings.palette.favorite()),
+                            );
+                        }
+                        if selected.is_some_and(|(i, k)| *i == index && *k == key) {
+                            plot_ui.points(
+                                Points::new(vec![[x, y]])
+                                    .radius(radius * 7.0 / 3.0)
+                                    .filled(false)
+                                    .color(settings.palette.selection()),
+                            );
+                            // The GSV fields behind the marker, to check the projection
+                            let mapping = &source.decoder.gsv_mapping;
+                            let reported =
+                                parser::gsv_reported_position(&sat.sentence, &sat.id, mapping);
+                            if let Some((el, az)) = reported.filter(|_| settings.debug_panel) {
+                                plot_ui.text(
+                                    Text::new([x, y - 0.06].into(), format!("GSV az {az} el {el}"))
+                                        .anchor(egui::Align2::CENTER_TOP)
+                                        .color(settings.palette.selection()),
+                                );
+                            }
+                        }
+
+                        positions.push(((index, key), x, y));
+                    }
+
+                    // Fade out satellites that just disappeared
+                    for (sat, lost) in &source.lost_satellites {
+                        let fade = 1.0 - lost.elapsed().as_secs_f32() / LOSS_FADE.as_secs_f32();
+                        if fade <= 0.0 {
+                            continue;
+                        }
+                        let Some((x, y)) = view.project(sat.longitude, sat.latitude) else {
+                            continue;
+                        };
+                        let color = egui::Color32::GRAY.gamma_multiply(fade);
+
+                        plot_ui.points(
+                            Points::new(vec![[x, y]])
+                                .radius(radius)
+                                .shape(SOURCE_MARKERS[index])
+                                .color(color),
+                        );
+                        plot_ui.text(Text::new([x, y].into(), label(&sat.id)).color(color));
+                    }
+                }
+
+                match focus {
+                    Some(SkyFocus::Selected) => {
+                        let target = positions.iter().find(|(key, ..)| Some(key) == selected);
+                        if let Some((_, x, y)) = target {
+                            plot_ui.set_plot_bounds(PlotBounds::from_min_max(
+                                [x - SKY_FOCUS_HALF_WIDTH, y - SKY_FOCUS_HALF_WIDTH],
+                                [x + SKY_FOCUS_HALF_WIDTH, y + SKY_FOCUS_HALF_WIDTH],
+                            ));
+                        }
+                    }
+                    Some(SkyFocus::Reset) => {
+                        plot_ui.set_plot_bounds(PlotBounds::from_min_max([-1.2, -1.2], [1.2, 1.2]));
+                    }
+                    None => {}
+                }
+
+                if !pdops.is_empty() {
+                    let bounds = plot_ui.plot_bounds();
+                    plot_ui.text(
+                        Text::new(bounds.min().into(), format!("Geometry {}", pdops.join(", ")))
+                            .anchor(egui::Align2::LEFT_BOTTOM)
+                            .color(ring_color),
+                    );
+                }
+
+                if settings.used_only {
+                    let bounds = plot_ui.plot_bounds();
+                    plot_ui.text(
+                        Text::new(bounds.max().into(), "Used in fix only")
+                            .anchor(egui::Align2::RIGHT_TOP)
+                            .color(WARNING_COLOR),
+                    );
+                }
+                let scrubbed: Vec<String> = sources
+                    .iter()
+                    .filter_map(|s| s.scrub.map(|t| format!("{} {}", s.label, clock_text(t))))
+                    .collect();
+                if !scrubbed.is_empty() {
+                    let bounds = plot_ui.plot_bounds();
+                    let corner = PlotPoint::new(bounds.min()[0], bounds.max()[1]);
+                    plot_ui.text(
+                        Text::new(corner, format!("History: {}", scrubbed.join(", ")))
+                            .anchor(egui::Align2::LEFT_TOP)
+                            .color(WARNING_COLOR),
+                    );
+                }
+
+                // Sky direction under the cursor, for aiming an antenna
+                let hovered = plot_ui.response().hovered();
+                let pointer = plot_ui.pointer_coordinate().filter(|_| hovered);
+                let under_cursor = pointer.and_then(|p| view.unproject(p.x, p.y));
+                if let Some((azimuth, elevation)) = under_cursor {
+                    let bounds = plot_ui.plot_bounds();
+                    let corner = [bounds.min()[0], bounds.max()[1]];
+                    plot_ui.text(
+                        Text::new(corner.into(), format!("Az {azimuth:.0}°  El {elevation:.0}°"))
+                            .anchor(egui::Align2::LEFT_TOP)
+                            .color(egui::Color32::LIGHT_BLUE),
+                    );
+                }
+
+                // Pick the nearest satellite to a click, if it's close enough
+                if !plot_ui.response().clicked() {
+                    return None;
+                }
+                let pointer = plot_ui.pointer_coordinate()?;
+                positions
+                    .into_iter()
+                    .map(|(key, x, y)| (key, (x - pointer.x).hypot(y - pointer.y)))
+                    .filter(|(_, dist)| *dist < 0.1)
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(key, _)| key)
+            })
+            .inner
+    }
+
+    /// Replaces the reader thread if no data has arrived within the
+    /// watchdog timeout while reading.
+    fn check_watchdog(&self, source_arc: &Arc<Mutex<SourceState>>, settings: &Settings) {
+        let timeout_secs = settings.watchdog_timeout_secs;
+        let mut source = source_arc.lock().unwrap_or_else(PoisonError::into_inner);
+        let timeout = Duration::from_secs_f32(timeout_secs);
+        if !source.mode.is_active()
+            || source.kind != SourceKind::Serial
+            || source.respawn_pending
+            || source.last_data.is_none_or(|t| t.elapsed() <= timeout)
+        {
+            return;
+        }
+        let Some(port_name) = source.selected_port.clone() else {
+            return;
+        };
+
+        // The new reader is started by `settle_stop` once this one exits, as
+        // it holds the port until its current read times out
+        if let Some(abort) = &source.reader_abort {
+            abort.store(true, Ordering::Relaxed);
+        }
+        source.push_log(format!(
+            "[watchdog] no data from {port_name} for {timeout_secs:.0} s, restarting reader"
+        ));
+        source.health.reconnects += 1;
+        source.mode = AppMode::Reconnecting;
+        source.respawn_pending = true;
+        source.last_data = Some(Instant::now());
+    }
+
+    /// Retries a busy port while "Wait for the port" is on, until it opens,
+    /// fails some other way or the wait runs out.
+    fn wait_for_port(&self, source_arc: &Arc<Mutex<SourceState>>, settings: &Settings) {
+        let mut source = source_arc.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(mut wait) = source.port_wait else {
+            return;
+        };
+        let port = source.selected_port.clone().unwrap_or_default();
+        match (&source.mode, source.health.fault) {
+            (AppMode::Connecting, _) => return,
+            (AppMode::Reading, _) => {
+                let tries = wait.attempts;
+                source.push_log(format!("[open] {port} was released, opened after {tries} tries"));
+                source.port_wait = None;
+                return;
+            }
+            (_, Some(PortFault::InUse)) => {}
+            // Stopped, or failing for another reason that its message explains
+            _ => {
+                source.port_wait = None;
+                return;
+            }
+        }
+        match wait.step(Instant::now()) {
+            WaitStep::Wait => {}
+            WaitStep::Retry => source.start_serial(source_arc, settings),
+            WaitStep::GiveUp => {
+                let limit = reader::PORT_WAIT_LIMIT.as_secs();
+                source.push_log(format!("[open] gave up waiting {limit} s for {port}"));
+                source.port_wait = None;
+                return;
+            }
+        }
+        source.port_wait = Some(wait);
+    }
+
+    /// Rescans the ports every few seconds while auto-start is on, and
+    /// starts the first free source on a matching device that appeared.
+    fn scan_for_devices(
+        &self,
+        ctx: &egui::Context,
+        state: &mut AppState,
+        source_arcs: &[Arc<Mutex<SourceState>>],
+        sources: &mut [MutexGuard<SourceState>],
+    ) {
+        if !state.settings.auto_start.enabled {
+            state.port_scan = None;
+            state.port_scan_thread = None;
+            return;
+        }
+        ctx.request_repaint_after(autostart::SCAN_INTERVAL);
+        // Enumerating can take a while on some systems, so it runs off the
+        // UI thread and the result is taken up on a later frame
+        if !state.port_scan_thread.as_ref().is_some_and(|t| t.is_finished()) {
+            if state.port_scan_thread.is_none()
+                && state.port_scan.is_none_or(|t| t.elapsed() >= autostart::SCAN_INTERVAL)
+            {
+                state.port_scan = Some(Instant::now());
+                let ctx = ctx.clone();
+                state.port_scan_thread = Some(std::thread::spawn(move || {
+                    let ports = enumerate_port_entries();
+                    ctx.request_repaint();
+                    ports
+                }));
+            }
+            return;
+        }
+        let scanned = state.port_scan_thread.take().map(|t| t.join());
+        let ports = match scanned {
+            Some(Ok(Ok(ports))) => ports,
+            Some(Ok(Err(e))) => {
+                state.port_error = Some(e);
+                return;
+            }
+            _ => return,
+        };
+        state.ports = ports.iter().map(|p| p.name.clone()).collect();
+        state.port_error = None;
+
+        // Idle, or retrying a port that has gone while the device came
+        // back under another name
+        let listed = |port: &Option<String>| port.as_ref().is_some_and(|p| state.ports.contains(p));
+        let free = sources.iter().position(|s| match s.mode {
+            AppMode::Idle | AppMode::Error(_) => true,
+            AppMode::Reconnecting => !listed(&s.selected_port),
+            _ => false,
+        });
+        let config = &state.settings.auto_start;
+        let action = state.auto_starter.scan(config, &ports, free.is_some(), Instant::now());
+        state.detected_ports = ports;
+        let (Some(index), Some(action)) = (free, action) else {
+            return;
+        };
+        let source = &mut sources[index];
+        match action {
+            AutoStartAction::Start(port) => {
+                if let Some(abort) = source.reader_abort.take() {
+                    abort.store(true, Ordering::Relaxed);
+                }
+                source.push_log(format!("[auto-start] {port} detected, starting to read"));
+                source.selected_port = Some(port);
+                source.start_serial(&source_arcs[index], &state.settings);
+            }
+            AutoStartAction::HoldOff(port) => source.push_log(format!(
+                "[auto-start] {port} keeps dropping out; waiting before starting it again"
+            )),
+        }
+    }
+
+    fn draw_status_line(
+        &self,
+        ui: &mut egui::Ui,
+        source: &SourceState,
+        accent: Option<egui::Color32>,
+        settings: &Settings,
+    ) {
+        let (diagnostics, bearing) = (settings.diagnostics, settings.bearing_unit);
+        let used = source.satellites.iter().filter(|s| s.used_in_fix).count();
+        let (fix_type, hdop) = match &source.fix {
+            Some(fix) => (
+                fix.quality_name(),
+                fix.hdop.map_or("—".to_string(), |h| format!("{h:.1}")),
+            ),
+            None => ("No fix", "—".to_string()),
+        };
+
+        let origin = match source.kind {
+            SourceKind::Serial => format!(
+                "{} @ {}",
+                source.selected_port.as_deref().unwrap_or("no port"),
+                source.baud_rate
+            ),
+            SourceKind::Replay => "file replay".to_string(),
+            SourceKind::Tail => format!("following {}", source.tail_path.trim()),
+            SourceKind::Simulator => "simulator".to_string(),
+            SourceKind::Tcp => format!(
+                "repeater on 127.0.0.1:{}",
+                source.handoff_port.map_or("?".to_string(), |p| p.to_string())
+            ),
+        };
+
+        ui.horizontal(|ui| {
+            let name = egui::RichText::new(format!("{}: {origin}", source.label));
+            ui.label(accent.map_or(name.clone(), |color| name.color(color)));
+            ui.separator();
+            ui.label(source.connection_state());
+            let serial = source.kind == SourceKind::Serial;
+            if let Some(device) = source.device_text().filter(|_| serial) {
+                ui.separator();
+                ui.label(device);
+            }
+            if let Some(protocol) = source.protocol {
+                ui.separator();
+                ui.label(format!("Protocol: {}", protocol.name()));
+            }
+            ui.separator();
+            ui.label(fix_type);
+            if let Some(mode) = source.fix_mode {
+                // GSA and GGA can briefly disagree around acquisition and loss
+                let has_position = source.fix.as_ref().is_some_and(|f| f.quality > 0);
+                if has_position == (mode == FixMode::NoFix) {
+                    ui.colored_label(WARNING_COLOR, format!("{} (GSA)", mode.name()))
+                        .on_hover_text("GSA fix mode disagrees with the GGA quality");
+                } else {
+                    ui.label(mode.name());
+                }
+            }
+            match source.rmc.as_ref().and_then(|r| r.nav_status) {
+                Some(NavStatus::Simulator) => {
+                    ui.separator();
+                    ui.label(alert_badge("⚠ SIMULATOR MODE")).on_hover_text(
+                        "The receiver reports simulated output, not a real position",
+                    );
+                }
+                Some(status) => {
+                    ui.separator();
+                    ui.label(format!("Nav: {}", status.name()));
+                }
+                None => {}
+            }
+            match source.antenna {
+                Some(status) if status.is_fault() => {
+                    ui.separator();
+                    ui.label(alert_badge(&format!("⚠ ANTENNA {}", status.name().to_uppercase())))
+                        .on_hover_text("Check the antenna cable and connector");
+                }
+                Some(_) => {
+                    ui.separator();
+                    ui.label("Antenna OK");
+                }
+                None => {}
+            }
+            ui.separator();
+            ui.label(format!("Sats {used}/{}", source.satellites.len()));
+            if let Some(systems) = source.fix_constellations() {
+                ui.label(format!("Fix from {systems}"));
+            }
+            ui.separator();
+            ui.label(format!("HDOP {hdop}"));
+            if let Some(fix) = source.fix.as_ref().filter(|f| f.is_rtk()) {
+                ui.separator();
+                let text = egui::RichText::new(fix.quality_name().to_uppercase())
+                    .strong()
+                    .color(settings.palette.quality(fix.quality));
+                ui.label(text).on_hover_text("GGA fix quality 4 is RTK fixed, 5 RTK float");
+                if let Some(age) = fix.dgps_age {
+                    let max_age = settings.alarm_thresholds.max_rtk_age;
+                    let station = fix.dgps_station.as_deref().unwrap_or("—");
+                    let age_text = format!("correction age {age:.1} s, station {station}");
+                    if age > max_age {
+                        ui.colored_label(WARNING_COLOR, format!("⚠ {age_text}"))
+                            .on_hover_text(format!("Older than the {max_age:.0} s alarm limit"));
+                    } else {
+                        ui.label(age_text);
+                    }
+                }
+            } else if let Some(fix) = source.fix.as_ref().filter(|f| f.is_differential()) {
+                if let Some(age) = fix.dgps_age {
+                    ui.separator();
+                    ui.label(format!(
+                        "DGPS age: {age:.1}s, station: {}",
+                        fix.dgps_station.as_deref().unwrap_or("—")
+                    ));
+                }
+            }
+            if let Some(rmc) = source.rmc.as_ref().filter(|r| r.valid) {
+                if let (Some(speed), Some(course)) = (rmc.speed_knots, rmc.course) {
+                    ui.separator();
+                    let course = bearing.format(course, rmc.variation, 0);
+                    ui.label(format!("{speed:.1} kn, course {course}"));
+                }
+            }
+            ui.separator();
+            ui.label(format!("{} sentences/s", source.sentences_per_second()));
+            if let Some(warning) = source.fix_loss_warning() {
+                ui.separator();
+                ui.colored_label(WARNING_COLOR, format!("⚠ {warning}"));
+            }
+            let alarms: Vec<&str> = source.alarms.active().map(|a| a.detail.as_str()).collect();
+            if !alarms.is_empty() {
+                ui.separator();
+                ui.label(alert_badge(&format!("⚠ {} ALARM", alarms.len())))
+                    .on_hover_text(alarms.join("\n"));
+            }
+            if diagnostics && source.connection_state() == "Reading" {
+                let live =
+                    matches!(source.kind, SourceKind::Serial | SourceKind::Tail | SourceKind::Tcp);
+                let now = live.then(unix_now);
+                let warnings =
+                    diagnostics::time_warnings(source.fix.as_ref(), source.rmc.as_ref(), now);
+                for warning in warnings {
+                    ui.separator();
+                    ui.colored_label(WARNING_COLOR, format!("⚠ {warning}"));
+                }
+                self.draw_fix_rate(ui, source);
+            }
+        });
+    }
+
+    /// The fix update rate by receiver time and by arrival, flagged when
+    /// it falls short of the rate last configured.
+    fn draw_fix_rate(&self, ui: &mut egui::Ui, source: &SourceState) {
+        let (Some(reported), Some(received)) =
+            (source.fix_rate.reported_hz(), source.fix_rate.received_hz())
+        else {
+            return;
+        };
+        ui.separator();
+        let text = format!(
+            "Fixes {} by receiver time, {} received",
+            inventory::rate_text(reported),
+            inventory::rate_text(received)
+        );
+        let hover = format!(
+            "Over the last {:.0} s of GGA/RMC. Receiver time below arrival time means \
+             fixes are delayed or lost on the way",
+            rate::WINDOW_SECS
+        );
+        match source.expected_rate_hz {
+            Some(expected) if reported.min(received) < expected * rate::SHORTFALL => {
+                let text = format!("⚠ {text}, expected {}", inventory::rate_text(expected));
+                ui.colored_label(WARNING_COLOR, text).on_hover_text(hover);
+            }
+            Some(expected) => {
+                let text = format!("{text}, expected {}", inventory::rate_text(expected));
+                ui.label(text).on_hover_text(hover);
+            }
+            None => {
+                ui.label(text).on_hover_text(hover);
+            }
+        }
+    }
+
+    fn draw_mask_editor(&self, ui: &mut egui::Ui, mask: &mut ElevationMask) {
+        ui.horizontal(|ui| {
+            ui.label("Minimum elevation (°):");
+            ui.add(egui::DragValue::new(&mut mask.min_elevation).clamp_range(0.0..=90.0));
+        });
+
+        let mut remove = None;
+        for (i, sector) in mask.sectors.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label("Azimuth");
+                ui.add(egui::DragValue::new(&mut sector.from_azimuth).clamp_range(0.0..=360.0));
+                ui.label("to");
+                ui.add(egui::DragValue::new(&mut sector.to_azimuth).clamp_range(0.0..=360.0));
+                ui.label("min elevation");
+                ui.add(egui::DragValue::new(&mut sector.min_elevation).clamp_range(0.0..=90.0));
+                if ui.small_button("✖").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            mask.sectors.remove(i);
+        }
+
+        if ui.button("Add sector").clicked() {
+            mask.sectors.push(MaskSector {
+                from_azimuth: 0.0,
+                to_azimuth: 90.0,
+                min_elevation: 15.0,
+            });
+        }
+    }
+
+    /// Opens a fresh satellite CSV whenever a source starts reading with
+    /// logging on, and closes it when either stops. A failed file stays
+    /// closed until logging is toggled.
+    fn sync_satellite_log(&self, source: &mut SourceState, settings: &Settings) {
+        if !settings.satellite_csv {
+            source.sat_log = None;
+            source.sat_log_error = None;
+        } else if !source.mode.is_active() {
+            source.sat_log = None;
+        } else if source.sat_log.is_none() && source.sat_log_error.is_none() {
+            let dir = Path::new(settings.satellite_csv_dir.trim());
+            let decimator = settings.decimation.decimator();
+            match SatelliteLog::create(dir, &source.label, unix_now(), decimator) {
+                Ok(log) => source.sat_log = Some(log),
+                Err(e) => source.sat_log_error = Some(e),
+            }
+        }
+        if let Some(log) = source.sat_log.as_mut() {
+            if let Err(e) = log.sync_if_due(settings.flush_interval()) {
+                source.sat_log = None;
+                source.sat_log_error = Some(e);
+            }
+        }
+    }
+
+    /// Opens and closes the auto-save log with reading, as for the
+    /// satellite CSV, and syncs it every flush interval.
+    fn sync_autosave_log(&self, source: &mut SourceState, settings: &Settings) {
+        if !settings.autosave_log {
+            source.autosave = None;
+            source.autosave_error = None;
+        } else if !source.mode.is_active() {
+            source.autosave = None;
+        } else if source.autosave.is_none() && source.autosave_error.is_none() {
+            let dir = Path::new(settings.autosave_dir.trim());
+            match AutosaveLog::create(dir, &source.label, unix_now()) {
+                Ok(log) => source.autosave = Some(log),
+                Err(e) => source.autosave_error = Some(e),
+            }
+        }
+        if let Some(log) = source.autosave.as_mut() {
+            if let Err(e) = log.sync_if_due(settings.flush_interval()) {
+                source.autosave = None;
+                source.autosave_error = Some(e);
+            }
+        }
+    }
+
+    /// The first-run guide: finding the receiver's port, choosing a baud
+    /// rate, and the simulator or a log file for trying the app without a
+    /// receiver. Whatever it starts runs on the first source.
+    fn draw_onboarding(
+        &self,
+        ctx: &egui::Context,
+        state: &mut AppState,
+        source_arc: &Arc<Mutex<SourceState>>,
+        source: &mut SourceState,
+    ) {
+        let Some(step) = state.onboarding_step else {
+            return;
+        };
+        let mut next_step = Some(step);
+        let (mut done, mut replayed) = (false, false);
+        egui::Window::new("Getting started")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.set_max_width(420.0);
+                let idle = source.mode.is_idle();
+                match step {
+                    0 => {
+                        ui.label(
+                            "This app reads the NMEA sentences a GNSS receiver sends over a \
+                             serial port and shows the fix, the satellites in view and their \
+                             signal strength.",
+                        );
+                        ui.label("Plug the receiver in and check that its port is listed:");
+                        if state.ports.is_empty() {
+                            ui.colored_label(WARNING_COLOR, "No serial ports found yet.");
+                            ui.label(missing_port_hint());
+                        }
+                        for port in &state.ports {
+                            ui.monospace(port);
+                        }
+                        if ui.button("Refresh Ports").clicked() {
+                            match enumerate_ports() {
+                                Ok(ports) => state.ports = ports,
+                                Err(e) => state.port_error = Some(e),
+                            }
+                        }
+                    }
+                    1 => {
+                        ui.label(
+                            "Pick the receiver's port and baud rate. Most NMEA receivers talk \
+                             at 9600 or 4800 baud; u-blox modules are often set to 38400 or \
+                             115200. At the wrong rate the log fills with garbage and a \
+                             warning appears under the baud rate.",
+                        );
+                        ui.add_enabled_ui(idle, |ui| {
+                            egui::ComboBox::from_id_source("onboarding_port")
+                                .selected_text(
+                                    source.selected_port.as_deref().unwrap_or("Select a Port"),
+                                )
+                                .show_ui(ui, |cb| {
+                                    for port in &state.ports {
+                                        let port_choice = &mut source.selected_port;
+                                        cb.selectable_value(port_choice, Some(port.clone()), port);
+                                    }
+                                });
+                            egui::ComboBox::from_id_source("onboarding_baud")
+                                .selected_text(format!("{} baud", source.baud_rate))
+                                .show_ui(ui, |cb| {
+                                    for baud in BAUD_RATES {
+                                        let text = baud.to_string();
+                                        cb.selectable_value(&mut source.baud_rate, baud, text);
+                                    }
+                                });
+                        });
+                        let can_start = idle && source.selected_port.is_some();
+                        if ui.add_enabled(can_start, egui::Button::new("Start Reading")).clicked() {
+                            source.start_serial(source_arc, &state.settings);
+                            done = true;
+                        }
+                    }
+                    _ => {
+                        ui.label("No receiver to hand? Either of these works without one:");
+                        let simulate = ui
+                            .add_enabled(idle, egui::Button::new("Start the simulator"))
+                            .on_hover_text("A made-up receiver moving through a made-up sky");
+                        if simulate.clicked() {
+                            source.start_simulator(source_arc);
+                            done = true;
+                        }
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut source.replay_path)
+                                    .hint_text("drive.nmea"),
+                            )
+                            .on_hover_text("A captured NMEA log; gzipped ones are decompressed");
+                            let can_replay = idle && !source.replay_path.trim().is_empty();
+                            if ui.add_enabled(can_replay, egui::Button::new("Replay")).clicked() {
+                                source.start_replay(source_arc, source.replay_timed, 0.0);
+                                replayed = true;
+                            }
+                        });
+                        if let Some(err) = &source.replay_error {
+                            ui.colored_label(egui::Color32::RED, err);
+                        }
+                        ui.label("Both are also under each source's controls.");
+                    }
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} of {ONBOARDING_STEPS}", step + 1));
+                    if step > 0 && ui.button("Back").clicked() {
+                        next_step = Some(step - 1);
+                    }
+                    if step + 1 < ONBOARDING_STEPS && ui.button("Next").clicked() {
+                        next_step = Some(step + 1);
+                    }
+                    let close = if step + 1 < ONBOARDING_STEPS { "Skip" } else { "Done" };
+                    if ui.button(close).clicked() {
+                        done = true;
+                    }
+                });
+            });
+        // A replay that failed to open keeps the guide up to show why
+        done |= replayed && source.replay_error.is_none();
+        if done {
+            state.onboarding_step = None;
+            state.settings.onboarding_done = true;
+        } else {
+            state.onboarding_step = next_step;
+        }
+    }
+
+    /// Starts, restarts or stops the repeater to match the settings. A
+    /// port that fails to open turns it back off rather than retrying.
+    fn sync_repeater(&self, state: &mut AppState) {
+        let wanted = state.settings.repeater_config();
+        let failure = state.repeater.as_ref().and_then(Repeater::failure);
+        if failure.is_none() && state.repeater.as_ref().map(|r| &r.config) == wanted.as_ref() {
+            return;
+        }
+
+        let running = state.repeater.take();
+        let started = match (failure, wanted) {
+            (Some(failure), _) => Err(failure),
+            (None, Some(config)) => match running {
+                Some(repeater) => repeater.restart(config),
+                None => Repeater::start(config),
+            },
+            (None, None) => return,
+        };
+        match started {
+            Ok(repeater) => {
+                state.repeater = Some(repeater);
+                state.repeater_error = None;
+            }
+            Err(e) => {
+                state.repeater_error = Some(e);
+                state.settings.repeater_enabled = false;
+            }
+        }
+    }
+
+    /// Advertises the repeater's TCP port while it repeats an open serial
+    /// port, so another instance finding that port busy can read from it.
+    fn sync_repeater_advert(&self, state: &mut AppState, sources: &[MutexGuard<SourceState>]) {
+        let repeated = sources.get(state.settings.repeater_source);
+        let port_name = repeated
+            .filter(|s| s.kind == SourceKind::Serial && s.mode.is_active())
+            .and_then(|s| s.selected_port.as_deref());
+        let wanted = port_name.zip(state.repeater.as_ref().and_then(|r| r.tcp_port));
+        let current = state.repeater_advert.as_ref().map(|a| (a.port_name.as_str(), a.tcp_port));
+        if current == wanted {
+            return;
+        }
+        state.repeater_advert = None;
+        if let Some((port_name, tcp_port)) = wanted {
+            match Advert::publish(port_name, tcp_port) {
+                Ok(advert) => state.repeater_advert = Some(advert),
+                Err(e) => state.repeater_error = Some(format!("Advertising the repeater: {e}")),
+            }
+        }
+    }
+
+    fn draw_repeater_controls(&self, ui: &mut egui::Ui, state: &mut AppState, labels: &[String]) {
+        let settings = &mut state.settings;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.repeater_enabled, "Repeat source")
+                .on_hover_text("Send everything the source receives on to other programs");
+            let current = labels.get(settings.repeater_source).map_or("—", String::as_str);
+            egui::ComboBox::from_id_source("repeater_source")
+                .selected_text(current)
+                .show_ui(ui, |cb| {
+                    for (index, label) in labels.iter().enumerate() {
+                        cb.selectable_value(&mut settings.repeater_source, index, label);
+                    }
+                });
+            ui.checkbox(&mut settings.repeater_forward_invalid, "Include bad checksums")
+                .on_hover_text("Also pass on lines failing their checksum, and lines that \
+                                aren't sentences, exactly as received");
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.repeater_tcp, "TCP on 127.0.0.1 port");
+            let mut port = state.repeater_port_edit.unwrap_or(settings.repeater_tcp_port);
+            if ui.add(egui::DragValue::new(&mut port).clamp_range(1024..=65535)).changed() {
+                state.repeater_port_edit = Some(port);
+            }
+            let edited = state.repeater_port_edit.filter(|p| *p != settings.repeater_tcp_port);
+            if ui.add_enabled(edited.is_some(), egui::Button::new("Apply")).clicked() {
+                settings.repeater_tcp_port = port;
+                state.repeater_port_edit = None;
+            }
+            if let Some(repeater) = state.repeater.as_ref().filter(|r| r.tcp_port.is_some()) {
+                ui.label(format!("{} clients", repeater.clients()));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Serial port:");
+            let mut name = state
+                .repeater_serial_edit
+                .clone()
+                .unwrap_or_else(|| settings.repeater_serial_port.clone());
+            let edit = ui
+                .add(
+                    egui::TextEdit::singleline(&mut name)
+                        .hint_text("none")
+                        .desired_width(120.0),
+                )
+                .on_hover_text("e.g. one end of a virtual null-modem pair, or a second adapter");
+            if edit.changed() {
+                state.repeater_serial_edit = Some(name.clone());
+            }
+            let edited = state.repeater_serial_edit.as_ref();
+            let edited = edited.filter(|n| n.trim() != settings.repeater_serial_port);
+            if ui.add_enabled(edited.is_some(), egui::Button::new("Apply")).clicked() {
+                settings.repeater_serial_port = name.trim().to_string();
+                state.repeater_serial_edit = None;
+            }
+            egui::ComboBox::from_id_source("repeater_baud")
+                .selected_text(format!("{} baud", settings.repeater_baud))
+                .show_ui(ui, |cb| {
+                    for baud in BAUD_RATES {
+                        cb.selectable_value(&mut settings.repeater_baud, baud, baud.to_string());
+                    }
+                });
+        });
+        if let Some(err) = &state.repeater_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+    }
+
+    /// Starts, restarts or stops the status server to match the settings.
+    /// A failed bind turns the toggle back off rather than retrying.
+    #[cfg(feature = "http")]
+    fn sync_http_server(&self, state: &mut AppState) {
+        let wanted = state.settings.http_enabled.then_some(state.settings.http_port);
+        if state.http_server.as_ref().map(|s| s.port) == wanted {
+            return;
+        }
+
+        state.http_server = None;
+        if let Some(port) = wanted {
+            match http::start(Arc::clone(&self.state), port) {
+                Ok(server) => {
+                    state.http_server = Some(server);
+                    state.http_error = None;
+                }
+                Err(e) => {
+                    state.http_error = Some(e);
+                    state.settings.http_enabled = false;
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn draw_http_controls(&self, ui: &mut egui::Ui, state: &mut AppState) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.settings.http_enabled, "Serve JSON at");
+            ui.label(format!("http://127.0.0.1:{}/status", state.settings.http_port));
+            ui.label("Port:");
+            let mut port = state.http_port_edit.unwrap_or(state.settings.http_port);
+            if ui.add(egui::DragValue::new(&mut port).clamp_range(1024..=65535)).changed() {
+                state.http_port_edit = Some(port);
+            }
+            let edited = state.http_port_edit.filter(|p| *p != state.settings.http_port);
+            if ui.add_enabled(edited.is_some(), egui::Button::new("Apply")).clicked() {
+                state.settings.http_port = port;
+                state.http_port_edit = None;
+            }
+        });
+        if let Some(err) = &state.http_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+    }
+
+    /// Capture, show/hide and delete the ghost skies on the sky map, and
+    /// export the current sky as JSON.
+    fn draw_snapshot_controls(
+        &self,
+        ui: &mut egui::Ui,
+        state: &mut AppState,
+        sources: &[MutexGuard<SourceState>],
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Snapshot name:");
+            ui.text_edit_singleline(&mut state.snapshot_name);
+            let full = state.sky_snapshots.len() >= MAX_SNAPSHOTS;
+            let button = ui
+                .add_enabled(!full, egui::Button::new("Snapshot sky"))
+                .on_disabled_hover_text("Delete a snapshot first");
+            if button.clicked() {
+                let name = match state.snapshot_name.trim() {
+                    "" => format!("Snapshot {}", state.sky_snapshots.len() + 1),
+                    name => name.to_string(),
+                };
+                state.sky_snapshots.push(SkySnapshot {
+                    name,
+                    satellites: sources.iter().flat_map(|s| s.satellites.clone()).collect(),
+                    visible: true,
+                });
+                state.snapshot_name.clear();
+            }
+        });
+
+        let mut delete = None;
+        let palette = state.settings.palette;
+        for (index, snapshot) in state.sky_snapshots.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.colored_label(palette.snapshot(index), "◇");
+                ui.checkbox(
+                    &mut snapshot.visible,
+                    format!("{} ({} satellites)", snapshot.name, snapshot.satellites.len()),
+                );
+                if ui.small_button("Delete").clicked() {
+                    delete = Some(index);
+                }
+            });
+        }
+        if let Some(index) = delete {
+            state.sky_snapshots.remove(index);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Export sky data (.json):");
+            ui.text_edit_singleline(&mut state.sky_export_path);
+            let named = !state.sky_export_path.trim().is_empty();
+            if ui
+                .add_enabled(named, egui::Button::new("Export"))
+                .on_hover_text("PRN, constellation, azimuth, elevation, SNR and use of every \
+                                satellite in view, with the time and position")
+                .clicked()
+            {
+                let path = Path::new(state.sky_export_path.trim());
+                let skies: Vec<export::SkySource> = sources
+                    .iter()
+                    .map(|s| export::SkySource {
+                        label: &s.label,
+                        fix: s.displayed_fix(false).map(|(fix, _)| fix),
+                        satellites: &s.satellites,
+                    })
+                    .collect();
+                state.sky_export_status = Some(match export::write_sky(path, unix_now(), &skies) {
+                    Ok(()) => format!("Exported sky data to {}", path.display()),
+                    Err(e) => e,
+                });
+            }
+        });
+        if let Some(status) = &state.sky_export_status {
+            ui.label(status);
+        }
+    }
+
+    /// Mirrors the live session to the recovery file every flush
+    /// interval, or `RECOVERY_INTERVAL` if that is longer, once there is
+    /// something worth keeping. Only the copy is made here; the file is
+    /// written on another thread. Paused while an earlier run's file is
+    /// still on offer.
+    fn save_recovery(&self, state: &mut AppState, sources: &[MutexGuard<SourceState>]) {
+        if state.recovery_write.as_ref().is_some_and(|w| w.is_finished()) {
+            if let Some(Ok(Err(e))) = state.recovery_write.take().map(|w| w.join()) {
+                state.session_status = Some(format!("Could not write recovery file: {e}"));
+            }
+        }
+        let interval = state.settings.flush_interval().max(RECOVERY_INTERVAL);
+        if state.recovered_session.is_some()
+            || state.recovery_write.is_some()
+            || state.recovery_saved.is_some_and(|t| t.elapsed() < interval)
+            || sources.iter().all(|s| s.nmea_log.is_empty())
+        {
+            return;
+        }
+        state.recovery_saved = Some(Instant::now());
+
+        let session = Session::new(
+            state.settings.clone(),
+            sources
+                .iter()
+                .map(|s| SourceSession::capture(s).truncated(session::RECOVERY_FIXES))
+                .collect(),
+            state.waypoints.clone(),
+        );
+        state.recovery_write = Some(std::thread::spawn(move || session.save_recovery()));
+    }
+
+    /// Offers the session of a run that did not exit cleanly for review.
+    fn draw_recovery_prompt(
+        &self,
+        ctx: &egui::Context,
+        state: &mut AppState,
+        sources: &mut [MutexGuard<SourceState>],
+    ) {
+        let Some((session, _)) = &state.recovered_session else {
+            return;
+        };
+        let fixes: usize = session.sources.iter().map(|s| s.fixes.len()).sum();
+        let lines: usize = session.sources.iter().map(|s| s.log.len()).sum();
+        let minutes_ago = ((unix_now() - session.exported_at) / 60.0).max(0.0);
+
+        let mut choice = None;
+        egui::Window::new("Recover session")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("The last run did not exit cleanly.");
+                ui.label(format!(
+                    "Saved {minutes_ago:.0} min ago: {lines} log lines, {fixes} fixes."
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Restore for review").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Discard").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+
+        let Some(restore) = choice else {
+            return;
+        };
+        let Some((session, path)) = state.recovered_session.take() else {
+            return;
+        };
+        session::remove_recovery(&path);
+        if restore {
+            for source in sources.iter_mut() {
+                source.stop_reader();
+            }
+            state.apply_session(session);
+            state.session_status = Some("Restored the recovered session".to_string());
+        }
+    }
+
+    fn draw_new_session_prompt(
+        &self,
+        ctx: &egui::Context,
+        state: &mut AppState,
+        sources: &mut [MutexGuard<SourceState>],
+    ) {
+        if !state.confirm_new_session {
+            return;
+        }
+        egui::Window::new("New session")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("Recording is in progress. Stop it and discard all data?");
+                ui.horizontal(|ui| {
+                    if ui.button("Discard and start over").clicked() {
+                        state.new_session(sources);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        state.confirm_new_session = false;
+                    }
+                });
+            });
+    }
+
+    /// Session export/import row. Importing stops all readers and replaces
+    /// the sources with the recorded ones.
+    fn draw_session_controls(
+        &self,
+        ui: &mut egui::Ui,
+        state: &mut AppState,
+        sources: &mut [MutexGuard<SourceState>],
+    ) {
+        ui.horizontal(|ui| {
+            if ui
+                .button("New Session")
+                .on_hover_text("Stop reading and clear all data, keeping the settings")
+                .clicked()
+            {
+                let recording = sources
+                    .iter()
+                    .any(|s| s.mode.is_active() || s.sat_log.is_some() || s.autosave.is_some());
+                if recording {
+                    state.confirm_new_session = true;
+                } else {
+                    state.new_session(sources);
+                }
+            }
+            ui.separator();
+            ui.label("Session file:");
+            ui.text_edit_singleline(&mut state.session_path);
+            let path = Path::new(state.session_path.trim()).to_path_buf();
+
+            if ui.button("Export Session").clicked() {
+                let session = Session::new(
+                    state.settings.clone(),
+                    sources.iter().map(|s| SourceSession::capture(s)).collect(),
+                    state.waypoints.clone(),
+                );
+                state.session_status = Some(match session.save(&path) {
+                    Ok(()) => format!("Exported session to {}", path.display()),
+                    Err(e) => e,
+                });
+            }
+
+            if ui.button("Import Session").clicked() {
+                match Session::load(&path) {
+                    Ok(session) => {
+                        for source in sources.iter_mut() {
+                            source.stop_reader();
+                        }
+                        state.apply_session(session);
+                        state.session_status =
+                            Some(format!("Imported session from {}", path.display()));
+                    }
+                    Err(e) => state.session_status = Some(e),
+                }
+            }
+        });
+
+        if let Some(status) = &state.session_status {
+            ui.label(status);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Note:");
+            let entry = egui::TextEdit::singleline(&mut state.note_text)
+                .hint_text("moved antenna to window");
+            let entered =
+                ui.add(entry).lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let typed = !state.note_text.trim().is_empty();
+            let added = ui
+                .add_enabled(typed, egui::Button::new("Add note"))
+                .on_hover_text("Timestamp the note into every source's log and event list")
+                .clicked();
+            if typed && (added || entered) {
+                for source in sources.iter_mut() {
+                    source.add_note(&state.note_text);
+                }
+                state.note_text.clear();
+            }
+        });
+        if let Some(notes) = sources.first().map(|s| &s.notes).filter(|n| !n.is_empty()) {
+            egui::CollapsingHeader::new(format!("Notes ({})", notes.len())).show(ui, |ui| {
+                for note in notes {
+                    let text = note.line.strip_prefix(NOTE_PREFIX).unwrap_or(&note.line);
+                    ui.label(format!("{}  {text}", clock_text(note.time)));
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.settings.satellite_csv, "Log satellites to CSV")
+                .on_hover_text(
+                    "Append every satellite's azimuth, elevation and SNR each cycle; \
+                     a new file per reading session",
+                );
+            ui.label("Folder:");
+            ui.text_edit_singleline(&mut state.settings.satellite_csv_dir);
+        });
+        if let Some(kept) = state.settings.decimation.describe() {
+            let note = format!("Satellite CSV decimated: {kept} of fixes");
+            ui.colored_label(WARNING_COLOR, note);
+        }
+        for source in sources.iter() {
+            if let Some(log) = &source.sat_log {
+                ui.label(format!(
+                    "Source {}: {} rows to {}",
+                    source.label,
+                    log.rows,
+                    log.path.display()
+                ));
+            }
+            if let Some(err) = &source.sat_log_error {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.settings.autosave_log, "Auto-save NMEA log")
+                .on_hover_text(
+                    "Append every received sentence to a file as it arrives; \
+                     a new file per reading session",
+                );
+            ui.label("Folder:");
+            ui.text_edit_singleline(&mut state.settings.autosave_dir);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Flush to disk every");
+            ui.add(egui::DragValue::new(&mut state.settings.flush_secs).clamp_range(1..=3600));
+            ui.label("s").on_hover_text(
+                "The auto-save log and satellite CSV are synced to disk this often; a power \
+                 cut loses at most this much data. The recovery file is rewritten no \
+                 more often than every 30 s",
+            );
+        });
+        for source in sources.iter() {
+            if let Some(log) = &source.autosave {
+                ui.label(format!(
+                    "Source {}: {} lines to {}, synced {:.0} s ago",
+                    source.label,
+                    log.lines,
+                    log.path.display(),
+                    log.synced.elapsed().as_secs_f32()
+                ));
+            }
+            if let Some(err) = &source.autosave_error {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+        }
+    }
+
+    /// Replays each source's capture at once, lined up from their starts
+    /// or by their timestamps, for before/after comparisons side by side.
+    fn draw_comparison(
+        &self,
+        ui: &mut egui::Ui,
+        source_arcs: &[Arc<Mutex<SourceState>>],
+        sources: &mut [MutexGuard<SourceState>],
+        align: &mut ReplayAlign,
+    ) {
+        ui.label("Plays each source's replay file at its original timing. Set the files and \
+                  any offsets in the source panels.");
+        ui.horizontal(|ui| {
+            for source in sources.iter_mut() {
+                let file = source.replay_path.trim();
+                let file = Path::new(file).file_name().map_or("no file".into(), |f| {
+                    f.to_string_lossy()
+                });
+                let text = format!("{}: {file}", source.label);
+                ui.checkbox(&mut source.in_comparison, text);
+            }
+        });
+
+        let chosen: Vec<usize> = (0..sources.len())
+            .filter(|&i| sources[i].in_comparison && !sources[i].replay_path.trim().is_empty())
+            .collect();
+        let idle = sources.iter().all(|s| s.mode.is_idle());
+        ui.horizontal(|ui| {
+            ui.label("Line up:");
+            egui::ComboBox::from_id_source("compare_align")
+                .selected_text(align.name())
+                .show_ui(ui, |ui| {
+                    for option in ReplayAlign::ALL {
+                        ui.selectable_value(align, option, option.name())
+                            .on_hover_text(option.description());
+                    }
+                })
+                .response
+                .on_hover_text(align.description());
+
+            let start = ui.add_enabled(idle && !chosen.is_empty(), egui::Button::new("Replay"));
+            if start.on_disabled_hover_text("Stop every source and pick a file first").clicked() {
+                let starts: Vec<Option<f64>> = match align {
+                    ReplayAlign::Elapsed => vec![None; chosen.len()],
+                    ReplayAlign::Timestamps => chosen
+                        .iter()
+                        .map(|&i| reader::capture_start(Path::new(sources[i].replay_path.trim())))
+                        .collect(),
+                };
+                let offsets = reader::align_by_timestamps(&starts);
+                for (&i, offset) in chosen.iter().zip(offsets) {
+                    sources[i].start_replay(&source_arcs[i], true, offset);
+                }
+            }
+            let comparing = chosen.iter().any(|&i| sources[i].kind == SourceKind::Replay);
+            let running = sources.iter().any(|s| s.mode.is_active());
+            if running && comparing && ui.button("Stop all").clicked() {
+                for &i in &chosen {
+                    sources[i].stop_reader();
+                }
+            }
+        });
+    }
+
+    /// Port/baud selection and start button for one source.
+    fn draw_source_controls(
+        &self,
+        ui: &mut egui::Ui,
+        ports: &[String],
+        source_arc: &Arc<Mutex<SourceState>>,
+        source: &mut SourceState,
+        settings: &mut Settings,
+        accent: Option<egui::Color32>,
+    ) {
+        ui.horizontal(|ui| {
+            let heading = egui::RichText::new("Source").heading();
+            ui.label(accent.map_or(heading.clone(), |color| heading.color(color)));
+            // Renamed when the edit is done, so the label keying this
+            // source's widgets doesn't change with every keystroke
+            let id = ui.id().with("source_label");
+            if !ui.memory(|m| m.has_focus(id)) {
+                source.label_text = source.label.clone();
+            }
+            let edit = egui::TextEdit::singleline(&mut source.label_text)
+                .id(id)
+                .font(egui::TextStyle::Heading)
+                .desired_width(120.0);
+            let response = ui.add(edit).on_hover_text("Rename, e.g. Rover or Base");
+            let typed = source.label_text.trim();
+            if response.lost_focus() && !typed.is_empty() {
+                source.label = typed.to_string();
+            }
+        });
+        ui.collapsing("Connection profiles", |ui| {
+            self.draw_profile_controls(ui, source, settings);
+        });
+
+        let placeholder = if ports.is_empty() { "No ports found" } else { "Select a Port" };
+        egui::ComboBox::from_id_source(("port", &source.label))
+            .selected_text(source.selected_port.as_deref().unwrap_or(placeholder))
+            .show_ui(ui, |cb| {
+                for port in ports {
+                    cb.selectable_value(
+                        &mut source.selected_port,
+                        Some(port.clone()),
+                        port,
+                    );
+                }
+            });
+
+        let idle = source.mode.is_idle();
+        // A serial port being read can take a new baud rate by restarting
+        ui.add_enabled_ui(idle || source.kind == SourceKind::Serial, |ui| {
+            egui::ComboBox::from_id_source(("baud", &source.label))
+                .selected_text(format!("{} baud", source.baud_rate))
+                .show_ui(ui, |cb| {
+                    for baud in BAUD_RATES {
+                        cb.selectable_value(&mut source.baud_rate, baud, baud.to_string());
+                    }
+                });
+        });
+        let active = source.mode.is_active();
+        let actual = source.health.port_settings.filter(|_| active);
+        if source.serial_params_changed(settings) {
+            ui.horizontal(|ui| {
+                ui.colored_label(WARNING_COLOR, "Port settings changed since it was opened");
+                if ui
+                    .button("Restart to apply")
+                    .on_hover_text("Reopen the port with the new settings; the log and \
+                                    statistics are kept")
+                    .clicked()
+                {
+                    source.restart_serial();
+                }
+            });
+        } else if let Some(note) = source.open_fallback.as_ref().filter(|_| active) {
+            ui.colored_label(WARNING_COLOR, format!("⚠ {note}"));
+        } else if let Some(warning) = actual.and_then(|p| p.baud_mismatch(source.baud_rate)) {
+            ui.colored_label(WARNING_COLOR, format!("⚠ {warning}"));
+        }
+        self.draw_binary_only_notice(ui, source);
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut source.log_valid_only, "Log valid only");
+            if source.discarded_lines > 0 {
+                ui.label(format!("({} discarded)", source.discarded_lines));
+            }
+            ui.checkbox(&mut source.binary_as_hex, "Hex dump binary noise");
+        });
+
+        ui.collapsing("Receiver commands", |ui| self.draw_command_controls(ui, source));
+
+        ui.horizontal(|ui| {
+            let can_start = idle && source.selected_port.is_some();
+            let start_text = match source.mode {
+                AppMode::Connecting => "Connecting…",
+                AppMode::Reconnecting => "Reconnecting…",
+                AppMode::Reading | AppMode::Paused => "Reading",
+                AppMode::Stopping => "Stopping…",
+                AppMode::Idle | AppMode::Error(_) => "Start Reading",
+            };
+            let start = ui
+                .add_enabled(can_start, egui::Button::new(start_text))
+                .on_disabled_hover_text(if idle {
+                    "Select a port first"
+                } else if source.mode == AppMode::Stopping {
+                    "Still stopping"
+                } else {
+                    "Already reading"
+                });
+            if start.clicked() {
+                source.start_serial(source_arc, settings);
+            }
+            match source.mode {
+                AppMode::Reading if ui.button("Pause").clicked() => source.mode = AppMode::Paused,
+                AppMode::Paused if ui.button("Resume").clicked() => source.mode = AppMode::Reading,
+                _ => {}
+            }
+            if active && ui.button("Stop").clicked() {
+                source.stop_reader();
+            }
+        });
+
+        let port = source.selected_port.clone().unwrap_or_else(|| "port".to_string());
+        if source.mode == AppMode::Connecting {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                if source.applying_settings {
+                    ui.label(format!("Reconnecting to {port} with new settings…"));
+                } else {
+                    ui.label(format!("Connecting to {port}…"));
+                }
+            });
+        } else if let Some(fault) = source.health.fault {
+            ui.horizontal(|ui| {
+                ui.colored_label(egui::Color32::RED, fault.message());
+                let retry = if fault.recoverable() { "Reconnect now" } else { "Retry" };
+                if ui.button(retry).clicked() {
+                    source.stop_reader();
+                    source.start_serial(source_arc, settings);
+                }
+            });
+            if fault == PortFault::NoPermission {
+                ui.label(missing_port_hint());
+            }
+            if fault == PortFault::InUse && source.port_wait.is_none() {
+                ui.horizontal(|ui| {
+                    let minutes = reader::PORT_WAIT_LIMIT.as_secs() / 60;
+                    if ui
+                        .button("Wait for the port")
+                        .on_hover_text(format!(
+                            "Retry every few seconds, for up to {minutes} minutes, until the \
+                             other program lets go of the port"
+                        ))
+                        .clicked()
+                    {
+                        source.port_wait = Some(PortWait::new(Instant::now()));
+                    }
+                    if let Some(tcp_port) = source.handoff_port {
+                        if ui
+                            .button(format!("Read the other instance's repeater (port {tcp_port})"))
+                            .on_hover_text("Another instance of this program holds the port and \
+                                            repeats it over TCP; read that stream instead")
+                            .clicked()
+                        {
+                            source.start_tcp(source_arc, tcp_port);
+                        }
+                    }
+                });
+            }
+        } else if let AppMode::Error(err) = &source.mode {
+            ui.colored_label(egui::Color32::RED, err);
+        } else if source.mode == AppMode::Reconnecting {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!("Reconnecting to {port}…"));
+            });
+            if let Some(err) = &source.health.last_error {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+        }
+        if let Some(wait) = source.port_wait {
+            let left = wait.remaining(Instant::now()).as_secs();
+            let waiting = format!("Waiting for {port} to be released, {left} s left");
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(waiting);
+                if ui.button("Cancel").clicked() {
+                    source.port_wait = None;
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Replay file:");
+            ui.text_edit_singleline(&mut source.replay_path);
+            let can_start = idle && !source.replay_path.trim().is_empty();
+            if ui.add_enabled(can_start, egui::Button::new("Replay")).clicked() {
+                source.start_replay(source_arc, source.replay_timed, 0.0);
+            }
+            let has_log = source.captured_log.is_some() || !source.nmea_log.is_empty();
+            if ui
+                .add_enabled(idle && has_log, egui::Button::new("Replay buffer"))
+                .on_hover_text("Feed the sentences in the log back through the parser from the \
+                                start, e.g. after changing the parsed sentences; the captured \
+                                log is kept")
+                .clicked()
+            {
+                source.replay_buffer(source_arc);
+            }
+            let timing = egui::Checkbox::new(&mut source.replay_timed, "Original timing");
+            ui.add_enabled(idle, timing)
+                .on_hover_text("Reproduce the gaps between the file's GGA/RMC/ZDA timestamps, \
+                                pauses included; files without timestamps replay at a fixed rate");
+            ui.label("Offset:");
+            ui.add_enabled(
+                idle,
+                egui::DragValue::new(&mut source.replay_offset_secs)
+                    .speed(0.1)
+                    .suffix(" s"),
+            )
+            .on_hover_text("Positive starts the replay later; negative skips that much of a \
+                            timestamped file");
+        });
+        if let Some(err) = &source.replay_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        if let Some(captured) = &source.captured_log {
+            let mut restore = false;
+            ui.horizontal(|ui| {
+                ui.label(format!("Log replayed from a capture of {} lines", captured.len()));
+                restore = ui
+                    .add_enabled(idle, egui::Button::new("Restore captured log"))
+                    .on_hover_text("Put the log back as captured; the analysis stays")
+                    .clicked();
+            });
+            if restore {
+                source.nmea_log = source.captured_log.take().unwrap_or_default();
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Tail file:");
+            ui.text_edit_singleline(&mut source.tail_path);
+            let can_start = idle && !source.tail_path.trim().is_empty();
+            if ui
+                .add_enabled(can_start, egui::Button::new("Follow"))
+                .on_hover_text("Read lines as another program appends them, like tail -f")
+                .clicked()
+            {
+                let path = PathBuf::from(source.tail_path.trim());
+                match reader::spawn_tail(Arc::clone(source_arc), path) {
+                    Ok(abort) => {
+                        source.kind = SourceKind::Tail;
+                        source.replay = None;
+                        source.reader_abort = Some(abort);
+                        source.last_data = Some(Instant::now());
+                        source.mode = AppMode::Reading;
+                        source.tail_error = None;
+                    }
+                    Err(e) => source.tail_error = Some(e),
+                }
+            }
+        });
+        if let Some(err) = &source.tail_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(idle, egui::Button::new("Simulate"))
+                .on_hover_text("Generate sentences from a built-in simulated receiver")
+                .clicked()
+            {
+                source.start_simulator(source_arc);
+            }
+            ui.add_enabled_ui(idle, |ui| {
+                ui.collapsing("Simulator settings", |ui| {
+                    let config = &mut source.sim_config;
+                    ui.add(egui::Slider::new(&mut config.satellites, 1..=32).text("satellites"));
+                    ui.add(egui::Slider::new(&mut config.noise_m, 0.0..=20.0).text("noise (m)"));
+                    ui.add(
+                        egui::Slider::new(&mut config.speed_mps, 0.0..=50.0).text("speed (m/s)"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut config.heading_deg, 0.0..=359.0)
+                            .text("heading (°)"),
+                    );
+                });
+            });
+        });
+
+        if let Some(progress) = &source.replay {
+            self.draw_replay_progress(ui, progress, &source.sentence_counts);
+        }
+    }
+
+    /// Loads the source's connection choices from a saved profile, saves
+    /// them as one, and shares profiles through a file.
+    fn draw_profile_controls(
+        &self,
+        ui: &mut egui::Ui,
+        source: &mut SourceState,
+        settings: &mut Settings,
+    ) {
+        let idle = source.mode.is_idle();
+        let mut chosen = None;
+        ui.add_enabled_ui(idle && !settings.profiles.is_empty(), |ui| {
+            egui::ComboBox::from_id_source(("profile", &source.label))
+                .selected_text(if settings.profiles.is_empty() {
+                    "No saved profiles"
+                } else {
+                    "Load a profile"
+                })
+                .show_ui(ui, |cb| {
+                    for (index, profile) in settings.profiles.iter().enumerate() {
+                        let text = format!("{} ({})", profile.name, profile.kind.name());
+                        let current = profile.name == source.profile_name;
+                        if cb.selectable_label(current, text).clicked() {
+                            chosen = Some(index);
+                        }
+                    }
+                });
+        });
+        if let Some(index) = chosen {
+            let profile = settings.profiles[index].clone();
+            profile.apply(source, settings);
+            source.profile_status = Some(Ok(format!("Loaded {}", profile.name)));
+            source.profile_name = profile.name;
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut source.profile_name)
+                    .hint_text("Profile name")
+                    .desired_width(120.0),
+            );
+            let name = source.profile_name.trim().to_string();
+            let exists = settings.profiles.iter().any(|p| p.name == name);
+            let save_text = if exists { "Update" } else { "Save" };
+            if ui.add_enabled(!name.is_empty(), egui::Button::new(save_text)).clicked() {
+                let profile = Profile::capture(&name, source, settings);
+                profiles::save(&mut settings.profiles, profile);
+                source.profile_status = Some(Ok(format!("Saved {name}")));
+            }
+            if ui.add_enabled(exists, egui::Button::new("Delete")).clicked() {
+                settings.profiles.retain(|p| p.name != name);
+                source.profile_status = Some(Ok(format!("Deleted {name}")));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut source.profile_file)
+                    .hint_text("profiles.json")
+                    .desired_width(120.0),
+            );
+            let path = PathBuf::from(source.profile_file.trim());
+            let has_path = !source.profile_file.trim().is_empty();
+            if ui
+                .add_enabled(has_path, egui::Button::new("Import"))
+                .on_hover_text("Add the file's profiles; ones with a saved name replace it")
+                .clicked()
+            {
+                source.profile_status = Some(
+                    profiles::import(&path, &mut settings.profiles)
+                        .map(|n| format!("Imported {n} profiles")),
+                );
+            }
+            let can_export = has_path && !settings.profiles.is_empty();
+            if ui.add_enabled(can_export, egui::Button::new("Export")).clicked() {
+                source.profile_status = Some(
+                    profiles::export(&path, &settings.profiles)
+                        .map(|()| format!("Exported to {}", path.display())),
+                );
+            }
+        });
+        match &source.profile_status {
+            Some(Ok(status)) => {
+                ui.label(status);
+            }
+            Some(Err(err)) => {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+            None => {}
+        }
+    }
+
+    /// Offers to switch on NMEA when a serial receiver sends only UBX, as
+    /// u-blox modules configured for binary do, and says whether it took.
+    fn draw_binary_only_notice(&self, ui: &mut egui::Ui, source: &mut SourceState) {
+        let serial = source.kind == SourceKind::Serial && source.mode.is_active();
+        match source.protocol.filter(|_| serial) {
+            Some(Protocol::Ubx) => {
+                ui.horizontal(|ui| {
+                    ui.colored_label(WARNING_COLOR, "⚠ Only UBX binary is arriving, no NMEA");
+                    if ui
+                        .button("Enable NMEA output")
+                        .on_hover_text("Send UBX-CFG-PRT allowing NMEA on UART1 and USB, and \
+                                        UBX-CFG-MSG turning on GGA, RMC, GSA and GSV; not \
+                                        saved in the receiver")
+                        .clicked()
+                    {
+                        source.enable_nmea_output();
+                    }
+                });
+                let waited = source.nmea_enable_sent.filter(|t| t.elapsed() > NMEA_ENABLE_WAIT);
+                if waited.is_some() {
+                    ui.colored_label(
+                        WARNING_COLOR,
+                        "Still no NMEA; check the baud rate, or the receiver may be on another \
+                         port (UART2, SPI, I2C)",
+                    );
+                }
+            }
+            Some(Protocol::Nmea)
+                if source.nmea_enable_sent.is_some_and(|t| t.elapsed() < NMEA_ENABLE_NOTICE) =>
+            {
+                ui.label("✔ NMEA output enabled and arriving");
+            }
+            _ => {}
+        }
+    }
+
+    /// Output rate presets for the chosen receiver family, plus a free-form
+    /// sentence. Commands go to the serial reader's writer thread.
+    fn draw_command_controls(&self, ui: &mut egui::Ui, source: &mut SourceState) {
+        let can_send = source.mode.is_active() && source.kind == SourceKind::Serial;
+        if !can_send {
+            ui.label("Start reading a serial port to send commands.");
+        }
+
+        egui::ComboBox::from_id_source(("receiver_family", &source.label))
+            .selected_text(source.receiver_family.name())
+            .show_ui(ui, |cb| {
+                for family in ReceiverFamily::ALL {
+                    cb.selectable_value(&mut source.receiver_family, family, family.name());
+                }
+            });
+
+        ui.horizontal_wrapped(|ui| {
+            for preset in RatePreset::ALL {
+                let commands = commands::preset_commands(source.receiver_family, preset);
+                let button = ui.add_enabled(
+                    can_send && !commands.is_empty(),
+                    egui::Button::new(preset.name()),
+                );
+                if button.clicked() {
+                    for command in commands {
+                        source.send_command(command);
+                    }
+                    source.expected_rate_hz = preset.fix_rate_hz().or(source.expected_rate_hz);
+                }
+            }
+        });
+        if source.receiver_family == ReceiverFamily::Generic {
+            ui.label("No presets for generic receivers; send a sentence below.");
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            for kind in ResetKind::ALL {
+                let supported = !commands::reset_commands(source.receiver_family, kind).is_empty();
+                let button = ui
+                    .add_enabled(can_send && supported, egui::Button::new(kind.name()))
+                    .on_hover_text(kind.description());
+                match kind {
+                    _ if !button.clicked() => {}
+                    ResetKind::Factory => source.confirm_factory_reset = true,
+                    kind => source.send_reset(kind),
+                }
+            }
+        });
+        if source.confirm_factory_reset {
+            egui::Window::new(format!("Factory reset ({})", source.label))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ui.ctx(), |ui| {
+                    ui.label(
+                        "This wipes the receiver's saved configuration, including baud rate \
+                         and message settings, and cold starts it.",
+                    );
+                    ui.horizontal(|ui| {
+                        if ui.button("Reset to factory defaults").clicked() {
+                            source.send_reset(ResetKind::Factory);
+                            source.confirm_factory_reset = false;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            source.confirm_factory_reset = false;
+                        }
+                    });
+                });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("$");
+            ui.text_edit_singleline(&mut source.command_text);
+            let body = source.command_text.trim().trim_start_matches('$').to_string();
+            if ui
+                .add_enabled(can_send && !body.is_empty(), egui::Button::new("Send"))
+                .on_hover_text("The checksum and CR/LF are added")
+                .clicked()
+            {
+                source.send_command(commands::nmea_sentence(&body));
+            }
+        });
+        if source.receiver_family == ReceiverFamily::Ublox {
+            ui.horizontal(|ui| {
+                ui.label("Dynamic model");
+                egui::ComboBox::from_id_source(("dynamic_model", &source.label))
+                    .selected_text(source.dynamic_model_choice.name())
+                    .show_ui(ui, |cb| {
+                        for model in DynamicModel::ALL {
+                            let choice = &mut source.dynamic_model_choice;
+                            cb.selectable_value(choice, model, model.name());
+                        }
+                    });
+                if ui
+                    .add_enabled(can_send, egui::Button::new("Set"))
+                    .on_hover_text("Sends UBX-CFG-NAV5, then polls it back")
+                    .clicked()
+                {
+                    let model = source.dynamic_model_choice;
+                    source.send_command(commands::dynamic_model_command(model));
+                    source.send_command(commands::dynamic_model_poll());
+                }
+                if ui.add_enabled(can_send, egui::Button::new("Query")).clicked() {
+                    source.send_command(commands::dynamic_model_poll());
+                }
+            });
+            ui.label(match source.dynamic_model {
+                Some(model) => format!("Current dynamic model: {}", model.name()),
+                None => "Current dynamic model: not reported yet".to_string(),
+            });
+        }
+
+        if let Some(err) = &source.command_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+    }
+
+    fn draw_replay_progress(
+        &self,
+        ui: &mut egui::Ui,
+        progress: &ReplayProgress,
+        counts: &BTreeMap<String, u64>,
+    ) {
+        let name = progress
+            .path
+            .file_name()
+            .map_or_else(|| progress.path.display().to_string(), |n| n.to_string_lossy().into());
+
+        if progress.finished {
+            ui.label(format!(
+                "Replay of {name} complete: {} lines, {} bytes",
+                progress.lines_done, progress.bytes_done
+            ));
+        } else {
+            ui.add(egui::ProgressBar::new(progress.fraction()).show_percentage());
+            ui.label(format!(
+                "{name}: {} lines, {} / {} bytes",
+                progress.lines_done, progress.bytes_done, progress.total_bytes
+            ));
+        }
+
+        let pacing = if progress.timed { "original timing" } else { "fixed rate" };
+        match progress.elapsed_secs {
+            Some(secs) => ui.label(format!("Elapsed {} in file time, {pacing}", format_hms(secs))),
+            None => ui.label(format!("No timestamps yet, {pacing}")),
+        };
+
+        let summary: Vec<String> = counts.iter().map(|(kind, n)| format!("{kind}: {n}")).collect();
+        ui.label(summary.join("  "));
+    }
+
+    /// How much of a high-rate stream the saved files keep.
+    fn draw_decimation(&self, ui: &mut egui::Ui, decimation: &mut Decimation) {
+        ui.horizontal(|ui| {
+            ui.label("Saved files keep:");
+            egui::ComboBox::from_id_source("decimation")
+                .selected_text(decimation.mode.name())
+                .show_ui(ui, |cb| {
+                    for mode in DecimationMode::ALL {
+                        cb.selectable_value(&mut decimation.mode, mode, mode.name());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Thins the saved log, the track export (GPX/CSV) and the satellite CSV, \
+                     keeping whole position cycles. The live display, graphs and the graph \
+                     CSV keep every cycle. A satellite CSV already open keeps its setting.",
+                );
+            if decimation.mode == DecimationMode::EveryNth {
+                ui.label("N");
+                ui.add(egui::DragValue::new(&mut decimation.every).clamp_range(2..=1000));
+            }
+        });
+    }
+
+    /// Live NMEA log of the chosen source. Follows new lines until the user
+    /// scrolls up, then holds position until they return to the bottom.
+    fn draw_stream(&self, ui: &mut egui::Ui, state: &mut AppState, sources: &[&SourceState]) {
+        ui.horizontal(|ui| {
+            ui.label("Live NMEA Data:");
+            if sources.len() > 1 {
+                for (index, source) in sources.iter().enumerate() {
+                    ui.selectable_value(&mut state.stream_source, index, &source.label);
+                }
+            }
+            if state.log_scroll_locked && ui.button("⏬ Jump to bottom").clicked() {
+                state.log_scroll_locked = false;
+            }
+        });
+
+        let source = sources[state.stream_source.min(sources.len() - 1)];
+        ui.horizontal(|ui| {
+            ui.label("Save log:");
+            ui.text_edit_singleline(&mut state.log_save_path);
+            let format = &mut state.settings.log_format;
+            egui::ComboBox::from_id_source("log_format")
+                .selected_text(format.name())
+                .show_ui(ui, |ui| {
+                    for option in LogFormat::ALL {
+                        ui.selectable_value(format, option, option.name())
+                            .on_hover_text(option.description());
+                    }
+                });
+            let clock = &mut state.settings.log_clock;
+            ui.add_enabled_ui(*format == LogFormat::Timestamped, |ui| {
+                egui::ComboBox::from_id_source("log_clock")
+                    .selected_text(clock.name())
+                    .show_ui(ui, |ui| {
+                        for option in LogClock::ALL {
+                            ui.selectable_value(clock, option, option.name())
+                                .on_hover_text(option.description());
+                        }
+                    })
+                    .response
+                    .on_hover_text(clock.description());
+            });
+            if ui.button("Save").clicked() {
+                let path = Path::new(state.log_save_path.trim());
+                let decimation = state.settings.decimation;
+                let track = export::Track {
+                    label: &source.label,
+                    fixes: decimation.fixes(source.fix_history.iter()),
+                };
+                let decimals = state.settings.coordinate_decimals;
+                let prefer_combined = state.settings.parsed_sentences.prefer_combined;
+                let log = &decimation.log(&source.nmea_log, prefer_combined);
+                let written = export::write_log(path, *format, *clock, &track, log, decimals);
+                state.log_save_status = Some(match written {
+                    Ok(()) => format!("Saved {} to {}", format.name(), path.display()),
+                    Err(e) => e,
+                });
+            }
+        });
+        self.draw_decimation(ui, &mut state.settings.decimation);
+        if let Some(status) = &state.log_save_status {
+            ui.label(status);
+        }
+        if source.log_dropped > 0 {
+            ui.horizontal(|ui| {
+                let note = "(oldest lines dropped — increase limit in settings)";
+                ui.label(egui::RichText::new(note).small().weak()).on_hover_text(format!(
+                    "{} lines no longer in the log; save it or raise the limit to keep more",
+                    source.log_dropped
+                ));
+                ui.label(egui::RichText::new("Log lines:").small().weak());
+                ui.add(
+                    egui::DragValue::new(&mut state.settings.max_log_lines)
+                        .clamp_range(100..=MAX_LOG_LINES)
+                        .speed(10),
+                );
+            });
+        }
+        if source.binary_chunks > 0 {
+            ui.colored_label(
+                WARNING_COLOR,
+                format!(
+                    "Binary data detected: {} chunks kept out of the log{}",
+                    source.binary_chunks,
+                    if source.binary_as_hex { " (shown as hex)" } else { "" }
+                ),
+            );
+        }
+        let mut area = egui::ScrollArea::both().auto_shrink([false, false]);
+        if !state.log_scroll_locked {
+            area = area.vertical_scroll_offset(f32::MAX);
+        }
+
+        // Only the rows in view are laid out, however full the log is
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let output = area.show_rows(ui, row_height, source.nmea_log.len(), |ui, rows| {
+            for entry in &source.nmea_log[rows] {
+                let text = egui::RichText::new(&entry.line)
+                    .monospace()
+                    .color(log_line_color(&entry.line, state.settings.palette));
+                ui.add(egui::Label::new(text).wrap(false));
+            }
+        });
+
+        let max_offset = (output.content_size.y - output.inner_rect.height()).max(0.0);
+        let at_bottom = output.state.offset.y >= max_offset - 1.0;
+        let scrolled_up = ui.rect_contains_pointer(output.inner_rect)
+            && ui.input(|i| i.smooth_scroll_delta.y > 0.0);
+
+        if scrolled_up {
+            state.log_scroll_locked = true;
+        } else if at_bottom {
+            state.log_scroll_locked = false;
+        }
+    }
+
+    /// Current fix of each source, one copyable value per row. Values are
+    /// only shown while the source is delivering data.
+    fn draw_position(&self, ui: &mut egui::Ui, sources: &[&SourceState], settings: &Settings) {
+        let decimals = settings.coordinate_decimals;
+        let (bearing, altitude_unit) = (settings.bearing_unit, settings.altitude_unit);
+        for source in sources {
+            let live = source.connection_state() == "Reading" || source.scrub.is_some();
+            let variation = source.variation();
+            let fix = source.fix.as_ref().filter(|_| live);
+            let held = source.displayed_fix(settings.hold_last_fix).filter(|_| live);
+            let position = held.map(|(f, _)| f);
+            let rmc = source.rmc.as_ref().filter(|r| live && r.valid);
+            let nav_status = source.rmc.as_ref().filter(|_| live).and_then(|r| r.nav_status);
+
+            let rows = [
+                ("Latitude", position.map(|f| format!("{:.decimals$}", f.latitude))),
+                ("Longitude", position.map(|f| format!("{:.decimals$}", f.longitude))),
+                ("Altitude", position.and_then(|f| f.altitude).map(|a| altitude_unit.format(a, 1))),
+                ("Fix type", fix.map(|f| f.quality_name().to_string())),
+                ("Fix mode", source.fix_mode.filter(|_| live).map(|m| m.name().to_string())),
+                ("Nav status", nav_status.map(|n| n.name().to_string())),
+                ("HDOP", fix.and_then(|f| f.hdop).map(|h| format!("{h:.1}"))),
+                ("Speed", rmc.and_then(|r| r.speed_knots).map(|s| format!("{s:.1} kn"))),
+                ("Course", rmc.and_then(|r| r.course).map(|c| bearing.format(c, variation, 1))),
+                ("UTC time", fix.and_then(|f| f.utc_time.clone())),
+                ("Satellites used", fix.map(|f| f.satellites_used.to_string())),
+                ("Systems in fix", fix.and(source.fix_constellations())),
+                ("TTFF", source.ttff_text()),
+            ];
+
+            ui.horizontal(|ui| {
+                ui.strong(format!("Source {}", source.label));
+                if let Some(time) = source.scrub {
+                    ui.colored_label(WARNING_COLOR, format!("⏪ history, {}", clock_text(time)))
+                        .on_hover_text("A past fix; speed and course are live");
+                }
+                if held.is_some_and(|(_, stale)| stale) {
+                    ui.colored_label(WARNING_COLOR, "(last known, stale)");
+                }
+                if nav_status == Some(NavStatus::Simulator) {
+                    ui.colored_label(egui::Color32::RED, "⚠ simulated, not a real position");
+                }
+                if let Some(status) = source.antenna.filter(|a| live && a.is_fault()) {
+                    ui.colored_label(egui::Color32::RED, format!("⚠ antenna {}", status.name()));
+                }
+            });
+            if let Some(fix) = fix {
+                let score = score::score(&settings.score_weights, fix, &source.satellites);
+                draw_score_gauge(ui, &score, settings.palette);
+            }
+            egui::Grid::new(("position", &source.label))
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, value) in rows {
+                        ui.label(name);
+                        match value {
+                            Some(value) => {
+                                ui.monospace(&value);
+                                if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                                    ui.output_mut(|o| o.copied_text = value);
+                                }
+                            }
+                            None => {
+                                ui.monospace("—");
+                                ui.label("");
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            // The raw sentences behind the values, for bug reports
+            let sentences = [
+                ("Copy GGA sentence", position.map(|f| f.sentence.as_str())),
+                ("Copy RMC sentence", rmc.map(|r| r.sentence.as_str())),
+            ];
+            ui.horizontal(|ui| {
+                for (label, sentence) in sentences {
+                    let sentence = sentence.filter(|s| !s.is_empty());
+                    if let Some(sentence) = sentence {
+                        if ui.small_button(label).on_hover_text(sentence).clicked() {
+                            ui.output_mut(|o| o.copied_text = sentence.to_string());
+                        }
+                    }
+                }
+            });
+            ui.separator();
+        }
+    }
+
+    /// Trip distance per source and the distance and bearing to the current
+    /// position from the last waypoint, or from the start of the trail.
+    fn draw_trip_summary(
+        &self,
+        ui: &mut egui::Ui,
+        sources: &[&SourceState],
+        waypoints: &[Waypoint],
+        settings: &mut Settings,
+    ) {
+        ui.horizontal(|ui| {
+            ui.strong("Trip");
+            let current = settings.distance_mode;
+            egui::ComboBox::from_id_source("distance_mode")
+                .selected_text(current.name())
+                .show_ui(ui, |cb| {
+                    for mode in DistanceMode::ALL {
+                        cb.selectable_value(&mut settings.distance_mode, mode, mode.name())
+                            .on_hover_text(mode.description());
+                    }
+                })
+                .response
+                .on_hover_text(current.description());
+            let unit = &mut settings.bearing_unit;
+            egui::ComboBox::from_id_source("bearing_unit")
+                .selected_text(unit.name())
+                .show_ui(ui, |cb| {
+                    for choice in BearingUnit::ALL {
+                        cb.selectable_value(unit, choice, choice.name());
+                    }
+                })
+                .response
+                .on_hover_text("How bearings and courses are shown; magnetic uses the \
+                                variation the receiver reports in RMC");
+            let unit = &mut settings.altitude_unit;
+            egui::ComboBox::from_id_source("altitude_unit")
+                .selected_text(unit.name())
+                .show_ui(ui, |cb| {
+                    for choice in AltitudeUnit::ALL {
+                        cb.selectable_value(unit, choice, choice.name());
+                    }
+                })
+                .response
+                .on_hover_text("How altitudes are shown; exports and sessions stay in metres");
+        });
+
+        let mode = settings.distance_mode;
+        let bearing = settings.bearing_unit;
+        egui::Grid::new("trip_summary").striped(true).show(ui, |ui| {
+            for source in sources {
+                let Some(current) = source.fix_history.back() else {
+                    continue;
+                };
+                let here = [current.fix.latitude, current.fix.longitude];
+                let last_waypoint = waypoints.iter().rev().find(|w| w.source == source.label);
+                let (from_name, from) = match last_waypoint {
+                    Some(wpt) => (wpt.name.as_str(), [wpt.latitude, wpt.longitude]),
+                    None => {
+                        let start = &source.fix_history[0].fix;
+                        ("start", [start.latitude, start.longitude])
+                    }
+                };
+
+                ui.label(format!("Source {}", source.label));
+                ui.monospace(format_distance(source.trip_distance_m(mode)));
+                ui.label(format!("from {from_name}:"));
+                ui.monospace(format!(
+                    "{}  {}",
+                    format_distance(mode.distance_m(from, here)),
+                    bearing.format(mode.bearing_deg(from, here), source.variation(), 1)
+                ));
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Distance and bearing from each source's fix to a typed target, with
+    /// an arrow pointing to it relative to the course while moving.
+    fn draw_go_to(&self, ui: &mut egui::Ui, state: &mut AppState, sources: &[&SourceState]) {
+        ui.horizontal(|ui| {
+            ui.strong("Go to");
+            let entry = egui::TextEdit::singleline(&mut state.go_to_text)
+                .hint_text("lat, lon")
+                .desired_width(180.0);
+            let entered =
+                ui.add(entry).lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let typed = !state.go_to_text.trim().is_empty();
+            if (entered || ui.add_enabled(typed, egui::Button::new("Set")).clicked()) && typed {
+                match coords::parse_position(&state.go_to_text) {
+                    Ok(position) => {
+                        state.settings.go_to = Some([position.latitude, position.longitude]);
+                        state.go_to_error = None;
+                    }
+                    Err(e) => state.go_to_error = Some(e),
+                }
+            }
+            if state.settings.go_to.is_some() && ui.button("Clear").clicked() {
+                state.settings.go_to = None;
+            }
+        });
+        if let Some(err) = &state.go_to_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        let Some(target) = state.settings.go_to else {
+            return;
+        };
+        let decimals = state.settings.coordinate_decimals;
+        ui.label(format!("Target {:.decimals$}, {:.decimals$}", target[0], target[1]));
+
+        let mode = state.settings.distance_mode;
+        let unit = state.settings.bearing_unit;
+        egui::Grid::new("go_to").striped(true).show(ui, |ui| {
+            for source in sources {
+                ui.label(format!("Source {}", source.label));
+                let Some(fix) = source.fix.as_ref().filter(|f| f.has_position()) else {
+                    ui.monospace("—");
+                    ui.end_row();
+                    continue;
+                };
+                let here = [fix.latitude, fix.longitude];
+                let bearing = mode.bearing_deg(here, target);
+                ui.monospace(format!(
+                    "{}  {}",
+                    format_distance(mode.distance_m(here, target)),
+                    unit.format(bearing, source.variation(), 1)
+                ));
+                let (angle, hint) = match source.moving_course() {
+                    Some(course) => {
+                        let relative = geo::relative_bearing_deg(bearing, course);
+                        (relative, format!("{relative:+.0}° from the course"))
+                    }
+                    None => (bearing, "North up: not moving".to_string()),
+                };
+                draw_arrow(ui, angle).on_hover_text(hint);
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Survey-in settings, then each source's averaging progress, running
+    /// mean and spread, and the frozen coordinate once done.
+    fn draw_survey(
+        &self,
+        ui: &mut egui::Ui,
+        state: &mut AppState,
+        sources: &mut [MutexGuard<SourceState>],
+    ) {
+        draw_survey_config(ui, &mut state.settings.survey);
+        ui.separator();
+
+        let decimals = state.settings.coordinate_decimals.max(8);
+        let now = unix_now();
+        for source in sources.iter_mut() {
+            let source = &mut **source;
+            ui.strong(format!("Source {}", source.label));
+            let Some(survey) = source.survey.as_mut() else {
+                if ui.button("Start survey").clicked() {
+                    source.survey = Some(Survey::start(state.settings.survey, now));
+                }
+                ui.separator();
+                continue;
+            };
+
+            let progress = survey.progress(now);
+            let window = match survey.config.stop {
+                SurveyStop::Duration => format!(
+                    "{} of {}",
+                    format_hms((now - survey.started).max(0.0)),
+                    format_hms(f64::from(survey.config.duration_secs))
+                ),
+                SurveyStop::Count => format!("of {} fixes", survey.config.count),
+            };
+            let result = survey.estimate();
+            let accepted = result.as_ref().map_or(0, |r| r.fixes);
+            if survey.is_done() {
+                ui.label(format!("Finished: {accepted} fixes, {} rejected", survey.rejected));
+            } else {
+                ui.add(egui::ProgressBar::new(progress).text(format!(
+                    "{accepted} fixes, {} rejected, {window}",
+                    survey.rejected
+                )));
+            }
+
+            if let Some(result) = &result {
+                let coordinate = format!(
+                    "{:.decimals$}, {:.decimals$}",
+                    result.latitude, result.longitude
+                );
+                egui::Grid::new(("survey", &source.label)).show(ui, |ui| {
+                    ui.label(if survey.is_done() { "Averaged" } else { "Running mean" });
+                    ui.monospace(&coordinate);
+                    ui.end_row();
+                    if let Some(altitude) = result.altitude {
+                        ui.label("Altitude");
+                        ui.monospace(format!("{altitude:.2} m"));
+                        ui.end_row();
+                    }
+                    ui.label("σ east / north");
+                    ui.monospace(format!("{:.2} m / {:.2} m", result.std_east, result.std_north));
+                    ui.end_row();
+                    ui.label("σ horizontal")
+                        .on_hover_text("DRMS of the fixes about the averaged position");
+                    ui.monospace(format!("{:.2} m", result.std_horizontal));
+                    ui.end_row();
+                    if let Some(vertical) = result.std_vertical {
+                        ui.label("σ vertical");
+                        ui.monospace(format!("{vertical:.2} m"));
+                        ui.end_row();
+                    }
+                });
+                if survey.is_done() {
+                    ui.horizontal(|ui| {
+                        if ui.button("📋 Copy").clicked() {
+                            ui.output_mut(|o| o.copied_text = coordinate.clone());
+                        }
+                        if ui
+                            .button("Add as waypoint")
+                            .on_hover_text("Exported with the track as a GPX waypoint")
+                            .clicked()
+                        {
+                            state.waypoints.push(Waypoint {
+                                time: now,
+                                source: source.label.clone(),
+                                name: format!("Survey {}", state.waypoints.len() + 1),
+                                latitude: result.latitude,
+                                longitude: result.longitude,
+                                altitude: result.altitude,
+                                note: String::new(),
+                            });
+                        }
+                    });
+                }
+            }
+
+            let mut discard = false;
+            ui.horizontal(|ui| {
+                if !survey.is_done() && ui.button("Finish now").clicked() {
+                    survey.finish();
+                }
+                if ui.button("Restart").clicked() {
+                    *survey = Survey::start(state.settings.survey, now);
+                }
+                discard = ui.button("Discard").clicked();
+            });
+            if discard {
+                source.survey = None;
+            }
+            ui.separator();
+        }
+    }
+
+    /// Each source's fixes as east/north metres from a reference, with the
+    /// current offset, the spread about the mean and Up over time.
+    fn draw_scatter(
+        &self,
+        ui: &mut egui::Ui,
+        sources: &mut [MutexGuard<SourceState>],
+        settings: &Settings,
+    ) {
+        let decimals = settings.coordinate_decimals;
+        let mode = settings.distance_mode;
+        let now = unix_now();
+        for source in sources.iter_mut() {
+            ui.strong(format!("Source {}", source.label));
+            self.draw_enu_reference_controls(ui, source);
+            let Some((reference, points)) = source.scatter_points() else {
+                ui.weak("Waiting for a fix");
+                ui.separator();
+                continue;
+            };
+            let origin = [reference[0], reference[1]];
+            let current = source.fix_history.back().map(|r| [r.fix.latitude, r.fix.longitude]);
+            let east_north: Vec<[f64; 2]> =
+                points.iter().map(|p| p.east_north).filter(|p| is_finite_point(*p)).collect();
+            let scatter = geo::Scatter::of(&east_north);
+            let ups: Vec<f64> = points.iter().filter_map(|p| p.up).collect();
+            let std_up = (ups.len() >= 2).then(|| {
+                let mean = ups.iter().sum::<f64>() / ups.len() as f64;
+                let squares: f64 = ups.iter().map(|u| (u - mean).powi(2)).sum();
+                (squares / (ups.len() - 1) as f64).sqrt()
+            });
+
+            egui::Grid::new(("scatter", &source.label)).show(ui, |ui| {
+                ui.label("Reference");
+                let from = match source.scatter_reference {
+                    Some(r) if r.typed => "entered",
+                    Some(_) => "set",
+                    None => "oldest fix",
+                };
+                ui.monospace(format!(
+                    "{:.decimals$}, {:.decimals$}, {:.1} m ({from})",
+                    reference[0], reference[1], reference[2]
+                ));
+                ui.end_row();
+                if let Some(here) = current {
+                    ui.label("Current");
+                    let up = points.last().and_then(|p| p.up);
+                    let bearing = mode.bearing_deg(origin, here);
+                    ui.monospace(format!(
+                        "{:.2} m at {}{}",
+                        mode.distance_m(origin, here),
+                        settings.bearing_unit.format(bearing, source.variation(), 1),
+                        up.map_or(String::new(), |u| format!(", up {u:+.2} m"))
+                    ));
+                    ui.end_row();
+                }
+                ui.label("Fixes");
+                ui.monospace(points.len().to_string());
+                ui.end_row();
+                if let Some(scatter) = &scatter {
+                    ui.label("σ east / north");
+                    ui.monospace(format!(
+                        "{:.2} m / {:.2} m",
+                        scatter.std_east, scatter.std_north
+                    ));
+                    ui.end_row();
+                    ui.label("σ horizontal")
+                        .on_hover_text("DRMS: √(σE² + σN²) about the mean position");
+                    ui.monospace(format!("{:.2} m", scatter.horizontal()));
+                    ui.end_row();
+                }
+                if let Some(std_up) = std_up {
+                    ui.label("σ up");
+                    ui.monospace(format!("{std_up:.2} m"));
+                    ui.end_row();
+                }
+            });
+
+            Plot::new(("scatter_plot", &source.label))
+                .height(200.0)
+                .data_aspect(1.0)
+                .x_axis_label("East (m)")
+                .y_axis_label("North (m)")
+                .show(ui, |plot_ui| {
+                    plot_ui.points(
+                        Points::new(PlotPoints::from(east_north.clone()))
+                            .radius(2.0)
+                            .color(settings.palette.series(0).gamma_multiply(0.6))
+                            .name("Fixes"),
+                    );
+                    plot_ui.points(
+                        Points::new(vec![[0.0, 0.0]])
+                            .shape(MarkerShape::Cross)
+                            .radius(6.0)
+                            .color(settings.palette.waypoint())
+                            .name("Reference"),
+                    );
+                    if let Some(scatter) = &scatter {
+                        plot_ui.points(
+                            Points::new(vec![scatter.mean])
+                                .shape(MarkerShape::Plus)
+                                .radius(6.0)
+                                .name("Mean"),
+                        );
+                    }
+                    if let Some(last) = east_north.last() {
+                        plot_ui.points(
+                            Points::new(vec![*last])
+                                .radius(4.0)
+                                .color(settings.palette.series(1))
+                                .name("Current"),
+                        );
+                    }
+                });
+
+            let up: Vec<[f64; 2]> = points
+                .iter()
+                .filter_map(|p| Some([(p.time - now) / 60.0, p.up?]))
+                .collect();
+            if !up.is_empty() {
+                Plot::new(("up_plot", &source.label))
+                    .height(100.0)
+                    .include_x(0.0)
+                    .x_axis_label("minutes")
+                    .y_axis_label("Up (m)")
+                    .show(ui, |plot_ui| {
+                        plot_ui.hline(HLine::new(0.0).color(settings.palette.waypoint()));
+                        let line = Line::new(PlotPoints::from(up));
+                        plot_ui.line(line.color(settings.palette.series(0)).name("Up"));
+                    });
+            }
+            ui.separator();
+        }
+    }
+
+    /// Setting the scatter reference to the current fix, the oldest one
+    /// kept, or a typed coordinate and optional height.
+    fn draw_enu_reference_controls(&self, ui: &mut egui::Ui, source: &mut SourceState) {
+        let current = source.fix_history.back().map(|r| &r.fix);
+        ui.horizontal(|ui| {
+            if let Some(fix) = current {
+                if ui
+                    .button("Set reference here")
+                    .on_hover_text("Measure from the current fix and restart the statistics")
+                    .clicked()
+                {
+                    source.scatter_reference = Some(EnuReference {
+                        since: unix_now(),
+                        position: [fix.latitude, fix.longitude],
+                        altitude: fix.altitude,
+                        typed: false,
+                    });
+                }
+            }
+            if source.scatter_reference.is_some() && ui.button("Use oldest fix").clicked() {
+                source.scatter_reference = None;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Reference:");
+            ui.add(
+                egui::TextEdit::singleline(&mut source.enu_reference_text)
+                    .hint_text("48.1173, 11.5167")
+                    .desired_width(180.0),
+            );
+            ui.label("height (m):");
+            ui.add(
+                egui::TextEdit::singleline(&mut source.enu_height_text)
+                    .hint_text("first fix")
+                    .desired_width(60.0),
+            );
+            if ui.button("Use").clicked() {
+                let height = source.enu_height_text.trim();
+                let altitude = match height {
+                    "" => Ok(None),
+                    text => text
+                        .parse::<f64>()
+                        .map(Some)
+                        .map_err(|_| format!("\"{text}\" is not a height in metres")),
+                };
+                let parsed = coords::parse_position(&source.enu_reference_text);
+                match (parsed, altitude) {
+                    (Ok(position), Ok(altitude)) => {
+                        // A known point measures every fix kept
+                        source.scatter_reference = Some(EnuReference {
+                            since: f64::NEG_INFINITY,
+                            position: [position.latitude, position.longitude],
+                            altitude,
+                            typed: true,
+                        });
+                        source.enu_reference_error = None;
+                    }
+                    (Err(e), _) | (_, Err(e)) => source.enu_reference_error = Some(e),
+                }
+            }
+        });
+        if let Some(err) = &source.enu_reference_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+    }
+
+    /// GPS week, time of week and leap seconds for sources reporting them.
+    fn draw_gps_time(&self, ui: &mut egui::Ui, sources: &[&SourceState]) {
+        let dash = || "—".to_string();
+        for source in sources {
+            let Some(time) = &source.gps_time else {
+                continue;
+            };
+            ui.strong(format!("Source {}", source.label));
+            egui::Grid::new(("gps_time", &source.label)).show(ui, |ui| {
+                ui.label("GPS week");
+                ui.monospace(time.week.map_or_else(dash, |w| w.to_string()));
+                ui.end_row();
+                ui.label("Time of week");
+                ui.monospace(time.tow_secs.map_or_else(dash, |t| format!("{t:.3} s")));
+                ui.end_row();
+                ui.label("Leap seconds");
+                ui.monospace(time.leap_seconds.map_or_else(dash, |l| l.to_string()));
+                ui.end_row();
+                ui.label("GPS − UTC");
+                ui.monospace(time.leap_seconds.map_or_else(dash, |l| format!("{l:+} s")));
+                ui.end_row();
+            });
+        }
+    }
+
+    /// SNR bar per satellite and a per-constellation summary, both after
+    /// the minimum SNR filter.
+    fn draw_signal(&self, ui: &mut egui::Ui, settings: &mut Settings, sources: &[&SourceState]) {
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut settings.min_snr, 0..=50).text("Min SNR (dB-Hz)"))
+                .on_hover_text("Hide weaker satellites from the list, map and charts; 0 shows \
+                                all. Favorites are always shown.");
+            ui.checkbox(&mut settings.group_by_band, "Group by band")
+                .on_hover_text("Compare coverage per frequency band; a satellite tracked on \
+                                several bands appears once in each");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Show SNR as:");
+            for unit in SnrUnit::ALL {
+                ui.selectable_value(&mut settings.snr_display.unit, unit, unit.name());
+            }
+            ui.add_enabled(
+                settings.snr_display.unit == SnrUnit::Percent,
+                egui::DragValue::new(&mut settings.snr_display.full_scale)
+                    .clamp_range(10..=70)
+                    .prefix("100% = ")
+                    .suffix(" dB-Hz"),
+            )
+            .on_hover_text("Strength shown as a full bar; stronger signals are capped at 100%");
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.snr_grid, "Grid");
+            ui.checkbox(&mut settings.snr_histogram, "Histogram")
+                .on_hover_text("How the satellites' C/N0 is spread, in 5 dB-Hz bins: many \
+                                weak signals point to an obstructed sky");
+            ui.checkbox(&mut settings.snr_threshold_line, "Threshold:")
+                .on_hover_text("Line marking a usable signal, to see which satellites clear it");
+            ui.add_enabled_ui(
+                settings.snr_threshold_line && !settings.snr_threshold_from_filter,
+                |ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut settings.snr_threshold)
+                            .clamp_range(0..=60)
+                            .suffix(" dB-Hz"),
+                    );
+                },
+            );
+            ui.add_enabled(
+                settings.snr_threshold_line,
+                egui::Checkbox::new(&mut settings.snr_threshold_from_filter, "Use min SNR"),
+            )
+            .on_hover_text("Draw the line at the minimum SNR filter instead");
+        });
+        let threshold = settings.snr_threshold_db();
+        let display = settings.snr_display;
+
+        for source in sources {
+            let mut sats: Vec<&Satellite> = source.visible_satellites(settings).collect();
+            ui.strong(format!("Source {}", source.label));
+
+            let group_of = |sat: &Satellite| -> String {
+                if settings.group_by_band {
+                    sat.band.clone().unwrap_or_else(|| SINGLE_BAND.to_string())
+                } else {
+                    sat.constellation.name().to_string()
+                }
+            };
+            if settings.group_by_band {
+                sats.sort_by_key(|sat| (group_of(sat), sat.constellation.name(), sat.id.clone()));
+            }
+
+            // A one-bar gap between band groups
+            let mut x = 0.0;
+            let mut previous: Option<String> = None;
+            let bars: Vec<Bar> = sats
+                .iter()
+                .map(|sat| {
+                    let group = group_of(sat);
+                    if settings.group_by_band && previous.as_ref().is_some_and(|p| *p != group) {
+                        x += 1.0;
+                    }
+                    let name = match &sat.band {
+                        Some(band) => format!("{} {} {band}", sat.constellation.name(), sat.id),
+                        None => format!("{} {}", sat.constellation.name(), sat.id),
+                    };
+                    let bar = Bar::new(x, display.value(f64::from(sat.strength)))
+                        .name(name)
+                        .fill(settings.palette.constellation(sat.constellation))
+                        .width(0.7);
+                    x += 1.0;
+                    previous = Some(group);
+                    bar
+                })
+                .collect();
+            Plot::new(("snr_bars", &source.label))
+                .height(140.0)
+                .include_y(0.0)
+                .include_y(display.value(50.0))
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .show_x(false)
+                .show_axes([false, true])
+                .show_grid([false, settings.snr_grid])
+                .y_grid_spacer(snr_grid_marks)
+                .y_axis_label(display.axis_label())
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(BarChart::new(bars));
+                    if let Some(threshold) = threshold.map(f64::from) {
+                        plot_ui.hline(
+                            HLine::new(display.value(threshold))
+                                .name(format!("Threshold {}", display.text(threshold)))
+                                .style(LineStyle::dashed_loose())
+                                .color(WARNING_COLOR),
+                        );
+                    }
+                });
+
+            let mut groups: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+            for sat in &sats {
+                groups.entry(group_of(sat)).or_default().push(sat.strength);
+            }
+            let heading = if settings.group_by_band { "Band" } else { "System" };
+            self.draw_snr_summary(ui, &source.label, heading, &groups, display);
+            if settings.snr_histogram {
+                let histogram = SnrHistogram::new(sats.iter().map(|s| s.strength));
+                self.draw_snr_histogram(ui, &source.label, histogram.as_ref());
+            }
+            ui.separator();
+        }
+        let palette = settings.palette;
+        self.draw_favorites(ui, &mut settings.favorite_satellites, sources, display, palette);
+    }
+
+    /// Satellites per 5 dB-Hz of C/N0, with the mean and median marked.
+    /// The axis stays in dB-Hz whatever the SNR display unit, as the bins do.
+    fn draw_snr_histogram(&self, ui: &mut egui::Ui, label: &str, histogram: Option<&SnrHistogram>) {
+        let Some(histogram) = histogram else {
+            ui.weak("No satellite with an SNR to plot");
+            return;
+        };
+        let bin = f64::from(SNR_BIN_DB);
+        let bars: Vec<Bar> = histogram
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let from = i as f64 * bin;
+                Bar::new(from + bin / 2.0, *count as f64)
+                    .width(bin * 0.9)
+                    .name(format!("{from:.0}–{:.0} dB-Hz", from + bin))
+            })
+            .collect();
+        let marker = ui.visuals().text_color();
+        Plot::new(("snr_histogram", label))
+            .height(100.0)
+            .include_x(0.0)
+            .include_y(0.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .x_axis_label("C/N0 (dB-Hz)")
+            .y_axis_label("Sats")
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(BarChart::new(bars));
+                plot_ui.vline(
+                    VLine::new(histogram.mean)
+                        .name(format!("Mean {:.1}", histogram.mean))
+                        .style(LineStyle::dashed_loose())
+                        .color(marker),
+                );
+                plot_ui.vline(
+                    VLine::new(histogram.median)
+                        .name(format!("Median {:.1}", histogram.median))
+                        .color(WARNING_COLOR),
+                );
+            });
+        ui.label(format!(
+            "Mean {:.1} dB-Hz, median {:.1} dB-Hz",
+            histogram.mean, histogram.median
+        ));
+    }
+
+    /// SNR sparkline of each favorite satellite, kept while it is out of
+    /// view, with a button to unfavorite it.
+    fn draw_favorites(
+        &self,
+        ui: &mut egui::Ui,
+        favorites: &mut BTreeSet<String>,
+        sources: &[&SourceState],
+        display: SnrDisplay,
+        palette: Palette,
+    ) {
+        ui.horizontal(|ui| {
+            ui.strong("Favorites");
+            if favorites.is_empty() {
+                ui.weak("right-click a satellite in the list to add one");
+            } else if ui.small_button("Clear all").clicked() {
+                favorites.clear();
+            }
+        });
+
+        let mut remove = None;
+        for key in favorites.iter() {
+            ui.horizontal(|ui| {
+                if ui.small_button("✖").on_hover_text("Remove from favorites").clicked() {
+                    remove = Some(key.clone());
+                }
+                ui.colored_label(palette.favorite(), format!("★ {key}"));
+                for source in sources {
+                    let snr = source.satellites.iter().find(|s| s.key() == *key);
+                    ui.label(match snr {
+                        Some(sat) => {
+                            format!("{}: {}", source.label, display.text(f64::from(sat.strength)))
+                        }
+                        None => format!("{}: not in view", source.label),
+                    });
+                }
+            });
+            Plot::new(("favorite_sparkline", key.as_str()))
+                .height(40.0)
+                .show_axes([false, true])
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .include_y(0.0)
+                .include_y(display.value(50.0))
+                .show(ui, |plot_ui| {
+                    for (index, source) in sources.iter().enumerate() {
+                        let Some(history) = source.snr_history.get(key) else {
+                            continue;
+                        };
+                        let points: PlotPoints = history
+                            .values()
+                            .enumerate()
+                            .map(|(i, snr)| [i as f64, display.value(f64::from(*snr))])
+                            .collect::<Vec<_>>()
+                            .into();
+                        let line = Line::new(points).color(palette.series(index));
+                        plot_ui.line(line.name(&source.label));
+                    }
+                });
+        }
+        if let Some(key) = remove {
+            favorites.remove(&key);
+        }
+    }
+
+    /// One bar per satellite, grouped by constellation and sorted by PRN:
+    /// filled when used in the fix, hollow when only tracked, with a
+    /// dashed line at the C/N0 threshold. The sources share `size`.
+    fn draw_cn0_bars(
+        &self,
+        ui: &mut egui::Ui,
+        sources: &[&SourceState],
+        settings: &Settings,
+        size: egui::Vec2,
+    ) {
+        let height = size.y / sources.len().max(1) as f32;
+        let display = settings.snr_display;
+        // Label positions below are in dB-Hz, stretched to the display unit
+        let scale = display.value(1.0);
+        for source in sources {
+            let mut sats: Vec<&Satellite> = source.visible_satellites(settings).collect();
+            let prn = |s: &Satellite| s.id.parse::<u32>().unwrap_or(u32::MAX);
+            sats.sort_by_key(|s| {
+                let c = s.constellation;
+                (c == Constellation::Unknown, c as u8, prn(s))
+            });
+
+            // A one-bar gap between constellations
+            let mut bars = Vec::new();
+            let mut labels = Vec::new();
+            let mut groups: Vec<(f64, Constellation)> = Vec::new();
+            let mut x = 0.0;
+            for (i, sat) in sats.iter().enumerate() {
+                if i > 0 && sats[i - 1].constellation != sat.constellation {
+                    x += 1.0;
+                }
+                if groups.last().is_none_or(|(_, c)| *c != sat.constellation) {
+                    groups.push((x, sat.constellation));
+                }
+                let color = settings.palette.constellation(sat.constellation);
+                let bar = Bar::new(x, display.value(f64::from(sat.strength)))
+                    .name(format!("{} {}", sat.constellation.name(), sat.id))
+                    .width(0.8)
+                    .stroke(egui::Stroke::new(1.5, color));
+                bars.push(if sat.used_in_fix {
+                    bar.fill(color)
+                } else {
+                    bar.fill(egui::Color32::TRANSPARENT)
+                });
+                labels.push((x, sat.id.clone()));
+                x += 1.0;
+            }
+
+            if sources.len() > 1 {
+                ui.small(format!("Source {}", source.label));
+            }
+            Plot::new(("cn0_bars", &source.label))
+                .width(size.x)
+                .height(height)
+                .include_y(-6.0 * scale)
+                .include_y(55.0 * scale)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .show_x(false)
+                .show_axes([false, true])
+                .show_grid([false, true])
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(BarChart::new(bars));
+                    plot_ui.hline(
+                        HLine::new(display.value(f64::from(settings.cn0_threshold)))
+                            .style(LineStyle::dashed_loose())
+                            .color(WARNING_COLOR),
+                    );
+                    for (x, id) in labels {
+                        let prn = Text::new(PlotPoint::new(x, -2.0 * scale), id);
+                        plot_ui.text(prn.color(egui::Color32::GRAY));
+                    }
+                    for (x, constellation) in groups {
+                        plot_ui.text(
+                            Text::new(PlotPoint::new(x, 53.0 * scale), constellation.name())
+                                .color(settings.palette.constellation(constellation))
+                                .anchor(egui::Align2::LEFT_CENTER),
+                        );
+                    }
+                });
+        }
+    }
+
+    /// Satellite count and SNR spread per constellation or band.
+    fn draw_snr_summary(
+        &self,
+        ui: &mut egui::Ui,
+        label: &str,
+        heading: &str,
+        groups: &BTreeMap<String, Vec<u8>>,
+        display: SnrDisplay,
+    ) {
+        egui::Grid::new(("snr_summary", label))
+            .striped(true)
+            .show(ui, |ui| {
+                for title in [heading, "Sats", "Min", "Mean", "Max"] {
+                    ui.strong(title);
+                }
+                ui.end_row();
+                for (name, snrs) in groups {
+                    let min = snrs.iter().min().copied().unwrap_or(0);
+                    let max = snrs.iter().max().copied().unwrap_or(0);
+                    let mean = snrs.iter().map(|s| *s as f64).sum::<f64>() / snrs.len() as f64;
+                    ui.label(name);
+                    ui.label(snrs.len().to_string());
+                    ui.label(display.number(f64::from(min)));
+                    ui.label(match display.unit {
+                        SnrUnit::DbHz => format!("{mean:.1}"),
+                        SnrUnit::Percent => display.number(mean),
+                    });
+                    ui.label(display.number(f64::from(max)));
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Reader internals per source, for triaging readers that stop.
+    fn draw_reader_health(&self, ui: &mut egui::Ui, sources: &[&SourceState]) {
+        for source in sources {
+            ui.strong(format!("Source {}", source.label));
+            egui::Grid::new(("reader_health", &source.label))
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, value) in source.health_rows() {
+                        ui.label(name);
+                        ui.monospace(value);
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+        }
+    }
+
+    /// The diagnostics bundle of every source, for review before it is
+    /// copied into a bug report.
+    fn collect_diagnostics(
+        &self,
+        settings: &Settings,
+        inventories: &[Option<Inventory>],
+        sources: &[&SourceState],
+    ) -> String {
+        let reports: Vec<bugreport::SourceReport> = sources
+            .iter()
+            .zip(inventories)
+            .map(|(source, inventory)| {
+                let scan = inventory.as_ref().filter(|i| i.rows.is_some());
+                let inventory = match scan {
+                    Some(scan) => scan.to_text(&source.label),
+                    None => source
+                        .sentence_counts
+                        .iter()
+                        .map(|(kind, count)| format!("{kind:<8} {count:>6}\n"))
+                        .collect(),
+                };
+                let skip = source.nmea_log.len().saturating_sub(bugreport::LOG_TAIL);
+                bugreport::SourceReport {
+                    label: source.label.clone(),
+                    health: source.health_rows(),
+                    inventory,
+                    log_tail: source.nmea_log[skip..].iter().map(|e| e.line.clone()).collect(),
+                }
+            })
+            .collect();
+        let settings_json = serde_json::to_string_pretty(settings).unwrap_or_default();
+        bugreport::bundle(&settings_json, &enumerate_port_entries(), &reports)
+    }
+
+    /// The collected bundle, editable so anything private can be removed
+    /// before it is copied or saved.
+    fn draw_diagnostics_bundle(&self, ui: &mut egui::Ui, state: &mut AppState) {
+        let Some(text) = state.diagnostics_bundle.as_mut() else {
+            return;
+        };
+        ui.colored_label(
+            WARNING_COLOR,
+            "Nothing is redacted: check the ports, paths and log lines before sharing",
+        );
+        ui.horizontal(|ui| {
+            if ui.button("Copy").clicked() {
+                ui.output_mut(|o| o.copied_text = text.clone());
+                state.diagnostics_status = Some("Copied to the clipboard".to_string());
+            }
+            ui.add(
+                egui::TextEdit::singleline(&mut state.diagnostics_path)
+                    .hint_text("diagnostics.txt"),
+            );
+            let has_path = !state.diagnostics_path.trim().is_empty();
+            if ui
+                .add_enabled(has_path, egui::Button::new("Save"))
+                .on_disabled_hover_text("Enter a path first")
+                .clicked()
+            {
+                let path = Path::new(state.diagnostics_path.trim());
+                state.diagnostics_status = Some(match std::fs::write(path, text.as_bytes()) {
+                    Ok(()) => format!("Saved to {}", path.display()),
+                    Err(e) => format!("Could not save {}: {e}", path.display()),
+                });
+            }
+        });
+        if let Some(status) = &state.diagnostics_status {
+            ui.label(status);
+        }
+        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(text)
+                    .code_editor()
+                    .desired_width(f32::INFINITY),
+            );
+        });
+    }
+
+    /// Trigger settings and the events captured from each source, newest
+    /// first, each expandable to the satellites, fix and log at the time.
+    fn draw_events(
+        &self,
+        ui: &mut egui::Ui,
+        triggers: &mut EventTriggers,
+        palette: Palette,
+        sources: &mut [MutexGuard<SourceState>],
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Capture when:");
+            ui.checkbox(&mut triggers.fix_lost, "Fix lost");
+            ui.checkbox(&mut triggers.high_hdop, "HDOP >");
+            ui.add(
+                egui::DragValue::new(&mut triggers.hdop_limit)
+                    .clamp_range(0.5..=50.0)
+                    .speed(0.1),
+            );
+            ui.checkbox(&mut triggers.few_satellites, "Satellites used <");
+            ui.add(egui::DragValue::new(&mut triggers.min_satellites).clamp_range(1..=12));
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut triggers.clock_jump, "Clock jump").on_hover_text(
+                "The receiver's UTC going backwards or leaping ahead, as in spliced logs \
+                 and after receiver resets",
+            );
+            ui.add_enabled(
+                triggers.clock_jump,
+                egui::Checkbox::new(&mut triggers.clock_jump_resets_graphs, "then restart graphs"),
+            )
+            .on_hover_text("Clear the altitude, speed and satellites-in-view graphs");
+        });
+        ui.label(format!(
+            "Each capture keeps the satellites, fix and surrounding log lines; the last {} \
+             per source are kept.",
+            events::MAX_EVENTS
+        ));
+        ui.separator();
+
+        let now = unix_now();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for source in sources.iter_mut() {
+                let source = &mut **source;
+                let recorded = &mut source.events.events;
+                ui.horizontal(|ui| {
+                    ui.strong(format!("Source {}: {} events", source.label, recorded.len()));
+                    if !recorded.is_empty() && ui.small_button("Clear").clicked() {
+                        recorded.clear();
+                    }
+                });
+                for event in recorded.iter().rev() {
+                    let title = format!(
+                        "{} UTC  {}  ({} ago)",
+                        event.fix.utc_time.as_deref().unwrap_or("--:--:--"),
+                        event.reason,
+                        format_hms((now - event.time).max(0.0))
+                    );
+                    egui::CollapsingHeader::new(title)
+                        .id_source(("event", &source.label, event.time.to_bits(), &event.reason))
+                        .show(ui, |ui| self.draw_event(ui, event, palette));
+                }
+                let changes = &source.quality.changes;
+                if !changes.is_empty() {
+                    egui::CollapsingHeader::new(format!("Fix quality changes ({})", changes.len()))
+                        .id_source(("quality_changes", &source.label))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for change in changes.iter().rev() {
+                                let text = format!(
+                                    "{}  {} (from {})",
+                                    clock_text(change.time),
+                                    change.describe(),
+                                    fix_quality_name(change.from)
+                                );
+                                ui.colored_label(palette.quality(change.to), text);
+                            }
+                        });
+                }
+            }
+        });
+    }
+
+    /// Alarm thresholds and each source's alarm history, newest first,
+    /// with the alarms still active highlighted.
+    fn draw_alarms(
+        &self,
+        ui: &mut egui::Ui,
+        state: &mut AppState,
+        sources: &mut [MutexGuard<SourceState>],
+    ) {
+        draw_alarm_thresholds(ui, &mut state.settings.alarm_thresholds);
+        draw_notify_policy(ui, &mut state.settings.alarm_notify);
+        ui.horizontal(|ui| {
+            ui.label("Export (.csv):");
+            ui.text_edit_singleline(&mut state.alarm_export_path);
+            if ui.button("Export").clicked() {
+                let path = Path::new(state.alarm_export_path.trim());
+                let alarms: Vec<(&str, Vec<_>)> = sources
+                    .iter()
+                    .map(|s| (s.label.as_str(), s.alarms.alarms.iter().collect()))
+                    .collect();
+                state.alarm_export_status = Some(match export::write_alarms(path, &alarms) {
+                    Ok(()) => format!("Exported alarms to {}", path.display()),
+                    Err(e) => e,
+                });
+            }
+        });
+        if let Some(status) = &state.alarm_export_status {
+            ui.label(status);
+        }
+        ui.separator();
+
+        let now = unix_now();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for source in sources.iter_mut() {
+                let source = &mut **source;
+                let monitor = &mut source.alarms;
+                ui.horizontal(|ui| {
+                    ui.strong(format!(
+                        "Source {}: {} alarms, {} active",
+                        source.label,
+                        monitor.alarms.len(),
+                        monitor.active().count()
+                    ));
+                    if monitor.held_back > 0 {
+                        ui.label(format!("{} notifications held back", monitor.held_back))
+                            .on_hover_text("Throttled or in the quiet hours; they are in the \
+                                            history all the same");
+                    }
+                    if monitor.alarms.iter().any(|a| !a.is_active())
+                        && ui.small_button("Clear").on_hover_text("Forget cleared alarms").clicked()
+                    {
+                        monitor.clear_history();
+                    }
+                });
+                for alarm in monitor.alarms.iter().rev() {
+                    let text = match alarm.cleared {
+                        Some(cleared) => format!(
+                            "{}  {}  for {}",
+                            clock_text(alarm.raised),
+                            alarm.detail,
+                            format_hms(cleared - alarm.raised)
+                        ),
+                        None => format!(
+                            "{}  {}  active for {}",
+                            clock_text(alarm.raised),
+                            alarm.detail,
+                            format_hms((now - alarm.raised).max(0.0))
+                        ),
+                    };
+                    if alarm.is_active() {
+                        ui.colored_label(egui::Color32::RED, text);
+                    } else {
+                        ui.label(text);
+                    }
+                }
+            }
+        });
+    }
+
+    fn draw_event(&self, ui: &mut egui::Ui, event: &CapturedEvent, palette: Palette) {
+        let fix = &event.fix;
+        ui.label(format!(
+            "{}, {} satellites used, HDOP {}, at {:.6}, {:.6}",
+            fix.quality_name(),
+            fix.satellites_used,
+            fix.hdop.map_or("—".to_string(), |h| format!("{h:.1}")),
+            fix.latitude,
+            fix.longitude
+        ));
+        let in_view: Vec<String> = event
+            .satellites
+            .iter()
+            .map(|s| {
+                let used = if s.used_in_fix { "*" } else { "" };
+                format!("{} {}{used} {} dB-Hz", s.constellation.name(), s.id, s.strength)
+            })
+            .collect();
+        ui.label(format!("{} in view (* used): {}", in_view.len(), in_view.join(", ")));
+        if event.is_filling() {
+            ui.weak("collecting the lines that follow…");
+        }
+        for line in &event.log {
+            let text = egui::RichText::new(line)
+                .monospace()
+                .color(log_line_color(line, palette));
+            ui.add(egui::Label::new(text).wrap(false));
+        }
+    }
+
+    /// Satellites in view over the last half hour, ticked up where
+    /// satellites were acquired and down where they were lost.
+    fn draw_view_history(&self, ui: &mut egui::Ui, sources: &[&SourceState], palette: Palette) {
+        let now = unix_now();
+        for (index, source) in sources.iter().enumerate() {
+            let minutes_ago = |time: f64| (time - now) / 60.0;
+            let since = source.graphs_since.unwrap_or(f64::MIN);
+            let samples = || source.view_history.since(since);
+            let counts: PlotPoints =
+                samples().map(|(t, s)| [minutes_ago(*t), s.count as f64]).collect();
+            let ticks = |changed: fn(&ViewSample) -> usize| -> Vec<[f64; 2]> {
+                samples()
+                    .filter(|(_, s)| changed(s) > 0)
+                    .map(|(t, s)| [minutes_ago(*t), s.count as f64])
+                    .collect()
+            };
+            let (acquired, lost) = (ticks(|s| s.acquired), ticks(|s| s.lost));
+
+            ui.strong(format!("Source {}", source.label));
+            Plot::new(("view_history", &source.label))
+                .height(140.0)
+                .include_x(-VIEW_HISTORY_SECS / 60.0)
+                .include_x(0.0)
+                .include_y(0.0)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .x_axis_label("minutes")
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(counts).color(palette.series(index)).name("In view"));
+                    plot_ui.points(
+                        Points::new(acquired)
+                            .shape(MarkerShape::Up)
+                            .radius(4.0)
+                            .color(palette.acquired())
+                            .name("Acquired"),
+                    );
+                    plot_ui.points(
+                        Points::new(lost)
+                            .shape(MarkerShape::Down)
+                            .radius(4.0)
+                            .color(palette.lost())
+                            .name("Lost"),
+                    );
+                });
+            ui.separator();
+        }
+    }
+
+    /// Saves every buffered graph sample, not just those since the graphs
+    /// were last cleared.
+    fn draw_series_export(
+        &self,
+        ui: &mut egui::Ui,
+        state: &mut AppState,
+        sources: &[&SourceState],
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Export time series (.csv):");
+            ui.text_edit_singleline(&mut state.series_export_path);
+            if ui.button("Export").clicked() {
+                let path = Path::new(state.series_export_path.trim());
+                let series: Vec<export::SeriesExport> = sources
+                    .iter()
+                    .map(|s| export::SeriesExport {
+                        label: &s.label,
+                        fixes: s.fix_history.iter().collect(),
+                        in_view: s.view_history.iter().map(|(t, v)| (*t, v.count)).collect(),
+                    })
+                    .collect();
+                state.series_export_status = Some(match export::write_time_series(path, &series) {
+                    Ok(()) => format!("Exported time series to {}", path.display()),
+                    Err(e) => e,
+                });
+            }
+        });
+        if let Some(status) = &state.series_export_status {
+            ui.label(status);
+        }
+    }
+
+    /// Altitude, speed and HDOP over the fixes kept, with each fix quality
+    /// change marked where it happened.
+    fn draw_fix_graphs(&self, ui: &mut egui::Ui, sources: &[&SourceState], settings: &Settings) {
+        let (palette, altitude_unit) = (settings.palette, settings.altitude_unit);
+        let altitude_label = format!("Altitude ({})", altitude_unit.suffix());
+        let now = unix_now();
+        for (index, source) in sources.iter().enumerate() {
+            let minutes_ago = |time: f64| (time - now) / 60.0;
+            let since = source.graphs_since.unwrap_or(f64::MIN);
+            let series = |history: &TimeSeries, value: &dyn Fn(f64) -> f64| -> Vec<[f64; 2]> {
+                history.since(since).map(|(t, v)| [minutes_ago(*t), value(*v)]).collect()
+            };
+            let altitude = series(&source.altitude_history, &|a| altitude_unit.value(a));
+            let graphs = [
+                ("altitude_graph", altitude_label.as_str(), altitude),
+                ("speed_graph", "Speed (kn)", series(&source.speed_history, &|v| v)),
+                ("hdop_graph", "HDOP", series(&source.hdop_history, &|v| v)),
+            ];
+
+            ui.strong(format!("Source {}", source.label));
+            for (id, label, points) in graphs {
+                // Labels sit at the highest value so they never widen the
+                // automatic bounds
+                let top = points.iter().map(|p| p[1]).reduce(f64::max);
+                Plot::new((id, &source.label))
+                    .height(120.0)
+                    .include_x(0.0)
+                    .link_axis(egui::Id::new(("fix_graphs", &source.label)), true, false)
+                    .x_axis_label("minutes")
+                    .y_axis_label(label)
+                    .show(ui, |plot_ui| {
+                        let line = Line::new(PlotPoints::from(points)).color(palette.series(index));
+                        plot_ui.line(line.name(label));
+                        let Some(top) = top else {
+                            return;
+                        };
+                        for change in source.quality.changes.iter().filter(|c| c.time >= since) {
+                            let x = minutes_ago(change.time);
+                            let color = palette.quality(change.to);
+                            let line = VLine::new(x).color(color);
+                            plot_ui.vline(line.style(LineStyle::dashed_dense()));
+                            plot_ui.text(
+                                Text::new(PlotPoint::new(x, top), change.describe())
+                                    .color(color)
+                                    .anchor(egui::Align2::LEFT_TOP),
+                            );
+                        }
+                    });
+            }
+            ui.separator();
+        }
+    }
+
+    /// Per-type sentence counters for each source.
+    fn draw_statistics(
+        &self,
+        ui: &mut egui::Ui,
+        inventories: &mut [Option<Inventory>],
+        sources: &[&SourceState],
+    ) {
+        for (source, inventory) in sources.iter().zip(inventories.iter_mut()) {
+            ui.strong(format!("Source {}", source.label));
+            self.draw_inventory(ui, inventory, source);
+            let anomalies: u64 = source.field_anomalies.values().sum();
+            if anomalies > 0 {
+                ui.colored_label(
+                    WARNING_COLOR,
+                    format!("⚠ {anomalies} sentences with an unexpected field count"),
+                )
+                .on_hover_text("More or fewer fields than any NMEA version of the type has: \
+                                a firmware quirk or corrupted lines. The parser reads what it \
+                                can of them regardless");
+            }
+            for (sentence, [without, with]) in source.mixed_nmea_versions() {
+                let text = format!("{sentence} {without} before 4.10, {with} 4.10+");
+                ui.colored_label(WARNING_COLOR, format!("⚠ Mixed NMEA versions: {text}"))
+                .on_hover_text("Only some of these carry the signal or system ID NMEA 4.10 \
+                                added, so a receiver mixing versions or logs merged from two \
+                                receivers; it can explain satellites grouped inconsistently");
+            }
+            egui::Grid::new(("sentence_counts", &source.label))
+                .striped(true)
+                .show(ui, |ui| {
+                    for (kind, count) in &source.sentence_counts {
+                        ui.monospace(kind);
+                        ui.label(count.to_string());
+                        match source.field_anomalies.get(kind) {
+                            Some(bad) => ui.colored_label(WARNING_COLOR, format!("{bad} bad")),
+                            None => ui.label(""),
+                        };
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+        }
+    }
+
+    /// Runs pasted sentences through the live path's parsers and shows
+    /// what each made of them.
+    fn draw_inspector(&self, ui: &mut egui::Ui, text: &mut String, settings: &mut Settings) {
+        let field_indices = &mut settings.inspector_field_indices;
+        ui.horizontal(|ui| {
+            ui.label("Paste one or more sentences, one per line:");
+            ui.checkbox(field_indices, "Field indices")
+                .on_hover_text("List the fields of sentences no parser handles by index, \
+                                for working out proprietary ones");
+        });
+        ui.add(
+            egui::TextEdit::multiline(text)
+                .code_editor()
+                .desired_rows(3)
+                .desired_width(f32::INFINITY)
+                .hint_text("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47"),
+        );
+        ui.collapsing("GSV field mapping", |ui| {
+            ui.label("For receivers that send the satellite values out of the standard order: \
+                      offsets from each satellite's PRN field. Applies to every source; \
+                      pasted GSV sentences are decoded both ways below.");
+            let mapping = &mut settings.gsv_mapping;
+            ui.horizontal(|ui| {
+                let max = GsvMapping::MAX_BLOCK;
+                ui.label("Elevation");
+                ui.add(egui::DragValue::new(&mut mapping.elevation).clamp_range(1..=max - 1));
+                ui.label("Azimuth");
+                ui.add(egui::DragValue::new(&mut mapping.azimuth).clamp_range(1..=max - 1));
+                ui.label("SNR");
+                ui.add(egui::DragValue::new(&mut mapping.snr).clamp_range(1..=max - 1));
+                ui.label("Fields per satellite");
+                ui.add(egui::DragValue::new(&mut mapping.block).clamp_range(4..=max));
+                if ui.add_enabled(!mapping.is_standard(), egui::Button::new("Standard")).clicked() {
+                    *mapping = GsvMapping::STANDARD;
+                }
+            });
+            if let Some(problem) = mapping.problem() {
+                ui.colored_label(WARNING_COLOR, format!("Not used, {problem}"));
+            }
+        });
+        let inspections = inspect::inspect_all(text, &settings.gsv_mapping);
+        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            for (i, inspection) in inspections.iter().enumerate() {
+                ui.separator();
+                ui.monospace(&inspection.line);
+                egui::Grid::new(("inspection", i)).striped(true).show(ui, |ui| {
+                    ui.label("Type");
+                    ui.label(inspection.kind.as_deref().unwrap_or("—"));
+                    ui.end_row();
+                    ui.label("Checksum");
+                    ui.label(inspection.checksum.describe());
+                    ui.end_row();
+                    for decoded in &inspection.decoded {
+                        for (name, value) in &decoded.fields {
+                            ui.label(format!("{} {name}", decoded.parser));
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                    }
+                    if settings.inspector_field_indices && inspection.is_unknown() {
+                        for (index, field) in inspect::indexed_fields(&inspection.line) {
+                            ui.label(format!("Field {index}"));
+                            ui.monospace(format!("{field:?}"));
+                            ui.end_row();
+                        }
+                    }
+                });
+                if let Some(anomaly) = &inspection.anomaly {
+                    ui.colored_label(WARNING_COLOR, anomaly.describe());
+                }
+                for problem in &inspection.problems {
+                    ui.colored_label(WARNING_COLOR, problem);
+                }
+            }
+        });
+    }
+
+    /// The extremes of each source since the session started, with when
+    /// each was set.
+    fn draw_session_records(&self, ui: &mut egui::Ui, sources: &[&SourceState]) {
+        for source in sources {
+            ui.strong(format!("Source {}", source.label));
+            egui::Grid::new(("session_records", &source.label))
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, value, record) in source.records.rows() {
+                        ui.label(name);
+                        ui.monospace(value);
+                        ui.label(record.map_or(String::new(), |r| clock_text(r.time)));
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+        }
+    }
+
+    /// The sentence inventory scan of one source: a button to start it,
+    /// its progress, then a table of sentence types and rates.
+    fn draw_inventory(
+        &self,
+        ui: &mut egui::Ui,
+        inventory: &mut Option<Inventory>,
+        source: &SourceState,
+    ) {
+        let now = unix_now();
+        let running = inventory.as_mut().is_some_and(|i| !i.update(&source.sentence_counts, now));
+        ui.horizontal(|ui| {
+            let reading = source.mode == AppMode::Reading;
+            let scan = ui
+                .add_enabled(reading && !running, egui::Button::new("Scan sentence types"))
+                .on_hover_text(format!(
+                    "Count the sentence types the receiver sends over {:.0} s",
+                    inventory::SCAN_SECS
+                ));
+            if scan.clicked() {
+                *inventory = Some(Inventory::start(&source.sentence_counts, now));
+            }
+            match inventory {
+                Some(scan) if running => {
+                    ui.add(egui::ProgressBar::new(scan.progress(now)).desired_width(120.0));
+                    ui.ctx().request_repaint_after(Duration::from_millis(250));
+                }
+                Some(scan) => {
+                    if ui.small_button("📋 Copy").clicked() {
+                        let text = scan.to_text(&source.label);
+                        ui.output_mut(|o| o.copied_text = text);
+                    }
+                    if ui.small_button("Clear").clicked() {
+                        *inventory = None;
+                    }
+                }
+                None => {}
+            }
+        });
+        let Some(rows) = inventory.as_ref().and_then(|i| i.rows.as_ref()) else {
+            return;
+        };
+        if rows.is_empty() {
+            ui.colored_label(WARNING_COLOR, "No sentences arrived during the scan");
+            return;
+        }
+        egui::Grid::new(("inventory", &source.label))
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Sentence");
+                ui.strong("Count");
+                ui.strong("Rate");
+                ui.end_row();
+                for row in rows {
+                    ui.monospace(&row.kind);
+                    ui.label(row.count.to_string());
+                    ui.label(row.rate_text());
+                    ui.end_row();
+                }
+            });
+        ui.add_space(4.0);
+    }
+
+    /// A slider back through the kept fixes; the position, satellite
+    /// list and sky map show the chosen one until Live is pressed, while
+    /// reading carries on.
+    fn draw_fix_scrubber(&self, ui: &mut egui::Ui, source: &mut SourceState) {
+        if source.scrubbed().is_none() {
+            // Live, or the snapshot has aged out of the history
+            source.scrub = None;
+        }
+        let count = source.snapshots.len();
+        if count < 2 {
+            return;
+        }
+        ui.horizontal(|ui| {
+            let newest = count - 1;
+            let mut index = source
+                .scrub
+                .map_or(newest, |time| source.snapshots.partition_point(|s| s.time < time));
+            let slider = egui::Slider::new(&mut index, 0..=newest).show_value(false);
+            if ui.add(slider).on_hover_text("Drag back through recent fixes").changed() {
+                source.scrub = (index < newest).then(|| source.snapshots[index].time);
+            }
+            match source.scrubbed() {
+                Some(snapshot) => {
+                    let ago = unix_now() - snapshot.time;
+                    let text = format!("History: {} ({ago:.0} s ago)", clock_text(snapshot.time));
+                    ui.colored_label(WARNING_COLOR, text);
+                    if ui.button("⏵ Live").clicked() {
+                        source.scrub = None;
+                    }
+                }
+                None => {
+                    ui.label(format!("Live ({count} fixes kept)"));
+                }
+            }
+        });
+    }
+
+    fn draw_satellite_list(
+        &self,
+        ui: &mut egui::Ui,
+        index: usize,
+        source: &mut SourceState,
+        selected: &mut Option<(usize, String)>,
+        sort: &mut SatelliteSort,
+        settings: &mut Settings,
+    ) {
+        let palette = settings.palette;
+        ui.horizontal(|ui| {
+            ui.heading(format!("Satellites ({})", source.label))
+                .on_hover_text("↑/↓ or Tab/Shift+Tab step through the rows");
+            if settings.used_only {
+                ui.colored_label(WARNING_COLOR, "Used in fix only");
+                if !source.satellites.iter().any(|s| s.used_in_fix) {
+                    ui.label("(none marked used; is GSA parsed?)");
+                }
+            }
+            if let Some(snapshot) = source.scrubbed() {
+                let text = format!("⏪ as at {}", clock_text(snapshot.time));
+                ui.colored_label(WARNING_COLOR, text);
+            }
+            if source.frozen_rows.is_some() {
+                ui.colored_label(WARNING_COLOR, "⏸ paused while hovered");
+            }
+            ui.checkbox(&mut settings.stable_satellite_rows, "Stable rows")
+                .on_hover_text("Keep each satellite in the same row, newly acquired ones at \
+                                the bottom, instead of sorting by a column");
+            let used = &source.decoder.used;
+            if let Some(age) = used.age().filter(|_| used.is_stale()) {
+                ui.colored_label(WARNING_COLOR, format!("⚠ GSA {age:.0} s old"))
+                    .on_hover_text("The used-in-fix marks come from a GSA this far behind the \
+                                    latest fix; the receiver may send GSA less often than GGA");
+            }
+            let implausible = source.satellites.iter().filter(|s| s.implausible().is_some());
+            let count = implausible.count();
+            if count > 0 {
+                ui.colored_label(palette.implausible(), format!("⚠ {count} implausible"))
+                    .on_hover_text("Elevation, azimuth or SNR out of range, a sign of GSV \
+                                    parsing trouble with this receiver");
+            }
+        });
+
+        // Hovering freezes the rows so one can be read; header clicks still
+        // re-sort the frozen set
+        let smooth_snr = settings.smooth_snr;
+        let stable = settings.stable_satellite_rows;
+        let current: HashSet<String> = source.satellites.iter().map(Satellite::key).collect();
+        let mut rows = source.frozen_rows.take().unwrap_or_else(|| {
+            // Stable rows keep just-lost satellites, greyed, until they fade
+            let fading = source
+                .lost_satellites
+                .iter()
+                .map(|(sat, _)| sat)
+                .filter(|sat| stable && !current.contains(&sat.key()));
+            source
+                .visible_satellites(settings)
+                .chain(fading)
+                .map(|sat| {
+                    let snr = source.display_snr(sat, smooth_snr, settings.snr_display);
+                    (sat.clone(), snr)
+                })
+                .collect()
+        });
+        if stable {
+            let row = |sat: &Satellite| source.row_order.iter().position(|k| *k == sat.key());
+            rows.sort_by_key(|(sat, _)| row(sat).unwrap_or(usize::MAX));
+        } else {
+            rows.sort_by(|(a, _), (b, _)| sort.compare(a, b));
+        }
+        source.list_order = rows.iter().map(|(sat, _)| sat.key()).collect();
+
+        // A satellite tracked on several bands is reported in one GSV per band
+        let mut sentences: HashMap<String, Vec<&str>> = HashMap::new();
+        for (sat, _) in &rows {
+            let lines = sentences.entry(sat.key()).or_default();
+            if !sat.sentence.is_empty() && !lines.contains(&sat.sentence.as_str()) {
+                lines.push(&sat.sentence);
+            }
+        }
+
+        let mut clicked = None;
+        let table = egui::ScrollArea::vertical()
+            .id_source(("satellites", index))
+            .show(ui, |ui| {
+                egui::Grid::new(("satellite_table", index))
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        for column in SortColumn::ALL {
+                            let active = sort.column == column && !stable;
+                            let arrow = match (active, sort.descending) {
+                                (false, _) => "",
+                                (true, false) => " ⏶",
+                                (true, true) => " ⏷",
+                            };
+                            let header = egui::RichText::new(format!("{}{arrow}", column.title()))
+                                .strong();
+                            if ui.selectable_label(active, header).clicked() {
+                                // Sorting takes over from stable rows
+                                if stable {
+                                    *sort = SatelliteSort {
+                                        column,
+                                        descending: false,
+                                    };
+                                } else {
+                                    sort.toggle(column);
+                                }
+                                settings.stable_satellite_rows = false;
+                            }
+                        }
+                        ui.strong("Sentence");
+                        ui.end_row();
+
+                        for (sat, snr) in &rows {
+                            let key = sat.key();
+                            let expanded = source.expanded_rows.contains(&key);
+                            if ui
+                                .small_button(if expanded { "▾" } else { "▸" })
+                                .on_hover_text("Show the GSV sentences this row came from")
+                                .clicked()
+                                && !source.expanded_rows.remove(&key)
+                            {
+                                source.expanded_rows.insert(key.clone());
+                            }
+                            let is_selected = selected
+                                .as_ref()
+                                .is_some_and(|(i, k)| *i == index && *k == key);
+                            let favorite = settings.favorite_satellites.contains(&key);
+                            let mut label = if favorite {
+                                egui::RichText::new(format!("★ {}", sat.id))
+                                    .color(palette.favorite())
+                            } else {
+                                egui::RichText::new(&sat.id)
+                            };
+                            if source.is_new(&key) {
+                                label = label.color(palette.acquired()).strong();
+                            }
+                            // Dimmed while held over or fading out
+                            let lost = !current.contains(&key) || source.is_held(&key);
+                            if lost {
+                                label = egui::RichText::new(&sat.id).color(palette.held());
+                            }
+                            let implausible = sat.implausible();
+                            if implausible.is_some() {
+                                label = egui::RichText::new(format!("⚠ {}", sat.id))
+                                    .color(palette.implausible())
+                                    .strong();
+                            }
+                            let mut id = ui.selectable_label(is_selected, label);
+                            if is_selected && std::mem::take(&mut source.scroll_to_selected) {
+                                id.scroll_to_me(None);
+                            }
+                            if let Some(reason) = &implausible {
+                                id = id.on_hover_text(format!(
+                                    "{reason}: the GSV fields were probably read out of \
+                                     alignment"
+                                ));
+                            }
+                            if id.clicked() {
+                                clicked = Some((index, key.clone()));
+                            }
+                            if id.secondary_clicked() {
+                                toggle_favorite(&mut settings.favorite_satellites, &key);
+                            }
+                            ui.label(sat.constellation.name());
+                            let value = |text: String| match implausible {
+                                Some(_) => egui::RichText::new(text).color(palette.implausible()),
+                                None if lost => egui::RichText::new(text).color(palette.held()),
+                                None => egui::RichText::new(text),
+                            };
+                            ui.label(value(format!("{:.0}°", sat.latitude)));
+                            ui.label(value(format!("{:.0}°", sat.longitude)));
+                            let snr = ui.label(match implausible {
+                                None if !lost => egui::RichText::new(snr.clone())
+                                    .color(settings.palette.snr(f32::from(sat.strength))),
+                                _ => value(snr.clone()),
+                            });
+                            if smooth_snr {
+                                let raw = settings.snr_display.text(f64::from(sat.strength));
+                                snr.on_hover_text(format!("Raw: {raw}"));
+                            }
+                            ui.label(if sat.used_in_fix { "✔" } else { "" });
+                            match sentences.get(&key).filter(|_| expanded) {
+                                Some(lines) if !lines.is_empty() => {
+                                    let text = egui::RichText::new(lines.join("\n"))
+                                        .monospace()
+                                        .small();
+                                    ui.add(egui::Label::new(text).wrap(false));
+                                }
+                                Some(_) => {
+                                    ui.weak("not recorded");
+                                }
+                                None => {
+                                    ui.label("");
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        if ui.rect_contains_pointer(table.inner_rect) {
+            source.frozen_rows = Some(rows);
+        }
+        if clicked.is_some() {
+            *selected = clicked;
+        }
+    }
+
+    fn draw_track(&self, ui: &mut egui::Ui, state: &mut AppState, sources: &[&SourceState]) {
+        ui.horizontal(|ui| {
+            ui.label("Compare GPX:");
+            ui.text_edit_singleline(&mut state.gpx_path);
+            if ui.button("Import").clicked() {
+                match gpx::load_track(Path::new(state.gpx_path.trim())) {
+                    Ok(points) => {
+                        state.reference_track = points;
+                        state.gpx_error = None;
+                        state.track_fit_all = true;
+                    }
+                    Err(e) => state.gpx_error = Some(e),
+                }
+            }
+            if !state.reference_track.is_empty() && ui.button("Clear").clicked() {
+                state.reference_track.clear();
+            }
+            ui.checkbox(&mut state.track_fit_all, "Fit all");
+            ui.checkbox(&mut state.settings.hold_last_fix, "Hold last fix")
+                .on_hover_text("On loss of fix keep the last position, marked stale; otherwise \
+                                blank it and leave a gap in the track");
+            ui.checkbox(&mut state.settings.track_by_quality, "Colour by fix quality")
+                .on_hover_text("GPS, DGPS, RTK float and RTK fixed stretches in their own colours");
+        });
+
+        if let Some(err) = &state.gpx_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Export track (.gpx/.csv):");
+            ui.text_edit_singleline(&mut state.export_path);
+            if ui.button("Export").clicked() {
+                let path = Path::new(state.export_path.trim());
+                let decimation = state.settings.decimation;
+                let tracks: Vec<export::Track> = sources
+                    .iter()
+                    .map(|s| export::Track {
+                        label: &s.label,
+                        fixes: decimation.fixes(s.fix_history.iter()),
+                    })
+                    .collect();
+                let decimals = state.settings.coordinate_decimals;
+                let written = export::write_tracks(path, &tracks, &state.waypoints, decimals);
+                state.export_status = Some(match written {
+                    Ok(()) => format!("Exported track to {}", path.display()),
+                    Err(e) => e,
+                });
+            }
+            ui.label("Decimals:");
+            ui.add(egui::DragValue::new(&mut state.settings.coordinate_decimals).clamp_range(0..=9))
+                .on_hover_text("Latitude/longitude decimals shown and exported; 6 ≈ 0.1 m");
+        });
+        if let Some(kept) = state.settings.decimation.describe() {
+            ui.colored_label(WARNING_COLOR, format!("Export decimated: {kept}"));
+        }
+        if let Some(status) = &state.export_status {
+            ui.label(status);
+        }
+
+        self.draw_waypoint_controls(ui, state, sources);
+
+        let decimals = state.settings.coordinate_decimals;
+        let altitude_unit = state.settings.altitude_unit;
+        for source in sources {
+            if let Some((fix, stale)) = source.displayed_fix(state.settings.hold_last_fix) {
+                ui.label(format!(
+                    "{}{}: Lat: {:.p$}  Lon: {:.p$}  Alt: {}  Quality: {}  Sats: {}  HDOP: {}",
+                    source.label,
+                    if stale { " (last known, stale)" } else { "" },
+                    fix.latitude,
+                    fix.longitude,
+                    fix.altitude.map_or("—".to_string(), |a| altitude_unit.format(a, 1)),
+                    fix.quality,
+                    fix.satellites_used,
+                    fix.hdop.map_or("—".to_string(), |h| format!("{h:.1}")),
+                    p = decimals,
+                ));
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.settings.map_tiles, "Map tiles");
+            if state.settings.map_tiles {
+                ui.label("URL:");
+                let current = &state.settings.tile_url;
+                let mut url = state.tile_url_edit.clone().unwrap_or_else(|| current.clone());
+                let entry = ui.text_edit_singleline(&mut url);
+                if entry.changed() {
+                    state.tile_url_edit = Some(url.clone());
+                }
+                let entered = entry.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                let edited = state.tile_url_edit.as_ref().is_some_and(|u| u != current);
+                let apply = ui.add_enabled(edited, egui::Button::new("Apply")).clicked();
+                if edited && (apply || entered) {
+                    state.settings.tile_url = url.trim().to_string();
+                    state.tile_url_edit = None;
+                }
+            }
+        });
+        if !state.settings.map_tiles {
+            state.tile_layer = None;
+        } else if state
+            .tile_layer
+            .as_ref()
+            .is_none_or(|layer| layer.url_template != state.settings.tile_url)
+        {
+            state.tile_layer = Some(tiles::TileLayer::new(&state.settings.tile_url));
+        }
+
+        let fit_all = state.track_fit_all;
+        let hold_last_fix = state.settings.hold_last_fix;
+        let by_quality = state.settings.track_by_quality;
+        let palette = state.settings.palette;
+        let ctx = ui.ctx().clone();
+        let response = Plot::new("track_plot")
+            .data_aspect(1.0)
+            .show(ui, |plot_ui| {
+                // Tiles go first so the tracks draw on top
+                if let Some(layer) = state.tile_layer.as_mut() {
+                    let bounds = plot_ui.plot_bounds();
+                    for (key, texture) in layer.tiles(&ctx, bounds.min(), bounds.max()) {
+                        let ([west, south], [east, north]) = key.bounds();
+                        plot_ui.image(PlotImage::new(
+                            texture,
+                            PlotPoint::new((west + east) / 2.0, (south + north) / 2.0),
+                            [(east - west) as f32, (north - south) as f32],
+                        ));
+                    }
+                }
+
+                if !state.reference_track.is_empty() {
+                    let reference: PlotPoints = state.reference_track.clone().into();
+                    plot_ui.line(
+                        Line::new(reference)
+                            .color(egui::Color32::from_gray(120).gamma_multiply(0.5))
+                            .name("Reference"),
+                    );
+                }
+
+                for (index, source) in sources.iter().enumerate() {
+                    // Gaps at each loss of fix, unless the last fix is held;
+                    // coloured by quality, a new segment at each change too
+                    let mut segments: Vec<(u8, Vec<[f64; 2]>)> = Vec::new();
+                    for record in source.fix_history.iter() {
+                        let point = [record.fix.longitude, record.fix.latitude];
+                        if !is_finite_point(point) {
+                            continue;
+                        }
+                        let quality = record.fix.quality;
+                        let gap = record.segment_start && !hold_last_fix;
+                        match segments.last_mut().filter(|_| !gap) {
+                            Some((q, segment)) if !by_quality || *q == quality => {
+                                segment.push(point)
+                            }
+                            Some((_, segment)) => {
+                                // Starts where the last one ended, leaving no gap
+                                let joint = segment[segment.len() - 1];
+                                segments.push((quality, vec![joint, point]));
+                            }
+                            None => segments.push((quality, vec![point])),
+                        }
+                    }
+                    for (quality, segment) in segments {
+                        let (color, name) = if by_quality {
+                            let name = format!("{} {}", source.label, fix_quality_name(quality));
+                            (palette.quality(quality), name)
+                        } else {
+                            (palette.series(index), format!("Live {}", source.label))
+                        };
+                        plot_ui.line(Line::new(PlotPoints::from(segment)).color(color).name(name));
+                    }
+                }
+
+                if !state.waypoints.is_empty() {
+                    let points: Vec<[f64; 2]> =
+                        state.waypoints.iter().map(|w| [w.longitude, w.latitude]).collect();
+                    plot_ui.points(
+                        Points::new(points)
+                            .shape(MarkerShape::Diamond)
+                            .radius(6.0)
+                            .color(palette.waypoint())
+                            .name("Waypoints"),
+                    );
+                    for wpt in &state.waypoints {
+                        plot_ui.text(
+                            Text::new(PlotPoint::new(wpt.longitude, wpt.latitude), &wpt.name)
+                                .color(palette.waypoint())
+                                .anchor(egui::Align2::LEFT_BOTTOM),
+                        );
+                    }
+                }
+
+                if fit_all && state.tile_layer.is_some() {
+                    // Tiles would count towards auto bounds and zoom out
+                    // forever, so fit the tracks by hand
+                    let points = state.reference_track.iter().copied().chain(
+                        sources
+                            .iter()
+                            .flat_map(|s| s.fix_history.iter())
+                            .map(|r| [r.fix.longitude, r.fix.latitude]),
+                    );
+                    if let Some(bounds) = fit_bounds(points) {
+                        plot_ui.set_plot_bounds(bounds);
+                    }
+                } else if fit_all {
+                    plot_ui.set_auto_bounds(egui::Vec2b::TRUE);
+                }
+            })
+            .response;
+
+        if let Some(layer) = &state.tile_layer {
+            ui.horizontal(|ui| {
+                ui.small(tiles::ATTRIBUTION);
+                if let Some(err) = &layer.last_error {
+                    ui.small(format!("(some tiles unavailable: {err})"));
+                }
+            });
+            if layer.is_loading() {
+                ui.ctx().request_repaint_after(Duration::from_millis(200));
+            }
+        }
+
+        // Panning or zooming by hand leaves "fit all" mode
+        let scrolled = ui.input(|i| i.smooth_scroll_delta != egui::Vec2::ZERO);
+        if response.dragged() || (response.hovered() && scrolled) {
+            state.track_fit_all = false;
+        }
+    }
+
+    /// Fix type, satellites used, HDOP and position of each source side by
+    /// side, in lettering sized to fill the window, with the errors and
+    /// warnings the hidden panels would show beneath.
+    fn draw_big_readout(
+        &self,
+        ui: &mut egui::Ui,
+        sources: &[&SourceState],
+        port_error: Option<&str>,
+        settings: &mut Settings,
+    ) {
+        let shortcut = ui.ctx().format_shortcut(&BIG_READOUT_SHORTCUT);
+        ui.horizontal(|ui| {
+            if ui.small_button(format!("Full view ({shortcut})")).clicked() {
+                settings.big_readout = false;
+            }
+            if let Some(err) = port_error {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+        });
+        let decimals = settings.coordinate_decimals;
+        let normal = ui.visuals().strong_text_color();
+        ui.columns(sources.len().max(1), |columns| {
+            for (ui, source) in columns.iter_mut().zip(sources) {
+                let live = source.connection_state() == "Reading";
+                let fix = source.fix.as_ref().filter(|_| live);
+                let held = source.displayed_fix(settings.hold_last_fix).filter(|_| live);
+                let (fix_type, fix_color) = match fix {
+                    Some(fix) => {
+                        let color = settings.palette.quality(fix.quality);
+                        (fix.quality_name().to_uppercase(), color)
+                    }
+                    None => (source.connection_state().to_uppercase(), egui::Color32::GRAY),
+                };
+                let position_color = match held {
+                    Some((_, true)) => WARNING_COLOR,
+                    _ => normal,
+                };
+                let coordinate = |value: Option<f64>| {
+                    value.map_or("—".to_string(), |v| format!("{v:.decimals$}"))
+                };
+                let hdop = fix.and_then(|f| f.hdop);
+                let hdop = hdop.map_or("—".to_string(), |h| format!("{h:.1}"));
+                let rows = [
+                    (fix_type, fix_color),
+                    (format!("{} sats", fix.map_or(0, |f| f.satellites_used)), normal),
+                    (format!("HDOP {hdop}"), normal),
+                    (coordinate(held.map(|(f, _)| f.latitude)), position_color),
+                    (coordinate(held.map(|(f, _)| f.longitude)), position_color),
+                ];
+
+                let alerts = source.alerts();
+
+                // As large as lets the longest row fit the column and all
+                // rows, between a smaller source label and alerts, fit the
+                // height
+                let longest = rows.iter().map(|(t, _)| t.chars().count()).max().unwrap_or(1);
+                let by_width = ui.available_width() / (longest as f32 * BIG_READOUT_CHAR_WIDTH);
+                let lines = rows.len() as f32 + 0.4 + alerts.len() as f32 * 0.4;
+                let by_height = ui.available_height() / (lines * 1.3);
+                let size = by_width.min(by_height).max(12.0);
+                ui.vertical_centered(|ui| {
+                    let label = format!("Source {}", source.label);
+                    ui.label(egui::RichText::new(label).size(size * 0.4));
+                    for (text, color) in rows {
+                        ui.label(egui::RichText::new(text).monospace().size(size).color(color));
+                    }
+                    for (color, alert) in alerts {
+                        let text = egui::RichText::new(format!("⚠ {alert}")).size(size * 0.4);
+                        ui.label(text.color(color));
+                    }
+                });
+            }
+        });
+    }
+
+    /// The sky map, C/N0 bars and key numbers of every source in one
+    /// window, side by side when it is wide enough and stacked otherwise.
+    fn draw_dashboard(&self, ui: &mut egui::Ui, state: &mut AppState, sources: &[&SourceState]) {
+        let (side, wide) = dashboard_layout(ui.available_size());
+        let settings = &state.settings;
+        let selected = state.selected_satellite.as_ref();
+        let snapshots = &state.sky_snapshots;
+        let map = |ui: &mut egui::Ui| {
+            ui.allocate_ui(egui::Vec2::splat(side), |ui| {
+                self.draw_satellite_map(ui, sources, selected, settings, None, snapshots)
+            })
+            .inner
+        };
+        let details = |ui: &mut egui::Ui| {
+            self.draw_key_stats(ui, sources, settings);
+            ui.add_space(4.0);
+            let size = egui::vec2(ui.available_width(), ui.available_height().max(120.0));
+            self.draw_cn0_bars(ui, sources, settings, size);
+        };
+        let clicked = if wide {
+            ui.horizontal_top(|ui| {
+                let clicked = map(ui);
+                ui.vertical(details);
+                clicked
+            })
+            .inner
+        } else {
+            let clicked = map(ui);
+            details(ui);
+            clicked
+        };
+        if clicked.is_some() {
+            state.selected_satellite = clicked;
+        }
+    }
+
+    /// Fix type, satellites used and in view, HDOP and speed, one row per
+    /// source.
+    fn draw_key_stats(&self, ui: &mut egui::Ui, sources: &[&SourceState], settings: &Settings) {
+        egui::Grid::new("key_stats").striped(true).show(ui, |ui| {
+            for heading in ["Source", "Fix", "Used / in view", "HDOP", "Speed"] {
+                ui.strong(heading);
+            }
+            ui.end_row();
+            for source in sources {
+                let live = source.connection_state() == "Reading" || source.scrub.is_some();
+                let fix = source.fix.as_ref().filter(|_| live);
+                let rmc = source.rmc.as_ref().filter(|r| live && r.valid);
+                ui.label(&source.label);
+                match fix {
+                    Some(fix) => {
+                        let color = settings.palette.quality(fix.quality);
+                        ui.colored_label(color, fix.quality_name())
+                    }
+                    None => ui.label(source.connection_state()),
+                };
+                let used = fix.map_or("—".to_string(), |f| f.satellites_used.to_string());
+                ui.monospace(format!("{used} / {}", source.satellites.len()));
+                let hdop = fix.and_then(|f| f.hdop);
+                ui.monospace(hdop.map_or("—".to_string(), |h| format!("{h:.1}")));
+                let speed = rmc.and_then(|r| r.speed_knots);
+                ui.monospace(speed.map_or("—".to_string(), |s| format!("{s:.1} kn")));
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Drops a named waypoint at the first source's current fix, from the
+    /// button or Ctrl+D. Disabled while no source has a position.
+    fn draw_waypoint_controls(
+        &self,
+        ui: &mut egui::Ui,
+        state: &mut AppState,
+        sources: &[&SourceState],
+    ) {
+        let current = sources.iter().find_map(|s| match s.displayed_fix(false) {
+            Some((fix, false)) => Some((&s.label, fix)),
+            _ => None,
+        });
+        let shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::D);
+
+        let mut mark = false;
+        ui.horizontal(|ui| {
+            ui.label("Waypoint:");
+            ui.add(egui::TextEdit::singleline(&mut state.waypoint_name).hint_text("optional name"));
+            mark = ui
+                .add_enabled(current.is_some(), egui::Button::new("Mark waypoint"))
+                .on_hover_text(format!("Shortcut: {}", ui.ctx().format_shortcut(&shortcut)))
+                .on_disabled_hover_text("Needs a current fix")
+                .clicked();
+            if !state.waypoints.is_empty() {
+                ui.label(format!("{} marked", state.waypoints.len()));
+                if ui.button("Clear waypoints").clicked() {
+                    state.waypoints.clear();
+                }
+            }
+        });
+        mark |= ui.input_mut(|i| i.consume_shortcut(&shortcut));
+
+        ui.horizontal(|ui| {
+            ui.label("Or at:");
+            let entry = egui::TextEdit::singleline(&mut state.waypoint_position)
+                .hint_text("48.1173, 11.5167 or 48°07'02\"N 11°31'00\"E");
+            let entered =
+                ui.add(entry).lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let add = ui
+                .add_enabled(!state.waypoint_position.trim().is_empty(), egui::Button::new("Add"))
+                .on_hover_text("Decimal degrees, degrees and minutes, DMS or NMEA ddmm.mmmm");
+            if !(entered || add.clicked()) || state.waypoint_position.trim().is_empty() {
+                return;
+            }
+            match coords::parse_position(&state.waypoint_position) {
+                Ok(position) => {
+                    let name = match state.waypoint_name.trim() {
+                        "" => format!("WP{}", state.waypoints.len() + 1),
+                        name => name.to_string(),
+                    };
+                    state.waypoints.push(Waypoint {
+                        time: unix_now(),
+                        source: format!("entered as {}", position.format.name()),
+                        name,
+                        latitude: position.latitude,
+                        longitude: position.longitude,
+                        altitude: None,
+                        note: String::new(),
+                    });
+                    state.waypoint_name.clear();
+                    state.waypoint_position.clear();
+                    state.waypoint_position_error = None;
+                }
+                Err(e) => state.waypoint_position_error = Some(e),
+            }
+        });
+        if let Some(err) = &state.waypoint_position_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+
+        if let (true, Some((source, fix))) = (mark, current) {
+            let name = match state.waypoint_name.trim() {
+                "" => format!("WP{}", state.waypoints.len() + 1),
+                name => name.to_string(),
+            };
+            state.waypoints.push(Waypoint {
+                time: unix_now(),
+                source: source.clone(),
+                name,
+                latitude: fix.latitude,
+                longitude: fix.longitude,
+                altitude: fix.altitude,
+                note: String::new(),
+            });
+            state.waypoint_name.clear();
+        }
+
+        egui::CollapsingHeader::new(format!("Waypoint list ({})", state.waypoints.len()))
+            .id_source("waypoint_list")
+            .show(ui, |ui| self.draw_waypoint_list(ui, state));
+    }
+
+    /// Editable waypoints, loaded from and saved to a GPX file.
+    fn draw_waypoint_list(&self, ui: &mut egui::Ui, state: &mut AppState) {
+        ui.horizontal(|ui| {
+            ui.label("GPX file:");
+            ui.text_edit_singleline(&mut state.waypoint_file);
+            let path = PathBuf::from(state.waypoint_file.trim());
+            let named = !state.waypoint_file.trim().is_empty();
+            if ui
+                .add_enabled(named, egui::Button::new("Load"))
+                .on_hover_text("Add the file's <wpt> points; any tracks in it are ignored")
+                .clicked()
+            {
+                state.waypoint_file_status = Some(match gpx::load_waypoints(&path) {
+                    Ok(loaded) => {
+                        let message = format!("Loaded {} waypoints", loaded.len());
+                        state.waypoints.extend(loaded);
+                        message
+                    }
+                    Err(e) => e,
+                });
+            }
+            let save = egui::Button::new("Save");
+            if ui.add_enabled(named && !state.waypoints.is_empty(), save).clicked() {
+                let decimals = state.settings.coordinate_decimals;
+                let written = export::write_waypoints(&path, &state.waypoints, decimals);
+                let count = state.waypoints.len();
+                state.waypoint_file_status = Some(match written {
+                    Ok(()) => format!("Saved {count} waypoints to {}", path.display()),
+                    Err(e) => e,
+                });
+            }
+        });
+        if let Some(status) = &state.waypoint_file_status {
+            ui.label(status);
+        }
+
+        let decimals = state.settings.coordinate_decimals;
+        let mut remove = None;
+        egui::Grid::new("waypoint_grid").striped(true).show(ui, |ui| {
+            for title in ["Name", "Latitude", "Longitude", "Note", ""] {
+                ui.strong(title);
+            }
+            ui.end_row();
+            for (index, wpt) in state.waypoints.iter_mut().enumerate() {
+                ui.add(egui::TextEdit::singleline(&mut wpt.name).desired_width(100.0));
+                for (value, limit) in [(&mut wpt.latitude, 90.0), (&mut wpt.longitude, 180.0)] {
+                    ui.add(
+                        egui::DragValue::new(value)
+                            .clamp_range(-limit..=limit)
+                            .speed(1e-6)
+                            .fixed_decimals(decimals)
+                            .suffix("°"),
+                    );
+                }
+                ui.add(egui::TextEdit::singleline(&mut wpt.note).desired_width(160.0));
+                if ui
+                    .small_button("✖")
+                    .on_hover_text(format!("Delete · from {}", wpt.source))
+                    .clicked()
+                {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = remove {
+            state.waypoints.remove(index);
+        }
+    }
+
+    fn draw_satellite_detail(
+        &self,
+        ui: &mut egui::Ui,
+        source: &SourceState,
+        key: &str,
+        selected: &mut Option<(usize, String)>,
+        settings: &mut Settings,
+    ) {
+        let Some(sat) = source.satellites.iter().find(|s| s.key() == key) else {
+            return;
+        };
+
+        match sat.known_name().filter(|_| settings.satellite_names) {
+            Some(name) => ui.heading(format!("PRN {} ({name})", sat.id)),
+            None => ui.heading(format!("PRN {}", sat.id)),
+        };
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.satellite_names, "Show SVN/slot").on_hover_text(
+                "Label GPS and GLONASS PRNs with the satellite currently assigned",
+            );
+            let favorite = settings.favorite_satellites.contains(key);
+            let star = if favorite { "★ Favorite" } else { "☆ Favorite" };
+            if ui
+                .selectable_label(favorite, star)
+                .on_hover_text("Always show this satellite and keep its SNR history")
+                .clicked()
+            {
+                toggle_favorite(&mut settings.favorite_satellites, key);
+            }
+        });
+        ui.label(format!("Source: {}", source.label));
+        ui.label(format!("Constellation: {}", sat.constellation.name()));
+        ui.label(format!("Band: {}", sat.band.as_deref().unwrap_or("—")));
+        ui.label(format!("Elevation: {:.0}°", sat.latitude));
+        ui.label(format!("Azimuth: {:.0}°", sat.longitude));
+        let display = settings.snr_display;
+        ui.label(format!("SNR: {}", display.text(f64::from(sat.strength))));
+        if let Some(avg) = source.snr_smoothed.get(key) {
+            ui.label(format!("Smoothed SNR: {}", display.text(f64::from(*avg))));
+        }
+        ui.label(format!("Used in fix: {}", if sat.used_in_fix { "yes" } else { "no" }));
+        if let Some(seen) = source.last_seen.get(key) {
+            ui.label(format!("Last seen: {:.1} s ago", seen.elapsed().as_secs_f32()));
+        }
+        if settings.debug_panel {
+            // Reported against plotted, for suspicions about the sky map math
+            let mapping = &source.decoder.gsv_mapping;
+            let reported = parser::gsv_reported_position(&sat.sentence, &sat.id, mapping);
+            let (el, az) = reported.unwrap_or(("—", "—"));
+            ui.label(format!("GSV reported: az {az}, el {el}"));
+            let view = SkyView::new(settings, &[source]);
+            match view.project(sat.longitude, sat.latitude) {
+                Some((x, y)) => {
+                    ui.label(format!("Plotted at: ({x:.3}, {y:.3})"));
+                    if let Some((azimuth, elevation)) = view.unproject(x, y) {
+                        ui.label(format!("Read back: az {azimuth:.1}°, el {elevation:.1}°"));
+                    }
+                }
+                None => {
+                    ui.label("Plotted at: nowhere, the position isn't a number");
+                }
+            }
+        }
+        if !sat.sentence.is_empty()
+            && ui
+                .small_button("Copy source sentence")
+                .on_hover_text(&sat.sentence)
+                .clicked()
+        {
+            ui.output_mut(|o| o.copied_text = sat.sentence.clone());
+        }
+
+        ui.separator();
+        let snr_history = source.snr_history.get(key);
+        match snr_history.and_then(|h| Some((h.min()?, h.mean()?, h.max()?))) {
+            Some((min, mean, max)) => ui.label(format!(
+                "SNR history: min {}, mean {}, max {}",
+                display.text(min),
+                display.text(mean),
+                display.text(max)
+            )),
+            None => ui.label("SNR history"),
+        };
+
+        let history: PlotPoints = snr_history
+            .map(|h| {
+                h.values()
+                    .enumerate()
+                    .map(|(i, snr)| [i as f64, display.value(f64::from(*snr))])
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+            .into();
+
+        Plot::new("satellite_snr_sparkline")
+            .height(80.0)
+            .show_axes([false, true])
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .include_y(display.value(50.0))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(history));
+            });
+
+        ui.separator();
+        if ui.button("Clear selection").clicked() {
+            *selected = None;
+        }
+    }
+}
+
+// =====================================================================
+// Main App UI
+// =====================================================================
+impl eframe::App for MyApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.state.lock().unwrap().settings);
+    }
+
+    /// A clean exit leaves nothing to recover.
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(write) = state.recovery_write.take() {
+            let _ = write.join();
+        }
+        let _ = std::fs::remove_file(session::recovery_path());
+        state.recovery_lock = None;
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let frame_start = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let source_arcs = state.sources.clone();
+        state.frame_starts.push_back(frame_start);
+        while state
+            .frame_starts
+            .front()
+            .is_some_and(|t| frame_start.duration_since(*t) > FRAME_RATE_WINDOW)
+        {
+            state.frame_starts.pop_front();
+        }
+
+        for source in &source_arcs {
+            self.check_watchdog(source, &state.settings);
+            self.wait_for_port(source, &state.settings);
+        }
+        state.settings.default_sky_sizes();
+        self.sync_repeater(&mut state);
+        #[cfg(feature = "http")]
+        self.sync_http_server(&mut state);
+
+        // A reader that panicked mid-update poisons its lock; the UI carries
+        // on with whatever state it left
+        let locked = Instant::now();
+        let mut sources: Vec<_> = source_arcs
+            .iter()
+            .map(|s| s.lock().unwrap_or_else(PoisonError::into_inner))
+            .collect();
+        for (index, source) in sources.iter_mut().enumerate() {
+            if source.settle_stop() {
+                source.respawn_serial(&source_arcs[index], &state.settings);
+            }
+            source.expire_satellites(state.settings.satellite_timeout());
+            let mirrored = index == state.settings.repeater_source;
+            source.repeater = state.repeater.as_ref().filter(|_| mirrored).map(Repeater::sender);
+            source.loss_grace_secs = state.settings.loss_grace_secs;
+            source.loss_grace_cycles = state.settings.loss_grace_cycles;
+            let caps = state.settings.buffer_caps();
+            if source.caps != caps || source.log_limit != caps.log_lines {
+                source.set_caps(caps);
+            }
+            source.decoder.filter = state.settings.parsed_sentences;
+            source.decoder.gsv_mapping = state.settings.gsv_mapping;
+            source.events.triggers = state.settings.event_triggers;
+            source.alarms.thresholds = state.settings.alarm_thresholds;
+            source.alarms.notify = state.settings.alarm_notify;
+            if source.alarms.notification.take().is_some() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+                    egui::UserAttentionType::Critical,
+                ));
+            }
+            if source.repaint.is_none() {
+                source.repaint = Some(ctx.clone());
+            }
+            if source.favorites != state.settings.favorite_satellites {
+                source.favorites = state.settings.favorite_satellites.clone();
+            }
+            self.sync_satellite_log(source, &state.settings);
+            self.sync_autosave_log(source, &state.settings);
+        }
+        self.scan_for_devices(ctx, &mut state, &source_arcs, &mut sources);
+        self.sync_repeater_advert(&mut state, &sources);
+        // Idle sources let egui sleep until input; live ones repaint at a
+        // rate backed off when frames get expensive
+        let backoff = Duration::from_secs_f32(state.frame_ms * FRAME_BACKOFF / 1000.0);
+        if sources.iter().any(|s| s.has_animations()) {
+            ctx.request_repaint_after(Duration::from_millis(50).max(backoff));
+        } else if sources.iter().any(|s| s.mode.is_active() || s.port_wait.is_some()) {
+            ctx.request_repaint_after(LIVE_REPAINT.max(backoff));
+        }
+        // Wake a stopped source's frozen sky when it is due to be cleared
+        let timeout = state.settings.satellite_timeout();
+        let pending = sources.iter().filter(|s| s.mode != AppMode::Paused);
+        if let Some(due) = pending.filter_map(|s| s.last_gsv).map(|t| t + timeout).min() {
+            ctx.request_repaint_after(due.saturating_duration_since(Instant::now()));
+        }
+        self.save_recovery(&mut state, &sources);
+        self.draw_recovery_prompt(ctx, &mut state, &mut sources);
+        self.draw_new_session_prompt(ctx, &mut state, &mut sources);
+
+        // Drop the selection once its satellite disappears
+        if let Some((index, key)) = &state.selected_satellite {
+            let present = sources
+                .get(*index)
+                .is_some_and(|s| s.satellites.iter().any(|sat| sat.key() == *key));
+            if !present {
+                state.selected_satellite = None;
+            }
+        }
+
+        // Arrow keys step through the satellite table of the selected
+        // satellite's source, the first source's when none is selected
+        if let Some(forward) = satellite_step(ctx) {
+            let index = state.selected_satellite.as_ref().map_or(0, |(i, _)| *i);
+            if let Some(source) = sources.get_mut(index) {
+                let current = state.selected_satellite.as_ref().map(|(_, k)| k.as_str());
+                if let Some(key) = step_selection(&source.list_order, current, forward) {
+                    state.selected_satellite = Some((index, key));
+                    source.scroll_to_selected = true;
+                }
+            }
+        }
+
+        if ctx.input_mut(|i| i.consume_shortcut(&BIG_READOUT_SHORTCUT)) {
+            state.settings.big_readout = !state.settings.big_readout;
+        }
+        // The big readout stands in for every panel and window
+        if state.settings.big_readout {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let all: Vec<&SourceState> = sources.iter().map(|s| &**s).collect();
+                let state = &mut *state;
+                let port_error = state.port_error.as_deref();
+                self.draw_big_readout(ui, &all, port_error, &mut state.settings);
+            });
+            drop(sources);
+            state.record_lock_time(locked);
+            state.record_frame_time(frame_start);
+            return;
+        }
+
+        // Status bar, one line per source
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            for (index, source) in sources.iter().enumerate() {
+                let settings = &state.settings;
+                let accent = source_accent(settings.palette, index, sources.len());
+                self.draw_status_line(ui, source, accent, settings);
+            }
+        });
+
+        if let Some(first) = sources.first_mut() {
+            self.draw_onboarding(ctx, &mut state, &source_arcs[0], first);
+        }
+
+        // Satellite detail panel, pinned while a satellite is selected
+        if let Some((index, key)) = state.selected_satellite.clone() {
+            let state = &mut *state;
+            egui::SidePanel::right("satellite_detail")
+                .resizable(true)
+                .default_width(220.0)
+                .show(ctx, |ui| {
+                    self.draw_satellite_detail(
+                        ui,
+                        &sources[index],
+                        &key,
+                        &mut state.selected_satellite,
+                        &mut state.settings,
+                    );
+                });
+        }
+
+        // Main panel
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Select COM Port");
+
+            ui.horizontal(|ui| {
+                if ui.button("Refresh Ports").clicked() {
+                    match enumerate_ports() {
+                        Ok(ports) => {
+                            state.ports = ports;
+                            state.port_error = None;
+                        }
+                        Err(e) => state.port_error = Some(e),
+                    }
+                }
+                if let Some(err) = &state.port_error {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+                let shortcut = ui.ctx().format_shortcut(&BIG_READOUT_SHORTCUT);
+                if ui
+                    .button("Big readout")
+                    .on_hover_text(format!(
+                        "Just the fix, satellites, HDOP and position, large enough to read \
+                         from a distance. Shortcut: {shortcut}"
+                    ))
+                    .clicked()
+                {
+                    state.settings.big_readout = true;
+                }
+                let collect = ui.button("Diagnostics bundle…").on_hover_text(
+                    "Gather the settings, ports, reader state, sentence inventory and recent \
+                     log into one text to review and paste into a bug report",
+                );
+                if collect.clicked() {
+                    let all: Vec<&SourceState> = sources.iter().map(|s| &**s).collect();
+                    let text = self.collect_diagnostics(&state.settings, &state.inventories, &all);
+                    state.diagnostics_bundle = Some(text);
+                    state.diagnostics_status = None;
+                }
+            });
+            ui.horizontal(|ui| {
+                let used_only = &mut state.settings.used_only;
+                ui.label("Show:");
+                let all = egui::RichText::new("All satellites").strong();
+                ui.selectable_value(used_only, false, all)
+                    .on_hover_text("Every satellite in view");
+                let used = egui::RichText::new("Used in fix").strong();
+                ui.selectable_value(used_only, true, used)
+                    .on_hover_text("Only those the receiver's GSA lists in the solution, in the \
+                                    satellite lists, sky map and signal charts");
+            });
+            ui.horizontal(|ui| {
+                let auto_start = &mut state.settings.auto_start;
+                ui.checkbox(&mut auto_start.enabled, "Auto-start on device").on_hover_text(
+                    "Rescan the ports every few seconds and start reading a matching device \
+                     as soon as it appears",
+                );
+                ui.add_enabled(
+                    auto_start.enabled,
+                    egui::TextEdit::singleline(&mut auto_start.pattern)
+                        .hint_text("1546:01A7, ttyACM*"),
+                )
+                .on_hover_text("USB VID:PID pairs (PID may be *) or port names with * and ?");
+            });
+            if state.settings.auto_start.enabled && !state.detected_ports.is_empty() {
+                let detected: Vec<String> =
+                    state.detected_ports.iter().map(PortEntry::describe).collect();
+                ui.weak(format!("Detected: {}", detected.join(", ")));
+            }
+            if state.ports.is_empty() && state.port_error.is_none() {
+                ui.colored_label(
+                    WARNING_COLOR,
+                    "No serial ports found — plug in a device and click Refresh Ports",
+                );
+                ui.label(missing_port_hint());
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Watchdog timeout (s):");
+                ui.add(
+                    egui::DragValue::new(&mut state.settings.watchdog_timeout_secs)
+                        .clamp_range(1.0..=60.0)
+                        .speed(0.5),
+                );
+                ui.checkbox(&mut state.settings.debug_panel, "Debug")
+                    .on_hover_text("Show the reader health panel");
+                ui.checkbox(&mut state.settings.diagnostics, "Diagnostics")
+                    .on_hover_text("Check GGA/RMC times against each other and the system clock");
+                ui.checkbox(&mut state.settings.smooth_snr, "Smooth SNR")
+                    .on_hover_text("Show a moving average; hover a value for the raw SNR");
+
+                ui.label("Read timeout (ms):");
+                ui.add(
+                    egui::DragValue::new(&mut state.settings.read_timeout_ms)
+                        .clamp_range(50..=10_000)
+                        .speed(10),
+                )
+                .on_hover_text(
+                    "How long a serial read waits for data. Stop takes effect once the \
+                     current read returns, so shorter is more responsive; raise it for \
+                     slow devices. Applies when a reader starts.",
+                );
+
+                let reading = sources.iter().any(|s| s.mode.is_active());
+                ui.label("Flow control:");
+                ui.add_enabled_ui(!reading, |ui| {
+                    let flow = &mut state.settings.flow_control;
+                    egui::ComboBox::from_id_source("flow_control")
+                        .selected_text(flow.name())
+                        .show_ui(ui, |ui| {
+                            for option in FlowControl::ALL {
+                                ui.selectable_value(flow, option, option.name());
+                            }
+                        });
+                })
+                .response
+                .on_hover_text("Handshaking some adapters need to stream reliably at high baud")
+                .on_disabled_hover_text("Stop reading to change");
+                ui.checkbox(&mut state.settings.skip_leading_garbage, "Skip startup noise")
+                    .on_hover_text(
+                        "Discard bytes before the first sentence after connecting or a \
+                         reset, where receivers often send a few bytes of garbage. Applies \
+                         when a reader starts.",
+                    );
+                ui.checkbox(&mut state.settings.open_fallback, "Fall back to 9600 8N1")
+                    .on_hover_text(
+                        "When an adapter refuses the baud rate or flow control, retry once \
+                         at 9600 baud 8N1 without flow control. A port that opens is never \
+                         overridden.",
+                    );
+
+                ui.label("Log lines:");
+                ui.add(
+                    egui::DragValue::new(&mut state.settings.max_log_lines)
+                        .clamp_range(100..=MAX_LOG_LINES)
+                        .speed(10),
+                )
+                .on_hover_text("Lines kept in each source's GPS Stream before the oldest \
+                                are dropped");
+                ui.checkbox(&mut state.settings.low_memory, "Low memory").on_hover_text(
+                    "Hold the log, track, fix history and SNR histories to smaller caps, for \
+                     long runs on small machines; Reader health shows what they use",
+                );
+
+                if source_arcs.len() < MAX_SOURCES && ui.button("Add second source").clicked() {
+                    state
+                        .sources
+                        .push(Arc::new(Mutex::new(SourceState::new("B"))));
+                }
+            });
 
-        // Main panel
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Select COM Port");
+            if state.settings.low_memory {
+                draw_buffer_caps(ui, &mut state.settings.low_memory_caps);
+            }
+            self.draw_session_controls(ui, &mut state, &mut sources);
+            if sources.len() > 1 {
+                ui.collapsing("Compare captures", |ui| {
+                    let align = &mut state.settings.compare_align;
+                    self.draw_comparison(ui, &source_arcs, &mut sources, align);
+                });
+            }
+            ui.collapsing("Repeater", |ui| {
+                let labels: Vec<String> = sources.iter().map(|s| s.label.clone()).collect();
+                self.draw_repeater_controls(ui, &mut state, &labels);
+            });
+            #[cfg(feature = "http")]
+            self.draw_http_controls(ui, &mut state);
 
-            let ports = state.ports.clone();
+            ui.collapsing("Elevation mask", |ui| {
+                self.draw_mask_editor(ui, &mut state.settings.elevation_mask);
+            });
 
-            egui::ComboBox::from_label("COM Port")
-                .selected_text(state.selected_port.as_deref().unwrap_or("Select a Port"))
-                .show_ui(ui, |cb| {
-                    for port in ports {
-                        cb.selectable_value(
-                            &mut state.selected_port,
-                            Some(port.clone()),
-                            port,
-                        );
+            ui.collapsing("Parsed sentences", |ui| {
+                ui.label("Unticked types are still logged but not parsed.");
+                ui.horizontal(|ui| {
+                    for (name, enabled) in state.settings.parsed_sentences.toggles() {
+                        ui.checkbox(enabled, name);
                     }
                 });
+                ui.checkbox(
+                    &mut state.settings.parsed_sentences.prefer_combined,
+                    "Prefer combined GN solution",
+                )
+                .on_hover_text("Ignore GPGGA/GPRMC and similar while the receiver also sends \
+                                GNGGA/GNRMC; disagreements are noted in the log");
+            });
 
-            if ui.button("Start Reading").clicked() && !state.is_reading {
-                if let Some(port_name) = state.selected_port.clone() {
-                    let state_clone = Arc::clone(&self.state);
-
-                    // Thread for GPS streaming
-                    thread::spawn(move || {
-                        let port = serialport::new(port_name, 9600)
-                            .timeout(Duration::from_millis(1000))
-                            .open();
-
-                        if let Ok(mut serial) = port {
-                            let mut buf = [0u8; 1024];
-
-                            loop {
-                                match serial.read(&mut buf) {
-                                    Ok(n) => {
-                                        let data = String::from_utf8_lossy(&buf[..n]);
-                                        let mut satellites = Vec::new();
-
-                                        for line in data.lines() {
-
-                                            // 🔵 Append NMEA line to log
-                                            {
-                                                let mut st = state_clone.lock().unwrap();
-                                                st.nmea_log.push(line.to_string());
-
-                                                // Keep log trimmed
-                                                if st.nmea_log.len() > 500 {
-                                                    st.nmea_log.remove(0);
-                                                }
-                                            }
-
-                                            // Parse GSV
-                                            if line.starts_with("$GPGSV") {
-                                                let fields: Vec<&str> = line.split(',').collect();
-                                                let mut i = 4;
-
-                                                while i + 3 < fields.len() {
-                                                    satellites.push(Satellite {
-                                                        id: fields[i].to_string(),
-                                                        latitude: fields[i + 1].parse().unwrap_or(0.0),
-                                                        longitude: fields[i + 2].parse().unwrap_or(0.0),
-                                                        strength: fields[i + 3].parse().unwrap_or(0),
-                                                    });
-                                                    i += 4;
-                                                }
-                                            }
-                                        }
-
-                                        // Update satellites
-                                        let mut st = state_clone.lock().unwrap();
-                                        st.satellites = satellites;
-                                    }
-                                    Err(_) => break,
-                                }
-
-                                thread::sleep(Duration::from_millis(200));
+            ui.collapsing("Sky map", |ui| {
+                ui.checkbox(&mut state.settings.sky_heading_up, "Heading up")
+                    .on_hover_text("Rotate by the smoothed RMC course, held while stationary; \
+                                    north-up until the receiver has moved");
+                ui.checkbox(&mut state.settings.sky_flip_east_west, "Flip east/west");
+                ui.checkbox(&mut state.settings.sky_trails, "Trails")
+                    .on_hover_text("Trace the path each satellite in view has taken across \
+                                    the sky since it was acquired");
+                ui.checkbox(&mut state.settings.sky_geometry, "Geometry contribution")
+                    .on_hover_text("Ring satellites by how much PDOP would rise without them: \
+                                    lone satellites in an empty part of the sky matter most, \
+                                    ones in a tight cluster repeat each other. Geometry only, \
+                                    so it differs a little from the receiver's DOP");
+                let settings = &mut state.settings;
+                if let Some(radius) = settings.sky_marker_radius.as_mut() {
+                    ui.add(egui::Slider::new(radius, 1.0..=15.0).text("Marker size"));
+                }
+                if let Some(scale) = settings.sky_label_scale.as_mut() {
+                    ui.add(egui::Slider::new(scale, 0.5..=3.0).text("Label scale"));
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Clear satellites after");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.satellite_timeout_secs)
+                            .clamp_range(1.0..=300.0)
+                            .speed(0.5)
+                            .suffix(" s"),
+                    );
+                    ui.label("without GSV");
+                })
+                .response
+                .on_hover_text("Empties the list and sky map when the receiver stops reporting \
+                                satellites, instead of leaving them frozen");
+                ui.horizontal(|ui| {
+                    ui.label("Keep missing satellites for");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.loss_grace_cycles)
+                            .clamp_range(0..=10)
+                            .suffix(" cycles"),
+                    );
+                    ui.label("or");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.loss_grace_secs)
+                            .clamp_range(0.0..=60.0)
+                            .speed(0.5)
+                            .suffix(" s"),
+                    );
+                })
+                .response
+                .on_hover_text("A satellite dropping out of GSV stays, dimmed, until it has \
+                                missed this many cycles or gone this long, whichever comes \
+                                first, so marginal satellites don't flicker");
+                ui.horizontal(|ui| {
+                    ui.label("Colors:");
+                    let palette = &mut state.settings.palette;
+                    egui::ComboBox::from_id_source("palette")
+                        .selected_text(palette.name())
+                        .show_ui(ui, |cb| {
+                            for option in Palette::ALL {
+                                cb.selectable_value(palette, option, option.name());
                             }
-                        }
+                        });
+                })
+                .response
+                .on_hover_text("Constellation, SNR, fix quality and graph line colors");
+                ui.horizontal(|ui| {
+                    ui.label("Projection:");
+                    let current = state.settings.sky_projection;
+                    egui::ComboBox::from_id_source("sky_projection")
+                        .selected_text(current.name())
+                        .show_ui(ui, |cb| {
+                            for projection in SkyProjection::ALL {
+                                cb.selectable_value(
+                                    &mut state.settings.sky_projection,
+                                    projection,
+                                    projection.name(),
+                                )
+                                .on_hover_text(projection.description());
+                            }
+                        })
+                        .response
+                        .on_hover_text(current.description());
+                });
+                ui.horizontal(|ui| {
+                    let settings = &mut state.settings;
+                    ui.checkbox(&mut settings.sky_panel_visible, "Floating panel");
+                    ui.add_enabled_ui(settings.sky_panel_visible, |ui| {
+                        egui::ComboBox::from_id_source("sky_panel_corner")
+                            .selected_text(settings.sky_panel_corner.name())
+                            .show_ui(ui, |cb| {
+                                for corner in PanelCorner::ALL {
+                                    cb.selectable_value(
+                                        &mut settings.sky_panel_corner,
+                                        corner,
+                                        corner.name(),
+                                    );
+                                }
+                            });
+                        let [x, y] = &mut settings.sky_panel_offset;
+                        ui.label("inset");
+                        ui.add(egui::DragValue::new(x).clamp_range(0.0..=2000.0).suffix(" pt"));
+                        ui.add(egui::DragValue::new(y).clamp_range(0.0..=2000.0).suffix(" pt"));
                     });
-
-                    state.is_reading = true;
-                }
-            }
+                })
+                .response
+                .on_hover_text("The C/N0 bars or sky map pinned over the main window");
+                ui.separator();
+                self.draw_snapshot_controls(ui, &mut state, &sources);
+            });
 
             ui.separator();
-            ui.heading("Satellites");
 
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                for sat in &state.satellites {
-                    ui.horizontal(|ui| {
-                        ui.label(format!("ID: {}", sat.id));
-                        ui.label(format!("Elv: {:.2}", sat.latitude));
-                        ui.label(format!("Azm: {:.2}", sat.longitude));
-                        ui.label(format!("Strength: {}", sat.strength));
-                    });
+            let ports = state.ports.clone();
+            let state = &mut *state;
+            let selected_before = state.selected_satellite.clone();
+            ui.columns(sources.len(), |columns| {
+                for (index, column) in columns.iter_mut().enumerate() {
+                    let accent = source_accent(state.settings.palette, index, source_arcs.len());
+                    let source = &mut *sources[index];
+                    self.draw_source_controls(
+                        column,
+                        &ports,
+                        &source_arcs[index],
+                        source,
+                        &mut state.settings,
+                        accent,
+                    );
+                    self.draw_fix_scrubber(column, source);
+                    column.separator();
+                    self.draw_satellite_list(
+                        column,
+                        index,
+                        source,
+                        &mut state.selected_satellite,
+                        &mut state.satellite_sort,
+                        &mut state.settings,
+                    );
                 }
             });
+            // A rename or profile may have given both sources one label,
+            // which their widgets and exports are told apart by
+            if sources.len() > 1 && sources[0].label == sources[1].label {
+                sources[1].label += " 2";
+            }
+            // Picking a satellite from a list zooms the sky map onto it
+            if state.selected_satellite.is_some() && state.selected_satellite != selected_before {
+                state.sky_focus = Some(SkyFocus::Selected);
+            }
         });
 
+        // =====================================================================
+        // Freeze-frame events
+        // =====================================================================
+        egui::Window::new("Events")
+            .default_open(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                let palette = state.settings.palette;
+                self.draw_events(ui, &mut state.settings.event_triggers, palette, &mut sources);
+            });
+
+        // =====================================================================
+        // Alarm thresholds and history
+        // =====================================================================
+        egui::Window::new("Alarms")
+            .default_open(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_alarms(ui, &mut state, &mut sources);
+            });
+
+        // =====================================================================
+        // Survey-in: averaging a stationary position
+        // =====================================================================
+        egui::Window::new("Survey-in")
+            .default_open(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_survey(ui, &mut state, &mut sources);
+            });
+
+        // =====================================================================
+        // Position scatter, for judging noise while stationary
+        // =====================================================================
+        egui::Window::new("Position scatter")
+            .default_open(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_scatter(ui, &mut sources, &state.settings);
+            });
+
+        // =====================================================================
+        // Sentence inspector, for pasted sentences
+        // =====================================================================
+        egui::Window::new("Sentence inspector")
+            .default_open(false)
+            .default_width(450.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                let state = &mut *state;
+                self.draw_inspector(ui, &mut state.inspector_text, &mut state.settings);
+            });
+
+        // The windows below only read, so they draw from copies and the
+        // readers carry on meanwhile
+        let views: Vec<SourceState> = sources.iter().map(|s| s.view()).collect();
+        drop(sources);
+        state.record_lock_time(locked);
+        let sources: Vec<&SourceState> = views.iter().collect();
+
         // =====================================================================
         // NEW: Live GPS Stream Window
         // =====================================================================
@@ -193,25 +7484,207 @@ impl eframe::App for MyApp {
             .default_height(300.0)
             .resizable(true)
             .show(ctx, |ui| {
-                ui.label("Live NMEA Data:");
+                self.draw_stream(ui, &mut state, &sources);
+            });
 
-                egui::ScrollArea::vertical()
-                    .stick_to_bottom(true)
-                    .show(ui, |ui| {
-                        for line in &state.nmea_log {
-                            ui.monospace(line);
-                        }
-                    });
+        // =====================================================================
+        // Signal strength
+        // =====================================================================
+        egui::Window::new("Signal")
+            .default_width(400.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_signal(ui, &mut state.settings, &sources);
             });
 
         // =====================================================================
-        // Mini floating sky map
+        // Reader health, behind the debug toggle
+        // =====================================================================
+        if state.settings.debug_panel {
+            let (frame_ms, lock_ms) = (state.frame_ms, state.lock_ms);
+            let rate = frame_rate(&state.frame_starts);
+            egui::Window::new("Reader health")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    ui.label(format!("UI frame time: {frame_ms:.1} ms"));
+                    // Frames drawn while idle are the UI's idle CPU cost
+                    ui.label(format!(
+                        "UI frame rate: {rate:.1}/s, about {:.1}% of one core",
+                        rate * frame_ms / 10.0
+                    ))
+                    .on_hover_text("Idle sources repaint only on input; live ones when data \
+                                    arrives, at most every 100 ms");
+                    ui.label(format!("Source locks held: {lock_ms:.1} ms per frame"))
+                        .on_hover_text("Readers wait while the UI holds a source; the charts, \
+                                        map, log and readouts draw from copies");
+                    ui.separator();
+                    self.draw_reader_health(ui, &sources);
+                });
+        }
+
+        // =====================================================================
+        // Statistics
+        // =====================================================================
+        egui::Window::new("Statistics")
+            .default_open(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_statistics(ui, &mut state.inventories, &sources);
+            });
+
+        // =====================================================================
+        // Session records
+        // =====================================================================
+        egui::Window::new("Session records")
+            .default_open(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_session_records(ui, &sources);
+            });
+
+        // =====================================================================
+        // Diagnostics bundle, open while one is under review
+        // =====================================================================
+        if state.diagnostics_bundle.is_some() {
+            let mut open = true;
+            egui::Window::new("Diagnostics bundle")
+                .open(&mut open)
+                .default_width(500.0)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    self.draw_diagnostics_bundle(ui, &mut state);
+                });
+            if !open {
+                state.diagnostics_bundle = None;
+            }
+        }
+
+        // =====================================================================
+        // Satellites in view history
+        // =====================================================================
+        egui::Window::new("Satellites in view")
+            .default_open(false)
+            .default_width(400.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_view_history(ui, &sources, state.settings.palette);
+            });
+
+        // =====================================================================
+        // Position readout
+        // =====================================================================
+        egui::Window::new("Position")
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_position(ui, &sources, &state.settings);
+                let state = &mut *state;
+                self.draw_trip_summary(ui, &sources, &state.waypoints, &mut state.settings);
+                self.draw_go_to(ui, state, &sources);
+                ui.collapsing("Signal score weights", |ui| {
+                    draw_score_weights(ui, &mut state.settings.score_weights);
+                });
+            });
+
+        // =====================================================================
+        // GPS time, hidden unless a receiver reports it
+        // =====================================================================
+        if sources.iter().any(|s| s.gps_time.is_some()) {
+            egui::Window::new("GPS Time")
+                .resizable(false)
+                .show(ctx, |ui| {
+                    self.draw_gps_time(ui, &sources);
+                });
+        }
+
+        // =====================================================================
+        // Altitude and speed over time
+        // =====================================================================
+        egui::Window::new("Altitude and speed")
+            .default_open(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_series_export(ui, &mut state, &sources);
+                self.draw_fix_graphs(ui, &sources, &state.settings);
+            });
+
+        // =====================================================================
+        // Track plot
+        // =====================================================================
+        egui::Window::new("Track")
+            .default_width(400.0)
+            .default_height(300.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.draw_track(ui, &mut state, &sources);
+            });
+
+        // =====================================================================
+        // Dashboard: sky map, bars and key numbers together
         // =====================================================================
-        egui::Area::new("mini_sky_map".into())
-            .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+        egui::Window::new("Dashboard")
+            .default_open(false)
+            .default_size([720.0, 380.0])
+            .resizable(true)
             .show(ctx, |ui| {
-                self.draw_satellite_map(ui, &state.satellites);
+                self.draw_dashboard(ui, &mut state, &sources);
             });
+
+        // =====================================================================
+        // Mini floating sky map
+        // =====================================================================
+        if state.settings.sky_panel_visible {
+            let (corner, offset) =
+                corner_anchor(state.settings.sky_panel_corner, state.settings.sky_panel_offset);
+            egui::Area::new("mini_sky_map".into())
+                .anchor(corner, offset)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        let view = &mut state.settings.sky_panel;
+                        ui.selectable_value(view, SkyPanelView::Bars, "C/N0 bars");
+                        ui.selectable_value(view, SkyPanelView::Polar, "Sky map");
+                    });
+
+                    match state.settings.sky_panel {
+                        SkyPanelView::Bars => {
+                            let size = egui::Vec2::splat(SKY_PANEL_SIZE);
+                            self.draw_cn0_bars(ui, &sources, &state.settings, size);
+                            ui.horizontal(|ui| {
+                                ui.label("Threshold:");
+                                ui.add(
+                                    egui::DragValue::new(&mut state.settings.cn0_threshold)
+                                        .clamp_range(0..=60)
+                                        .suffix(" dB-Hz"),
+                                );
+                            });
+                        }
+                        SkyPanelView::Polar => {
+                            let focus = state.sky_focus.take();
+                            let size = egui::Vec2::splat(SKY_PANEL_SIZE);
+                            let clicked = ui
+                                .allocate_ui(size, |ui| {
+                                    self.draw_satellite_map(
+                                        ui,
+                                        &sources,
+                                        state.selected_satellite.as_ref(),
+                                        &state.settings,
+                                        focus,
+                                        &state.sky_snapshots,
+                                    )
+                                })
+                                .inner;
+                            if clicked.is_some() {
+                                state.selected_satellite = clicked;
+                            }
+                            if ui.small_button("Reset view").clicked() {
+                                state.sky_focus = Some(SkyFocus::Reset);
+                                ctx.request_repaint();
+                            }
+                        }
+                    }
+                });
+        }
+
+        state.record_frame_time(frame_start);
     }
 }
 
@@ -224,6 +7697,636 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "NMEA GPS Viewer",
         options,
-        Box::new(|_cc| Box::new(MyApp::default())),
+        Box::new(|cc| Box::new(MyApp::new(cc))),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scatter_counts_fixes_since_the_reference() {
+        let mut source = SourceState::new("A");
+        assert!(source.scatter_points().is_none());
+        for (time, latitude) in [(1.0, 48.0), (2.0, 48.00001), (3.0, 48.00002)] {
+            Arc::make_mut(&mut source.fix_history).push_back(FixRecord {
+                time,
+                fix: GgaFix {
+                    latitude,
+                    longitude: 11.0,
+                    altitude: Some(500.0 + time),
+                    quality: 1,
+                    ..Default::default()
+                },
+                segment_start: false,
+                speed_knots: None,
+            });
+        }
+        let (reference, points) = source.scatter_points().unwrap();
+        assert_eq!(reference, [48.0, 11.0, 501.0]);
+        assert_eq!(points.len(), 3);
+        assert!((points[2].east_north[1] - 2.224).abs() < 1e-3);
+        assert!((points[2].up.unwrap() - 2.0).abs() < 1e-3);
+
+        source.scatter_reference = Some(EnuReference {
+            since: 2.5,
+            position: [48.00002, 11.0],
+            altitude: None,
+            typed: false,
+        });
+        let (reference, points) = source.scatter_points().unwrap();
+        assert_eq!(reference[2], 503.0);
+        assert_eq!(points.len(), 1);
+        assert!(points[0].east_north[0].abs() < 1e-6 && points[0].east_north[1].abs() < 1e-6);
+        assert_eq!(points[0].up, Some(0.0));
+    }
+
+    #[test]
+    fn panel_insets_point_away_from_the_corner() {
+        let inset = [10.0, 20.0];
+        assert_eq!(corner_anchor(PanelCorner::TopLeft, inset).1, [10.0, 20.0]);
+        assert_eq!(corner_anchor(PanelCorner::TopRight, inset).1, [-10.0, 20.0]);
+        let (align, offset) = corner_anchor(PanelCorner::BottomRight, inset);
+        assert_eq!((align, offset), (egui::Align2::RIGHT_BOTTOM, [-10.0, -20.0]));
+    }
+
+    #[test]
+    fn dashboard_goes_side_by_side_when_wide() {
+        let layout = |x, y| dashboard_layout(egui::vec2(x, y));
+        assert_eq!(layout(800.0, 500.0), (400.0, true));
+        assert_eq!(layout(800.0, 300.0), (300.0, true));
+        // Stacked, the map leaves half the height to the rest
+        assert_eq!(layout(400.0, 1000.0), (400.0, false));
+        assert_eq!(layout(400.0, 600.0), (300.0, false));
+        assert_eq!(layout(200.0, 100.0), (120.0, false));
+    }
+
+    #[test]
+    fn snr_grid_every_ten_db() {
+        let marks = snr_grid_marks(GridInput {
+            bounds: (-2.5, 52.0),
+            base_step_size: 0.1,
+        });
+        let values: Vec<f64> = marks.iter().map(|m| m.value).collect();
+        assert_eq!(values, [0.0, 10.0, 20.0, 30.0, 40.0, 50.0]);
+    }
+
+    #[test]
+    fn acquisition_fixes_never_reach_the_track() {
+        let mut source = SourceState::new("A");
+        for line in [
+            "$GPGGA,092751.000,,,,,0,00,99.99,,,,,,*67",
+            "$GPGGA,092751.000,4807.038,N,01131.000,E,0,00,99.99,,,,,,*67",
+            "$GPGGA,092752.000,0000.000,N,00000.000,E,1,04,2.0,0.0,M,,,,*02",
+        ] {
+            source.push_fix(parser::parse_gga(line).unwrap());
+        }
+        assert!(source.fix_history.is_empty());
+        assert!(source.displayed_fix(false).is_none());
+
+        let good = "$GPGGA,092753.000,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*5E";
+        source.push_fix(parser::parse_gga(good).unwrap());
+        assert_eq!(source.fix_history.len(), 1);
+        assert!(source.fix_history[0].segment_start);
+    }
+
+    #[test]
+    fn ttff_ignores_fixes_from_before_the_reset() {
+        let mut source = SourceState::new("A");
+        // Receiver clock reading 09:27:52 when the reset goes out
+        source.gps_clock_offset = Some(34_072.0 - unix_now().rem_euclid(86_400.0));
+        source.start_ttff("cold start");
+        let queued = "$GPGGA,092751.000,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*5C";
+        source.push_fix(parser::parse_gga(queued).unwrap());
+        assert!(source.ttff.is_none() && source.ttff_start.is_some());
+
+        let good = "$GPGGA,092753.000,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*5E";
+        source.push_fix(parser::parse_gga(good).unwrap());
+        assert_eq!(source.ttff.map(|(_, from)| from), Some("cold start"));
+    }
+
+    #[test]
+    fn fresh_source_keeps_connection_choices_only() {
+        let mut source = SourceState::new("B");
+        source.selected_port = Some("/dev/ttyUSB0".to_string());
+        source.baud_rate = 115_200;
+        source.push_log("$GPGGA".to_string());
+        let good = "$GPGGA,092753.000,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*5E";
+        source.push_fix(parser::parse_gga(good).unwrap());
+
+        let fresh = source.fresh();
+        assert_eq!(fresh.label, "B");
+        assert_eq!(fresh.selected_port.as_deref(), Some("/dev/ttyUSB0"));
+        assert_eq!(fresh.baud_rate, 115_200);
+        assert!(fresh.nmea_log.is_empty());
+        assert!(fresh.fix_history.is_empty() && fresh.fix.is_none());
+    }
+
+    #[test]
+    fn scrubbing_shows_a_past_fix_until_it_ages_out() {
+        let mut source = SourceState::new("A");
+        let gga = |sats: u8| GgaFix {
+            latitude: 48.1,
+            longitude: 11.5,
+            quality: 1,
+            satellites_used: sats,
+            ..Default::default()
+        };
+        source.push_fix(gga(5));
+        source.satellites = vec![Satellite::default()];
+        source.push_fix(gga(9));
+        source.scrub = Some(source.snapshots[0].time);
+        source.snapshots[1].time += 1.0;
+
+        let view = source.view();
+        assert_eq!(view.fix.map(|f| f.satellites_used), Some(5));
+        assert!(view.satellites.is_empty() && view.scrub.is_some());
+        assert_eq!(source.fix.as_ref().map(|f| f.satellites_used), Some(9));
+
+        for _ in 0..SNAPSHOT_HISTORY_LEN {
+            source.push_fix(gga(9));
+        }
+        assert!(source.scrubbed().is_none());
+        assert_eq!(source.view().fix.map(|f| f.satellites_used), Some(9));
+        assert!(source.view().scrub.is_none());
+    }
+
+    #[test]
+    fn view_copies_what_the_windows_draw() {
+        let mut source = SourceState::new("A");
+        source.push_log("$GPGGA".to_string());
+        let good = "$GPGGA,092753.000,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*5E";
+        source.push_fix(parser::parse_gga(good).unwrap());
+        source.command_text = "$PMTK605*31".to_string();
+
+        let view = source.view();
+        assert_eq!(view.label, "A");
+        assert_eq!(view.nmea_log.len(), source.nmea_log.len());
+        assert_eq!(view.fix_history.len(), 1);
+        // The big buffers are shared until the source is next written to
+        assert!(Arc::ptr_eq(&view.nmea_log, &source.nmea_log));
+        source.push_log("$GPTXT".to_string());
+        assert_eq!(view.nmea_log.len() + 1, source.nmea_log.len());
+        assert_eq!(view.fix.map(|f| f.satellites_used), Some(8));
+        // Input state is the UI's own and stays with the source
+        assert!(view.command_text.is_empty());
+    }
+
+    #[test]
+    fn stepping_the_selection_wraps() {
+        let order: Vec<String> = ["GPS-03", "GPS-07", "GPS-12"].map(String::from).into();
+        assert_eq!(step_selection(&order, None, true).as_deref(), Some("GPS-03"));
+        assert_eq!(step_selection(&order, None, false).as_deref(), Some("GPS-12"));
+        assert_eq!(step_selection(&order, Some("GPS-03"), true).as_deref(), Some("GPS-07"));
+        assert_eq!(step_selection(&order, Some("GPS-12"), true).as_deref(), Some("GPS-03"));
+        assert_eq!(step_selection(&order, Some("GPS-03"), false).as_deref(), Some("GPS-12"));
+        // A selection filtered out of the table starts over
+        assert_eq!(step_selection(&order, Some("GPS-30"), true).as_deref(), Some("GPS-03"));
+        assert_eq!(step_selection(&[], None, false), None);
+    }
+
+    #[test]
+    fn row_order_keeps_satellites_in_place() {
+        let mut source = SourceState::new("A");
+        let sats = |ids: &[&str]| -> Vec<Satellite> {
+            ids.iter()
+                .map(|id| Satellite {
+                    id: id.to_string(),
+                    constellation: Constellation::Gps,
+                    ..Default::default()
+                })
+                .collect()
+        };
+        source.update_satellites(sats(&["12", "03", "07"]));
+        source.update_satellites(sats(&["07", "21", "12", "03"]));
+        assert_eq!(source.row_order, ["GPS-12", "GPS-03", "GPS-07", "GPS-21"]);
+        // Lost satellites keep their row while they fade
+        source.update_satellites(sats(&["21", "07", "12"]));
+        assert_eq!(source.row_order, ["GPS-12", "GPS-03", "GPS-07", "GPS-21"]);
+        source.lost_satellites.clear();
+        source.update_satellites(sats(&["21", "07", "12"]));
+        assert_eq!(source.row_order, ["GPS-12", "GPS-07", "GPS-21"]);
+    }
+
+    #[test]
+    fn changed_port_settings_await_a_restart() {
+        let mut settings = Settings::default();
+        let mut source = SourceState::new("A");
+        source.serial_params = Some(SerialParams::new(9600, &settings));
+        source.mode = AppMode::Reading;
+        assert!(!source.serial_params_changed(&settings));
+
+        source.baud_rate = 115_200;
+        assert!(source.serial_params_changed(&settings));
+        source.baud_rate = 9600;
+        settings.flow_control = FlowControl::Hardware;
+        assert!(source.serial_params_changed(&settings));
+        // Nothing to restart once stopped
+        source.mode = AppMode::Idle;
+        assert!(!source.serial_params_changed(&settings));
+    }
+
+    #[test]
+    fn missing_satellites_are_held_for_the_grace_period() {
+        let mut source = SourceState::new("A");
+        source.loss_grace_cycles = 2;
+        source.loss_grace_secs = 5.0;
+        let both = parser::parse_gsv("$GPGSV,1,1,02,12,45,270,38,07,10,090,12*7B").unwrap();
+        source.update_satellites(both.clone());
+        let one = vec![both[0].clone()];
+
+        source.update_satellites(one.clone());
+        assert_eq!(source.satellites.len(), 2);
+        assert!(source.is_held("GPS-07") && !source.is_held("GPS-12"));
+        assert!(source.lost_satellites.is_empty());
+        // Back in time: no longer held, and not counted as a new acquisition
+        source.update_satellites(both.clone());
+        assert!(!source.is_held("GPS-07"));
+        assert_eq!(source.view_history.last().map(|(_, s)| s.acquired), Some(0));
+
+        for _ in 0..2 {
+            source.update_satellites(one.clone());
+        }
+        assert!(source.is_held("GPS-07"));
+        source.update_satellites(one.clone());
+        assert_eq!(source.satellites.len(), 1);
+        assert!(!source.is_held("GPS-07"));
+        assert_eq!(source.lost_satellites.len(), 1);
+
+        // Long unseen goes at once, whatever the cycles
+        source.update_satellites(both);
+        source.last_seen.insert("GPS-07".to_string(), Instant::now() - Duration::from_secs(6));
+        source.update_satellites(one);
+        assert_eq!(source.satellites.len(), 1);
+    }
+
+    #[test]
+    fn sessions_keep_held_satellites() {
+        let mut source = SourceState::new("A");
+        source.loss_grace_cycles = 2;
+        source.loss_grace_secs = 5.0;
+        let both = parser::parse_gsv("$GPGSV,1,1,02,12,45,270,38,07,10,090,12*7B").unwrap();
+        source.update_satellites(both.clone());
+        source.update_satellites(vec![both[0].clone()]);
+
+        let restored = SourceSession::capture(&source).restore();
+        assert_eq!(restored.satellites.len(), 2);
+        assert!(restored.is_held("GPS-07") && !restored.is_held("GPS-12"));
+    }
+
+    #[test]
+    fn poisoned_sources_do_not_stop_the_frame() {
+        let app = MyApp {
+            state: Arc::new(Mutex::new(AppState::default())),
+        };
+        let arc = Arc::new(Mutex::new(SourceState::new("A")));
+        let reader = Arc::clone(&arc);
+        let _ = std::thread::spawn(move || {
+            let _guard = reader.lock().unwrap();
+            panic!("reader panicked mid-update");
+        })
+        .join();
+        assert!(arc.is_poisoned());
+
+        let settings = Settings::default();
+        app.check_watchdog(&arc, &settings);
+        app.wait_for_port(&arc, &settings);
+        assert_eq!(arc.lock().unwrap_or_else(PoisonError::into_inner).mode, AppMode::Idle);
+    }
+
+    #[test]
+    fn frame_rate_over_the_window() {
+        let start = Instant::now();
+        assert_eq!(frame_rate(&VecDeque::from([start])), 0.0);
+        let starts: VecDeque<Instant> =
+            (0..5).map(|n| start + Duration::from_millis(250 * n)).collect();
+        assert_eq!(frame_rate(&starts), 4.0);
+    }
+
+    #[test]
+    fn buffer_replay_keeps_the_captured_log() {
+        let arc = Arc::new(Mutex::new(SourceState::new("A")));
+        let good = "$GPGGA,092753.000,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*5E";
+        {
+            let mut source = arc.lock().unwrap();
+            source.push_log(good.to_string());
+            source.push_fix(parser::parse_gga(good).unwrap());
+            source.push_log("[watchdog] no data".to_string());
+            source.replay_buffer(&arc);
+            assert!(source.fix_history.is_empty() && source.nmea_log.is_empty());
+        }
+        for _ in 0..200 {
+            if arc.lock().unwrap().mode == AppMode::Idle {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let source = arc.lock().unwrap();
+        assert_eq!(source.mode, AppMode::Idle);
+        assert_eq!(source.captured_log.as_ref().map(|log| log.len()), Some(2));
+        // Only the sentence is replayed, and parsed again
+        let lines: Vec<&str> = source.nmea_log.iter().map(|e| e.line.as_str()).collect();
+        assert_eq!(lines, [good]);
+        assert_eq!(source.fix_history.len(), 1);
+    }
+
+    #[test]
+    fn favorites_bypass_snr_filter_and_keep_history() {
+        let mut source = SourceState::new("A");
+        let weak = parser::parse_gsv("$GPGSV,1,1,02,12,45,270,38,07,10,090,12*7B").unwrap();
+        source.update_satellites(weak.clone());
+        let mut filter = Settings {
+            min_snr: 20,
+            ..Default::default()
+        };
+        let visible: Vec<&str> =
+            source.visible_satellites(&filter).map(|s| s.id.as_str()).collect();
+        assert_eq!(visible, ["12"]);
+
+        toggle_favorite(&mut source.favorites, "GPS-07");
+        let visible: Vec<&str> =
+            source.visible_satellites(&filter).map(|s| s.id.as_str()).collect();
+        assert_eq!(visible, ["12", "07"]);
+        source.satellites[0].used_in_fix = true;
+        filter.used_only = true;
+        let used: Vec<&str> = source.visible_satellites(&filter).map(|s| s.id.as_str()).collect();
+        assert_eq!(used, ["12"]);
+
+        source.update_satellites(vec![weak[0].clone()]);
+        assert_eq!(source.snr_history["GPS-07"].len(), 1);
+        toggle_favorite(&mut source.favorites, "GPS-07");
+        source.update_satellites(vec![weak[0].clone()]);
+        assert!(!source.snr_history.contains_key("GPS-07"));
+    }
+
+    #[test]
+    fn satellites_expire_without_gsv() {
+        let mut source = SourceState::new("A");
+        let sats = parser::parse_gsv("$GPGSV,1,1,01,12,45,270,38*4A").unwrap();
+        source.update_satellites(sats);
+        assert!(!source.expire_satellites(Duration::from_secs(5)));
+
+        source.last_gsv = Instant::now().checked_sub(Duration::from_secs(6));
+        source.mode = AppMode::Paused;
+        assert!(!source.expire_satellites(Duration::from_secs(5)));
+        source.mode = AppMode::Reading;
+        assert!(source.expire_satellites(Duration::from_secs(5)));
+        assert!(source.satellites.is_empty());
+        assert_eq!(source.lost_satellites.len(), 1);
+        // Only once per silence
+        assert!(!source.expire_satellites(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn log_trims_to_limit_and_counts_drops() {
+        let mut source = SourceState::new("A");
+        source.log_limit = 3;
+        for n in 0..5 {
+            source.push_log(format!("line {n}"));
+        }
+        assert_eq!(source.nmea_log.len(), 3);
+        assert_eq!(source.nmea_log[0].line, "line 2");
+        assert_eq!(source.log_dropped, 2);
+
+        source.log_limit = 1;
+        source.trim_log();
+        assert_eq!(source.nmea_log[0].line, "line 4");
+        assert_eq!(source.log_dropped, 4);
+    }
+
+    #[test]
+    fn big_readout_lists_errors_before_warnings() {
+        let mut source = SourceState::new("A");
+        assert!(source.alerts().is_empty());
+        source.mode = AppMode::Error("Port closed".to_string());
+        source.autosave_error = Some("disk full".to_string());
+        for used in [8, 6, 5] {
+            source.push_fix(GgaFix {
+                latitude: 48.1,
+                longitude: 11.5,
+                quality: 1,
+                satellites_used: used,
+                ..Default::default()
+            });
+        }
+        let alerts = source.alerts();
+        let texts: Vec<&str> = alerts.iter().map(|(_, text)| text.as_str()).collect();
+        assert_eq!(
+            texts,
+            ["Port closed", "disk full", "About to lose fix: 5 satellites used, 4 needed"]
+        );
+        assert_eq!(alerts[0].0, egui::Color32::RED);
+        assert_eq!(alerts[2].0, WARNING_COLOR);
+    }
+
+    #[test]
+    fn low_memory_caps_trim_every_buffer() {
+        let mut source = SourceState::new("A");
+        for n in 0..50 {
+            source.push_log(format!("line {n}"));
+            source.push_fix(GgaFix {
+                latitude: 48.1,
+                longitude: 11.5,
+                quality: 1,
+                altitude: Some(545.4),
+                hdop: Some(0.9),
+                ..Default::default()
+            });
+            source.update_satellites(vec![Satellite {
+                id: "05".to_string(),
+                constellation: Constellation::Gps,
+                latitude: f64::from(n),
+                ..Default::default()
+            }]);
+        }
+        assert_eq!(source.sky_trails["GPS-05"].len(), 50);
+        let full = source.measure_buffers();
+        assert_eq!(source.snapshots.len(), 50);
+
+        let mut settings = Settings {
+            low_memory: true,
+            ..Default::default()
+        };
+        settings.low_memory_caps.track_fixes = 10;
+        settings.low_memory_caps.trail_points = 5;
+        source.set_caps(settings.buffer_caps());
+        assert_eq!(source.log_limit, 200);
+        assert_eq!(source.fix_history.len(), 10);
+        assert_eq!((source.altitude_history.len(), source.hdop_history.len()), (10, 10));
+        // No RMC, so no speed to graph
+        assert!(source.speed_history.is_empty());
+        let trail = &source.sky_trails["GPS-05"];
+        assert_eq!(trail.values().next(), Some(&[0.0, 45.0]));
+        assert!(source.snapshots.is_empty());
+        assert!(source.measure_buffers().total() < full.total());
+        source.push_fix(GgaFix::default());
+        assert!(source.snapshots.is_empty());
+    }
+
+    #[test]
+    fn heading_up_holds_while_stationary() {
+        let rmc = |knots: f64, course: f64| RmcData {
+            valid: true,
+            speed_knots: Some(knots),
+            course: Some(course),
+            ..Default::default()
+        };
+        let mut source = SourceState::new("A");
+        source.note_course(&rmc(0.1, 200.0));
+        assert_eq!(source.heading_up_rotation, None);
+        source.note_course(&rmc(5.0, 350.0));
+        source.note_course(&rmc(5.0, 0.0));
+        let course = source.smoothed_course.unwrap();
+        assert!((course - 352.0).abs() < 1e-9);
+        // Within the hysteresis the map stays put
+        assert_eq!(source.heading_up_rotation, Some(350.0));
+        for _ in 0..5 {
+            source.note_course(&rmc(5.0, 10.0));
+        }
+        let rotation = source.heading_up_rotation.unwrap();
+        assert!(geo::relative_bearing_deg(rotation, 0.0) > 0.0);
+        // Stopped: a noisy course turns nothing
+        source.note_course(&rmc(0.2, 180.0));
+        assert_eq!(source.heading_up_rotation, Some(rotation));
+        source.rmc = Some(rmc(0.2, 180.0));
+        assert_eq!(source.moving_course(), None);
+    }
+
+    #[test]
+    fn notes_are_logged_and_captured() {
+        let mut source = SourceState::new("A");
+        source.push_log("$GPGGA,1".to_string());
+        source.add_note("  moved antenna to window ");
+        let last = source.nmea_log.last().unwrap();
+        assert_eq!(last.line, "[note] moved antenna to window");
+        assert_eq!(log_line_color(&last.line, Palette::Default), Palette::Default.note());
+        let event = source.events.events.back().unwrap();
+        assert_eq!(event.reason, "Note: moved antenna to window");
+        assert_eq!(event.log, ["$GPGGA,1", "[note] moved antenna to window"]);
+
+        // Kept past the log cap, and through a saved session
+        source.log_limit = 1;
+        source.push_log("$GPGGA,2".to_string());
+        assert_eq!(source.nmea_log.len(), 1);
+        let restored = SourceSession::capture(&source).restore();
+        assert_eq!(restored.notes.len(), 1);
+        assert_eq!(restored.notes[0].line, "[note] moved antenna to window");
+    }
+
+    #[test]
+    fn mixed_nmea_versions_are_noted_once() {
+        let mut source = SourceState::new("A");
+        source.note_nmea_version("GSV", true);
+        source.note_nmea_version("GSA", false);
+        assert_eq!(source.mixed_nmea_versions().count(), 0);
+        source.note_nmea_version("GSV", false);
+        source.note_nmea_version("GSV", false);
+        let mixed: Vec<_> = source.mixed_nmea_versions().collect();
+        assert_eq!(mixed, [("GSV", [2, 1])]);
+        let notes = source.nmea_log.iter().filter(|e| e.line.starts_with("[versions]")).count();
+        assert_eq!(notes, 1);
+    }
+
+    #[test]
+    fn stop_waits_for_the_reader_to_exit() {
+        let mut source = SourceState::new("A");
+        source.stop_reader();
+        assert_eq!(source.mode, AppMode::Idle);
+
+        let thread_flag = Arc::new(AtomicBool::new(false));
+        source.reader_abort = Some(Arc::clone(&thread_flag));
+        source.mode = AppMode::Paused;
+        assert!(source.mode.is_active());
+        source.stop_reader();
+        assert_eq!(source.mode, AppMode::Stopping);
+        assert!(thread_flag.load(Ordering::Relaxed));
+        assert_eq!(source.connection_state(), "Stopping");
+        assert!(!source.mode.is_idle());
+
+        source.settle_stop();
+        assert_eq!(source.mode, AppMode::Stopping);
+        drop(thread_flag);
+        source.settle_stop();
+        assert_eq!(source.mode, AppMode::Idle);
+        assert!(source.mode.is_idle());
+        assert!(source.reader_abort.is_none());
+    }
+
+    #[test]
+    fn respawn_waits_for_the_old_reader_to_exit() {
+        let mut source = SourceState::new("A");
+        let thread_flag = Arc::new(AtomicBool::new(false));
+        source.reader_abort = Some(Arc::clone(&thread_flag));
+        source.mode = AppMode::Reconnecting;
+        source.respawn_pending = true;
+        assert!(!source.settle_stop());
+        drop(thread_flag);
+        assert!(source.settle_stop());
+        assert!(!source.respawn_pending);
+        assert!(source.reader_abort.is_none());
+        assert_eq!(source.mode, AppMode::Reconnecting);
+    }
+
+    #[test]
+    fn restart_waits_for_the_old_reader_to_exit() {
+        let mut source = SourceState::new("A");
+        let thread_flag = Arc::new(AtomicBool::new(false));
+        source.reader_abort = Some(Arc::clone(&thread_flag));
+        source.mode = AppMode::Reading;
+        source.restart_serial();
+        assert_eq!(source.mode, AppMode::Stopping);
+        assert!(thread_flag.load(Ordering::Relaxed));
+        assert!(!source.settle_stop());
+        drop(thread_flag);
+        assert!(source.settle_stop());
+        assert!(source.reader_abort.is_none());
+    }
+
+    #[test]
+    fn sky_view_unproject_round_trips() {
+        for projection in SkyProjection::ALL {
+            let view = SkyView {
+                rotation: 30.0,
+                flip_east_west: true,
+                projection,
+            };
+            for (azimuth, elevation) in [(0.0, 10.0), (95.0, 45.0), (200.0, 5.0), (359.0, 70.0)] {
+                let (x, y) = view.project(azimuth, elevation).unwrap();
+                let (az, el) = view.unproject(x, y).unwrap();
+                assert!((az - azimuth).abs() < 1e-9 && (el - elevation).abs() < 1e-9);
+            }
+            assert!(view.unproject(1.0, 1.0).is_none());
+        }
+    }
+
+    #[test]
+    fn satellites_that_project_nowhere_are_skipped() {
+        // The parser reads "nan" and "inf" as missing, like an empty field
+        let gsv = parser::parse_gsv_message("$GPGSV,1,1,02,12,nan,045,40,07,60,inf,38").unwrap();
+        let positions: Vec<(f64, f64)> =
+            gsv.satellites.iter().map(|s| (s.latitude, s.longitude)).collect();
+        assert_eq!(positions, [(0.0, 45.0), (60.0, 0.0)]);
+
+        let view = SkyView {
+            rotation: 0.0,
+            flip_east_west: false,
+            projection: SkyProjection::Linear,
+        };
+        assert!(view.project(f64::NAN, 30.0).is_none());
+        let satellite = |id: &str, latitude: f64| Satellite {
+            id: id.to_string(),
+            latitude,
+            longitude: 90.0,
+            ..Default::default()
+        };
+        let (bad, good) = (satellite("12", f64::NAN), satellite("05", 45.0));
+        let placed: Vec<&str> =
+            view.place([&bad, &good].into_iter()).map(|(sat, ..)| sat.id.as_str()).collect();
+        assert_eq!(placed, ["05"]);
+
+        let bounds = fit_bounds([[f64::NAN, 1.0], [0.0, 0.0], [2.0, 2.0]].into_iter()).unwrap();
+        assert_eq!(bounds.min(), [-0.1, -0.1]);
+        assert_eq!(bounds.max(), [2.1, 2.1]);
+    }
+}
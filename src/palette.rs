@@ -0,0 +1,340 @@
+//! Named color palettes for the constellations, the SNR scale, fix
+//! qualities and graph lines. Every such color is looked up here, so a
+//! new palette is one more arm in each method.
+
+use eframe::egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::satellite::Constellation;
+
+/// SNR at the weak and strong ends of the gradient, dB-Hz.
+const SNR_WEAK: f32 = 15.0;
+const SNR_STRONG: f32 = 45.0;
+
+// Okabe and Ito's palette, told apart with the common color blindnesses
+const OI_ORANGE: Color32 = Color32::from_rgb(230, 159, 0);
+const OI_SKY_BLUE: Color32 = Color32::from_rgb(86, 180, 233);
+const OI_GREEN: Color32 = Color32::from_rgb(0, 158, 115);
+const OI_YELLOW: Color32 = Color32::from_rgb(240, 228, 66);
+const OI_BLUE: Color32 = Color32::from_rgb(0, 114, 178);
+const OI_VERMILLION: Color32 = Color32::from_rgb(213, 94, 0);
+const OI_PURPLE: Color32 = Color32::from_rgb(204, 121, 167);
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Default,
+    ColorblindSafe,
+    HighContrast,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [
+        Palette::Default,
+        Palette::ColorblindSafe,
+        Palette::HighContrast,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Default => "Default",
+            Palette::ColorblindSafe => "Colorblind-safe",
+            Palette::HighContrast => "High contrast",
+        }
+    }
+
+    pub fn constellation(&self, constellation: Constellation) -> Color32 {
+        use Constellation::*;
+        match self {
+            Palette::Default => match constellation {
+                Gps => Color32::from_rgb(100, 150, 240),
+                Glonass => Color32::from_rgb(230, 100, 100),
+                Galileo => Color32::from_rgb(110, 200, 110),
+                BeiDou => Color32::from_rgb(240, 160, 60),
+                Qzss => Color32::from_rgb(180, 120, 220),
+                Navic => Color32::from_rgb(70, 190, 190),
+                Sbas => Color32::from_rgb(200, 190, 90),
+                Unknown => Color32::GRAY,
+            },
+            Palette::ColorblindSafe => match constellation {
+                Gps => OI_BLUE,
+                Glonass => OI_VERMILLION,
+                Galileo => OI_GREEN,
+                BeiDou => OI_ORANGE,
+                Qzss => OI_PURPLE,
+                Navic => OI_SKY_BLUE,
+                Sbas => OI_YELLOW,
+                Unknown => Color32::GRAY,
+            },
+            Palette::HighContrast => match constellation {
+                Gps => Color32::from_rgb(0, 160, 255),
+                Glonass => Color32::from_rgb(255, 40, 40),
+                Galileo => Color32::from_rgb(0, 255, 0),
+                BeiDou => Color32::from_rgb(255, 140, 0),
+                Qzss => Color32::from_rgb(255, 0, 255),
+                Navic => Color32::from_rgb(0, 255, 255),
+                Sbas => Color32::from_rgb(255, 255, 0),
+                Unknown => Color32::WHITE,
+            },
+        }
+    }
+
+    /// A weak-to-strong gradient over `SNR_WEAK` to `SNR_STRONG` dB-Hz.
+    pub fn snr(&self, snr: f32) -> Color32 {
+        let stops = match self {
+            Palette::Default => [
+                Color32::from_rgb(230, 80, 80),
+                Color32::from_rgb(240, 200, 60),
+                Color32::from_rgb(40, 200, 80),
+            ],
+            // Viridis: reads the same in lightness order without color
+            Palette::ColorblindSafe => [
+                Color32::from_rgb(68, 1, 84),
+                Color32::from_rgb(33, 145, 140),
+                Color32::from_rgb(253, 231, 37),
+            ],
+            Palette::HighContrast => [
+                Color32::from_rgb(255, 0, 0),
+                Color32::from_rgb(255, 255, 0),
+                Color32::from_rgb(255, 255, 255),
+            ],
+        };
+        let t = ((snr - SNR_WEAK) / (SNR_STRONG - SNR_WEAK)).clamp(0.0, 1.0) * 2.0;
+        if t <= 1.0 {
+            lerp(stops[0], stops[1], t)
+        } else {
+            lerp(stops[1], stops[2], t - 1.0)
+        }
+    }
+
+    /// Track colour for a GGA fix quality, from no better than GPS up to
+    /// RTK fixed.
+    pub fn quality(&self, quality: u8) -> Color32 {
+        match self {
+            Palette::Default => match quality {
+                1 => Color32::from_rgb(90, 150, 255),
+                2 => Color32::from_rgb(0, 200, 210),
+                3 => Color32::from_rgb(160, 120, 255),
+                4 => Color32::from_rgb(40, 200, 80),
+                5 => Color32::from_rgb(240, 170, 0),
+                _ => Color32::from_gray(150),
+            },
+            Palette::ColorblindSafe => match quality {
+                1 => OI_BLUE,
+                2 => OI_SKY_BLUE,
+                3 => OI_PURPLE,
+                4 => OI_GREEN,
+                5 => OI_ORANGE,
+                _ => Color32::from_gray(150),
+            },
+            Palette::HighContrast => match quality {
+                1 => Color32::from_rgb(0, 160, 255),
+                2 => Color32::from_rgb(0, 255, 255),
+                3 => Color32::from_rgb(255, 0, 255),
+                4 => Color32::from_rgb(0, 255, 0),
+                5 => Color32::from_rgb(255, 255, 0),
+                _ => Color32::WHITE,
+            },
+        }
+    }
+
+    /// Graph line and track color of the `index`th source or series.
+    pub fn series(&self, index: usize) -> Color32 {
+        let colors = match self {
+            Palette::Default => [
+                Color32::from_rgb(100, 150, 240),
+                Color32::from_rgb(240, 120, 80),
+                Color32::from_rgb(110, 200, 110),
+            ],
+            Palette::ColorblindSafe => [OI_BLUE, OI_ORANGE, OI_SKY_BLUE],
+            Palette::HighContrast => [
+                Color32::from_rgb(0, 200, 255),
+                Color32::from_rgb(255, 255, 0),
+                Color32::from_rgb(255, 0, 255),
+            ],
+        };
+        colors[index % colors.len()]
+    }
+
+    /// Markers for satellites acquired and lost.
+    pub fn acquired(&self) -> Color32 {
+        match self {
+            Palette::Default => Color32::from_rgb(110, 200, 110),
+            Palette::ColorblindSafe => OI_SKY_BLUE,
+            Palette::HighContrast => Color32::from_rgb(0, 255, 0),
+        }
+    }
+
+    pub fn lost(&self) -> Color32 {
+        match self {
+            Palette::Default => Color32::from_rgb(230, 80, 80),
+            Palette::ColorblindSafe => OI_VERMILLION,
+            Palette::HighContrast => Color32::from_rgb(255, 0, 0),
+        }
+    }
+
+    /// Satellites held over from an earlier cycle, dimmed.
+    pub fn held(&self) -> Color32 {
+        match self {
+            Palette::Default | Palette::ColorblindSafe => Color32::GRAY,
+            Palette::HighContrast => Color32::from_gray(190),
+        }
+    }
+
+    /// Satellites below the elevation mask.
+    pub fn masked(&self) -> Color32 {
+        match self {
+            Palette::Default | Palette::ColorblindSafe => Color32::DARK_GRAY,
+            Palette::HighContrast => Color32::from_gray(110),
+        }
+    }
+
+    /// The ring around the selected satellite and its GSV fields.
+    pub fn selection(&self) -> Color32 {
+        match self {
+            Palette::Default => Color32::YELLOW,
+            Palette::ColorblindSafe => OI_YELLOW,
+            Palette::HighContrast => Color32::WHITE,
+        }
+    }
+
+    /// Favorite satellites in the list and on the sky map.
+    pub fn favorite(&self) -> Color32 {
+        match self {
+            Palette::Default | Palette::HighContrast => Color32::GOLD,
+            Palette::ColorblindSafe => OI_ORANGE,
+        }
+    }
+
+    /// Satellites whose reported values are out of range.
+    pub fn implausible(&self) -> Color32 {
+        match self {
+            Palette::Default => Color32::from_rgb(230, 80, 200),
+            Palette::ColorblindSafe => OI_PURPLE,
+            Palette::HighContrast => Color32::from_rgb(255, 0, 255),
+        }
+    }
+
+    /// Marked waypoints and the reference point on the track plots.
+    pub fn waypoint(&self) -> Color32 {
+        match self {
+            Palette::Default => Color32::from_rgb(250, 210, 60),
+            Palette::ColorblindSafe => OI_YELLOW,
+            Palette::HighContrast => Color32::from_rgb(255, 255, 0),
+        }
+    }
+
+    /// User notes in the log and event captures.
+    pub fn note(&self) -> Color32 {
+        match self {
+            Palette::Default => Color32::from_rgb(255, 140, 220),
+            Palette::ColorblindSafe => OI_YELLOW,
+            Palette::HighContrast => Color32::from_rgb(255, 255, 0),
+        }
+    }
+
+    /// Ghost color of the `index`th sky snapshot.
+    pub fn snapshot(&self, index: usize) -> Color32 {
+        let colors = match self {
+            Palette::Default => [
+                Color32::from_rgb(120, 180, 255),
+                Color32::from_rgb(255, 170, 80),
+            ],
+            Palette::ColorblindSafe => [OI_SKY_BLUE, OI_ORANGE],
+            Palette::HighContrast => [
+                Color32::from_rgb(0, 255, 255),
+                Color32::from_rgb(255, 140, 0),
+            ],
+        };
+        colors[index % colors.len()]
+    }
+
+    /// GPS Stream color of a sentence type without its talker, e.g. "GGA".
+    pub fn sentence(&self, kind: &str) -> Color32 {
+        match self {
+            Palette::Default => match kind {
+                "GGA" => Color32::from_rgb(110, 200, 110),
+                "RMC" => Color32::from_rgb(100, 150, 240),
+                "GSV" => Color32::from_rgb(70, 190, 190),
+                "GSA" => Color32::from_rgb(180, 120, 220),
+                "TXT" => Color32::from_rgb(240, 160, 60),
+                _ => Color32::GRAY,
+            },
+            Palette::ColorblindSafe => match kind {
+                "GGA" => OI_GREEN,
+                "RMC" => OI_BLUE,
+                "GSV" => OI_SKY_BLUE,
+                "GSA" => OI_PURPLE,
+                "TXT" => OI_ORANGE,
+                _ => Color32::GRAY,
+            },
+            Palette::HighContrast => match kind {
+                "GGA" => Color32::from_rgb(0, 255, 0),
+                "RMC" => Color32::from_rgb(0, 160, 255),
+                "GSV" => Color32::from_rgb(0, 255, 255),
+                "GSA" => Color32::from_rgb(255, 0, 255),
+                "TXT" => Color32::from_rgb(255, 140, 0),
+                _ => Color32::from_gray(190),
+            },
+        }
+    }
+
+    /// Log lines failing their checksum, whatever their type.
+    pub fn checksum_failure(&self) -> Color32 {
+        self.lost()
+    }
+
+    /// The signal score bar: poor below 40, fair below 70, good above.
+    pub fn score(&self, total: f32) -> Color32 {
+        let [poor, fair, good] = match self {
+            Palette::Default => [
+                Color32::RED,
+                Color32::from_rgb(240, 160, 60),
+                Color32::from_rgb(40, 200, 80),
+            ],
+            Palette::ColorblindSafe => [OI_VERMILLION, OI_ORANGE, OI_BLUE],
+            Palette::HighContrast => [
+                Color32::from_rgb(255, 0, 0),
+                Color32::from_rgb(255, 255, 0),
+                Color32::from_rgb(0, 255, 0),
+            ],
+        };
+        match total {
+            t if t < 40.0 => poor,
+            t if t < 70.0 => fair,
+            _ => good,
+        }
+    }
+}
+
+fn lerp(from: Color32, to: Color32, t: f32) -> Color32 {
+    let mix = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t).round() as u8;
+    Color32::from_rgb(mix(from.r(), to.r()), mix(from.g(), to.g()), mix(from.b(), to.b()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snr_gradient_runs_through_the_stops() {
+        let palette = Palette::ColorblindSafe;
+        assert_eq!(palette.snr(0.0), Color32::from_rgb(68, 1, 84));
+        assert_eq!(palette.snr(30.0), Color32::from_rgb(33, 145, 140));
+        assert_eq!(palette.snr(60.0), Color32::from_rgb(253, 231, 37));
+        assert_eq!(Palette::HighContrast.snr(37.5), Color32::from_rgb(255, 255, 128));
+        assert_eq!(Palette::Default.series(3), Palette::Default.series(0));
+    }
+
+    #[test]
+    fn constellations_are_told_apart() {
+        use Constellation::*;
+        let all = [Gps, Glonass, Galileo, BeiDou, Qzss, Navic, Sbas, Unknown];
+        for palette in Palette::ALL {
+            let colors: Vec<Color32> = all.iter().map(|c| palette.constellation(*c)).collect();
+            for (i, color) in colors.iter().enumerate() {
+                assert!(!colors[i + 1..].contains(color), "{} repeats a color", palette.name());
+            }
+        }
+    }
+}
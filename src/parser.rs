@@ -0,0 +1,1177 @@
+use serde::{Deserialize, Serialize};
+
+use crate::satellite::{Constellation, Satellite};
+
+/// Satellite blocks carried by a single GSV message.
+const GSV_SATS_PER_MESSAGE: usize = 4;
+/// `$--GSV,total,number,in_view` precede the satellite blocks.
+const GSV_HEADER_FIELDS: usize = 4;
+/// PRN, elevation, azimuth, SNR.
+const GSV_BLOCK_FIELDS: usize = 4;
+
+/// Checks the `*hh` XOR checksum of a sentence. Sentences without a
+/// checksum can't be verified and count as invalid.
+pub fn verify_checksum(line: &str) -> bool {
+    let line = line.trim();
+    let Some(body) = line.strip_prefix('$').or_else(|| line.strip_prefix('!')) else {
+        return false;
+    };
+    let Some((data, hex)) = body.rsplit_once('*') else {
+        return false;
+    };
+    let Ok(expected) = u8::from_str_radix(hex, 16) else {
+        return false;
+    };
+
+    checksum(data) == expected
+}
+
+/// XOR of the bytes between `$` and `*`.
+pub fn checksum(data: &str) -> u8 {
+    data.bytes().fold(0u8, |acc, b| acc ^ b)
+}
+
+/// Splits `$TTSSS,a,b*CS` into talker (`TT`), sentence (`SSS`) and the
+/// comma separated fields with the checksum removed.
+fn split_sentence(line: &str) -> Option<(&str, &str, Vec<&str>)> {
+    let line = line.trim();
+    let body = line.strip_prefix('$')?;
+    let body = body.split('*').next().unwrap_or(body);
+    let fields: Vec<&str> = body.split(',').collect();
+
+    let talker = fields[0].get(0..2)?;
+    let sentence = fields[0].get(2..)?;
+    Some((talker, sentence, fields))
+}
+
+/// The address field of a sentence (`GPGSV`, `PUBX`, ...), without the `$`.
+pub fn sentence_type(line: &str) -> Option<&str> {
+    let body = line.trim().strip_prefix('$')?;
+    let end = body.find([',', '*']).unwrap_or(body.len());
+    Some(&body[..end]).filter(|t| !t.is_empty())
+}
+
+/// Data fields, after the address, of the standard sentences in every
+/// NMEA version from 2.0 to 4.11; the later versions add trailing mode,
+/// status and system ID fields. `None` for types not checked.
+fn expected_fields(sentence: &str) -> Option<(usize, usize)> {
+    Some(match sentence {
+        "GGA" => (14, 14),
+        "RMC" => (11, 13),
+        "GSA" => (17, 18),
+        "VTG" => (8, 9),
+        "GLL" => (6, 7),
+        "GNS" => (12, 13),
+        "GBS" => (8, 10),
+        "GST" => (8, 8),
+        "ZDA" => (6, 6),
+        "TXT" => (4, 4),
+        _ => return None,
+    })
+}
+
+/// A known sentence with more or fewer fields than any version of it
+/// has, usually a firmware quirk or a line corrupted in transit.
+#[derive(Debug, PartialEq)]
+pub struct FieldCountAnomaly {
+    /// e.g. "GPGGA"
+    pub kind: String,
+    pub count: usize,
+    /// The counts the type may have, e.g. "11–13"
+    pub expected: String,
+}
+
+impl FieldCountAnomaly {
+    pub fn describe(&self) -> String {
+        format!("{} has {} fields, expected {}", self.kind, self.count, self.expected)
+    }
+}
+
+/// Checks the field count of a standard sentence. GSV has its header and
+/// up to four satellite blocks, as wide as `gsv` says, optionally
+/// followed by a signal ID.
+pub fn field_count_anomaly(line: &str, gsv: &GsvMapping) -> Option<FieldCountAnomaly> {
+    let (_, sentence, fields) = split_sentence(line)?;
+    let count = fields.len() - 1;
+    let (expected, valid) = if sentence == "GSV" {
+        let (header, block) = (GSV_HEADER_FIELDS - 1, gsv.usable().block);
+        let blocks = count.saturating_sub(header);
+        let valid =
+            count >= header && blocks % block <= 1 && blocks / block <= GSV_SATS_PER_MESSAGE;
+        let expected = format!("{header} + {block} per satellite (+1 signal ID)");
+        (expected, valid)
+    } else {
+        let (min, max) = expected_fields(sentence)?;
+        let expected = if min == max { min.to_string() } else { format!("{min}–{max}") };
+        (expected, (min..=max).contains(&count))
+    };
+    (!valid).then(|| FieldCountAnomaly {
+        kind: fields[0].to_string(),
+        count,
+        expected,
+    })
+}
+
+/// Whether a GSV carries the signal ID or a GSA the system ID that NMEA
+/// 4.10 added, as `("GSV", true)`; `None` for other types and for field
+/// counts of no version. GSV blocks are as wide as `gsv` says.
+pub fn nmea41_ids(line: &str, gsv: &GsvMapping) -> Option<(&'static str, bool)> {
+    if field_count_anomaly(line, gsv).is_some() {
+        return None;
+    }
+    let (_, sentence, fields) = split_sentence(line)?;
+    let count = fields.len() - 1;
+    match sentence {
+        "GSV" => {
+            let blocks = count - (GSV_HEADER_FIELDS - 1);
+            Some(("GSV", blocks % gsv.usable().block == 1))
+        }
+        "GSA" => Some(("GSA", count == 18)),
+        _ => None,
+    }
+}
+
+/// Parses the satellites from one `$--GSV` message on its own. A whole
+/// cycle, spread over several messages, is collected by
+/// [`GsvAssembler`](crate::gsv::GsvAssembler) instead.
+pub fn parse_gsv(line: &str) -> Option<Vec<Satellite>> {
+    parse_gsv_message(line).map(|message| message.satellites)
+}
+
+/// One `$--GSV` message with the header fields that place it in its
+/// talker's sequence.
+pub struct GsvMessage {
+    pub talker: String,
+    pub total: usize,
+    pub number: usize,
+    /// NMEA 4.10 signal ID, sent when each band has its own sequence
+    pub signal: Option<String>,
+    pub satellites: Vec<Satellite>,
+}
+
+/// Where the values sit in each GSV satellite block, as offsets from its
+/// PRN field, for receivers that send them out of the standard order or
+/// with an extra field per satellite.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GsvMapping {
+    pub elevation: usize,
+    pub azimuth: usize,
+    pub snr: usize,
+    /// Fields per satellite, PRN included
+    pub block: usize,
+}
+
+impl GsvMapping {
+    /// PRN, elevation, azimuth, SNR.
+    pub const STANDARD: GsvMapping = GsvMapping {
+        elevation: 1,
+        azimuth: 2,
+        snr: 3,
+        block: GSV_BLOCK_FIELDS,
+    };
+    /// The widest block a mapping can describe.
+    pub const MAX_BLOCK: usize = 8;
+
+    pub fn is_standard(&self) -> bool {
+        *self == Self::STANDARD
+    }
+
+    /// This mapping, or the standard one if it can't be used.
+    fn usable(&self) -> &GsvMapping {
+        if self.problem().is_none() {
+            self
+        } else {
+            &Self::STANDARD
+        }
+    }
+
+    /// Why the mapping can't be used: offsets outside the block, or two
+    /// values read from the same field.
+    pub fn problem(&self) -> Option<&'static str> {
+        let offsets = [self.elevation, self.azimuth, self.snr];
+        if !(GSV_BLOCK_FIELDS..=Self::MAX_BLOCK).contains(&self.block) {
+            Some("the block must have 4 to 8 fields")
+        } else if offsets.iter().any(|&o| o == 0 || o >= self.block) {
+            Some("each offset must fall inside the block, after the PRN")
+        } else if offsets[0] == offsets[1] || offsets[0] == offsets[2] || offsets[1] == offsets[2] {
+            Some("elevation, azimuth and SNR need fields of their own")
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for GsvMapping {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// Parses one `$--GSV` message.
+///
+/// The number of blocks is derived from the declared satellites-in-view
+/// count and the message number, so trailing empty padding and the
+/// NMEA 4.10 signal-ID field are never mistaken for satellite data.
+pub fn parse_gsv_message(line: &str) -> Option<GsvMessage> {
+    parse_gsv_message_with(line, &GsvMapping::STANDARD)
+}
+
+/// Parses one `$--GSV` message laid out as `mapping` says; an unusable
+/// mapping falls back to the standard one.
+pub fn parse_gsv_message_with(line: &str, mapping: &GsvMapping) -> Option<GsvMessage> {
+    let (talker, sentence, fields) = split_sentence(line)?;
+    if sentence != "GSV" {
+        return None;
+    }
+    let mapping = mapping.usable();
+    let block = mapping.block;
+
+    let constellation = Constellation::from_talker(talker);
+    let available = fields.len().saturating_sub(GSV_HEADER_FIELDS) / block;
+
+    let total: usize = fields.get(1)?.parse().unwrap_or(1);
+    let number: usize = fields.get(2)?.parse().unwrap_or(1);
+    let declared = fields
+        .get(3)
+        .and_then(|f| f.parse::<usize>().ok())
+        .map(|in_view| {
+            in_view
+                .saturating_sub(number.saturating_sub(1) * GSV_SATS_PER_MESSAGE)
+                .min(GSV_SATS_PER_MESSAGE)
+        });
+    let count = declared.unwrap_or(available).min(available);
+
+    // One field left over after the blocks is the signal ID
+    let signal_field = GSV_HEADER_FIELDS + count * block;
+    let signal = (fields.len() == signal_field + 1).then(|| fields[signal_field]);
+
+    let satellites = (0..count)
+        .map(|n| GSV_HEADER_FIELDS + n * block)
+        .filter(|&i| !fields[i].is_empty())
+        .map(|i| (i, constellation.refine_by_prn(fields[i])))
+        .map(|(i, constellation)| Satellite {
+            id: fields[i].to_string(),
+            latitude: finite(fields[i + mapping.elevation]).unwrap_or(0.0),
+            longitude: finite(fields[i + mapping.azimuth]).unwrap_or(0.0),
+            strength: fields[i + mapping.snr].parse().unwrap_or(0),
+            constellation,
+            band: signal.and_then(|id| constellation.band(id)).map(str::to_string),
+            used_in_fix: false,
+            sentence: line.trim().to_string(),
+        })
+        .collect();
+
+    Some(GsvMessage {
+        talker: talker.to_string(),
+        total,
+        number,
+        signal: signal.map(str::to_string),
+        satellites,
+    })
+}
+
+/// A field as a number, leaving out the "nan" and "inf" Rust would
+/// otherwise read, and digit runs too long to fit.
+fn finite(field: &str) -> Option<f64> {
+    field.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// The elevation and azimuth fields of `prn`'s block in a GSV sentence
+/// laid out as `mapping` says, exactly as the receiver sent them.
+pub fn gsv_reported_position<'a>(
+    line: &'a str,
+    prn: &str,
+    mapping: &GsvMapping,
+) -> Option<(&'a str, &'a str)> {
+    let (_, sentence, fields) = split_sentence(line)?;
+    if sentence != "GSV" {
+        return None;
+    }
+    let mapping = mapping.usable();
+    let block = fields[GSV_HEADER_FIELDS.min(fields.len())..]
+        .chunks(mapping.block)
+        .find(|block| block.len() > mapping.elevation.max(mapping.azimuth) && block[0] == prn)?;
+    Some((block[mapping.elevation], block[mapping.azimuth]))
+}
+
+/// Returns the PRNs listed as used in the fix by a `$--GSA` sentence.
+pub fn parse_gsa_prns(line: &str) -> Option<Vec<String>> {
+    let (_, sentence, fields) = split_sentence(line)?;
+    if sentence != "GSA" {
+        return None;
+    }
+
+    // Fields 3..=14 hold up to twelve PRNs
+    Some(
+        fields
+            .iter()
+            .skip(3)
+            .take(12)
+            .filter(|prn| !prn.is_empty())
+            .map(|prn| prn.to_string())
+            .collect(),
+    )
+}
+
+/// System whose PRNs a GSA lists: the talker's, or for combined `GN`
+/// sentences the NMEA 4.10 system ID field. `Unknown` when neither says.
+pub fn parse_gsa_system(line: &str) -> Option<Constellation> {
+    let (talker, sentence, fields) = split_sentence(line)?;
+    if sentence != "GSA" {
+        return None;
+    }
+    let system = match Constellation::from_talker(talker) {
+        Constellation::Unknown => fields.get(18).copied().map(Constellation::from_system_id),
+        system => Some(system),
+    };
+    Some(system.unwrap_or_default())
+}
+
+/// Fix dimensionality from GSA, as opposed to GGA's quality which says
+/// where the fix came from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FixMode {
+    NoFix,
+    TwoD,
+    ThreeD,
+}
+
+impl FixMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FixMode::NoFix => "No fix",
+            FixMode::TwoD => "2D",
+            FixMode::ThreeD => "3D",
+        }
+    }
+}
+
+/// Returns the fix mode of a `$--GSA` sentence: 1 none, 2 2D, 3 3D.
+pub fn parse_gsa_mode(line: &str) -> Option<FixMode> {
+    let (_, sentence, fields) = split_sentence(line)?;
+    if sentence != "GSA" {
+        return None;
+    }
+    match *fields.get(2)? {
+        "1" => Some(FixMode::NoFix),
+        "2" => Some(FixMode::TwoD),
+        "3" => Some(FixMode::ThreeD),
+        _ => None,
+    }
+}
+
+/// Position data from a `$--GGA` sentence.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GgaFix {
+    /// UTC time of the fix as `hh:mm:ss(.ss)`.
+    pub utc_time: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub quality: u8,
+    pub satellites_used: u8,
+    pub hdop: Option<f64>,
+    /// Metres above mean sea level, converted if the receiver sent feet
+    pub altitude: Option<f64>,
+    /// Height of the geoid above the ellipsoid, metres
+    pub geoid_separation: Option<f64>,
+    /// A length in a unit other than metres or feet, which was dropped
+    pub unit_warning: Option<String>,
+    /// Seconds since the last differential correction.
+    pub dgps_age: Option<f64>,
+    pub dgps_station: Option<String>,
+    /// The raw sentence this fix was parsed from.
+    pub sentence: String,
+}
+
+/// Human readable name of a GGA fix quality indicator.
+pub fn fix_quality_name(quality: u8) -> &'static str {
+    match quality {
+        0 => "No fix",
+        1 => "GPS",
+        2 => "DGPS",
+        3 => "PPS",
+        4 => "RTK fixed",
+        5 => "RTK float",
+        6 => "Estimated",
+        7 => "Manual",
+        8 => "Simulation",
+        _ => "Unknown",
+    }
+}
+
+impl GgaFix {
+    pub fn quality_name(&self) -> &'static str {
+        fix_quality_name(self.quality)
+    }
+
+    /// Whether the fix carries a real position. Receivers acquiring a fix
+    /// often send quality 0 or an exact 0,0 ("null island"), neither of
+    /// which may be plotted.
+    pub fn has_position(&self) -> bool {
+        self.quality > 0 && (self.latitude, self.longitude) != (0.0, 0.0)
+    }
+
+    /// DGPS, RTK fixed and RTK float all rely on a correction stream.
+    pub fn is_differential(&self) -> bool {
+        matches!(self.quality, 2 | 4 | 5)
+    }
+
+    /// RTK fixed or float.
+    pub fn is_rtk(&self) -> bool {
+        matches!(self.quality, 4 | 5)
+    }
+}
+
+/// Converts an NMEA `(d)ddmm.mmmm` coordinate plus hemisphere to signed
+/// decimal degrees.
+///
+/// The two digits before the decimal point are whole minutes whatever the
+/// field's width, so any number of degree digits or minute decimals works.
+fn nmea_to_decimal(value: &str, hemisphere: &str) -> Option<f64> {
+    let (sign, max_degrees) = match hemisphere {
+        "N" => (1.0, 90.0),
+        "S" => (-1.0, 90.0),
+        "E" => (1.0, 180.0),
+        "W" => (-1.0, 180.0),
+        _ => return None,
+    };
+
+    // Byte offsets below would split a multibyte character
+    let value = value.trim();
+    if !value.is_ascii() {
+        return None;
+    }
+    let point = value.find('.').unwrap_or(value.len());
+    let split = point.checked_sub(2)?;
+    let (degrees, minutes) = value.split_at(split);
+    let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if !digits(degrees) || !digits(&minutes[..2]) {
+        return None;
+    }
+
+    let degrees: f64 = if degrees.is_empty() { 0.0 } else { finite(degrees)? };
+    let minutes: f64 = finite(minutes)?;
+    let decimal = degrees + minutes / 60.0;
+    (minutes < 60.0 && decimal <= max_degrees).then_some(sign * decimal)
+}
+
+/// Formats an NMEA `hhmmss(.ss)` time field as `hh:mm:ss(.ss)`.
+fn format_utc(field: &str) -> Option<String> {
+    let (hms, fraction) = field.split_at(field.find('.').unwrap_or(field.len()));
+    if hms.len() != 6 || !hms.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{}:{}:{}{fraction}", &hms[0..2], &hms[2..4], &hms[4..6]))
+}
+
+/// Seconds since UTC midnight carried by a GGA, RMC or ZDA sentence, all
+/// of which put the time in the first field.
+pub fn sentence_time(line: &str) -> Option<f64> {
+    let (_, sentence, fields) = split_sentence(line)?;
+    if !matches!(sentence, "GGA" | "RMC" | "ZDA") {
+        return None;
+    }
+    seconds_of_day(&format_utc(fields.get(1)?)?)
+}
+
+/// Unix time of a GGA, RMC or ZDA sentence. RMC and ZDA carry the date;
+/// a GGA's time of day, or an RMC without a date, is put on the UTC day
+/// nearest `now`.
+pub fn sentence_unix_time(line: &str, now: f64) -> Option<f64> {
+    let seconds = sentence_time(line)?;
+    let (_, sentence, fields) = split_sentence(line)?;
+    let dated = match sentence {
+        "RMC" => parse_rmc(line).and_then(|rmc| rmc.unix_time()),
+        "ZDA" => {
+            let field = |i: usize| fields.get(i)?.parse::<i64>().ok();
+            let days = days_from_civil(field(4)?, field(3)?, field(2)?);
+            Some(days as f64 * 86_400.0 + seconds)
+        }
+        _ => None,
+    };
+    dated.or_else(|| Some(((now - seconds) / 86_400.0).round() * 86_400.0 + seconds))
+}
+
+/// Metres in a foot.
+pub const FEET_TO_METERS: f64 = 0.3048;
+
+/// A GGA length in metres from its value and unit fields. The unit is
+/// conventionally `M`; some receivers leave it empty, and `F` is taken as
+/// feet. Any other unit comes back as the error, the value being unusable.
+fn gga_length(value: &str, unit: &str) -> Result<Option<f64>, String> {
+    let Ok(value) = value.parse::<f64>() else {
+        return Ok(None);
+    };
+    match unit {
+        "M" | "" => Ok(Some(value)),
+        "F" => Ok(Some(value * FEET_TO_METERS)),
+        other => Err(other.to_string()),
+    }
+}
+
+/// Parses the position part of a `$--GGA` sentence.
+pub fn parse_gga(line: &str) -> Option<GgaFix> {
+    let (_, sentence, fields) = split_sentence(line)?;
+    if sentence != "GGA" || fields.len() < 10 {
+        return None;
+    }
+
+    // Without a fix the position fields are empty; keep the sentence so
+    // the loss of fix is seen, with a zero position nobody should plot
+    let quality = fields[6].parse().unwrap_or(0);
+    let position = (nmea_to_decimal(fields[2], fields[3]), nmea_to_decimal(fields[4], fields[5]));
+    let (latitude, longitude) = match position {
+        (Some(lat), Some(lon)) => (lat, lon),
+        _ if quality == 0 => (0.0, 0.0),
+        _ => return None,
+    };
+
+    let mut unit_warning = None;
+    let mut length = |name: &str, index: usize| {
+        let unit = fields.get(index + 1).copied().unwrap_or("");
+        gga_length(fields.get(index).copied().unwrap_or(""), unit).unwrap_or_else(|unit| {
+            unit_warning = Some(format!("{name} unit \"{unit}\" is neither metres nor feet"));
+            None
+        })
+    };
+    let altitude = length("altitude", 9);
+    let geoid_separation = length("geoid separation", 11);
+
+    Some(GgaFix {
+        utc_time: format_utc(fields[1]),
+        latitude,
+        longitude,
+        quality,
+        satellites_used: fields[7].parse().unwrap_or(0),
+        hdop: fields[8].parse().ok(),
+        altitude,
+        geoid_separation,
+        unit_warning,
+        dgps_age: fields.get(13).and_then(|f| f.parse().ok()),
+        dgps_station: fields.get(14).filter(|f| !f.is_empty()).map(|f| f.to_string()),
+        sentence: line.trim().to_string(),
+    })
+}
+
+/// Positioning mode from the RMC mode field (NMEA 2.3+), which NMEA 4.1
+/// receivers use as the navigational status.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NavStatus {
+    Autonomous,
+    Differential,
+    Estimated,
+    Manual,
+    Simulator,
+    NotValid,
+}
+
+impl NavStatus {
+    fn from_field(field: &str) -> Option<NavStatus> {
+        match field {
+            "A" => Some(NavStatus::Autonomous),
+            "D" => Some(NavStatus::Differential),
+            "E" => Some(NavStatus::Estimated),
+            "M" => Some(NavStatus::Manual),
+            "S" => Some(NavStatus::Simulator),
+            "N" => Some(NavStatus::NotValid),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NavStatus::Autonomous => "Autonomous",
+            NavStatus::Differential => "Differential",
+            NavStatus::Estimated => "Estimated",
+            NavStatus::Manual => "Manual",
+            NavStatus::Simulator => "Simulator",
+            NavStatus::NotValid => "Not valid",
+        }
+    }
+}
+
+/// Motion data from a `$--RMC` sentence.
+#[derive(Default, Clone, Debug)]
+pub struct RmcData {
+    /// Status `A`; `V` marks a receiver warning.
+    pub valid: bool,
+    pub speed_knots: Option<f64>,
+    /// Course over ground, degrees true.
+    pub course: Option<f64>,
+    /// Magnetic variation, degrees east of true north (west negative).
+    pub variation: Option<f64>,
+    /// UTC time as `hh:mm:ss(.ss)`.
+    pub utc_time: Option<String>,
+    /// UTC date as sent, `ddmmyy`.
+    pub date: Option<String>,
+    /// Absent from receivers older than NMEA 2.3
+    pub nav_status: Option<NavStatus>,
+    /// The raw sentence this was parsed from.
+    pub sentence: String,
+}
+
+impl RmcData {
+    /// Unix time of the sentence, from its date and time fields.
+    pub fn unix_time(&self) -> Option<f64> {
+        let date = self.date.as_deref().filter(|d| d.len() == 6)?;
+        let field = |range: std::ops::Range<usize>| date.get(range)?.parse::<i64>().ok();
+        let (day, month, yy) = (field(0..2)?, field(2..4)?, field(4..6)?);
+        // Two-digit years pivot on 1980, the GPS epoch
+        let year = if yy >= 80 { 1900 + yy } else { 2000 + yy };
+        let days = days_from_civil(year, month, day);
+        Some(days as f64 * 86_400.0 + seconds_of_day(self.utc_time.as_deref()?)?)
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Seconds since midnight of an `hh:mm:ss(.ss)` time.
+pub fn seconds_of_day(utc: &str) -> Option<f64> {
+    let mut parts = utc.splitn(3, ':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// Parses the status, speed and course of a `$--RMC` sentence.
+pub fn parse_rmc(line: &str) -> Option<RmcData> {
+    let (_, sentence, fields) = split_sentence(line)?;
+    if sentence != "RMC" || fields.len() < 9 {
+        return None;
+    }
+
+    Some(RmcData {
+        valid: fields[2] == "A",
+        speed_knots: fields[7].parse().ok(),
+        course: fields[8].parse().ok(),
+        variation: fields
+            .get(10)
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|v| if fields.get(11) == Some(&"W") { -v } else { v }),
+        utc_time: format_utc(fields[1]),
+        date: fields.get(9).filter(|d| !d.is_empty()).map(|d| d.to_string()),
+        nav_status: fields.get(12).and_then(|f| NavStatus::from_field(f)),
+        sentence: line.trim().to_string(),
+    })
+}
+
+/// Active antenna supervisor state.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AntennaStatus {
+    Ok,
+    Open,
+    Short,
+}
+
+impl AntennaStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AntennaStatus::Ok => "OK",
+            AntennaStatus::Open => "open circuit",
+            AntennaStatus::Short => "short circuit",
+        }
+    }
+
+    pub fn is_fault(&self) -> bool {
+        *self != AntennaStatus::Ok
+    }
+}
+
+/// Antenna status from u-blox `$GPTXT,..,ANTSTATUS=OK|OPEN|SHORT` or
+/// MediaTek `$PGTOP,11,n` (1 shorted, 2 internal, 3 active antenna).
+/// Initialising and unknown states give `None`.
+pub fn parse_antenna_status(line: &str) -> Option<AntennaStatus> {
+    let (_, sentence, fields) = split_sentence(line)?;
+    if fields[0] == "PGTOP" && fields.get(1) == Some(&"11") {
+        return match *fields.get(2)? {
+            "1" => Some(AntennaStatus::Short),
+            "2" | "3" => Some(AntennaStatus::Ok),
+            _ => None,
+        };
+    }
+    if sentence != "TXT" {
+        return None;
+    }
+    match fields.get(4)?.strip_prefix("ANTSTATUS=")? {
+        "OK" => Some(AntennaStatus::Ok),
+        "OPEN" => Some(AntennaStatus::Open),
+        "SHORT" => Some(AntennaStatus::Short),
+        _ => None,
+    }
+}
+
+/// Receiver model and firmware, as reported to an identification query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceInfo {
+    pub model: String,
+    pub firmware: String,
+}
+
+/// MediaTek's `$PMTK705,release,build id,model[,sdk]` reply to `$PMTK605`.
+pub fn parse_pmtk_release(line: &str) -> Option<DeviceInfo> {
+    let (_, _, fields) = split_sentence(line)?;
+    if fields[0] != "PMTK705" {
+        return None;
+    }
+    let release = fields.get(1).filter(|r| !r.is_empty())?;
+    let model = fields.get(3).filter(|m| !m.is_empty()).unwrap_or(&"MediaTek");
+    Some(DeviceInfo {
+        model: model.to_string(),
+        firmware: release.to_string(),
+    })
+}
+
+/// GPS week, time of week and leap seconds, from UBX-NAV-TIMEGPS or the
+/// u-blox `$PUBX,04` sentence. Missing or invalid fields stay `None`.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct GpsTime {
+    pub week: Option<u16>,
+    pub tow_secs: Option<f64>,
+    /// GPS minus UTC, in seconds.
+    pub leap_seconds: Option<i32>,
+}
+
+/// Parses `$PUBX,04,time,date,utcTow,utcWk,leapSec,...`. A trailing `D`
+/// on the leap seconds marks the firmware default, still used here.
+pub fn parse_pubx_time(line: &str) -> Option<GpsTime> {
+    let (_, _, fields) = split_sentence(line)?;
+    if fields[0] != "PUBX" || fields.get(1) != Some(&"04") || fields.len() < 7 {
+        return None;
+    }
+
+    let leap_seconds = fields[6].trim_end_matches('D').parse().ok();
+    // The sentence carries UTC time of week; GPS time runs ahead by the leap seconds
+    let tow_secs = fields[4]
+        .parse::<f64>()
+        .ok()
+        .map(|tow| tow + leap_seconds.unwrap_or(0) as f64);
+    Some(GpsTime {
+        week: fields[5].parse().ok(),
+        tow_secs,
+        leap_seconds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(sats: &[Satellite]) -> Vec<&str> {
+        sats.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn field_counts_out_of_range_are_flagged() {
+        // The shortest and longest form of each standard type
+        let valid = [
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47",
+            "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A",
+            "$GNRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W,A,V",
+            "$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*39",
+            "$GNGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1,1",
+            "$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K",
+            "$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K,A",
+            "$GPGLL,4916.45,N,12311.12,W,225444,A",
+            "$GPGLL,4916.45,N,12311.12,W,225444,A,A",
+            "$GPZDA,201530.00,04,07,2002,00,00",
+            "$GNGNS,014035.00,4332.69262,S,17235.48549,E,RR,13,0.9,25.63,11.24,,",
+            "$GNGNS,014035.00,4332.69262,S,17235.48549,E,RR,13,0.9,25.63,11.24,,,V",
+            "$GPGBS,015509.00,-0.031,-0.186,0.219,19,0.000,-0.354,6.972",
+            "$GNGBS,015509.00,-0.031,-0.186,0.219,19,0.000,-0.354,6.972,1,1",
+            "$GPGST,172814.0,0.006,0.023,0.020,273.6,0.023,0.020,0.031",
+            "$GPTXT,01,01,02,ANTSTATUS=OK",
+            "$GPGSV,3,3,10",
+            "$GPGSV,3,3,10,31,20,100,30,32,15,200,25",
+            "$GPGSV,3,1,10,01,40,083,46,02,17,308,41,12,07,344,39,14,22,228,45,1",
+            "$PUBX,04,073731.00,091202,113851.00,1196,15D,1930035,-2660.664,43",
+        ];
+        for line in valid {
+            assert_eq!(field_count_anomaly(line, &GsvMapping::STANDARD), None, "{line}");
+        }
+
+        // One field short or over each
+        let short_and_long = [
+            ("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,", 13),
+            ("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,,", 15),
+            ("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1", 10),
+            ("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W,A,V,", 14),
+            ("$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3", 16),
+            ("$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1,1,", 19),
+            ("$GPVTG,054.7,T,034.4,M,005.5,N,010.2", 7),
+            ("$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K,A,", 10),
+            ("$GPGLL,4916.45,N,12311.12,W,225444", 5),
+            ("$GPGLL,4916.45,N,12311.12,W,225444,A,A,", 8),
+            ("$GPZDA,201530.00,04,07,2002,00", 5),
+            ("$GPZDA,201530.00,04,07,2002,00,00,", 7),
+            ("$GNGNS,014035.00,4332.69262,S,17235.48549,E,RR,13,0.9,25.63,11.24,", 11),
+            ("$GNGNS,014035.00,4332.69262,S,17235.48549,E,RR,13,0.9,25.63,11.24,,,V,", 14),
+            ("$GPGBS,015509.00,-0.031,-0.186,0.219,19,0.000,-0.354", 7),
+            ("$GNGBS,015509.00,-0.031,-0.186,0.219,19,0.000,-0.354,6.972,1,1,", 11),
+            ("$GPGST,172814.0,0.006,0.023,0.020,273.6,0.023,0.020", 7),
+            ("$GPGST,172814.0,0.006,0.023,0.020,273.6,0.023,0.020,0.031,", 9),
+            ("$GPTXT,01,01,02", 3),
+            ("$GPGSV,3,3", 2),
+            ("$GPGSV,3,3,10,31,20,100", 6),
+            ("$GPGSV,3,3,10,31,20,100,30,1,2", 9),
+        ];
+        for (line, count) in short_and_long {
+            let anomaly = field_count_anomaly(line, &GsvMapping::STANDARD)
+                .unwrap_or_else(|| panic!("{line}"));
+            assert_eq!(anomaly.count, count, "{line}");
+        }
+        let anomaly = field_count_anomaly("$GNRMC,1,2,3", &GsvMapping::STANDARD).unwrap();
+        assert_eq!(anomaly.describe(), "GNRMC has 3 fields, expected 11–13");
+    }
+
+    #[test]
+    fn nmea41_ids_by_field_count() {
+        let cases = [
+            ("$GPGSV,3,3,10,31,20,100,30,32,15,200,25*7B", Some(("GSV", false))),
+            ("$GPGSV,3,3,10,31,20,100,30,32,15,200,25,1", Some(("GSV", true))),
+            ("$GPGSV,3,3,10", Some(("GSV", false))),
+            ("$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1", Some(("GSA", false))),
+            ("$GNGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1,1", Some(("GSA", true))),
+            ("$GPGSV,3,3,10,31,20,100,30,1,2", None),
+            ("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(nmea41_ids(line, &GsvMapping::STANDARD), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn gsv_last_message_with_two_satellites() {
+        // 12 fields: header + 2 blocks
+        let line = "$GPGSV,3,3,10,31,20,100,30,32,15,200,25*7B";
+        let sats = parse_gsv(line).unwrap();
+
+        assert_eq!(ids(&sats), ["31", "32"]);
+        assert_eq!(sats[1].latitude, 15.0);
+        assert_eq!(sats[1].longitude, 200.0);
+        assert_eq!(sats[1].strength, 25);
+        assert_eq!(sats[1].constellation, Constellation::Gps);
+        assert!(sats[1].band.is_none());
+        // Each satellite keeps the sentence it came from, for verification
+        assert!(sats.iter().all(|s| s.sentence == line));
+    }
+
+    #[test]
+    fn gsv_full_message_does_not_read_checksum_as_snr() {
+        // 20 fields: header + 4 blocks, last SNR carries the checksum
+        let line = "$GPGSV,3,1,11,03,03,111,00,04,15,270,00,06,01,010,00,13,06,292,42*74";
+        let sats = parse_gsv(line).unwrap();
+
+        assert_eq!(ids(&sats), ["03", "04", "06", "13"]);
+        assert_eq!(sats[3].strength, 42);
+    }
+
+    #[test]
+    fn gsv_with_signal_id() {
+        let line = "$GAGSV,2,1,08,02,57,061,45,07,33,287,41,08,40,175,39,13,14,320,33,7*74";
+        let sats = parse_gsv(line).unwrap();
+
+        assert_eq!(ids(&sats), ["02", "07", "08", "13"]);
+        assert_eq!(sats[3].strength, 33);
+        assert_eq!(sats[0].constellation, Constellation::Galileo);
+        assert_eq!(sats[0].band.as_deref(), Some("E1-BC"));
+    }
+
+    #[test]
+    fn gsv_with_a_remapped_block() {
+        // SNR before azimuth and an extra field per satellite
+        let line = "$GPGSV,1,1,02,12,45,38,270,X,07,10,12,090,Y";
+        let mapping = GsvMapping {
+            elevation: 1,
+            azimuth: 3,
+            snr: 2,
+            block: 5,
+        };
+        let sats = parse_gsv_message_with(line, &mapping).unwrap().satellites;
+        let values: Vec<_> = sats.iter().map(|s| (s.latitude, s.longitude, s.strength)).collect();
+        assert_eq!(values, [(45.0, 270.0, 38), (10.0, 90.0, 12)]);
+
+        let standard = "$GPGSV,1,1,01,12,45,270,38";
+        let mapped = &parse_gsv_message_with(standard, &GsvMapping::default()).unwrap().satellites;
+        let sat = &mapped[0];
+        assert_eq!((sat.latitude, sat.longitude, sat.strength), (45.0, 270.0, 38));
+        let clash = GsvMapping { snr: 1, ..GsvMapping::STANDARD };
+        assert!(clash.problem().is_some() && GsvMapping::STANDARD.problem().is_none());
+        // An unusable mapping reads the standard layout
+        assert_eq!(parse_gsv_message_with(standard, &clash).unwrap().satellites[0].strength, 38);
+
+        // Field counts are checked against the mapped block
+        assert_eq!(field_count_anomaly(line, &mapping), None);
+        assert_eq!(nmea41_ids(line, &mapping), Some(("GSV", false)));
+        assert_eq!(nmea41_ids(&format!("{line},1"), &mapping), Some(("GSV", true)));
+        let anomaly = field_count_anomaly(line, &GsvMapping::STANDARD).unwrap();
+        assert_eq!(anomaly.expected, "3 + 4 per satellite (+1 signal ID)");
+        assert!(field_count_anomaly(standard, &mapping).is_some());
+        assert_eq!(field_count_anomaly(standard, &clash), None);
+    }
+
+    #[test]
+    fn combined_gsv_classifies_by_prn() {
+        let line = "$GNGSV,1,1,04,05,40,083,46,72,21,310,38,46,33,211,40,311,55,120,44*00";
+        let systems: Vec<Constellation> =
+            parse_gsv(line).unwrap().iter().map(|s| s.constellation).collect();
+        assert_eq!(
+            systems,
+            [
+                Constellation::Gps,
+                Constellation::Glonass,
+                Constellation::Sbas,
+                Constellation::Galileo
+            ]
+        );
+    }
+
+    #[test]
+    fn gsv_reported_position_is_raw() {
+        let standard = &GsvMapping::STANDARD;
+        let line = "$GPGSV,1,1,02,12,45,270,38,07,05.5,090,12*00";
+        assert_eq!(gsv_reported_position(line, "07", standard), Some(("05.5", "090")));
+        assert_eq!(gsv_reported_position(line, "12", standard), Some(("45", "270")));
+        assert_eq!(gsv_reported_position(line, "08", standard), None);
+        assert_eq!(gsv_reported_position("$GPGGA,1*00", "12", standard), None);
+
+        // Azimuth first, and a fifth field per satellite
+        let mapping = GsvMapping { elevation: 2, azimuth: 1, snr: 3, block: 5 };
+        let line = "$GPGSV,1,1,02,12,270,45,38,1,07,090,05.5,12,0*00";
+        assert_eq!(gsv_reported_position(line, "07", &mapping), Some(("05.5", "090")));
+        assert_eq!(gsv_reported_position(line, "12", &mapping), Some(("45", "270")));
+    }
+
+    #[test]
+    fn gsv_signal_id_on_short_last_message() {
+        let line = "$GPGSV,3,3,10,31,20,100,30,32,15,200,25,1*6A";
+        let sats = parse_gsv(line).unwrap();
+
+        assert_eq!(ids(&sats), ["31", "32"]);
+        assert_eq!(sats[1].strength, 25);
+        assert_eq!(sats[1].band.as_deref(), Some("L1 C/A"));
+    }
+
+    #[test]
+    fn gsv_padded_with_empty_blocks() {
+        // Declares 10 in view, so only two blocks belong to message 3
+        let line = "$GPGSV,3,3,10,31,20,100,30,32,15,200,25,,,,,,,,*7B";
+        let sats = parse_gsv(line).unwrap();
+
+        assert_eq!(ids(&sats), ["31", "32"]);
+    }
+
+    #[test]
+    fn gsv_without_snr() {
+        let line = "$GLGSV,1,1,02,65,10,045,,66,80,300,*6C";
+        let sats = parse_gsv(line).unwrap();
+
+        assert_eq!(ids(&sats), ["65", "66"]);
+        assert_eq!(sats[0].strength, 0);
+        assert_eq!(sats[0].constellation, Constellation::Glonass);
+    }
+
+    #[test]
+    fn gga_dgps_fields() {
+        let line = "$GPGGA,092750.000,5321.6802,N,00630.3372,W,2,8,1.03,61.7,M,55.2,M,3.2,0120*59";
+        let fix = parse_gga(line).unwrap();
+        assert!(fix.is_differential());
+        assert_eq!(fix.dgps_age, Some(3.2));
+        assert_eq!(fix.dgps_station.as_deref(), Some("0120"));
+        assert_eq!(fix.utc_time.as_deref(), Some("09:27:50.000"));
+        assert_eq!(fix.sentence, line);
+
+        let line = "$GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,*76";
+        let fix = parse_gga(line).unwrap();
+        assert_eq!(fix.dgps_age, None);
+        assert_eq!(fix.dgps_station, None);
+    }
+
+    #[test]
+    fn gga_altitude_units() {
+        let gga = |units: (&str, &str)| {
+            let line = format!(
+                "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,{},46.9,{},,",
+                units.0, units.1
+            );
+            parse_gga(&line).unwrap()
+        };
+        let metres = gga(("M", "M"));
+        assert_eq!((metres.altitude, metres.geoid_separation), (Some(545.4), Some(46.9)));
+        assert_eq!(metres.unit_warning, None);
+        assert_eq!(gga(("", "")).altitude, Some(545.4));
+
+        let feet = gga(("F", "M"));
+        assert!((feet.altitude.unwrap() - 166.23792).abs() < 1e-9);
+        assert_eq!(feet.geoid_separation, Some(46.9));
+
+        // An unknown unit drops that length and says so; the fix stands
+        let odd = gga(("M", "K"));
+        assert_eq!((odd.altitude, odd.geoid_separation), (Some(545.4), None));
+        assert_eq!(
+            odd.unit_warning.as_deref(),
+            Some("geoid separation unit \"K\" is neither metres nor feet")
+        );
+        assert!(odd.has_position());
+    }
+
+    #[test]
+    fn coordinates_with_varying_widths() {
+        let close = |a: Option<f64>, b: f64| (a.unwrap() - b).abs() < 1e-9;
+        // 4 and 5 minute decimals
+        assert!(close(nmea_to_decimal("4807.0380", "N"), 48.0 + 7.038 / 60.0));
+        assert!(close(nmea_to_decimal("4807.03801", "S"), -(48.0 + 7.03801 / 60.0)));
+        assert!(close(nmea_to_decimal("01131.0000", "E"), 11.0 + 31.0 / 60.0));
+        assert!(close(nmea_to_decimal("01131.00005", "W"), -(11.0 + 31.00005 / 60.0)));
+        // Unpadded degrees, no decimals at all
+        assert!(close(nmea_to_decimal("930.5", "W"), -(9.0 + 30.5 / 60.0)));
+        assert!(close(nmea_to_decimal("4807", "N"), 48.0 + 7.0 / 60.0));
+
+        assert!(nmea_to_decimal("7.5", "N").is_none());
+        assert!(nmea_to_decimal("4860.0", "N").is_none());
+        assert!(nmea_to_decimal("9130.0", "S").is_none());
+        assert!(nmea_to_decimal("4807.038", "X").is_none());
+        assert!(nmea_to_decimal("€.5", "N").is_none());
+        assert!(nmea_to_decimal("1€.5", "N").is_none());
+        assert!(parse_gga("$GPGGA,123519,48€7.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47")
+            .is_none_or(|fix| !fix.has_position()));
+    }
+
+    #[test]
+    fn gga_without_fix_is_kept() {
+        let fix = parse_gga("$GPGGA,092751.000,,,,,0,00,99.99,,,,,,*67").unwrap();
+        assert_eq!(fix.quality, 0);
+        assert!(parse_gga("$GPGGA,092751.000,,,,,1,00,99.99,,,,,,*66").is_none());
+    }
+
+    #[test]
+    fn checksum_verification() {
+        assert!(verify_checksum("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47"));
+        assert!(verify_checksum("$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*39\r"));
+        assert!(!verify_checksum("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48"));
+        assert!(!verify_checksum("$GPGGA,123519,4807.038,N"));
+        assert!(!verify_checksum("$GPGGA,123519*ZZ"));
+    }
+
+    #[test]
+    fn gsa_lists_used_prns() {
+        let line = "$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*39";
+        assert_eq!(
+            parse_gsa_prns(line).unwrap(),
+            ["04", "05", "09", "12", "24"]
+        );
+        assert!(parse_gsa_prns("$GPGGA,123519,4807.038,N").is_none());
+        assert_eq!(parse_gsa_system(line), Some(Constellation::Gps));
+        let combined = "$GNGSA,A,3,11,12,,,,,,,,,,,1.6,0.9,1.3,3*3C";
+        assert_eq!(parse_gsa_system(combined), Some(Constellation::Galileo));
+        let legacy = "$GNGSA,A,3,11,12,,,,,,,,,,,1.6,0.9,1.3*23";
+        assert_eq!(parse_gsa_system(legacy), Some(Constellation::Unknown));
+    }
+
+    #[test]
+    fn gn_gsa_system_id_names_the_system() {
+        let gsa = |id: &str| format!("$GNGSA,A,3,11,70,,,,,,,,,,,1.6,0.9,1.3,{id}*00");
+        let systems = [
+            ("1", Constellation::Gps),
+            ("2", Constellation::Glonass),
+            ("3", Constellation::Galileo),
+            ("4", Constellation::BeiDou),
+        ];
+        for (id, system) in systems {
+            assert_eq!(parse_gsa_system(&gsa(id)), Some(system), "system ID {id}");
+        }
+        // An empty field is as good as none
+        assert_eq!(parse_gsa_system(&gsa("")), Some(Constellation::Unknown));
+    }
+
+    #[test]
+    fn gsa_fix_modes() {
+        let modes = [
+            ("$GPGSA,A,1,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*3B", FixMode::NoFix),
+            ("$GPGSA,A,2,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*38", FixMode::TwoD),
+            ("$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*39", FixMode::ThreeD),
+        ];
+        for (line, mode) in modes {
+            assert_eq!(parse_gsa_mode(line), Some(mode));
+        }
+        assert_eq!(parse_gsa_mode("$GPGSA,A,,,,,,,,,,,,,,,*03"), None);
+    }
+
+    #[test]
+    fn rmc_course_and_speed() {
+        let line = "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A";
+        let rmc = parse_rmc(line).unwrap();
+
+        assert!(rmc.valid);
+        assert_eq!(rmc.speed_knots, Some(22.4));
+        assert_eq!(rmc.course, Some(84.4));
+        assert_eq!(rmc.variation, Some(-3.1));
+        assert_eq!(rmc.utc_time.as_deref(), Some("12:35:19"));
+        // 1994-03-23 12:35:19 UTC
+        assert_eq!(rmc.unix_time(), Some(764_426_119.0));
+        assert_eq!(rmc.nav_status, None);
+    }
+
+    #[test]
+    fn rmc_nav_status() {
+        let rmc = parse_rmc("$GNRMC,000001,A,,,,,,,010124,,,S*40").unwrap();
+        assert_eq!(rmc.nav_status, Some(NavStatus::Simulator));
+        let rmc = parse_rmc("$GNRMC,000001,A,,,,,,,010124,,,D,V*2D").unwrap();
+        assert_eq!(rmc.nav_status, Some(NavStatus::Differential));
+    }
+
+    #[test]
+    fn pubx_time_with_default_leap_seconds() {
+        let line = "$PUBX,04,073731.00,091202,113851.00,1196,15D,1930035,-2660.664,43*71";
+        let time = parse_pubx_time(line).unwrap();
+
+        assert_eq!(time.week, Some(1196));
+        assert_eq!(time.leap_seconds, Some(15));
+        assert_eq!(time.tow_secs, Some(113_866.0));
+        assert!(parse_pubx_time("$PUBX,00,073731.00*00").is_none());
+    }
+
+    #[test]
+    fn sentence_times() {
+        assert_eq!(sentence_time("$GPZDA,235959.50,31,12,2023,00,00*60"), Some(86_399.5));
+        assert_eq!(sentence_time("$GNRMC,000001,A,,,,,,,010124,,,A*52"), Some(1.0));
+        assert_eq!(sentence_time("$GPGSV,1,1,01,12,45,270,38*4A"), None);
+        assert_eq!(sentence_time("$GPGGA,,,,,,0,00,,,M,,M,,*66"), None);
+
+        // 2023-12-31 23:59:59.5 UTC
+        let zda = 1_704_067_199.5;
+        assert_eq!(sentence_unix_time("$GPZDA,235959.50,31,12,2023,00,00*60", 0.0), Some(zda));
+        // Undated times land on the day nearest the system clock, across midnight
+        let gga = "$GPGGA,235959.50,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*60";
+        assert_eq!(sentence_unix_time(gga, zda + 1.0), Some(zda));
+        assert_eq!(sentence_unix_time(gga, zda - 300.0), Some(zda));
+    }
+
+    #[test]
+    fn pmtk_release_reply() {
+        let info = parse_pmtk_release("$PMTK705,AXN_2.31_3339_13101700,5632,PA6H,1.0*6B").unwrap();
+        assert_eq!(info.model, "PA6H");
+        assert_eq!(info.firmware, "AXN_2.31_3339_13101700");
+        assert_eq!(parse_pmtk_release("$PMTK001,605,3*31"), None);
+    }
+
+    #[test]
+    fn antenna_status_messages() {
+        let status = parse_antenna_status("$GPTXT,01,01,02,ANTSTATUS=SHORT*6D");
+        assert_eq!(status, Some(AntennaStatus::Short));
+        let status = parse_antenna_status("$GPTXT,01,01,02,ANTSTATUS=OPEN*2B");
+        assert_eq!(status, Some(AntennaStatus::Open));
+        assert_eq!(parse_antenna_status("$PGTOP,11,3*6F"), Some(AntennaStatus::Ok));
+        assert_eq!(parse_antenna_status("$GPTXT,01,01,02,ANTSTATUS=INIT*25"), None);
+    }
+}
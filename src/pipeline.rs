@@ -0,0 +1,600 @@
+//! From raw bytes to parsed events, for programs using the parsers
+//! without the GUI. A [`Pipeline`] splits what it is fed into lines,
+//! checks each sentence's checksum and hands fixes, RMC data, fix modes
+//! and finished GSV cycles to a callback as [`GpsEvent`]s; [`run`] feeds
+//! one from anything readable, such as a log file or a serial port. The
+//! GUI splits and logs the lines itself and hands them to the same
+//! [`Decoder`].
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, ErrorKind, Read};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::gsv::GsvAssembler;
+use crate::parser::{self, FixMode, GgaFix, GsvMapping, RmcData};
+use crate::satellite::{Constellation, Satellite};
+
+/// Bytes buffered without a line end before they are discarded, so a
+/// stream that never sends one can't grow the buffer without bound.
+const MAX_PARTIAL_LINE: usize = 4096;
+
+/// The sentences of `text`, however its lines end: `\n`, `\r\n`, a
+/// bare `\r` or the `\n\r` of a few receivers, mixed or not. Stray
+/// control characters around a sentence are trimmed and blank lines
+/// skipped.
+pub fn split_sentences(text: &str) -> impl Iterator<Item = &str> {
+    text.split(['\r', '\n'])
+        .map(|line| line.trim_matches(|c: char| c.is_control()))
+        .filter(|line| !line.is_empty())
+}
+
+/// Splits off and decodes the complete lines of `pending`, leaving a
+/// partial last line buffered until the rest of it arrives, and returns
+/// them ending in `\r\n` whatever the receiver sent. Only whole lines
+/// are decoded, so a read ending inside a multi-byte character doesn't
+/// turn valid text into replacement characters.
+fn take_complete_lines(pending: &mut Vec<u8>) -> Option<String> {
+    let end = pending.iter().rposition(|b| matches!(b, b'\r' | b'\n'))? + 1;
+    let lines: Vec<u8> = pending.drain(..end).collect();
+    let text = String::from_utf8_lossy(&lines);
+    Some(split_sentences(&text).map(|line| format!("{line}\r\n")).collect())
+}
+
+/// NMEA bytes awaiting their line end. A partial line outgrowing
+/// `MAX_PARTIAL_LINE` is thrown away, and bytes are then dropped up to
+/// the next `$` so parsing resumes on a sentence start.
+#[derive(Default)]
+pub struct LineBuffer {
+    pending: Vec<u8>,
+    resyncing: bool,
+}
+
+impl LineBuffer {
+    /// Adds `data`, returning the lines it completed and, when the
+    /// partial line overflowed, how many bytes of it were discarded.
+    pub fn push(&mut self, mut data: &[u8]) -> (Option<String>, Option<usize>) {
+        if self.resyncing {
+            let Some(start) = data.iter().position(|b| *b == b'$') else {
+                return (None, None);
+            };
+            self.resyncing = false;
+            data = &data[start..];
+        }
+        self.pending.extend_from_slice(data);
+        let lines = take_complete_lines(&mut self.pending);
+        if self.pending.len() <= MAX_PARTIAL_LINE {
+            return (lines, None);
+        }
+        let discarded = self.pending.len();
+        self.pending.clear();
+        self.pending.shrink_to(MAX_PARTIAL_LINE);
+        self.resyncing = true;
+        (lines, Some(discarded))
+    }
+}
+
+/// Keys of the satellites a GSA sentence lists as used: `GPS-12`, from
+/// the system the sentence names or else the PRN's range; a PRN outside
+/// every range stays bare.
+fn gsa_used_keys(line: &str) -> Option<Vec<String>> {
+    let prns = parser::parse_gsa_prns(line)?;
+    let system = parser::parse_gsa_system(line).unwrap_or_default();
+    let keys = prns.into_iter().map(|prn| match system.refine_by_prn(&prn) {
+        Constellation::Unknown => prn,
+        system => format!("{}-{prn}", system.name()),
+    });
+    Some(keys.collect())
+}
+
+/// Flags the satellites whose key, or bare PRN, is among `used`.
+pub fn mark_used(satellites: &mut [Satellite], used: &HashSet<String>) {
+    for sat in satellites {
+        sat.used_in_fix = used.contains(&sat.key()) || used.contains(&sat.id);
+    }
+}
+
+/// Seconds of receiver time a GSA can trail the fixes before the used
+/// markers count as stale.
+const GSA_STALE_SECS: f64 = 5.0;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// The satellites the latest GSA sentences list as used. Multi-GNSS
+/// receivers send a GSA per system, not always at the fix rate: each
+/// system's list is kept until its next GSA, so a cycle without one
+/// doesn't blank the markers. GSAs without a system, as NMEA before 4.1
+/// sends, are told apart by their place in each run of GSA sentences.
+#[derive(Default, Clone)]
+pub struct UsedSatellites {
+    // Keys per system, with the receiver time of day of their GSA
+    systems: HashMap<String, (HashSet<String>, Option<f64>)>,
+    // GSA sentences so far in the current run, if in one
+    run: Option<usize>,
+    // Time of day of the latest GGA, RMC or ZDA
+    clock: Option<f64>,
+}
+
+impl UsedSatellites {
+    /// Notes one sentence, returning whether it was a GSA.
+    fn push(&mut self, line: &str) -> bool {
+        let Some(keys) = gsa_used_keys(line) else {
+            self.run = None;
+            if let Some(time) = parser::sentence_time(line) {
+                self.clock = Some(time);
+            }
+            return false;
+        };
+        let place = self.run.map_or(0, |n| n + 1);
+        self.run = Some(place);
+        let system = match parser::parse_gsa_system(line).unwrap_or_default() {
+            Constellation::Unknown => format!("#{place}"),
+            system => system.name().to_string(),
+        };
+        self.systems.insert(system, (keys.into_iter().collect(), self.clock));
+        true
+    }
+
+    /// Keys of every satellite used, over all systems.
+    pub fn keys(&self) -> HashSet<String> {
+        self.systems.values().flat_map(|(keys, _)| keys.iter().cloned()).collect()
+    }
+
+    /// Seconds of receiver time the oldest system's GSA trails the latest
+    /// fix, when the receiver's times are known.
+    pub fn age(&self) -> Option<f64> {
+        let clock = self.clock?;
+        self.systems
+            .values()
+            .filter_map(|(_, time)| *time)
+            .map(|time| (clock - time).rem_euclid(SECONDS_PER_DAY))
+            .reduce(f64::max)
+    }
+
+    /// Whether the oldest system's GSA trails the fixes by more than
+    /// `GSA_STALE_SECS`.
+    pub fn is_stale(&self) -> bool {
+        self.age().is_some_and(|age| age > GSA_STALE_SECS)
+    }
+}
+
+/// Sentence types parsed into satellites, fix and time. Disabled types
+/// are still counted and logged raw.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SentenceFilter {
+    pub gga: bool,
+    pub gsa: bool,
+    pub gsv: bool,
+    pub rmc: bool,
+    pub pubx: bool,
+    /// Drop GP/GL/.. GGA and RMC while the receiver also sends the
+    /// combined GN solution
+    pub prefer_combined: bool,
+}
+
+impl Default for SentenceFilter {
+    fn default() -> Self {
+        Self {
+            gga: true,
+            gsa: true,
+            gsv: true,
+            rmc: true,
+            pubx: true,
+            prefer_combined: true,
+        }
+    }
+}
+
+impl SentenceFilter {
+    /// Each type's toggle with its display name.
+    pub fn toggles(&mut self) -> [(&'static str, &mut bool); 5] {
+        [
+            ("GGA", &mut self.gga),
+            ("GSA", &mut self.gsa),
+            ("GSV", &mut self.gsv),
+            ("RMC", &mut self.rmc),
+            ("PUBX", &mut self.pubx),
+        ]
+    }
+}
+
+/// A single-system sentence is dropped for this long after the combined
+/// `GN` version of it was seen.
+const COMBINED_HOLD: Duration = Duration::from_secs(2);
+
+/// Picks between single-system and combined (`GN`) solutions for
+/// receivers sending both, so the position doesn't flicker between them.
+#[derive(Default, Clone)]
+struct TalkerFilter {
+    combined_seen: HashMap<String, Instant>,
+}
+
+impl TalkerFilter {
+    /// Whether to use `line`: always for `GN`, and for other talkers
+    /// unless `prefer_combined` and a `GN` version was seen recently.
+    fn accept(&mut self, line: &str, now: Instant, prefer_combined: bool) -> bool {
+        // An address that isn't ASCII has no talker to tell apart
+        let Some((talker, sentence)) = parser::sentence_type(line)
+            .and_then(|kind| Some((kind.get(..2)?, kind.get(2..)?)))
+        else {
+            return true;
+        };
+        if talker == "GN" {
+            self.combined_seen.insert(sentence.to_string(), now);
+            return true;
+        }
+        !prefer_combined
+            || self
+                .combined_seen
+                .get(sentence)
+                .is_none_or(|seen| now.duration_since(*seen) > COMBINED_HOLD)
+    }
+}
+
+/// What a [`Decoder`] found in a sentence.
+#[derive(Clone, Debug)]
+pub enum GpsEvent {
+    /// A GGA fix, with or without a position
+    Fix(GgaFix),
+    /// A single-system GGA left out while the combined `GN` solution is
+    /// preferred, for comparing the two
+    SupersededFix(GgaFix),
+    /// RMC status, speed and course
+    Motion(RmcData),
+    /// 2D or 3D, from GSA
+    FixMode(FixMode),
+    /// Every satellite in view once a GSV cycle completes, those in the
+    /// latest GSA sentences marked used
+    Satellites(Vec<Satellite>),
+    /// A line whose checksum is missing or wrong, otherwise ignored
+    BadChecksum(String),
+}
+
+/// What one receiver's sentences have built up: the GSV sequences being
+/// collected, the satellites the GSAs list as used and which talkers
+/// are sending, carried from one sentence to the next.
+#[derive(Default, Clone)]
+pub struct Decoder {
+    /// Types parsed; the others are skipped
+    pub filter: SentenceFilter,
+    /// Where the values sit in each GSV satellite block
+    pub gsv_mapping: GsvMapping,
+    /// The satellites the latest GSAs list as used, for marking those of
+    /// a cycle that completed before them
+    pub used: UsedSatellites,
+    gsv: GsvAssembler,
+    talkers: TalkerFilter,
+}
+
+impl Decoder {
+    /// Parses one sentence received at `now`, passing what it holds to
+    /// `on_event` in the order GSV, GSA, GGA, RMC.
+    pub fn decode(&mut self, line: &str, now: Instant, mut on_event: impl FnMut(GpsEvent)) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        if !parser::verify_checksum(line) {
+            on_event(GpsEvent::BadChecksum(line.to_string()));
+            return;
+        }
+        let filter = self.filter;
+        let gsv = filter.gsv.then(|| parser::parse_gsv_message_with(line, &self.gsv_mapping));
+        if let Some(message) = gsv.flatten() {
+            if self.gsv.push(message) {
+                let mut satellites = self.gsv.satellites();
+                mark_used(&mut satellites, &self.used.keys());
+                on_event(GpsEvent::Satellites(satellites));
+            }
+        }
+        if filter.gsa {
+            self.used.push(line);
+            if let Some(mode) = parser::parse_gsa_mode(line) {
+                on_event(GpsEvent::FixMode(mode));
+            }
+        }
+        let preferred = self.talkers.accept(line, now, filter.prefer_combined);
+        if let Some(fix) = filter.gga.then_some(line).and_then(parser::parse_gga) {
+            on_event(if preferred { GpsEvent::Fix(fix) } else { GpsEvent::SupersededFix(fix) });
+        }
+        let rmc = filter.rmc.then_some(line).and_then(parser::parse_rmc);
+        if let Some(rmc) = rmc.filter(|_| preferred) {
+            on_event(GpsEvent::Motion(rmc));
+        }
+    }
+
+    /// Drops the GSV sequences collected so far, as when the receiver has
+    /// stopped reporting satellites.
+    pub fn reset_satellites(&mut self) {
+        self.gsv = GsvAssembler::default();
+    }
+}
+
+/// Turns a byte stream into [`GpsEvent`]s handed to a callback.
+pub struct Pipeline<F> {
+    on_event: F,
+    lines: LineBuffer,
+    decoder: Decoder,
+}
+
+impl<F: FnMut(GpsEvent)> Pipeline<F> {
+    /// A pipeline parsing every sentence type, preferring combined `GN`
+    /// solutions where the receiver sends them.
+    pub fn new(on_event: F) -> Self {
+        Self {
+            on_event,
+            lines: LineBuffer::default(),
+            decoder: Decoder::default(),
+        }
+    }
+
+    /// Reads GSV satellite blocks laid out as `mapping` says, for
+    /// receivers sending them out of the standard order.
+    pub fn with_mapping(mut self, mapping: GsvMapping) -> Self {
+        self.decoder.gsv_mapping = mapping;
+        self
+    }
+
+    /// Adds bytes as read. Complete lines are parsed straight away; a
+    /// partial one waits for its line end.
+    pub fn feed(&mut self, data: &[u8]) {
+        if let (Some(lines), _) = self.lines.push(data) {
+            for line in lines.lines() {
+                self.feed_line(line);
+            }
+        }
+    }
+
+    /// Parses one sentence.
+    pub fn feed_line(&mut self, line: &str) {
+        self.decoder.decode(line, Instant::now(), &mut self.on_event);
+    }
+}
+
+/// Reads `source` to its end, passing everything parsed to `on_event`.
+/// Read timeouts, as a quiet serial port gives, are waited out.
+pub fn run<R: Read>(mut source: R, on_event: impl FnMut(GpsEvent)) -> io::Result<()> {
+    let mut pipeline = Pipeline::new(on_event);
+    let mut buffer = [0; 1024];
+    loop {
+        match source.read(&mut buffer) {
+            // The last line may lack its line end
+            Ok(0) => {
+                pipeline.feed(b"\n");
+                return Ok(());
+            }
+            Ok(read) => pipeline.feed(&buffer[..read]),
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{SimConfig, Simulator};
+
+    #[test]
+    fn simulated_epochs_become_events() {
+        let mut simulator = Simulator::new(SimConfig::default());
+        let mut text = String::new();
+        for _ in 0..2 {
+            for sentence in simulator.next_epoch() {
+                text += &sentence;
+                text += "\r\n";
+            }
+        }
+        text += "$GPGGA,bad*00\r\n";
+        text += "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+
+        let mut events = Vec::new();
+        run(io::Cursor::new(text.into_bytes()), |event| events.push(event)).unwrap();
+        let fixes: Vec<&GgaFix> = events
+            .iter()
+            .filter_map(|e| match e {
+                GpsEvent::Fix(fix) => Some(fix),
+                _ => None,
+            })
+            .collect();
+        // The unterminated last line still counts
+        assert_eq!(fixes.len(), 3);
+        assert_eq!(fixes[2].satellites_used, 8);
+        assert!(events.iter().any(|e| matches!(e, GpsEvent::Motion(_))));
+        let bad = |e: &GpsEvent| matches!(e, GpsEvent::BadChecksum(l) if l == "$GPGGA,bad*00");
+        assert!(events.iter().any(bad));
+
+        let Some(GpsEvent::Satellites(satellites)) =
+            events.iter().rev().find(|e| matches!(e, GpsEvent::Satellites(_)))
+        else {
+            panic!("no GSV cycle");
+        };
+        let used = satellites.iter().filter(|s| s.used_in_fix).count();
+        assert_eq!(used, fixes[1].satellites_used as usize);
+    }
+
+    #[test]
+    fn pipelines_read_a_remapped_gsv() {
+        let mapping = GsvMapping {
+            elevation: 1,
+            azimuth: 3,
+            snr: 2,
+            block: 5,
+        };
+        let mut satellites = Vec::new();
+        let mut pipeline = Pipeline::new(|event| {
+            if let GpsEvent::Satellites(cycle) = event {
+                satellites.extend(cycle);
+            }
+        })
+        .with_mapping(mapping);
+        pipeline.feed(b"$GPGSV,1,1,01,12,45,38,270,X*30\r\n");
+        drop(pipeline);
+        let sat = &satellites[0];
+        assert_eq!((sat.latitude, sat.longitude, sat.strength), (45.0, 270.0, 38));
+    }
+
+    #[test]
+    fn single_system_fixes_give_way_to_combined_ones() {
+        let text = "$GNGGA,123519,4807.038,N,01131.000,E,1,12,0.8,545.4,M,46.9,M,,*53\r\n\
+                    $GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n";
+        let mut events = Vec::new();
+        run(io::Cursor::new(text), |event| events.push(event)).unwrap();
+        let fixes: Vec<(bool, u8)> = events
+            .iter()
+            .filter_map(|e| match e {
+                GpsEvent::Fix(fix) => Some((true, fix.satellites_used)),
+                GpsEvent::SupersededFix(fix) => Some((false, fix.satellites_used)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(fixes, [(true, 12), (false, 8)]);
+    }
+
+    #[test]
+    fn gn_system_ids_mark_the_right_satellites_used() {
+        // PRN 11 is in view on three systems, which no PRN range tells apart
+        let gsv = |talker: &str, prn: &str| {
+            let line = format!("${talker}GSV,1,1,01,{prn},45,120,40*00");
+            parser::parse_gsv_message(&line).unwrap().satellites
+        };
+        let mut satellites = [gsv("GP", "11"), gsv("GL", "70"), gsv("GA", "11"), gsv("GB", "11")]
+            .concat();
+        let mut used = UsedSatellites::default();
+        used.push("$GNGSA,A,3,11,,,,,,,,,,,,1.6,0.9,1.3,1*00");
+        used.push("$GNGSA,A,3,70,,,,,,,,,,,,1.6,0.9,1.3,2*00");
+        used.push("$GNGSA,A,3,11,,,,,,,,,,,,1.6,0.9,1.3,3*00");
+        used.push("$GNGSA,A,3,,,,,,,,,,,,,1.6,0.9,1.3,4*00");
+        mark_used(&mut satellites, &used.keys());
+        let flags: Vec<(String, bool)> =
+            satellites.iter().map(|s| (s.key(), s.used_in_fix)).collect();
+        assert_eq!(
+            flags,
+            [
+                ("GPS-11".to_string(), true),
+                ("GLONASS-70".to_string(), true),
+                ("Galileo-11".to_string(), true),
+                ("BeiDou-11".to_string(), false),
+            ]
+        );
+
+        // Without the field, the PRN's range says GPS rather than every
+        // system's PRN 11
+        let mut used = UsedSatellites::default();
+        used.push("$GNGSA,A,3,11,,,,,,,,,,,,1.6,0.9,1.3*00");
+        mark_used(&mut satellites, &used.keys());
+        let marked: Vec<String> =
+            satellites.iter().filter(|s| s.used_in_fix).map(Satellite::key).collect();
+        assert_eq!(marked, ["GPS-11"]);
+    }
+
+    #[test]
+    fn each_system_keeps_its_last_gsa() {
+        let gga = |time: &str| format!("$GPGGA,{time},4807.038,N,01131.000,E,1,08,0.9,,,,,,*00");
+        let mut used = UsedSatellites::default();
+        used.push(&gga("120000.00"));
+        assert!(used.push("$GNGSA,A,3,11,12,,,,,,,,,,,1.6,0.9,1.3,1*00"));
+        assert!(used.push("$GNGSA,A,3,11,,,,,,,,,,,,1.6,0.9,1.3,3*00"));
+        // Only GPS in the next cycle: Galileo's list stays
+        used.push(&gga("120001.00"));
+        used.push("$GNGSA,A,3,13,,,,,,,,,,,,1.6,0.9,1.3,1*00");
+        let mut keys: Vec<String> = used.keys().into_iter().collect();
+        keys.sort();
+        assert_eq!(keys, ["GPS-13", "Galileo-11"]);
+        assert_eq!(used.age(), Some(1.0));
+        assert!(!used.is_stale());
+        used.push(&gga("120006.50"));
+        assert!(used.is_stale());
+
+        // Without system IDs, the first and second GSA of each run
+        let mut used = UsedSatellites::default();
+        used.push("$GNGSA,A,3,11,,,,,,,,,,,,1.6,0.9,1.3*00");
+        used.push("$GNGSA,A,3,70,,,,,,,,,,,,1.6,0.9,1.3*00");
+        used.push(&gga("120000.00"));
+        used.push("$GNGSA,A,3,12,,,,,,,,,,,,1.6,0.9,1.3*00");
+        let mut keys: Vec<String> = used.keys().into_iter().collect();
+        keys.sort();
+        assert_eq!(keys, ["GLONASS-70", "GPS-12"]);
+        assert_eq!(used.age(), Some(0.0));
+    }
+
+    #[test]
+    fn combined_talker_preferred_while_present() {
+        let mut filter = TalkerFilter::default();
+        let start = Instant::now();
+        assert!(filter.accept("$GPGGA,1*00", start, true));
+        assert!(filter.accept("$GNGGA,1*00", start, true));
+        assert!(!filter.accept("$GPGGA,2*00", start, true));
+        assert!(filter.accept("$GPGGA,2*00", start, false));
+        // Other sentence types are unaffected
+        assert!(filter.accept("$GPRMC,2*00", start, true));
+        // Once the GN sentences stop, single-system ones are used again
+        let later = start + COMBINED_HOLD + Duration::from_millis(1);
+        assert!(filter.accept("$GPGGA,3*00", later, true));
+        assert!(filter.accept("$Ü1GGA,3*00", later, true));
+        assert!(filter.accept("$GÜGGA,3*00", later, true));
+    }
+
+    #[test]
+    fn tail_buffers_partial_lines() {
+        let mut pending = b"$GPGGA,1*00\r\n$GPRMC,".to_vec();
+        assert_eq!(take_complete_lines(&mut pending).as_deref(), Some("$GPGGA,1*00\r\n"));
+        assert_eq!(pending, b"$GPRMC,");
+        assert_eq!(take_complete_lines(&mut pending), None);
+        pending.extend_from_slice(b"2*00\n");
+        assert_eq!(take_complete_lines(&mut pending).as_deref(), Some("$GPRMC,2*00\r\n"));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn any_mix_of_line_ends_splits() {
+        let stream = b"$GPGGA,1*00\n$GPGGA,2*00\r\n$GPGGA,3*00\r$GPGGA,4*00\n\r$GPGGA,5*00\n\r";
+        let mut buffer = LineBuffer::default();
+        // Fed byte by byte, so a CR and its LF arrive in separate reads
+        let mut text = String::new();
+        for byte in stream {
+            text += &buffer.push(&[*byte]).0.unwrap_or_default();
+        }
+        let expected = ["$GPGGA,1*00", "$GPGGA,2*00", "$GPGGA,3*00", "$GPGGA,4*00", "$GPGGA,5*00"];
+        assert_eq!(text.lines().collect::<Vec<_>>(), expected);
+        assert_eq!(text.matches("\r\n").count(), 5);
+
+        // Control characters around a sentence would fail its checksum
+        let sentences: Vec<&str> = split_sentences("\0$GPGGA,1*00\x03\r\n\t\r\n").collect();
+        assert_eq!(sentences, ["$GPGGA,1*00"]);
+    }
+
+    #[test]
+    fn reads_split_inside_a_character_decode_cleanly() {
+        let text = "$GPTXT,01,01,02,Zürich°*00\r\n$GPGGA,1*00\r\n".as_bytes();
+        let split = text.iter().position(|b| *b == 0xC3).unwrap() + 1;
+        let mut pending = text[..split].to_vec();
+        assert_eq!(take_complete_lines(&mut pending), None);
+        pending.extend_from_slice(&text[split..]);
+        let lines = take_complete_lines(&mut pending).unwrap();
+        assert!(lines.starts_with("$GPTXT,01,01,02,Zürich°*00\r\n"));
+        assert!(!lines.contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn overlong_lines_are_dropped_without_growing_the_buffer() {
+        let mut buffer = LineBuffer::default();
+        assert_eq!(buffer.push(b"$GPGGA,1*00\r\n$GP"), (Some("$GPGGA,1*00\r\n".into()), None));
+
+        // A megabyte of junk without a line end, in serial-sized reads
+        let junk = [b'x'; 1024];
+        let mut discarded = 0;
+        for _ in 0..1024 {
+            let (lines, overflow) = buffer.push(&junk);
+            assert_eq!(lines, None);
+            discarded += overflow.unwrap_or(0);
+            assert!(buffer.pending.capacity() <= 2 * MAX_PARTIAL_LINE);
+        }
+        // Reported once, at the overflow
+        assert_eq!(discarded, 3 + MAX_PARTIAL_LINE);
+        // Junk up to the next `$` goes too, then lines parse again
+        let (lines, overflow) = buffer.push(b"xx\r\n$GPRMC,2*00\r\n");
+        assert_eq!((lines.as_deref(), overflow), (Some("$GPRMC,2*00\r\n"), None));
+    }
+}
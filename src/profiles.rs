@@ -0,0 +1,147 @@
+//! Named connection profiles: everything needed to talk to one receiver
+//! (source type, port, baud, handshaking, timeout, command family) and
+//! the source's label, saved under a name, so swapping between receivers
+//! is one pick from a list.
+//! Profiles live in the settings and can be shared as a JSON file.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::ReceiverFamily;
+use crate::settings::{FlowControl, Settings};
+use crate::simulator::SimConfig;
+use crate::{SourceKind, SourceState};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    /// What the source is called while using it, e.g. "Rover"
+    pub label: String,
+    pub kind: SourceKind,
+    pub port: Option<String>,
+    pub baud_rate: u32,
+    pub flow_control: FlowControl,
+    pub read_timeout_ms: u64,
+    pub skip_leading_garbage: bool,
+    pub replay_path: String,
+    pub replay_timed: bool,
+    pub tail_path: String,
+    pub sim_config: SimConfig,
+    pub receiver_family: ReceiverFamily,
+    /// The custom command left in the send box
+    pub command_text: String,
+}
+
+impl Profile {
+    pub fn capture(name: &str, source: &SourceState, settings: &Settings) -> Self {
+        Self {
+            name: name.to_string(),
+            label: source.label.clone(),
+            kind: source.kind,
+            port: source.selected_port.clone(),
+            baud_rate: source.baud_rate,
+            flow_control: settings.flow_control,
+            read_timeout_ms: settings.read_timeout_ms,
+            skip_leading_garbage: settings.skip_leading_garbage,
+            replay_path: source.replay_path.clone(),
+            replay_timed: source.replay_timed,
+            tail_path: source.tail_path.clone(),
+            sim_config: source.sim_config.clone(),
+            receiver_family: source.receiver_family,
+            command_text: source.command_text.clone(),
+        }
+    }
+
+    /// Fills in the source's connection choices and the serial settings;
+    /// the source still has to be started.
+    pub fn apply(&self, source: &mut SourceState, settings: &mut Settings) {
+        if !self.label.trim().is_empty() {
+            source.label = self.label.clone();
+        }
+        source.kind = self.kind;
+        source.selected_port = self.port.clone();
+        if self.baud_rate > 0 {
+            source.baud_rate = self.baud_rate;
+        }
+        source.replay_path = self.replay_path.clone();
+        source.replay_timed = self.replay_timed;
+        source.tail_path = self.tail_path.clone();
+        source.sim_config = self.sim_config.clone();
+        source.receiver_family = self.receiver_family;
+        source.command_text = self.command_text.clone();
+        settings.flow_control = self.flow_control;
+        if self.read_timeout_ms > 0 {
+            settings.read_timeout_ms = self.read_timeout_ms;
+        }
+        settings.skip_leading_garbage = self.skip_leading_garbage;
+    }
+}
+
+/// Adds `profile`, replacing any of the same name.
+pub fn save(profiles: &mut Vec<Profile>, profile: Profile) {
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+}
+
+pub fn export(path: &Path, profiles: &[Profile]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(profiles).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Could not write {}: {e}", path.display()))
+}
+
+/// Reads the profiles in `path` into `profiles`, those with a name
+/// already there replacing it. Returns how many were read.
+pub fn import(path: &Path, profiles: &mut Vec<Profile>) -> Result<usize, String> {
+    let json =
+        fs::read_to_string(path).map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+    let imported: Vec<Profile> = serde_json::from_str(&json)
+        .map_err(|e| format!("{} is not a profile file: {e}", path.display()))?;
+    let count = imported.len();
+    for profile in imported {
+        save(profiles, profile);
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_restores_what_capture_saved() {
+        let mut source = SourceState::new("Rover");
+        source.selected_port = Some("/dev/ttyACM0".to_string());
+        source.baud_rate = 38_400;
+        source.receiver_family = ReceiverFamily::Ublox;
+        let mut settings = Settings {
+            flow_control: FlowControl::Hardware,
+            read_timeout_ms: 250,
+            ..Default::default()
+        };
+        let mut profiles = vec![Profile::capture("rover", &source, &settings)];
+
+        // Round trip through a file, replacing the profile of the same name
+        let path = std::env::temp_dir().join("rust_nmea_profiles_test.json");
+        export(&path, &profiles).unwrap();
+        profiles[0].baud_rate = 9600;
+        assert_eq!(import(&path, &mut profiles), Ok(1));
+        let _ = fs::remove_file(&path);
+        assert_eq!(profiles.len(), 1);
+
+        let mut other = SourceState::new("B");
+        settings = Settings::default();
+        profiles[0].apply(&mut other, &mut settings);
+        assert_eq!(other.label, "Rover");
+        assert_eq!(other.selected_port.as_deref(), Some("/dev/ttyACM0"));
+        assert_eq!(other.baud_rate, 38_400);
+        assert!(other.receiver_family == ReceiverFamily::Ublox);
+        assert!(settings.flow_control == FlowControl::Hardware);
+        assert_eq!(settings.read_timeout_ms, 250);
+
+        assert!(import(Path::new("/nonexistent/profiles.json"), &mut profiles).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Changes of the GGA fix quality over a session, such as a receiver
+//! moving from GPS to DGPS to RTK. A new quality has to hold for a few
+//! seconds before it counts, so a receiver flapping between two modes
+//! does not flood the list.
+
+use std::collections::VecDeque;
+
+use crate::parser::fix_quality_name;
+
+/// Seconds a new quality must persist before the change is recorded.
+pub const DEBOUNCE_SECS: f64 = 3.0;
+/// Changes kept per source; the oldest are dropped past this.
+pub const MAX_CHANGES: usize = 200;
+
+/// Precision order of GGA qualities, higher is better. Estimated, manual
+/// and simulated positions rank just above no fix.
+pub fn quality_rank(quality: u8) -> u8 {
+    match quality {
+        0 => 0,
+        1 => 2,
+        3 => 3,
+        2 => 4,
+        5 => 5,
+        4 => 6,
+        _ => 1,
+    }
+}
+
+#[derive(Clone)]
+pub struct QualityChange {
+    /// Unix time the new quality was first seen
+    pub time: f64,
+    pub from: u8,
+    pub to: u8,
+}
+
+impl QualityChange {
+    pub fn is_upgrade(&self) -> bool {
+        quality_rank(self.to) > quality_rank(self.from)
+    }
+
+    /// e.g. "fix upgraded to RTK fixed"
+    pub fn describe(&self) -> String {
+        let direction = if self.is_upgrade() { "upgraded" } else { "downgraded" };
+        format!("fix {direction} to {}", fix_quality_name(self.to))
+    }
+}
+
+/// Follows one source's fix quality, recording debounced changes.
+#[derive(Default, Clone)]
+pub struct QualityTracker {
+    current: Option<u8>,
+    // A different quality seen since the given time, not yet settled
+    candidate: Option<(u8, f64)>,
+    pub changes: VecDeque<QualityChange>,
+}
+
+impl QualityTracker {
+    /// Notes the quality of a fix at `time`, returning the change it
+    /// settled, if any.
+    pub fn check(&mut self, time: f64, quality: u8) -> Option<&QualityChange> {
+        let Some(current) = self.current else {
+            self.current = Some(quality);
+            return None;
+        };
+        if quality == current {
+            self.candidate = None;
+            return None;
+        }
+        let since = match self.candidate {
+            Some((candidate, since)) if candidate == quality => since,
+            _ => {
+                self.candidate = Some((quality, time));
+                time
+            }
+        };
+        if time - since < DEBOUNCE_SECS {
+            return None;
+        }
+        self.current = Some(quality);
+        self.candidate = None;
+        self.changes.push_back(QualityChange {
+            time: since,
+            from: current,
+            to: quality,
+        });
+        if self.changes.len() > MAX_CHANGES {
+            self.changes.pop_front();
+        }
+        self.changes.back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_settled_changes_only() {
+        let mut tracker = QualityTracker::default();
+        assert!(tracker.check(0.0, 1).is_none());
+        // DGPS for two seconds, then back: flapping, not a change
+        tracker.check(1.0, 2);
+        tracker.check(2.0, 2);
+        tracker.check(3.0, 1);
+        assert!(tracker.changes.is_empty());
+
+        tracker.check(10.0, 5);
+        tracker.check(11.0, 4);
+        tracker.check(12.0, 4);
+        tracker.check(13.0, 4);
+        let change = tracker.check(14.0, 4).unwrap();
+        assert_eq!((change.time, change.from, change.to), (11.0, 1, 4));
+        assert_eq!(change.describe(), "fix upgraded to RTK fixed");
+
+        for time in 20..24 {
+            tracker.check(time as f64, 5);
+        }
+        let change = tracker.changes.back().unwrap();
+        assert_eq!(change.describe(), "fix downgraded to RTK float");
+        assert_eq!(tracker.changes.len(), 2);
+    }
+
+    #[test]
+    fn rtk_outranks_dgps_and_gps() {
+        let order: Vec<u8> = [0, 6, 1, 3, 2, 5, 4].map(quality_rank).to_vec();
+        assert!(order.windows(2).all(|w| w[0] < w[1]));
+    }
+}
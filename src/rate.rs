@@ -0,0 +1,88 @@
+//! Fix update rate, measured two ways over a short window: by the UTC
+//! stamps the receiver puts on its GGA and RMC sentences, and by when
+//! they arrive. A receiver set to 10 Hz whose stamps step by 0.1 s but
+//! that arrives slower is being throttled between it and the app.
+
+use std::collections::VecDeque;
+
+/// Seconds of epochs the rates are taken over.
+pub const WINDOW_SECS: f64 = 5.0;
+/// Below this share of the expected rate, the measured one is flagged.
+pub const SHORTFALL: f64 = 0.8;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// One fix epoch: the system time its first sentence arrived and the
+/// receiver's UTC time of day.
+#[derive(Clone, Copy)]
+struct Epoch {
+    arrived: f64,
+    utc: f64,
+}
+
+#[derive(Default, Clone)]
+pub struct RateMeter {
+    epochs: VecDeque<Epoch>,
+}
+
+impl RateMeter {
+    /// Notes a GGA or RMC stamped `utc` (seconds of day) arriving at
+    /// `arrived` (Unix seconds). The GGA and RMC of one epoch count once.
+    pub fn push(&mut self, arrived: f64, utc: f64) {
+        if self.epochs.back().is_some_and(|e| e.utc == utc) {
+            return;
+        }
+        // A clock jump or replay restart makes the old epochs meaningless
+        let backwards = self.epochs.back().is_some_and(|e| arrived < e.arrived);
+        if backwards {
+            self.epochs.clear();
+        }
+        self.epochs.push_back(Epoch { arrived, utc });
+        while self.epochs.front().is_some_and(|e| arrived - e.arrived > WINDOW_SECS) {
+            self.epochs.pop_front();
+        }
+    }
+
+    /// Epochs per second of receiver time.
+    pub fn reported_hz(&self) -> Option<f64> {
+        let (first, last) = (self.epochs.front()?, self.epochs.back()?);
+        let span = (last.utc - first.utc).rem_euclid(SECONDS_PER_DAY);
+        self.per_second(span)
+    }
+
+    /// Epochs per second as they arrived.
+    pub fn received_hz(&self) -> Option<f64> {
+        let (first, last) = (self.epochs.front()?, self.epochs.back()?);
+        self.per_second(last.arrived - first.arrived)
+    }
+
+    fn per_second(&self, span: f64) -> Option<f64> {
+        (self.epochs.len() >= 2 && span > 0.0).then(|| (self.epochs.len() - 1) as f64 / span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_by_stamp_and_by_arrival() {
+        let mut meter = RateMeter::default();
+        assert_eq!(meter.received_hz(), None);
+        // 10 Hz stamps across midnight arriving at half the pace; the RMC
+        // of each epoch adds nothing
+        for n in 0..20 {
+            let utc = (86_399.0 + f64::from(n) * 0.1) % 86_400.0;
+            let arrived = 1000.0 + f64::from(n) * 0.2;
+            meter.push(arrived, utc);
+            meter.push(arrived, utc);
+        }
+        assert!((meter.reported_hz().unwrap() - 10.0).abs() < 1e-6);
+        assert!((meter.received_hz().unwrap() - 5.0).abs() < 1e-6);
+
+        // Time going backwards starts over
+        meter.push(900.0, 10.0);
+        assert_eq!(meter.reported_hz(), None);
+        meter.push(905.0, 15.0);
+        assert_eq!(meter.reported_hz(), Some(0.2));
+    }
+}
@@ -0,0 +1,1628 @@
+use std::fs;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serialport::SerialPort;
+
+use crate::commands;
+use crate::events::ClockWatch;
+use crate::geo::DistanceMode;
+use crate::parser::{self, GgaFix};
+use crate::pipeline::{self, GpsEvent, LineBuffer};
+use crate::rate::RateMeter;
+use crate::repeater;
+use crate::settings::FlowControl;
+use crate::simulator::{SimConfig, Simulator, EPOCH_SECS};
+use crate::ubx::UbxDecoder;
+use crate::{unix_now, AppMode, SourceState};
+
+/// Reader thread internals for the debug panel.
+#[derive(Default, Clone)]
+pub struct ReaderHealth {
+    pub thread_alive: bool,
+    pub last_read_size: usize,
+    pub last_error: Option<String>,
+    /// Readers the watchdog has replaced since Start
+    pub reconnects: u32,
+    /// Bytes waiting in the driver's receive buffer after the last read
+    pub buffered_bytes: Option<u32>,
+    /// Why the port was lost, when that could be told apart
+    pub fault: Option<PortFault>,
+    /// Line settings the driver reports for the open port
+    pub port_settings: Option<PortSettings>,
+}
+
+/// How often the open port's settings are read back from the driver.
+const PORT_SETTINGS_POLL: Duration = Duration::from_secs(2);
+
+/// Line settings in effect on an open port, as the driver reports them.
+/// The OS may not honour every requested value, a high baud rate in
+/// particular.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PortSettings {
+    pub baud_rate: u32,
+    pub data_bits: serialport::DataBits,
+    pub parity: serialport::Parity,
+    pub stop_bits: serialport::StopBits,
+    pub flow_control: serialport::FlowControl,
+}
+
+impl PortSettings {
+    fn query(port: &dyn SerialPort) -> serialport::Result<Self> {
+        Ok(Self {
+            baud_rate: port.baud_rate()?,
+            data_bits: port.data_bits()?,
+            parity: port.parity()?,
+            stop_bits: port.stop_bits()?,
+            flow_control: port.flow_control()?,
+        })
+    }
+
+    /// E.g. "115200 baud 8N1, no flow control".
+    pub fn summary(&self) -> String {
+        let data_bits = match self.data_bits {
+            serialport::DataBits::Five => 5,
+            serialport::DataBits::Six => 6,
+            serialport::DataBits::Seven => 7,
+            serialport::DataBits::Eight => 8,
+        };
+        let parity = match self.parity {
+            serialport::Parity::None => 'N',
+            serialport::Parity::Odd => 'O',
+            serialport::Parity::Even => 'E',
+        };
+        let stop_bits = match self.stop_bits {
+            serialport::StopBits::One => 1,
+            serialport::StopBits::Two => 2,
+        };
+        let flow = match self.flow_control {
+            serialport::FlowControl::None => "no flow control",
+            serialport::FlowControl::Software => "XON/XOFF",
+            serialport::FlowControl::Hardware => "RTS/CTS",
+        };
+        format!("{} baud {data_bits}{parity}{stop_bits}, {flow}", self.baud_rate)
+    }
+
+    /// A warning when the port is not running at the requested rate.
+    pub fn baud_mismatch(&self, requested: u32) -> Option<String> {
+        (self.baud_rate != requested).then(|| {
+            format!("Requested {requested} baud but the port is at {}", self.baud_rate)
+        })
+    }
+}
+
+/// Reads the port's settings back, logging when they differ from what
+/// was last seen.
+fn refresh_port_settings(state: &Mutex<SourceState>, port: &dyn SerialPort) {
+    let Ok(settings) = PortSettings::query(port) else {
+        return;
+    };
+    let mut st = state.lock().unwrap();
+    let previous = st.health.port_settings.replace(settings);
+    if previous.is_some_and(|p| p != settings) {
+        st.push_log(format!("[port] settings changed to {}", settings.summary()));
+    }
+}
+
+/// Minimum spacing of talker conflict messages in the log.
+const CONFLICT_LOG_INTERVAL: Duration = Duration::from_secs(10);
+/// Positions further apart than this count as a conflict.
+const CONFLICT_DISTANCE_M: f64 = 1.0;
+
+/// Single-system fixes that disagreed with the combined (`GN`) one the
+/// decoder preferred, for receivers sending both.
+#[derive(Default, Clone)]
+pub struct TalkerConflicts {
+    last_log: Option<Instant>,
+    pub count: u64,
+}
+
+impl TalkerConflicts {
+    /// Counts a conflict, returning whether it is time to log one.
+    fn note(&mut self, now: Instant) -> bool {
+        self.count += 1;
+        let due = self.last_log.is_none_or(|t| now.duration_since(t) >= CONFLICT_LOG_INTERVAL);
+        if due {
+            self.last_log = Some(now);
+        }
+        due
+    }
+}
+
+/// How a dropped single-system fix disagrees with the combined one for
+/// the same epoch, if it does.
+fn gga_conflict(combined: &GgaFix, single: &GgaFix) -> Option<String> {
+    if combined.utc_time.is_none() || combined.utc_time != single.utc_time {
+        return None;
+    }
+    if combined.quality != single.quality {
+        return Some(format!("quality {} vs {}", single.quality, combined.quality));
+    }
+    let points = [
+        [single.latitude, single.longitude],
+        [combined.latitude, combined.longitude],
+    ];
+    let apart = DistanceMode::GreatCircle.distance_m(points[0], points[1]);
+    (single.has_position() && apart > CONFLICT_DISTANCE_M).then(|| format!("{apart:.1} m apart"))
+}
+
+/// Port failures that need different recoveries.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PortFault {
+    /// Unplugged; worth reconnecting once it is back
+    Removed,
+    /// Held open by another program; retrying won't help until it lets go
+    InUse,
+    NoPermission,
+}
+
+impl PortFault {
+    /// Tells the failures apart by whether the port is still listed and
+    /// the error kind, `None` standing for serialport's `NoDevice`, which
+    /// it reports for busy ports.
+    fn classify(still_listed: bool, kind: Option<io::ErrorKind>) -> Option<PortFault> {
+        match kind {
+            _ if !still_listed => Some(PortFault::Removed),
+            None => Some(PortFault::InUse),
+            Some(io::ErrorKind::PermissionDenied) => Some(PortFault::NoPermission),
+            Some(io::ErrorKind::BrokenPipe | io::ErrorKind::NotFound) => Some(PortFault::Removed),
+            Some(_) => None,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            PortFault::Removed => "Device was removed; reconnecting when it is plugged back in",
+            PortFault::InUse => "Port is in use by another program; close it and retry",
+            PortFault::NoPermission => "No permission to open the port",
+        }
+    }
+
+    /// Whether the watchdog should keep trying to reconnect.
+    pub fn recoverable(&self) -> bool {
+        *self == PortFault::Removed
+    }
+}
+
+/// How long "Wait for the port" keeps retrying a busy port, and how
+/// often.
+pub const PORT_WAIT_LIMIT: Duration = Duration::from_secs(300);
+const PORT_WAIT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A bounded wait for a busy port to be let go.
+#[derive(Clone, Copy, Debug)]
+pub struct PortWait {
+    deadline: Instant,
+    next_try: Instant,
+    pub attempts: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WaitStep {
+    Wait,
+    Retry,
+    GiveUp,
+}
+
+impl PortWait {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            deadline: now + PORT_WAIT_LIMIT,
+            next_try: now + PORT_WAIT_INTERVAL,
+            attempts: 0,
+        }
+    }
+
+    /// Whether to open the port again now, keep waiting, or stop.
+    pub fn step(&mut self, now: Instant) -> WaitStep {
+        if now >= self.deadline {
+            return WaitStep::GiveUp;
+        }
+        if now < self.next_try {
+            return WaitStep::Wait;
+        }
+        self.next_try = now + PORT_WAIT_INTERVAL;
+        self.attempts += 1;
+        WaitStep::Retry
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.deadline.saturating_duration_since(now)
+    }
+}
+
+/// What a port is retried with, once, when an adapter rejects the
+/// chosen settings.
+const FALLBACK_BAUD: u32 = 9600;
+
+/// Whether an open failed on the settings rather than the port: some
+/// USB-serial adapters refuse uncommon baud rates or flow control.
+fn rejected_settings(kind: &serialport::ErrorKind) -> bool {
+    matches!(
+        kind,
+        serialport::ErrorKind::InvalidInput | serialport::ErrorKind::Io(io::ErrorKind::InvalidInput)
+    )
+}
+
+/// e.g. "115200 baud 8N1, flow control Hardware (RTS/CTS)".
+fn describe_params(baud_rate: u32, flow_control: FlowControl) -> String {
+    format!("{baud_rate} baud 8N1, flow control {}", flow_control.name())
+}
+
+fn port_listed(port_name: &str) -> bool {
+    serialport::available_ports()
+        .map_or(true, |ports| ports.iter().any(|p| p.port_name == port_name))
+}
+
+/// Records a failed open or read. Faults that reconnecting can't fix stop
+/// the source instead of leaving the watchdog to retry forever.
+fn record_failure(
+    state: &Mutex<SourceState>,
+    message: String,
+    port_name: &str,
+    kind: Option<io::ErrorKind>,
+) {
+    let fault = PortFault::classify(port_listed(port_name), kind);
+    let handoff = repeater::advertised(port_name).filter(|_| fault == Some(PortFault::InUse));
+    let mut st = state.lock().unwrap();
+    if let Some(advert) = handoff {
+        if st.handoff_port != Some(advert.tcp_port) && st.port_wait.is_none() {
+            st.push_log(format!(
+                "[open] {port_name} is in use; another instance (PID {}) repeats it on TCP \
+                 port {}",
+                advert.pid, advert.tcp_port
+            ));
+        }
+    }
+    st.handoff_port = handoff.map(|advert| advert.tcp_port);
+    st.commands = None;
+    st.health.thread_alive = false;
+    st.health.fault = fault;
+    if fault.is_some_and(|f| !f.recoverable()) {
+        st.mode = AppMode::Error(message.clone());
+        st.reader_abort = None;
+    } else {
+        st.mode = AppMode::Reconnecting;
+    }
+    st.health.last_error = Some(message);
+}
+
+/// What a serial device is sending, sniffed from its first bytes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Protocol {
+    Nmea,
+    Ubx,
+    Unknown,
+}
+
+impl Protocol {
+    /// `$` at the start of a line means NMEA, the `0xB5 0x62` sync
+    /// characters mean u-blox UBX.
+    pub fn detect(data: &[u8]) -> Protocol {
+        if data.starts_with(&UBX_SYNC) {
+            Protocol::Ubx
+        } else if data.split(|b| *b == b'\n').any(|line| line.starts_with(b"$")) {
+            Protocol::Nmea
+        } else if data.windows(2).any(|w| w == UBX_SYNC) {
+            Protocol::Ubx
+        } else {
+            Protocol::Unknown
+        }
+    }
+
+    /// Whether `data` holds a whole NMEA sentence with a good checksum,
+    /// as a receiver switched out of UBX-only output starts sending.
+    pub fn has_nmea_sentence(data: &[u8]) -> bool {
+        data.split(|b| *b == b'\n').any(|line| {
+            line.starts_with(b"$") && std::str::from_utf8(line).is_ok_and(parser::verify_checksum)
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Protocol::Nmea => "NMEA",
+            Protocol::Ubx => "UBX",
+            Protocol::Unknown => "unknown",
+        }
+    }
+}
+
+const UBX_SYNC: [u8; 2] = [0xB5, 0x62];
+/// Bytes per hex dump line for binary data in the log.
+const HEX_LINE_BYTES: usize = 16;
+/// Share of non-printable bytes above which an NMEA chunk is treated as
+/// binary noise rather than text.
+const BINARY_NOISE_RATIO: f32 = 0.3;
+
+/// Whether a chunk is mostly bytes that can't appear in an NMEA sentence.
+fn is_binary_noise(data: &[u8]) -> bool {
+    let binary = data
+        .iter()
+        .filter(|b| !(b.is_ascii_graphic() || matches!(b, b' ' | b'\r' | b'\n' | b'\t')))
+        .count();
+    !data.is_empty() && binary as f32 / data.len() as f32 > BINARY_NOISE_RATIO
+}
+
+/// Where the first NMEA sentence or UBX frame starts in `data`.
+fn sync_offset(data: &[u8]) -> Option<usize> {
+    let dollar = data.iter().position(|b| *b == b'$');
+    let ubx = data.windows(2).position(|w| w == UBX_SYNC);
+    dollar.into_iter().chain(ubx).min()
+}
+
+/// Drops the bytes ahead of the first `$` or UBX sync once armed, as
+/// receivers often send a little noise at startup or after a reset.
+#[derive(Default)]
+struct LeadingGarbage {
+    skipping: bool,
+    skipped: usize,
+}
+
+impl LeadingGarbage {
+    fn arm(&mut self) {
+        self.skipping = true;
+        self.skipped = 0;
+    }
+
+    /// The part of `data` worth parsing and, once the sync is found, how
+    /// many bytes were dropped to reach it.
+    fn strip<'a>(&mut self, data: &'a [u8]) -> (&'a [u8], Option<usize>) {
+        if !self.skipping {
+            return (data, None);
+        }
+        match sync_offset(data) {
+            Some(start) => {
+                self.skipping = false;
+                (&data[start..], Some(self.skipped + start))
+            }
+            None => {
+                self.skipped += data.len();
+                (&[], None)
+            }
+        }
+    }
+}
+
+/// Starts a reader thread feeding `state` from `port_name`.
+///
+/// The returned flag asks the thread to exit; a thread stuck inside
+/// `read()` notices it as soon as the driver returns, and never touches
+/// the shared state again once it is set.
+///
+/// While the port is open, `SourceState::commands` feeds a writer thread
+/// sharing it.
+///
+/// The protocol is sniffed afresh on every (re)connect, and again on
+/// each chunk for as long as it stays unknown; UBX turns to NMEA once
+/// sentences arrive, as after enabling NMEA output. With `skip_garbage`,
+/// bytes ahead of the first sentence are dropped first.
+pub fn spawn(
+    state: Arc<Mutex<SourceState>>,
+    port_name: String,
+    baud_rate: u32,
+    read_timeout: Duration,
+    flow_control: FlowControl,
+    skip_garbage: bool,
+    open_fallback: bool,
+) -> Arc<AtomicBool> {
+    let abort = Arc::new(AtomicBool::new(false));
+    let abort_flag = Arc::clone(&abort);
+
+    // Thread for GPS streaming
+    thread::spawn(move || {
+        let open = |baud_rate: u32, flow_control: FlowControl| {
+            serialport::new(&port_name, baud_rate)
+                .timeout(read_timeout)
+                .flow_control(flow_control.to_serialport())
+                .open()
+        };
+        let mut port = open(baud_rate, flow_control);
+        let standard = (baud_rate, flow_control) == (FALLBACK_BAUD, FlowControl::None);
+        let rejected = match &port {
+            Err(e) if open_fallback && !standard && rejected_settings(&e.kind) => {
+                Some(e.to_string())
+            }
+            _ => None,
+        };
+        if let Some(reason) = rejected {
+            let tried = describe_params(baud_rate, flow_control);
+            let fallback = describe_params(FALLBACK_BAUD, FlowControl::None);
+            port = open(FALLBACK_BAUD, FlowControl::None);
+            let mut st = state.lock().unwrap();
+            match &port {
+                Ok(_) => {
+                    st.push_log(format!(
+                        "[open] {port_name} rejected {tried} ({reason}); opened at {fallback} \
+                         instead"
+                    ));
+                    let note = format!("{port_name} rejected {tried}: opened at {fallback}");
+                    st.open_fallback = Some(note);
+                }
+                Err(e) => st.push_log(format!(
+                    "[open] {port_name} rejected {tried} ({reason}), and {fallback} too ({e})"
+                )),
+            }
+        }
+
+        let mut serial = match port {
+            Ok(serial) => serial,
+            Err(e) => {
+                if !abort_flag.load(Ordering::Relaxed) {
+                    let kind = match e.kind {
+                        serialport::ErrorKind::NoDevice => None,
+                        serialport::ErrorKind::Io(kind) => Some(kind),
+                        _ => Some(io::ErrorKind::Other),
+                    };
+                    record_failure(&state, format!("open {port_name}: {e}"), &port_name, kind);
+                }
+                return;
+            }
+        };
+
+        // Commands are written from a second handle so they go out at once
+        // instead of waiting for the blocking read below to return
+        match serial.try_clone() {
+            Ok(writer) => {
+                let (commands, queued) = mpsc::channel();
+                let mut st = state.lock().unwrap();
+                // Ask what is on the other end before anything else is sent
+                for query in commands::identify_commands(st.receiver_family) {
+                    let _ = commands.send(query);
+                }
+                st.device = None;
+                st.device_query = Some(Instant::now());
+                st.commands = Some(commands);
+                drop(st);
+                let (state, abort_flag) = (Arc::clone(&state), Arc::clone(&abort_flag));
+                thread::spawn(move || write_commands(&state, &abort_flag, writer, queued));
+            }
+            Err(e) => {
+                let mut st = state.lock().unwrap();
+                st.commands = None;
+                st.health.last_error = Some(format!("open {port_name} for writing: {e}"));
+            }
+        }
+
+        let mut buf = [0u8; 1024];
+        let mut protocol = Protocol::Unknown;
+        let mut ubx = UbxDecoder::default();
+        // NMEA bytes after the last line end, completed by the next read
+        let mut pending = LineBuffer::default();
+        let mut garbage = LeadingGarbage::default();
+        if skip_garbage {
+            garbage.arm();
+        }
+        {
+            let mut st = state.lock().unwrap();
+            st.protocol = None;
+            st.clock_watch = ClockWatch::default();
+            st.fix_rate = RateMeter::default();
+            st.resync = false;
+            st.health.thread_alive = true;
+            st.health.port_settings = None;
+        }
+        refresh_port_settings(&state, &*serial);
+        let mut settings_checked = Instant::now();
+
+        loop {
+            if settings_checked.elapsed() >= PORT_SETTINGS_POLL {
+                refresh_port_settings(&state, &*serial);
+                settings_checked = Instant::now();
+            }
+            let read = serial.read(&mut buf);
+            if abort_flag.load(Ordering::Relaxed) {
+                return;
+            }
+            let n = match read {
+                Ok(n) => n,
+                Err(e) => {
+                    let message = format!("read {port_name}: {e}");
+                    record_failure(&state, message, &port_name, Some(e.kind()));
+                    return;
+                }
+            };
+            let restarted = {
+                let mut st = state.lock().unwrap();
+                let reconnected = matches!(st.mode, AppMode::Connecting | AppMode::Reconnecting);
+                if reconnected {
+                    st.mode = AppMode::Reading;
+                }
+                st.health.fault = None;
+                st.health.last_read_size = n;
+                if n > 0 {
+                    st.last_data = Some(Instant::now());
+                    // The read buffer as it came, before any of it is
+                    // stripped or decoded
+                    if let Some(repeater) = &st.repeater {
+                        let _ = repeater.send(buf[..n].to_vec());
+                    }
+                }
+                st.health.buffered_bytes = serial.bytes_to_read().ok();
+                let reset = std::mem::take(&mut st.resync);
+                if reset && skip_garbage {
+                    garbage.arm();
+                }
+                reconnected || reset
+            };
+            // A line cut off before a reconnect or receiver restart would
+            // otherwise be glued onto the first one after it
+            if restarted {
+                pending = LineBuffer::default();
+            }
+
+            let (bytes, skipped) = garbage.strip(&buf[..n]);
+            if let Some(skipped) = skipped.filter(|s| *s > 0) {
+                state.lock().unwrap().push_log(format!(
+                    "[sync] discarded {skipped} bytes before the first sentence"
+                ));
+            }
+            if protocol == Protocol::Unknown && !bytes.is_empty() {
+                protocol = Protocol::detect(bytes);
+                state.lock().unwrap().protocol = Some(protocol);
+            }
+            if protocol == Protocol::Ubx && Protocol::has_nmea_sentence(bytes) {
+                protocol = Protocol::Nmea;
+                let mut st = state.lock().unwrap();
+                st.protocol = Some(protocol);
+                st.push_log("[protocol] NMEA sentences arriving, reading them as NMEA".to_string());
+            }
+            match protocol {
+                Protocol::Nmea => {
+                    // Replies to UBX polls arrive among the NMEA
+                    read_ubx_replies(&state, &mut ubx, bytes);
+                    if is_binary_noise(bytes) {
+                        suppress_binary(&state, bytes);
+                    } else {
+                        let (lines, overflow) = pending.push(bytes);
+                        if let Some(lines) = lines {
+                            process_chunk(&state, &lines);
+                        }
+                        if let Some(discarded) = overflow {
+                            log_overflow(&state, discarded);
+                        }
+                    }
+                }
+                Protocol::Ubx => process_ubx(&state, &mut ubx, bytes),
+                Protocol::Unknown => log_binary(&state, protocol, bytes),
+            }
+
+            thread::sleep(Duration::from_millis(200));
+        }
+    });
+
+    abort
+}
+
+/// Bytes of whole lines handed to the parser per replay step, matching
+/// the serial read buffer.
+const REPLAY_CHUNK_BYTES: usize = 1024;
+/// Pause between fixed-rate replay steps, the live reader's cadence.
+const REPLAY_STEP: Duration = Duration::from_millis(200);
+
+/// How far a file replay has got.
+#[derive(Default)]
+pub struct ReplayProgress {
+    pub path: PathBuf,
+    pub lines_done: usize,
+    /// Size of the file as stored, compressed for a gzipped capture
+    pub total_bytes: usize,
+    /// Bytes of the file read so far, counted as stored
+    pub bytes_done: usize,
+    pub finished: bool,
+    /// Pacing by the file's own timestamps rather than fixed steps
+    pub timed: bool,
+    /// Time since the file's first timestamp, once one has been replayed
+    pub elapsed_secs: Option<f64>,
+}
+
+impl ReplayProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            1.0
+        } else {
+            (self.bytes_done as f32 / self.total_bytes as f32).min(1.0)
+        }
+    }
+}
+
+/// Follows the UTC timestamps of a replayed file.
+#[derive(Default)]
+struct ReplayClock {
+    last: Option<f64>,
+    elapsed: f64,
+}
+
+impl ReplayClock {
+    /// Seconds from the previous timestamp to `time`. Time rolls over at
+    /// midnight; a step backwards counts as no gap.
+    fn advance(&mut self, time: f64) -> f64 {
+        let gap = match self.last {
+            Some(last) if time - last < -43_200.0 => time - last + 86_400.0,
+            Some(last) => (time - last).max(0.0),
+            None => 0.0,
+        };
+        self.last = Some(time);
+        self.elapsed += gap;
+        gap
+    }
+
+    fn elapsed(&self) -> Option<f64> {
+        self.last.map(|_| self.elapsed)
+    }
+}
+
+/// Sleeps in short slices so Stop works during long logger pauses.
+/// Returns false if the replay was stopped.
+fn sleep_unless_aborted(duration: Duration, abort: &AtomicBool) -> bool {
+    let deadline = Instant::now() + duration;
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        if abort.load(Ordering::Relaxed) {
+            return false;
+        }
+        thread::sleep(left.min(Duration::from_millis(100)));
+    }
+    !abort.load(Ordering::Relaxed)
+}
+
+/// Holds a replay or simulation while the source is paused. Returns
+/// false if it was stopped.
+fn wait_while_paused(state: &Mutex<SourceState>, abort: &AtomicBool) -> bool {
+    while state.lock().unwrap().mode == AppMode::Paused {
+        if abort.load(Ordering::Relaxed) {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    !abort.load(Ordering::Relaxed)
+}
+
+/// Marks a file or simulated source as running. Done from the new thread,
+/// as the UI starting it is still holding the source's lock.
+fn mark_started(st: &mut SourceState) {
+    st.protocol = Some(Protocol::Nmea);
+    st.clock_watch = ClockWatch::default();
+    st.fix_rate = RateMeter::default();
+    st.health = ReaderHealth {
+        thread_alive: true,
+        ..Default::default()
+    };
+}
+
+/// Parses a replayed chunk and advances the progress to `read` bytes of
+/// the capture.
+fn replay_chunk(
+    state: &Mutex<SourceState>,
+    chunk: &mut String,
+    read: usize,
+    timed: bool,
+    elapsed: Option<f64>,
+) {
+    repeat(state, chunk.as_bytes());
+    process_chunk(state, chunk);
+    if let Some(progress) = state.lock().unwrap().replay.as_mut() {
+        progress.lines_done += chunk.lines().count();
+        progress.bytes_done = read;
+        progress.timed = timed && elapsed.is_some();
+        progress.elapsed_secs = elapsed;
+    }
+    chunk.clear();
+}
+
+/// Counts the bytes consumed through it, so a gzipped capture's progress
+/// is measured in the compressed bytes on disk.
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicUsize>,
+}
+
+impl<R: BufRead> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.count.fetch_add(amount, Ordering::Relaxed);
+        self.inner.consume(amount);
+    }
+}
+
+/// A capture being read: its decompressed text, and how many of its
+/// `total_bytes` as stored have been consumed.
+struct Capture {
+    text: Box<dyn BufRead + Send>,
+    read: Arc<AtomicUsize>,
+    total_bytes: usize,
+}
+
+impl Capture {
+    fn from_text(text: String) -> Self {
+        let read = Arc::new(AtomicUsize::new(0));
+        Capture {
+            total_bytes: text.len(),
+            text: Box::new(CountingReader {
+                inner: io::Cursor::new(text.into_bytes()),
+                count: Arc::clone(&read),
+            }),
+            read,
+        }
+    }
+
+    /// Reads the next line's sentences into `sentences`, returning false
+    /// at the end of the capture.
+    fn next_line(&mut self, line: &mut Vec<u8>, sentences: &mut Vec<String>) -> io::Result<bool> {
+        line.clear();
+        sentences.clear();
+        if self.text.read_until(b'\n', line)? == 0 {
+            return Ok(false);
+        }
+        let text = String::from_utf8_lossy(line);
+        sentences.extend(pipeline::split_sentences(&text).map(str::to_string));
+        Ok(true)
+    }
+}
+
+/// The first bytes of every gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Opens a capture for streaming, decompressing it as it's read when
+/// gzipped: by a `.gz` extension or, for renamed files, the gzip magic
+/// bytes.
+fn open_capture(path: &Path) -> Result<Capture, String> {
+    let fail = |e: io::Error| format!("{}: {e}", path.display());
+    let file = fs::File::open(path).map_err(fail)?;
+    let total_bytes = file.metadata().map_err(fail)?.len() as usize;
+    let mut file = io::BufReader::new(file);
+    let gzipped = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("gz"))
+        || file.fill_buf().map_err(fail)?.starts_with(&GZIP_MAGIC);
+
+    let read = Arc::new(AtomicUsize::new(0));
+    let file = CountingReader { inner: file, count: Arc::clone(&read) };
+    let text: Box<dyn BufRead + Send> = if gzipped {
+        // Concatenated members, as appended to by `gzip >>`, read as one
+        Box::new(io::BufReader::new(flate2::bufread::MultiGzDecoder::new(file)))
+    } else {
+        Box::new(file)
+    };
+    Ok(Capture { text, read, total_bytes })
+}
+
+/// Describes a read error part way through a capture; gzip's own errors
+/// mostly mean a damaged or truncated file.
+fn capture_error(path: &Path, e: &io::Error) -> String {
+    match e.kind() {
+        io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+            format!("{}: could not decompress: {e}", path.display())
+        }
+        _ => format!("{}: {e}", path.display()),
+    }
+}
+
+/// Seconds since UTC midnight of a capture's first timestamped sentence.
+/// Only read up to that sentence.
+pub fn capture_start(path: &Path) -> Option<f64> {
+    let mut capture = open_capture(path).ok()?;
+    let (mut line, mut sentences) = (Vec::new(), Vec::new());
+    while capture.next_line(&mut line, &mut sentences).ok()? {
+        if let Some(start) = sentences.iter().find_map(|s| parser::sentence_time(s)) {
+            return Some(start);
+        }
+    }
+    None
+}
+
+/// Replay offsets lining captures up by their first timestamps: each
+/// skips ahead to the latest start, across midnight if need be. Captures
+/// without timestamps start as they are.
+pub fn align_by_timestamps(starts: &[Option<f64>]) -> Vec<f64> {
+    let Some(reference) = starts.iter().flatten().next().copied() else {
+        return vec![0.0; starts.len()];
+    };
+    // Each start relative to the first, within half a day either way
+    let relative: Vec<Option<f64>> = starts
+        .iter()
+        .map(|start| start.map(|t| (t - reference + 43_200.0).rem_euclid(86_400.0) - 43_200.0))
+        .collect();
+    let latest = relative.iter().flatten().fold(f64::MIN, |a, b| a.max(*b));
+    relative.iter().map(|r| r.map_or(0.0, |r| r - latest)).collect()
+}
+
+/// Starts a thread replaying a captured NMEA file through the same
+/// parsing path as a live port. With `timed` the gaps between the file's
+/// GGA/RMC/ZDA timestamps are reproduced, logger pauses included; files
+/// without timestamps, or untimed replays, step at the live reader's
+/// cadence. The file is streamed, gzipped or not, rather than read into
+/// memory up front.
+///
+/// A positive `offset_secs` holds the start back that long; a negative
+/// one skips that much of a timestamped file, to line captures up.
+pub fn spawn_replay(
+    state: Arc<Mutex<SourceState>>,
+    path: PathBuf,
+    timed: bool,
+    offset_secs: f64,
+) -> Result<Arc<AtomicBool>, String> {
+    let capture = open_capture(&path)?;
+    Ok(spawn_capture(state, path, capture, timed, offset_secs))
+}
+
+/// Replays `text` as if read from a file, `name` standing in for its
+/// path in the progress display.
+pub fn spawn_replay_text(
+    state: Arc<Mutex<SourceState>>,
+    name: PathBuf,
+    text: String,
+    timed: bool,
+    offset_secs: f64,
+) -> Arc<AtomicBool> {
+    spawn_capture(state, name, Capture::from_text(text), timed, offset_secs)
+}
+
+fn spawn_capture(
+    state: Arc<Mutex<SourceState>>,
+    name: PathBuf,
+    mut capture: Capture,
+    timed: bool,
+    offset_secs: f64,
+) -> Arc<AtomicBool> {
+    let skip = (-offset_secs).max(0.0);
+    let abort = Arc::new(AtomicBool::new(false));
+    let abort_flag = Arc::clone(&abort);
+
+    thread::spawn(move || {
+        {
+            let mut st = state.lock().unwrap();
+            mark_started(&mut st);
+            st.replay = Some(ReplayProgress {
+                path: name.clone(),
+                total_bytes: capture.total_bytes,
+                ..Default::default()
+            });
+        }
+        if offset_secs > 0.0
+            && !sleep_unless_aborted(Duration::from_secs_f64(offset_secs), &abort_flag)
+        {
+            return;
+        }
+        let mut chunk = String::new();
+        let mut clock = ReplayClock::default();
+        let (mut line, mut sentences) = (Vec::new(), Vec::new());
+        let mut error = None;
+
+        loop {
+            match capture.next_line(&mut line, &mut sentences) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    error = Some(capture_error(&name, &e));
+                    break;
+                }
+            }
+            let read = capture.read.load(Ordering::Relaxed);
+            for sentence in &sentences {
+                let before = clock.elapsed();
+                let gap = parser::sentence_time(sentence).map_or(0.0, |t| clock.advance(t));
+                if skip > 0.0 && clock.elapsed().is_none_or(|elapsed| elapsed < skip) {
+                    if let Some(progress) = state.lock().unwrap().replay.as_mut() {
+                        progress.lines_done += 1;
+                        progress.bytes_done = read;
+                    }
+                    continue;
+                }
+                if timed && gap > 0.0 && !chunk.is_empty() {
+                    // A new epoch: hand over the previous one, then wait out the gap
+                    if !wait_while_paused(&state, &abort_flag) {
+                        return;
+                    }
+                    replay_chunk(&state, &mut chunk, read, timed, before);
+                    if !sleep_unless_aborted(Duration::from_secs_f64(gap), &abort_flag) {
+                        return;
+                    }
+                }
+
+                chunk.push_str(sentence);
+                chunk.push('\n');
+                // Untimestamped lines, before the first time or in a file
+                // without any, go at the fixed cadence
+                if (timed && clock.elapsed().is_some()) || chunk.len() < REPLAY_CHUNK_BYTES {
+                    continue;
+                }
+
+                if !wait_while_paused(&state, &abort_flag) {
+                    return;
+                }
+                replay_chunk(&state, &mut chunk, read, timed, clock.elapsed());
+                thread::sleep(REPLAY_STEP);
+            }
+        }
+        if !chunk.is_empty() && !abort_flag.load(Ordering::Relaxed) {
+            let read = capture.read.load(Ordering::Relaxed);
+            replay_chunk(&state, &mut chunk, read, timed, clock.elapsed());
+        }
+
+        let mut st = state.lock().unwrap();
+        if let Some(progress) = st.replay.as_mut() {
+            progress.finished = true;
+        }
+        if error.is_some() {
+            st.replay_error = error;
+        }
+        st.mode = AppMode::Idle;
+        st.reader_abort = None;
+        st.health.thread_alive = false;
+    });
+
+    abort
+}
+
+/// Pause between polls of a tailed file for new data.
+const TAIL_POLL: Duration = Duration::from_millis(200);
+
+/// Whether the file now at the tailed path is no longer the one open:
+/// truncated in place, or replaced by log rotation.
+fn tail_restarted(open: &fs::Metadata, now: &fs::Metadata, position: u64) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if (open.dev(), open.ino()) != (now.dev(), now.ino()) {
+            return true;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = open;
+    now.len() < position
+}
+
+fn log_overflow(state: &Mutex<SourceState>, discarded: usize) {
+    state.lock().unwrap().push_log(format!(
+        "[sync] discarded {discarded} bytes without a line end, resyncing at the next $"
+    ));
+}
+
+/// Starts a thread following a log file another process is appending to,
+/// like `tail -f`: only lines written from now on are read, and the file
+/// is reopened from the start when it is truncated or rotated.
+pub fn spawn_tail(
+    state: Arc<Mutex<SourceState>>,
+    path: PathBuf,
+) -> Result<Arc<AtomicBool>, String> {
+    let fail = |e: io::Error| format!("{}: {e}", path.display());
+    let mut file = fs::File::open(&path).map_err(fail)?;
+    let mut position = file.seek(SeekFrom::End(0)).map_err(fail)?;
+
+    let abort = Arc::new(AtomicBool::new(false));
+    let abort_flag = Arc::clone(&abort);
+
+    thread::spawn(move || {
+        mark_started(&mut state.lock().unwrap());
+        let mut pending = LineBuffer::default();
+        let mut buf = Vec::new();
+        while !abort_flag.load(Ordering::Relaxed) {
+            let restarted = match (file.metadata(), fs::metadata(&path)) {
+                (Ok(open), Ok(now)) => tail_restarted(&open, &now, position),
+                // Rotated away and not recreated yet
+                _ => false,
+            };
+            if restarted {
+                if let Ok(reopened) = fs::File::open(&path) {
+                    file = reopened;
+                    position = 0;
+                    pending = LineBuffer::default();
+                    state.lock().unwrap().push_log(format!(
+                        "[tail] {} was truncated or rotated, reading from the start",
+                        path.display()
+                    ));
+                }
+            }
+
+            buf.clear();
+            match file.read_to_end(&mut buf) {
+                Ok(n) => {
+                    position += n as u64;
+                    if n > 0 {
+                        repeat(&state, &buf);
+                    }
+                    let (lines, overflow) = pending.push(&buf);
+                    if let Some(lines) = lines {
+                        process_chunk(&state, &lines);
+                        state.lock().unwrap().health.last_read_size = n;
+                    }
+                    if let Some(discarded) = overflow {
+                        log_overflow(&state, discarded);
+                    }
+                }
+                Err(e) => {
+                    state.lock().unwrap().health.last_error =
+                        Some(format!("{}: {e}", path.display()));
+                }
+            }
+            thread::sleep(TAIL_POLL);
+        }
+    });
+
+    Ok(abort)
+}
+
+/// How long connecting to a repeater may take, and how often its reader
+/// checks the stop flag.
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const TCP_POLL: Duration = Duration::from_millis(200);
+
+/// Starts a thread reading the NMEA another instance's repeater sends on
+/// a local TCP port, for when that instance holds the receiver's port.
+pub fn spawn_tcp(state: Arc<Mutex<SourceState>>, port: u16) -> Arc<AtomicBool> {
+    use io::ErrorKind::{TimedOut, WouldBlock};
+    let abort = Arc::new(AtomicBool::new(false));
+    let abort_flag = Arc::clone(&abort);
+
+    thread::spawn(move || {
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let connected = TcpStream::connect_timeout(&address, TCP_CONNECT_TIMEOUT)
+            .and_then(|stream| stream.set_read_timeout(Some(TCP_POLL)).map(|()| stream));
+        let mut stream = match connected {
+            Ok(stream) => stream,
+            Err(e) => {
+                let mut st = state.lock().unwrap();
+                st.mode = AppMode::Error(format!("connect to {address}: {e}"));
+                st.health.thread_alive = false;
+                st.reader_abort = None;
+                return;
+            }
+        };
+        mark_started(&mut state.lock().unwrap());
+        state.lock().unwrap().push_log(format!("[tcp] reading the repeater on {address}"));
+
+        let mut pending = LineBuffer::default();
+        let mut buf = [0u8; 1024];
+        while !abort_flag.load(Ordering::Relaxed) {
+            let n = match stream.read(&mut buf) {
+                Ok(0) => {
+                    let mut st = state.lock().unwrap();
+                    st.mode = AppMode::Error(format!("The repeater on {address} closed"));
+                    st.health.thread_alive = false;
+                    st.reader_abort = None;
+                    return;
+                }
+                Ok(n) => n,
+                // Read timeouts, to check the stop flag
+                Err(e) if matches!(e.kind(), WouldBlock | TimedOut) => continue,
+                Err(e) => {
+                    let mut st = state.lock().unwrap();
+                    st.mode = AppMode::Error(format!("read {address}: {e}"));
+                    st.health.thread_alive = false;
+                    st.reader_abort = None;
+                    return;
+                }
+            };
+            repeat(&state, &buf[..n]);
+            let (lines, overflow) = pending.push(&buf[..n]);
+            if let Some(lines) = lines {
+                process_chunk(&state, &lines);
+                state.lock().unwrap().health.last_read_size = n;
+            }
+            if let Some(discarded) = overflow {
+                log_overflow(&state, discarded);
+            }
+        }
+    });
+
+    abort
+}
+
+/// Starts a thread feeding simulated epochs through the parsing path
+/// once per simulated second.
+pub fn spawn_simulator(state: Arc<Mutex<SourceState>>, config: SimConfig) -> Arc<AtomicBool> {
+    let abort = Arc::new(AtomicBool::new(false));
+    let abort_flag = Arc::clone(&abort);
+
+    thread::spawn(move || {
+        mark_started(&mut state.lock().unwrap());
+        let mut simulator = Simulator::new(config);
+        while wait_while_paused(&state, &abort_flag) {
+            let epoch = simulator.next_epoch().join("\r\n") + "\r\n";
+            repeat(&state, epoch.as_bytes());
+            process_chunk(&state, &epoch);
+            thread::sleep(Duration::from_secs_f64(EPOCH_SECS));
+        }
+    });
+
+    abort
+}
+
+/// Writes commands from the UI until the reader is replaced or the
+/// channel closes, echoing each in the log as `→ <command>`. Failures are
+/// logged and shown next to the command controls.
+fn write_commands(
+    state: &Mutex<SourceState>,
+    abort: &AtomicBool,
+    mut port: Box<dyn SerialPort>,
+    queued: Receiver<Vec<u8>>,
+) {
+    for command in queued {
+        let written = port.write_all(&command).and_then(|()| port.flush());
+        if abort.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut st = state.lock().unwrap();
+        let text = commands::describe(&command);
+        match written {
+            Ok(()) => st.push_log(format!("→ {text}")),
+            Err(e) => {
+                st.push_log(format!("→ {text} (write failed: {e})"));
+                st.command_error = Some(format!("Write failed: {e}"));
+                st.health.last_error = Some(format!("write: {e}"));
+            }
+        }
+    }
+}
+
+/// Keeps binary noise out of the text log, counting it instead, or hex
+/// dumps it when the source asks for that.
+fn suppress_binary(state: &Mutex<SourceState>, data: &[u8]) {
+    let mut st = state.lock().unwrap();
+    st.last_data = Some(Instant::now());
+    st.binary_chunks += 1;
+    if st.binary_as_hex {
+        drop(st);
+        log_binary(state, Protocol::Unknown, data);
+    }
+}
+
+/// Reassembles UBX frames, logging each and keeping the GPS time ones.
+fn process_ubx(state: &Mutex<SourceState>, decoder: &mut UbxDecoder, data: &[u8]) {
+    let frames = decoder.push(data);
+    let mut st = state.lock().unwrap();
+    st.last_data = Some(Instant::now());
+    if st.mode == AppMode::Paused {
+        return;
+    }
+    for frame in frames {
+        st.push_log(format!("[UBX] {}", frame.describe()));
+        if let Some(time) = frame.gps_time() {
+            st.gps_time = Some(time);
+        }
+        if let Some(status) = frame.antenna_status() {
+            st.set_antenna(status);
+        }
+        if let Some(info) = frame.device_info() {
+            st.set_device(info);
+        }
+        if let Some(model) = frame.dynamic_model() {
+            st.set_dynamic_model(model);
+        }
+    }
+}
+
+/// Picks identification replies out of UBX frames mixed into NMEA.
+fn read_ubx_replies(state: &Mutex<SourceState>, decoder: &mut UbxDecoder, data: &[u8]) {
+    if !data.contains(&0xB5) && decoder.is_empty() {
+        return;
+    }
+    for frame in decoder.push(data) {
+        if let Some(info) = frame.device_info() {
+            state.lock().unwrap().set_device(info);
+        }
+        if let Some(model) = frame.dynamic_model() {
+            state.lock().unwrap().set_dynamic_model(model);
+        }
+    }
+}
+
+/// Logs non-NMEA data as a hex dump, there being no decoder for it yet.
+fn log_binary(state: &Mutex<SourceState>, protocol: Protocol, data: &[u8]) {
+    let mut st = state.lock().unwrap();
+    st.last_data = Some(Instant::now());
+    for chunk in data.chunks(HEX_LINE_BYTES) {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02X}")).collect();
+        st.push_log(format!("[{}] {}", protocol.name(), hex.join(" ")));
+    }
+}
+
+/// Hands bytes to the repeater, when it repeats this source.
+fn repeat(state: &Mutex<SourceState>, bytes: &[u8]) {
+    if let Some(repeater) = &state.lock().unwrap().repeater {
+        let _ = repeater.send(bytes.to_vec());
+    }
+}
+
+/// Delay before the UI repaints for new data, capping data-driven frames
+/// at about ten a second.
+const DATA_REPAINT: Duration = Duration::from_millis(100);
+
+/// Parses a chunk of NMEA text into the source state. The whole chunk is
+/// applied under one lock, so a fast receiver costs the UI one contended
+/// lock per read rather than several per sentence.
+fn process_chunk(state: &Mutex<SourceState>, data: &str) {
+    let mut guard = state.lock().unwrap();
+    let st = &mut *guard;
+    let now = Instant::now();
+    st.last_data = Some(now);
+    if st.mode == AppMode::Paused {
+        return;
+    }
+    if let Some(ctx) = &st.repaint {
+        // Coalesces a burst of chunks into one frame
+        ctx.request_repaint_after(DATA_REPAINT);
+    }
+
+    let mut cycle = None;
+    let mut events = Vec::new();
+
+    for line in data.lines() {
+        let valid = parser::verify_checksum(line);
+        let system_time = unix_now();
+        // A corrupted time field would report a clock jump that never was
+        if valid {
+            st.check_clock(line, system_time);
+            if let Some(time) = parser::sentence_unix_time(line, system_time) {
+                st.gps_clock_offset = Some(time - system_time);
+            }
+        }
+
+        // 🔵 Append NMEA line to log
+        st.count_sentence(line);
+        if st.log_valid_only && !valid {
+            st.discarded_lines += 1;
+        } else {
+            st.push_log(line.to_string());
+        }
+        let mapping = st.decoder.gsv_mapping;
+        if let Some(anomaly) = parser::field_count_anomaly(line, &mapping) {
+            st.note_field_anomaly(anomaly);
+        }
+        if let Some((sentence, with_id)) = parser::nmea41_ids(line, &mapping) {
+            st.note_nmea_version(sentence, with_id);
+        }
+        // Logged and counted above, but a corrupted field must not reach
+        // the fix or the satellites
+        if !valid {
+            continue;
+        }
+
+        // GSV, GSA, GGA and RMC, as the library parses them
+        st.decoder.decode(line, now, |event| events.push(event));
+        for event in events.drain(..) {
+            match event {
+                GpsEvent::Satellites(satellites) => cycle = Some(satellites),
+                GpsEvent::FixMode(mode) => st.fix_mode = Some(mode),
+                GpsEvent::Fix(fix) => st.push_fix(fix),
+                GpsEvent::SupersededFix(fix) => {
+                    let conflict = st.fix.as_ref().and_then(|c| gga_conflict(c, &fix));
+                    if conflict.is_some() && st.talker_conflicts.note(now) {
+                        let total = st.talker_conflicts.count;
+                        st.push_log(format!(
+                            "[talker] {} disagrees with the combined GGA: {} \
+                             ({total} conflicts so far)",
+                            parser::sentence_type(line).unwrap_or("GGA"),
+                            conflict.unwrap_or_default()
+                        ));
+                    }
+                }
+                GpsEvent::Motion(rmc) => {
+                    st.note_fix_epoch(rmc.utc_time.as_deref());
+                    if let Some(knots) = rmc.speed_knots.filter(|_| rmc.valid) {
+                        st.records.push_speed(unix_now(), knots);
+                    }
+                    st.note_course(&rmc);
+                    st.rmc = Some(rmc);
+                }
+                GpsEvent::BadChecksum(_) => {}
+            }
+        }
+
+        // u-blox proprietary time
+        if let Some(time) = st.decoder.filter.pubx.then_some(line).and_then(parser::parse_pubx_time)
+        {
+            st.gps_time = Some(time);
+        }
+
+        if let Some(status) = parser::parse_antenna_status(line) {
+            st.set_antenna(status);
+        }
+        if let Some(info) = parser::parse_pmtk_release(line) {
+            st.set_device(info);
+        }
+    }
+
+    // Update satellites
+    if let Some(satellites) = cycle {
+        st.update_satellites(satellites);
+    }
+
+    let used = st.decoder.used.keys();
+    pipeline::mark_used(&mut st.satellites, &used);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupted_sentences_are_logged_but_not_parsed() {
+        let state = Mutex::new(SourceState::new("A"));
+        let gga = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+        let corrupted = gga.replace("4807.038", "4817.038");
+        process_chunk(&state, &format!("{corrupted}\r\n"));
+        let st = state.lock().unwrap();
+        assert!(st.fix.is_none());
+        assert!(st.nmea_log.iter().any(|e| e.line == corrupted));
+        drop(st);
+
+        process_chunk(&state, &format!("{gga}\r\n"));
+        assert!(state.lock().unwrap().fix.as_ref().is_some_and(|f| f.has_position()));
+    }
+
+    #[test]
+    fn corrupted_times_leave_the_clock_alone() {
+        let state = Mutex::new(SourceState::new("A"));
+        let gga = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+        process_chunk(&state, &format!("{gga}\r\n"));
+        let offset = state.lock().unwrap().gps_clock_offset;
+        assert!(offset.is_some());
+
+        // An hour back, but only because a digit was garbled
+        let corrupted = gga.replace("123519", "113519");
+        process_chunk(&state, &format!("{corrupted}\r\n"));
+        let st = state.lock().unwrap();
+        assert!(!st.nmea_log.iter().any(|e| e.line.starts_with("[clock]")));
+        assert!(st.events.events.is_empty());
+        let drift = (st.gps_clock_offset.unwrap() - offset.unwrap()).abs();
+        assert!(drift < 60.0, "offset moved by {drift} s");
+    }
+
+    #[test]
+    fn sources_start_while_the_ui_holds_the_lock() {
+        let state = Arc::new(Mutex::new(SourceState::new("A")));
+        let guard = state.lock().unwrap();
+        let abort = spawn_simulator(Arc::clone(&state), SimConfig::default());
+        drop(guard);
+        abort.store(true, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn replays_start_while_the_ui_holds_the_lock() {
+        let state = Arc::new(Mutex::new(SourceState::new("A")));
+        let guard = state.lock().unwrap();
+        let text = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\n";
+        let name = PathBuf::from("held.nmea");
+        let abort = spawn_replay_text(Arc::clone(&state), name, text.to_string(), false, 0.0);
+        drop(guard);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while state.lock().unwrap().replay.is_none() {
+            assert!(Instant::now() < deadline, "the replay never started");
+            thread::sleep(Duration::from_millis(5));
+        }
+        abort.store(true, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn tails_start_while_the_ui_holds_the_lock() {
+        let path = std::env::temp_dir().join("tail_held_test.nmea");
+        fs::write(&path, "").unwrap();
+        let state = Arc::new(Mutex::new(SourceState::new("A")));
+        let guard = state.lock().unwrap();
+        let abort = spawn_tail(Arc::clone(&state), path.clone()).unwrap();
+        drop(guard);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !state.lock().unwrap().health.thread_alive {
+            assert!(Instant::now() < deadline, "the tail never started");
+            thread::sleep(Duration::from_millis(5));
+        }
+        abort.store(true, Ordering::Relaxed);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_tcp_connects_leave_the_reader_dead() {
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let state = Arc::new(Mutex::new(SourceState::new("A")));
+        state.lock().unwrap().health.thread_alive = true;
+        spawn_tcp(Arc::clone(&state), port);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !matches!(state.lock().unwrap().mode, AppMode::Error(_)) {
+            assert!(Instant::now() < deadline, "the connect never failed");
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(!state.lock().unwrap().health.thread_alive);
+    }
+
+    #[test]
+    fn captures_line_up_by_their_first_timestamps() {
+        assert_eq!(align_by_timestamps(&[Some(100.0), Some(130.5)]), [-30.5, 0.0]);
+        // B started 20 s after A, past midnight
+        assert_eq!(align_by_timestamps(&[Some(86_390.0), Some(10.0)]), [-20.0, 0.0]);
+        assert_eq!(align_by_timestamps(&[None, Some(10.0)]), [0.0, 0.0]);
+        assert_eq!(align_by_timestamps(&[None, None]), [0.0, 0.0]);
+    }
+
+    /// Streams a whole capture, returning its sentences and the bytes
+    /// consumed from the file.
+    fn read_capture(path: &Path) -> Result<(String, usize), String> {
+        let mut capture = open_capture(path)?;
+        let (mut line, mut sentences, mut text) = (Vec::new(), Vec::new(), String::new());
+        while capture.next_line(&mut line, &mut sentences).map_err(|e| capture_error(path, &e))? {
+            sentences.iter().for_each(|s| text.push_str(&format!("{s}\n")));
+        }
+        assert_eq!(capture.total_bytes, fs::metadata(path).unwrap().len() as usize);
+        Ok((text, capture.read.load(Ordering::Relaxed)))
+    }
+
+    #[test]
+    fn gzipped_captures_are_decompressed() {
+        use flate2::write::GzEncoder;
+
+        let text = b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\n";
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(text).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let dir = std::env::temp_dir();
+        // Found by extension, and by the magic bytes when renamed
+        for name in ["replay_test.nmea.gz", "replay_test_renamed.nmea"] {
+            let path = dir.join(name);
+            fs::write(&path, &gzipped).unwrap();
+            let text = String::from_utf8(text.to_vec()).unwrap();
+            // Progress counts the compressed bytes
+            assert_eq!(read_capture(&path).unwrap(), (text, gzipped.len()));
+            assert_eq!(capture_start(&path), Some(12.0 * 3600.0 + 35.0 * 60.0 + 19.0));
+            fs::remove_file(&path).unwrap();
+        }
+        let plain = dir.join("replay_test_plain.nmea");
+        fs::write(&plain, text).unwrap();
+        let (replayed, read) = read_capture(&plain).unwrap();
+        assert_eq!((replayed.as_bytes(), read), (&text[..], text.len()));
+
+        let truncated = dir.join("replay_test_truncated.nmea.gz");
+        fs::write(&truncated, &gzipped[..gzipped.len() / 2]).unwrap();
+        let error = read_capture(&truncated).unwrap_err();
+        assert!(error.contains("could not decompress"), "{error}");
+        fs::remove_file(&plain).unwrap();
+        fs::remove_file(&truncated).unwrap();
+    }
+
+    #[test]
+    fn busy_port_wait_is_bounded() {
+        let start = Instant::now();
+        let mut wait = PortWait::new(start);
+        assert_eq!(wait.step(start), WaitStep::Wait);
+        assert_eq!(wait.step(start + PORT_WAIT_INTERVAL), WaitStep::Retry);
+        // Not again until another interval has passed
+        assert_eq!(wait.step(start + PORT_WAIT_INTERVAL), WaitStep::Wait);
+        assert_eq!(wait.step(start + PORT_WAIT_INTERVAL * 2), WaitStep::Retry);
+        assert_eq!(wait.attempts, 2);
+        assert_eq!(wait.remaining(start + PORT_WAIT_LIMIT / 2), PORT_WAIT_LIMIT / 2);
+        assert_eq!(wait.step(start + PORT_WAIT_LIMIT), WaitStep::GiveUp);
+    }
+
+    #[test]
+    fn reads_another_instances_repeater() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(SourceState::new("A")));
+        let abort = spawn_tcp(Arc::clone(&state), port);
+        let (mut client, _) = listener.accept().unwrap();
+        let gga = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n";
+        client.write_all(gga.as_bytes()).unwrap();
+
+        let waited = Instant::now();
+        while state.lock().unwrap().fix.is_none() && waited.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(state.lock().unwrap().fix.as_ref().map(|f| f.satellites_used), Some(8));
+        abort.store(true, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn replay_clock_follows_gaps_and_midnight() {
+        let mut clock = ReplayClock::default();
+        assert_eq!(clock.elapsed(), None);
+        assert_eq!(clock.advance(86_398.0), 0.0);
+        assert_eq!(clock.advance(86_399.0), 1.0);
+        // Day rollover, then a logger pause
+        assert_eq!(clock.advance(1.0), 2.0);
+        assert_eq!(clock.advance(31.0), 30.0);
+        // Out-of-order timestamps don't rewind
+        assert_eq!(clock.advance(30.0), 0.0);
+        assert_eq!(clock.elapsed(), Some(33.0));
+    }
+
+    #[test]
+    fn port_settings_summary() {
+        let settings = PortSettings {
+            baud_rate: 115_200,
+            data_bits: serialport::DataBits::Eight,
+            parity: serialport::Parity::None,
+            stop_bits: serialport::StopBits::One,
+            flow_control: serialport::FlowControl::Hardware,
+        };
+        assert_eq!(settings.summary(), "115200 baud 8N1, RTS/CTS");
+        assert_eq!(settings.baud_mismatch(115_200), None);
+        assert_eq!(
+            settings.baud_mismatch(921_600).as_deref(),
+            Some("Requested 921600 baud but the port is at 115200")
+        );
+    }
+
+    #[test]
+    fn settings_rejections_fall_back() {
+        assert!(rejected_settings(&serialport::ErrorKind::InvalidInput));
+        assert!(rejected_settings(&serialport::ErrorKind::Io(io::ErrorKind::InvalidInput)));
+        // A missing or busy port is not the settings' fault
+        assert!(!rejected_settings(&serialport::ErrorKind::NoDevice));
+        assert!(!rejected_settings(&serialport::ErrorKind::Io(io::ErrorKind::PermissionDenied)));
+        assert_eq!(
+            describe_params(FALLBACK_BAUD, FlowControl::None),
+            "9600 baud 8N1, flow control None"
+        );
+    }
+
+    #[test]
+    fn classifies_port_faults() {
+        use io::ErrorKind::*;
+        assert_eq!(PortFault::classify(false, Some(Other)), Some(PortFault::Removed));
+        assert_eq!(PortFault::classify(true, None), Some(PortFault::InUse));
+        assert_eq!(
+            PortFault::classify(true, Some(PermissionDenied)),
+            Some(PortFault::NoPermission)
+        );
+        assert_eq!(PortFault::classify(true, Some(BrokenPipe)), Some(PortFault::Removed));
+        assert_eq!(PortFault::classify(true, Some(TimedOut)), None);
+    }
+
+    #[test]
+    fn detects_protocol_from_first_bytes() {
+        assert_eq!(Protocol::detect(b"$GPGGA,123519*47\r\n"), Protocol::Nmea);
+        assert_eq!(Protocol::detect(b"A,1*3F\r\n$GPRMC,"), Protocol::Nmea);
+        assert_eq!(Protocol::detect(&[0xB5, 0x62, 0x01, 0x07]), Protocol::Ubx);
+        assert_eq!(Protocol::detect(&[0x00, 0xB5, 0x62, 0x01]), Protocol::Ubx);
+        assert_eq!(Protocol::detect(&[0x00, 0x13, 0x37]), Protocol::Unknown);
+
+        let mut switched = vec![0xB5, 0x62, 0x01, 0x07, 0x0A];
+        switched.extend(b"$GPGGA,123519*77\r\n");
+        assert!(Protocol::has_nmea_sentence(&switched));
+        assert!(!Protocol::has_nmea_sentence(b"\n$\x85\x13\n$GPGGA,1235"));
+    }
+
+    #[test]
+    fn leading_garbage_is_skipped_up_to_the_first_sync() {
+        let mut garbage = LeadingGarbage::default();
+        assert_eq!(garbage.strip(b"\xFF\x00$GP").0, b"\xFF\x00$GP");
+
+        garbage.arm();
+        assert_eq!(garbage.strip(&[0x00, 0xFE, 0x13]), (&[][..], None));
+        let (rest, skipped) = garbage.strip(b"\x80GA,1*00\r\n$GPGGA,1");
+        assert_eq!(rest, b"$GPGGA,1");
+        assert_eq!(skipped, Some(3 + 10));
+        // Only the start of the stream is touched
+        assert_eq!(garbage.strip(b"x$GPRMC").0, b"x$GPRMC");
+
+        garbage.arm();
+        let (rest, skipped) = garbage.strip(&[0x01, 0xB5, 0x62, 0x01, b'$']);
+        assert_eq!(rest, [0xB5, 0x62, 0x01, b'$']);
+        assert_eq!(skipped, Some(1));
+    }
+
+    #[test]
+    fn flags_mostly_binary_chunks() {
+        assert!(!is_binary_noise(b"$GPGGA,123519,4807.038,N*47\r\n"));
+        assert!(is_binary_noise(&[0xB5, 0x62, 0x01, 0x07, 0x00, 0x5C, b'$']));
+        assert!(!is_binary_noise(b""));
+    }
+}
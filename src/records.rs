@@ -0,0 +1,160 @@
+//! Session records: the extremes seen since the session started, each
+//! with when it happened, as a summary that needs no scrubbing through
+//! the log. Saved with the session and cleared by New Session.
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::GgaFix;
+use crate::satellite::Satellite;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub value: f64,
+    /// Unix time it was set
+    pub time: f64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionRecords {
+    pub max_speed_knots: Option<Record>,
+    pub max_altitude: Option<Record>,
+    pub min_hdop: Option<Record>,
+    pub max_hdop: Option<Record>,
+    pub max_satellites_used: Option<Record>,
+    pub best_snr: Option<Record>,
+    /// Key of the satellite with the best SNR, e.g. `GPS-12`
+    pub best_snr_satellite: String,
+}
+
+/// Replaces `record` when `value` beats it, by `better`.
+fn update(
+    record: &mut Option<Record>,
+    value: f64,
+    time: f64,
+    better: fn(f64, f64) -> bool,
+) -> bool {
+    let beaten = record.is_none_or(|r| better(value, r.value));
+    if beaten {
+        *record = Some(Record { value, time });
+    }
+    beaten
+}
+
+fn higher(a: f64, b: f64) -> bool {
+    a > b
+}
+
+fn lower(a: f64, b: f64) -> bool {
+    a < b
+}
+
+impl SessionRecords {
+    /// Notes a fix at `time`; fixes without a position set no records.
+    pub fn push_fix(&mut self, time: f64, fix: &GgaFix) {
+        if !fix.has_position() {
+            return;
+        }
+        if let Some(altitude) = fix.altitude {
+            update(&mut self.max_altitude, altitude, time, higher);
+        }
+        if let Some(hdop) = fix.hdop {
+            update(&mut self.min_hdop, hdop, time, lower);
+            update(&mut self.max_hdop, hdop, time, higher);
+        }
+        let used = f64::from(fix.satellites_used);
+        update(&mut self.max_satellites_used, used, time, higher);
+    }
+
+    pub fn push_speed(&mut self, time: f64, knots: f64) {
+        update(&mut self.max_speed_knots, knots, time, higher);
+    }
+
+    pub fn push_satellites(&mut self, time: f64, satellites: &[Satellite]) {
+        let Some(best) = satellites.iter().max_by_key(|s| s.strength) else {
+            return;
+        };
+        if update(&mut self.best_snr, f64::from(best.strength), time, higher) {
+            self.best_snr_satellite = best.key();
+        }
+    }
+
+    /// Name, value and record for each extreme, in display order.
+    pub fn rows(&self) -> Vec<(&'static str, String, Option<Record>)> {
+        let text = |record: Option<Record>, decimals: usize, unit: &str| {
+            record.map_or("—".to_string(), |r| format!("{:.*}{unit}", decimals, r.value))
+        };
+        let mut best_snr = text(self.best_snr, 0, " dB-Hz");
+        if self.best_snr.is_some() {
+            best_snr += &format!(" ({})", self.best_snr_satellite);
+        }
+        let used = self.max_satellites_used;
+        vec![
+            ("Max speed", text(self.max_speed_knots, 1, " kn"), self.max_speed_knots),
+            ("Max altitude", text(self.max_altitude, 1, " m"), self.max_altitude),
+            ("Best HDOP", text(self.min_hdop, 1, ""), self.min_hdop),
+            ("Worst HDOP", text(self.max_hdop, 1, ""), self.max_hdop),
+            ("Most satellites used", text(used, 0, ""), used),
+            ("Best SNR", best_snr, self.best_snr),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::satellite::Constellation;
+
+    fn fix(altitude: f64, hdop: f64, used: u8) -> GgaFix {
+        GgaFix {
+            latitude: 48.1,
+            longitude: 11.5,
+            quality: 1,
+            altitude: Some(altitude),
+            hdop: Some(hdop),
+            satellites_used: used,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keeps_the_extremes_and_when() {
+        let mut records = SessionRecords::default();
+        records.push_fix(10.0, &fix(500.0, 1.5, 7));
+        records.push_fix(20.0, &fix(520.0, 0.9, 9));
+        records.push_fix(30.0, &fix(510.0, 4.0, 5));
+        // No position: the zero altitude and HDOP 99 don't count
+        let no_position = GgaFix {
+            hdop: Some(99.0),
+            altitude: Some(0.0),
+            ..Default::default()
+        };
+        records.push_fix(40.0, &no_position);
+        records.push_speed(15.0, 3.2);
+        records.push_speed(25.0, 1.0);
+
+        let sats = |snrs: &[(&str, u8)]| -> Vec<Satellite> {
+            snrs.iter()
+                .map(|(id, strength)| Satellite {
+                    id: id.to_string(),
+                    strength: *strength,
+                    constellation: Constellation::Gps,
+                    ..Default::default()
+                })
+                .collect()
+        };
+        records.push_satellites(12.0, &sats(&[("12", 38), ("07", 44)]));
+        records.push_satellites(22.0, &sats(&[("12", 41)]));
+        records.push_satellites(32.0, &[]);
+
+        assert_eq!(records.max_altitude, Some(Record { value: 520.0, time: 20.0 }));
+        assert_eq!(records.min_hdop.map(|r| r.time), Some(20.0));
+        assert_eq!(records.max_hdop.map(|r| r.value), Some(4.0));
+        assert_eq!(records.max_satellites_used.map(|r| r.value), Some(9.0));
+        assert_eq!(records.max_speed_knots.map(|r| r.time), Some(15.0));
+        let rows = records.rows();
+        assert_eq!(rows[5].1, "44 dB-Hz (GPS-07)");
+        assert_eq!(rows[5].2.map(|r| r.time), Some(12.0));
+        assert_eq!(SessionRecords::default().rows()[0].1, "—");
+    }
+}
@@ -0,0 +1,415 @@
+//! NMEA repeater: sends on the bytes one source receives to TCP clients
+//! of a local port and/or out of a second serial port, so other programs
+//! can read the same stream without contending for the receiver's port.
+//! Bytes go out exactly as read, UBX frames and line endings included;
+//! unless bad checksums are asked for, only the NMEA sentences and UBX
+//! frames passing theirs. While a serial source is repeated over TCP, a
+//! small file in the temp directory advertises the port, so a second
+//! instance finding the receiver's port busy can read from it. An
+//! advert left by an instance that crashed is told apart by its port no
+//! longer taking connections.
+
+use std::fs;
+use std::io::Write;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use rust_nmea_parser::{commands, parser};
+
+use crate::logfile;
+
+/// How often the loop accepts clients and checks the stop flag.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// A client this slow to take a line is dropped rather than stalling the
+/// others.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+const UBX_SYNC: [u8; 2] = [0xB5, 0x62];
+/// Sync, class, id, length and checksum bytes around a UBX payload.
+const UBX_OVERHEAD: usize = 8;
+/// Frames claiming more payload than this are taken for a false sync.
+const MAX_UBX_PAYLOAD: usize = 4096;
+/// Bytes held waiting for a line end before they are given up on.
+const MAX_PENDING: usize = 64 * 1024;
+/// How long an advertised port gets to accept a test connection.
+const ADVERT_CHECK_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RepeaterConfig {
+    /// Local TCP port to listen on; 0 picks a free one
+    pub tcp_port: Option<u16>,
+    pub serial_port: Option<String>,
+    pub baud_rate: u32,
+    /// Pass on lines whose checksum fails, or that aren't sentences
+    pub forward_invalid: bool,
+}
+
+/// The listener and its clients, handed back by a stopped repeater's
+/// thread so a restarted one keeps them.
+struct Tcp {
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+}
+
+/// A running repeater; dropping it stops the thread and waits for it, so
+/// the port is free again once the drop returns.
+pub struct Repeater {
+    pub config: RepeaterConfig,
+    /// The port listened on, once bound
+    pub tcp_port: Option<u16>,
+    bytes: Sender<Vec<u8>>,
+    clients: Arc<AtomicUsize>,
+    // Why the thread gave up, e.g. the serial port would not open
+    failure: Arc<Mutex<Option<String>>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Option<Tcp>>>,
+}
+
+impl Drop for Repeater {
+    fn drop(&mut self) {
+        self.stop_thread();
+    }
+}
+
+impl Repeater {
+    pub fn start(config: RepeaterConfig) -> Result<Self, String> {
+        Self::start_with(config, None)
+    }
+
+    /// Stops this repeater and starts one with `config`, keeping the
+    /// listener and its clients when the TCP port stays the same.
+    pub fn restart(mut self, config: RepeaterConfig) -> Result<Self, String> {
+        let tcp = self.stop_thread().filter(|_| config.tcp_port == self.config.tcp_port);
+        Self::start_with(config, tcp)
+    }
+
+    fn start_with(config: RepeaterConfig, tcp: Option<Tcp>) -> Result<Self, String> {
+        let tcp = match (tcp, config.tcp_port) {
+            (Some(tcp), _) => Some(tcp),
+            (None, Some(port)) => Some(Tcp {
+                listener: TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+                    .and_then(|l| l.set_nonblocking(true).map(|()| l))
+                    .map_err(|e| format!("Repeater port {port}: {e}"))?,
+                clients: Vec::new(),
+            }),
+            (None, None) => None,
+        };
+        let tcp_port = tcp
+            .as_ref()
+            .and_then(|tcp| tcp.listener.local_addr().ok())
+            .map(|a| a.port());
+
+        let (bytes, receiver) = mpsc::channel();
+        let clients = Arc::new(AtomicUsize::new(0));
+        let failure = Arc::new(Mutex::new(None));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (config, clients, failure, stop) =
+                (config.clone(), Arc::clone(&clients), Arc::clone(&failure), Arc::clone(&stop));
+            thread::spawn(move || run(receiver, tcp, &config, &clients, &failure, &stop))
+        };
+        Ok(Self {
+            config,
+            tcp_port,
+            bytes,
+            clients,
+            failure,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    fn stop_thread(&mut self) -> Option<Tcp> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.take()?.join().ok().flatten()
+    }
+
+    /// Where a source's reader hands the bytes it reads.
+    pub fn sender(&self) -> Sender<Vec<u8>> {
+        self.bytes.clone()
+    }
+
+    /// TCP clients connected.
+    pub fn clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+
+    /// Why the repeater stopped by itself, if it has.
+    pub fn failure(&self) -> Option<String> {
+        self.failure.lock().unwrap().clone()
+    }
+}
+
+/// Where the TCP port repeating `port_name` is advertised, e.g.
+/// `nmea_repeater__dev_ttyUSB0.port`.
+fn advert_path(port_name: &str) -> PathBuf {
+    let name = logfile::file_name_part(port_name);
+    std::env::temp_dir().join(format!("nmea_repeater_{name}.port"))
+}
+
+/// An instance repeating a serial port, as its advert says.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Advertised {
+    pub tcp_port: u16,
+    /// Process ID of the repeating instance
+    pub pid: u32,
+}
+
+/// The instance repeating `port_name` over TCP, if one says so and its
+/// port takes a connection. An advert whose port doesn't is stale, left
+/// by a crash, and removed.
+pub fn advertised(port_name: &str) -> Option<Advertised> {
+    let path = advert_path(port_name);
+    let text = fs::read_to_string(&path).ok()?;
+    let mut fields = text.split_whitespace().map(str::parse::<u32>);
+    let (Some(Ok(tcp_port)), Some(Ok(pid))) = (fields.next(), fields.next()) else {
+        return None;
+    };
+    let tcp_port = u16::try_from(tcp_port).ok()?;
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, tcp_port));
+    if TcpStream::connect_timeout(&address, ADVERT_CHECK_TIMEOUT).is_err() {
+        let _ = fs::remove_file(&path);
+        return None;
+    }
+    Some(Advertised { tcp_port, pid })
+}
+
+/// An advertisement of the TCP port a serial port is repeated on, and
+/// the process repeating it; withdrawn when dropped.
+pub struct Advert {
+    pub port_name: String,
+    pub tcp_port: u16,
+}
+
+impl Advert {
+    pub fn publish(port_name: &str, tcp_port: u16) -> Result<Self, String> {
+        let path = advert_path(port_name);
+        let text = format!("{tcp_port} {}", std::process::id());
+        fs::write(&path, text).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(Self {
+            port_name: port_name.to_string(),
+            tcp_port,
+        })
+    }
+}
+
+impl Drop for Advert {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(advert_path(&self.port_name));
+    }
+}
+
+fn run(
+    bytes: Receiver<Vec<u8>>,
+    mut tcp: Option<Tcp>,
+    config: &RepeaterConfig,
+    client_count: &AtomicUsize,
+    failure: &Mutex<Option<String>>,
+    stop: &AtomicBool,
+) -> Option<Tcp> {
+    // Opened here, as a busy or missing port can take a while to fail
+    let mut serial = match &config.serial_port {
+        Some(name) => match serialport::new(name, config.baud_rate).timeout(WRITE_TIMEOUT).open() {
+            Ok(port) => Some(port),
+            Err(e) => {
+                *failure.lock().unwrap() = Some(format!("Repeater serial port {name}: {e}"));
+                return tcp;
+            }
+        },
+        None => None,
+    };
+    let mut filter = (!config.forward_invalid).then(ChecksumFilter::default);
+    while !stop.load(Ordering::Relaxed) {
+        if let Some(Tcp { listener, clients }) = tcp.as_mut() {
+            while let Ok((stream, _)) = listener.accept() {
+                let ready = stream
+                    .set_nonblocking(false)
+                    .and_then(|()| stream.set_write_timeout(Some(WRITE_TIMEOUT)));
+                if ready.is_ok() {
+                    let _ = stream.set_nodelay(true);
+                    clients.push(stream);
+                }
+            }
+        }
+        match bytes.recv_timeout(POLL_INTERVAL) {
+            Ok(data) => {
+                let data = match filter.as_mut() {
+                    Some(filter) => filter.push(&data),
+                    None => data,
+                };
+                if data.is_empty() {
+                    continue;
+                }
+                if let Some(Tcp { clients, .. }) = tcp.as_mut() {
+                    clients.retain_mut(|client| client.write_all(&data).is_ok());
+                }
+                if let Some(port) = serial.as_mut() {
+                    let _ = port.write_all(&data);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        client_count.store(tcp.as_ref().map_or(0, |tcp| tcp.clients.len()), Ordering::Relaxed);
+    }
+    tcp
+}
+
+/// Picks the NMEA sentences and UBX frames passing their checksums out
+/// of the byte stream, each exactly as received.
+#[derive(Default)]
+struct ChecksumFilter {
+    pending: Vec<u8>,
+}
+
+impl ChecksumFilter {
+    fn push(&mut self, data: &[u8]) -> Vec<u8> {
+        self.pending.extend_from_slice(data);
+        let mut passed = Vec::new();
+        let mut start = 0;
+        loop {
+            let rest = &self.pending[start..];
+            let sync = rest.windows(2).position(|w| w == UBX_SYNC);
+            let line_end = rest.iter().position(|&b| b == b'\n');
+            match (sync, line_end) {
+                (Some(0), _) => {
+                    let Some(&[low, high]) = rest.get(4..6) else {
+                        break;
+                    };
+                    let len = usize::from(u16::from_le_bytes([low, high]));
+                    if len > MAX_UBX_PAYLOAD {
+                        start += UBX_SYNC.len();
+                        continue;
+                    }
+                    let Some(frame) = rest.get(..UBX_OVERHEAD + len) else {
+                        break;
+                    };
+                    let payload = &frame[UBX_SYNC.len() + 4..frame.len() - 2];
+                    if commands::ubx_frame(frame[2], frame[3], payload) == frame {
+                        passed.extend_from_slice(frame);
+                    }
+                    start += frame.len();
+                }
+                // Whatever comes before a frame is not a sentence
+                (Some(sync), end) if end.is_none_or(|end| sync < end) => start += sync,
+                (_, Some(end)) => {
+                    let line = &rest[..=end];
+                    let text = std::str::from_utf8(line).unwrap_or_default();
+                    if parser::verify_checksum(text.trim_end()) {
+                        passed.extend_from_slice(line);
+                    }
+                    start += end + 1;
+                }
+                _ => break,
+            }
+        }
+        self.pending.drain(..start);
+        if self.pending.len() > MAX_PENDING {
+            self.pending.clear();
+        }
+        passed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::time::Instant;
+
+    fn connect(repeater: &Repeater) -> TcpStream {
+        let client = TcpStream::connect((Ipv4Addr::LOCALHOST, repeater.tcp_port.unwrap())).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let waited = Instant::now();
+        while repeater.clients() == 0 && waited.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(20));
+        }
+        client
+    }
+
+    #[test]
+    fn clients_get_valid_lines() {
+        let config = RepeaterConfig {
+            tcp_port: Some(0),
+            serial_port: None,
+            baud_rate: 9600,
+            forward_invalid: false,
+        };
+        let repeater = Repeater::start(config.clone()).unwrap();
+        let client = connect(&repeater);
+
+        let good = "$GPGSV,1,1,01,12,45,270,38*44\n";
+        let sender = repeater.sender();
+        sender.send(b"$GPGSV,1,1,01,12,45,270,38*4A\r\n".to_vec()).unwrap();
+        sender.send(good.as_bytes().to_vec()).unwrap();
+        let mut line = String::new();
+        let mut reader = BufReader::new(&client);
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, good);
+
+        // Restarting keeps the port and the client
+        let port = repeater.tcp_port;
+        let repeater = repeater
+            .restart(RepeaterConfig {
+                forward_invalid: true,
+                ..config
+            })
+            .unwrap();
+        assert_eq!(repeater.tcp_port, port);
+        let bad = "$GPGSV,1,1,01,12,45,270,38*4A\r\n";
+        repeater.sender().send(bad.as_bytes().to_vec()).unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, bad);
+    }
+
+    #[test]
+    fn filter_keeps_valid_sentences_and_frames_as_received() {
+        let frame = commands::ubx_frame(0x0A, 0x04, b"ROM CORE 3.01");
+        let mut corrupted = frame.clone();
+        corrupted[8] ^= 1;
+        let good = b"$GPGSV,1,1,01,12,45,270,38*44\n";
+        let stream = [
+            &good[..],
+            &frame,
+            b"noise\r\n$GPGSV,1,1,01,12,45,270,38*4A\r\n",
+            &corrupted,
+            b"\xFF\x00",
+            &frame[..5],
+        ]
+        .concat();
+
+        let mut filter = ChecksumFilter::default();
+        let mut passed = Vec::new();
+        // Split mid-sentence and mid-frame
+        for chunk in stream.chunks(7) {
+            passed.extend(filter.push(chunk));
+        }
+        passed.extend(filter.push(&frame[5..]));
+        assert_eq!(passed, [&good[..], &frame, &frame].concat());
+    }
+
+    #[test]
+    fn adverts_last_while_held() {
+        let port_name = "/dev/ttyADVERT_TEST";
+        assert!(advert_path(port_name).ends_with("nmea_repeater__dev_ttyADVERT_TEST.port"));
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let tcp_port = listener.local_addr().unwrap().port();
+        let advert = Advert::publish(port_name, tcp_port).unwrap();
+        let pid = std::process::id();
+        assert_eq!(advertised(port_name), Some(Advertised { tcp_port, pid }));
+        drop(advert);
+        assert_eq!(advertised(port_name), None);
+
+        // Left behind by a crash: the port no longer listens
+        let advert = Advert::publish(port_name, tcp_port).unwrap();
+        std::mem::forget(advert);
+        drop(listener);
+        assert_eq!(advertised(port_name), None);
+        assert!(!advert_path(port_name).exists());
+    }
+}
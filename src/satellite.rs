@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+/// Highest C/N0 a real receiver reports; anything above is misparsed.
+pub const MAX_PLAUSIBLE_SNR: u8 = 60;
+
+/// PRN labels, compiled in from an editable text table.
+const NAMES: &str = include_str!("satellite_names.txt");
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum Constellation {
+    #[default]
+    Unknown,
+    Gps,
+    Glonass,
+    Galileo,
+    BeiDou,
+    Qzss,
+    Navic,
+    Sbas,
+}
+
+impl Constellation {
+    /// Classify from the two-letter NMEA talker ID (`GP`, `GL`, ...).
+    pub fn from_talker(talker: &str) -> Self {
+        match talker {
+            "GP" => Constellation::Gps,
+            "GL" => Constellation::Glonass,
+            "GA" => Constellation::Galileo,
+            "GB" | "BD" => Constellation::BeiDou,
+            "GQ" | "QZ" => Constellation::Qzss,
+            "GI" => Constellation::Navic,
+            _ => Constellation::Unknown,
+        }
+    }
+
+    /// Classify from the NMEA 4.10 GNSS system ID sent at the end of GSA.
+    /// GSV ends in a signal ID instead, and names its system by talker.
+    pub fn from_system_id(id: &str) -> Self {
+        match id {
+            "1" => Constellation::Gps,
+            "2" => Constellation::Glonass,
+            "3" => Constellation::Galileo,
+            "4" => Constellation::BeiDou,
+            "5" => Constellation::Qzss,
+            "6" => Constellation::Navic,
+            _ => Constellation::Unknown,
+        }
+    }
+
+    /// Classify from the PRN alone, for combined `GN` (or otherwise
+    /// unattributed) GSV sentences. The ranges are NMEA 2.3/4.x's plus the
+    /// extended numbering u-blox and others use:
+    ///
+    /// | PRN       | System  |
+    /// |-----------|---------|
+    /// | 1–32      | GPS     |
+    /// | 33–64     | SBAS (NMEA numbering of PRN 120–151) |
+    /// | 65–96     | GLONASS |
+    /// | 120–158   | SBAS    |
+    /// | 193–200   | QZSS    |
+    /// | 201–263   | BeiDou  |
+    /// | 301–336   | Galileo |
+    /// | 401–463   | BeiDou  |
+    ///
+    /// Galileo's own 1–36, BeiDou's 1–63 and QZSS's 1–10 only appear under
+    /// their talker IDs, so are not guessed here.
+    pub fn from_prn(prn: u16) -> Self {
+        match prn {
+            1..=32 => Constellation::Gps,
+            33..=64 | 120..=158 => Constellation::Sbas,
+            65..=96 => Constellation::Glonass,
+            193..=200 => Constellation::Qzss,
+            201..=263 | 401..=463 => Constellation::BeiDou,
+            301..=336 => Constellation::Galileo,
+            _ => Constellation::Unknown,
+        }
+    }
+
+    /// Narrows a talker's system down for one PRN: unattributed PRNs are
+    /// classified by range, and the `GP` talker, which also carries SBAS
+    /// and QZSS on many receivers, is split by range too. Other talkers
+    /// are taken at their word.
+    pub fn refine_by_prn(self, prn: &str) -> Self {
+        let Ok(number) = prn.parse::<u16>() else {
+            return self;
+        };
+        match self {
+            Constellation::Unknown => Constellation::from_prn(number),
+            Constellation::Gps => match Constellation::from_prn(number) {
+                system @ (Constellation::Sbas | Constellation::Qzss) => system,
+                _ => Constellation::Gps,
+            },
+            system => system,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Constellation::Unknown => "Unknown",
+            Constellation::Gps => "GPS",
+            Constellation::Glonass => "GLONASS",
+            Constellation::Galileo => "Galileo",
+            Constellation::BeiDou => "BeiDou",
+            Constellation::Qzss => "QZSS",
+            Constellation::Navic => "NavIC",
+            Constellation::Sbas => "SBAS",
+        }
+    }
+
+    /// Map an NMEA 4.10+ GSV signal ID to a human readable band name.
+    pub fn band(&self, signal_id: &str) -> Option<&'static str> {
+        let band = match (self, signal_id) {
+            (Constellation::Gps, "1") => "L1 C/A",
+            (Constellation::Gps, "2") => "L1 P(Y)",
+            (Constellation::Gps, "3") => "L1 M",
+            (Constellation::Gps, "4") => "L2 P(Y)",
+            (Constellation::Gps, "5") => "L2C-M",
+            (Constellation::Gps, "6") => "L2C-L",
+            (Constellation::Gps, "7") => "L5-I",
+            (Constellation::Gps, "8") => "L5-Q",
+            (Constellation::Glonass, "1") => "G1 C/A",
+            (Constellation::Glonass, "2") => "G1 P",
+            (Constellation::Glonass, "3") => "G2 C/A",
+            (Constellation::Glonass, "4") => "G2 P",
+            (Constellation::Galileo, "1") => "E5a",
+            (Constellation::Galileo, "2") => "E5b",
+            (Constellation::Galileo, "3") => "E5 a+b",
+            (Constellation::Galileo, "4") => "E6-A",
+            (Constellation::Galileo, "5") => "E6-BC",
+            (Constellation::Galileo, "6") => "E1-A",
+            (Constellation::Galileo, "7") => "E1-BC",
+            (Constellation::BeiDou, "1") => "B1I",
+            (Constellation::BeiDou, "2") => "B1Q",
+            (Constellation::BeiDou, "3") => "B1C",
+            (Constellation::BeiDou, "4") => "B1A",
+            (Constellation::BeiDou, "5") => "B2a",
+            (Constellation::BeiDou, "6") => "B2b",
+            (Constellation::BeiDou, "7") => "B2 a+b",
+            (Constellation::BeiDou, "8") => "B3I",
+            (Constellation::BeiDou, "9") => "B3Q",
+            (Constellation::BeiDou, "A") => "B3A",
+            (Constellation::BeiDou, "B") => "B2I",
+            (Constellation::BeiDou, "C") => "B2Q",
+            (Constellation::Qzss, "1") => "L1 C/A",
+            (Constellation::Qzss, "2") => "L1C (D)",
+            (Constellation::Qzss, "3") => "L1C (P)",
+            (Constellation::Qzss, "4") => "LIS",
+            (Constellation::Qzss, "5") => "L2C-M",
+            (Constellation::Qzss, "6") => "L2C-L",
+            (Constellation::Qzss, "7") => "L5-I",
+            (Constellation::Qzss, "8") => "L5-Q",
+            (Constellation::Qzss, "9") => "L6D",
+            (Constellation::Qzss, "A") => "L6E",
+            (Constellation::Navic, "1") => "L5 SPS",
+            (Constellation::Navic, "2") => "S SPS",
+            _ => return None,
+        };
+        Some(band)
+    }
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Satellite {
+    pub id: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub strength: u8,
+    pub constellation: Constellation,
+    pub band: Option<String>,
+    pub used_in_fix: bool,
+    /// The raw GSV sentence the satellite was last reported in.
+    #[serde(default)]
+    pub sentence: String,
+}
+
+impl Satellite {
+    /// PRNs are only unique within a constellation, so selection and
+    /// history are keyed on both.
+    pub fn key(&self) -> String {
+        format!("{}-{}", self.constellation.name(), self.id)
+    }
+
+    /// Why the reported values can't be right, if they can't: elevation
+    /// outside 0–90°, azimuth outside 0–360° or an SNR above
+    /// `MAX_PLAUSIBLE_SNR`. These usually mean the GSV fields were read
+    /// out of alignment.
+    pub fn implausible(&self) -> Option<String> {
+        let mut problems = Vec::new();
+        if !(0.0..=90.0).contains(&self.latitude) {
+            problems.push(format!("elevation {}°", self.latitude));
+        }
+        if !(0.0..=360.0).contains(&self.longitude) {
+            problems.push(format!("azimuth {}°", self.longitude));
+        }
+        if self.strength > MAX_PLAUSIBLE_SNR {
+            problems.push(format!("SNR {} dB-Hz", self.strength));
+        }
+        (!problems.is_empty()).then(|| format!("Implausible {}", problems.join(", ")))
+    }
+
+    /// The physical satellite behind a GPS or GLONASS PRN, e.g.
+    /// "SVN 50, block IIR-M", from `satellite_names.txt`.
+    pub fn known_name(&self) -> Option<&'static str> {
+        let prn = self.id.parse().ok()?;
+        known_names().get(&(self.constellation, prn)).copied()
+    }
+}
+
+/// Width of the SNR histogram's bins, dB-Hz.
+pub const SNR_BIN_DB: u8 = 5;
+
+/// How C/N0 is spread over a set of satellites, those with no SNR left
+/// out as not tracked.
+#[derive(Debug, PartialEq)]
+pub struct SnrHistogram {
+    /// Satellites per `SNR_BIN_DB` bin, the first from 0; at least up to
+    /// 50 dB-Hz so the axis holds still
+    pub counts: Vec<usize>,
+    pub mean: f64,
+    pub median: f64,
+}
+
+impl SnrHistogram {
+    pub fn new(snrs: impl IntoIterator<Item = u8>) -> Option<Self> {
+        let mut snrs: Vec<u8> = snrs.into_iter().filter(|s| *s > 0).collect();
+        snrs.sort_unstable();
+        let highest = *snrs.last()?;
+        let mut counts = vec![0; usize::from((highest / SNR_BIN_DB + 1).max(50 / SNR_BIN_DB))];
+        for snr in &snrs {
+            counts[usize::from(snr / SNR_BIN_DB)] += 1;
+        }
+        let mid = snrs.len() / 2;
+        let median = if snrs.len().is_multiple_of(2) {
+            (f64::from(snrs[mid - 1]) + f64::from(snrs[mid])) / 2.0
+        } else {
+            f64::from(snrs[mid])
+        };
+        let mean = snrs.iter().map(|s| f64::from(*s)).sum::<f64>() / snrs.len() as f64;
+        Some(Self {
+            counts,
+            mean,
+            median,
+        })
+    }
+}
+
+/// A move between two trail samples longer than this, degrees, is a
+/// satellite lost and found elsewhere, so the trail breaks there.
+pub const TRAIL_GAP_DEG: f64 = 20.0;
+/// Trail segments are drawn through points at most this far apart,
+/// degrees, so they curve with the sky map's projection.
+const TRAIL_STEP_DEG: f64 = 2.0;
+
+/// Signed change from azimuth `from` to `to` the short way round,
+/// -180 to 180 degrees: 359° to 1° is +2°, not -358°.
+pub fn azimuth_delta(from: f64, to: f64) -> f64 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Splits a satellite's `[azimuth, elevation]` samples into the runs to
+/// draw as lines, filling each step in at `TRAIL_STEP_DEG` along the short
+/// way round so a pass through north stays a short arc instead of a line
+/// across the map. Azimuths come out within 0–360°.
+pub fn trail_segments(samples: &[[f64; 2]]) -> Vec<Vec<[f64; 2]>> {
+    let mut segments: Vec<Vec<[f64; 2]>> = Vec::new();
+    let mut previous: Option<[f64; 2]> = None;
+    for &[azimuth, elevation] in samples {
+        if !(azimuth.is_finite() && elevation.is_finite()) {
+            previous = None;
+            continue;
+        }
+        let point = [azimuth.rem_euclid(360.0), elevation];
+        let Some([from_az, from_el]) = previous else {
+            segments.push(vec![point]);
+            previous = Some(point);
+            continue;
+        };
+        let d_az = azimuth_delta(from_az, point[0]);
+        let d_el = point[1] - from_el;
+        if d_az.abs().max(d_el.abs()) > TRAIL_GAP_DEG {
+            segments.push(vec![point]);
+        } else {
+            let steps = (d_az.abs().max(d_el.abs()) / TRAIL_STEP_DEG).ceil().max(1.0);
+            let segment = segments.last_mut().expect("started above");
+            for step in 1..=steps as usize {
+                let t = step as f64 / steps;
+                segment.push([(from_az + d_az * t).rem_euclid(360.0), from_el + d_el * t]);
+            }
+        }
+        previous = Some(point);
+    }
+    segments.retain(|segment| segment.len() > 1);
+    segments
+}
+
+fn known_names() -> &'static HashMap<(Constellation, u32), &'static str> {
+    static BY_PRN: OnceLock<HashMap<(Constellation, u32), &'static str>> = OnceLock::new();
+    BY_PRN.get_or_init(|| NAMES.lines().filter_map(parse_name).collect())
+}
+
+/// One `<system> <prn> <label>` line of the table.
+fn parse_name(line: &'static str) -> Option<((Constellation, u32), &'static str)> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return None;
+    }
+    let mut parts = line.splitn(3, ' ');
+    let constellation = match parts.next()? {
+        "GPS" => Constellation::Gps,
+        "GLONASS" => Constellation::Glonass,
+        _ => return None,
+    };
+    let prn = parts.next()?.parse().ok()?;
+    Some(((constellation, prn), parts.next()?.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snr_histogram_bins_and_averages() {
+        let histogram = SnrHistogram::new([0, 12, 14, 38, 41, 44, 45]).unwrap();
+        assert_eq!(histogram.counts, [0, 0, 2, 0, 0, 0, 0, 1, 2, 1]);
+        assert_eq!(histogram.median, 39.5);
+        assert!((histogram.mean - 32.333).abs() < 1e-3);
+        assert_eq!(SnrHistogram::new([52]).unwrap().counts.len(), 11);
+        assert_eq!(SnrHistogram::new([0, 0]), None);
+    }
+
+    #[test]
+    fn known_names_table() {
+        let entries = NAMES.lines().filter(|l| !l.trim().is_empty() && !l.starts_with('#'));
+        assert_eq!(entries.count(), known_names().len(), "every table line must parse");
+
+        let sat = |constellation, id: &str| Satellite {
+            id: id.to_string(),
+            constellation,
+            ..Default::default()
+        };
+        assert_eq!(sat(Constellation::Gps, "05").known_name(), Some("SVN 50, block IIR-M"));
+        assert_eq!(sat(Constellation::Glonass, "65").known_name(), Some("Slot 1, plane 1"));
+        assert_eq!(sat(Constellation::Galileo, "5").known_name(), None);
+    }
+
+    #[test]
+    fn misaligned_gsv_fields_are_implausible() {
+        let aligned = crate::parser::parse_gsv("$GPGSV,1,1,02,12,45,270,38,07,10,090,12*00");
+        assert!(aligned.unwrap().iter().all(|s| s.implausible().is_none()));
+
+        // Elevation missing: azimuth read as elevation, SNR as azimuth
+        let shifted = crate::parser::parse_gsv("$GPGSV,1,1,01,12,270,38,,*00").unwrap();
+        assert_eq!(shifted[0].implausible().as_deref(), Some("Implausible elevation 270°"));
+        // A field too many: the second block starts one early
+        let shifted = crate::parser::parse_gsv("$GPGSV,1,1,02,12,45,45,270,38,07,10,090,12*00");
+        assert_eq!(shifted.unwrap()[1].implausible().as_deref(), Some("Implausible SNR 90 dB-Hz"));
+        let sat = Satellite {
+            longitude: 400.0,
+            strength: 70,
+            ..Default::default()
+        };
+        assert_eq!(
+            sat.implausible().as_deref(),
+            Some("Implausible azimuth 400°, SNR 70 dB-Hz")
+        );
+        let below = Satellite {
+            latitude: -3.0,
+            ..Default::default()
+        };
+        assert!(below.implausible().is_some());
+    }
+
+    #[test]
+    fn classifies_unattributed_prns_by_range() {
+        let classify = |talker, prn| Constellation::from_talker(talker).refine_by_prn(prn);
+        assert_eq!(classify("GN", "07"), Constellation::Gps);
+        assert_eq!(classify("GN", "46"), Constellation::Sbas);
+        assert_eq!(classify("GN", "131"), Constellation::Sbas);
+        assert_eq!(classify("GN", "72"), Constellation::Glonass);
+        assert_eq!(classify("GN", "195"), Constellation::Qzss);
+        assert_eq!(classify("GN", "214"), Constellation::BeiDou);
+        assert_eq!(classify("GN", "405"), Constellation::BeiDou);
+        assert_eq!(classify("GN", "311"), Constellation::Galileo);
+        assert_eq!(classify("GN", "500"), Constellation::Unknown);
+        // GP carries SBAS and QZSS too; other talkers keep their PRNs
+        assert_eq!(classify("GP", "48"), Constellation::Sbas);
+        assert_eq!(classify("GP", "193"), Constellation::Qzss);
+        assert_eq!(classify("GP", "12"), Constellation::Gps);
+        assert_eq!(classify("GA", "12"), Constellation::Galileo);
+        assert_eq!(classify("GB", "214"), Constellation::BeiDou);
+        assert_eq!(classify("GL", "x"), Constellation::Glonass);
+    }
+
+    #[test]
+    fn trails_pass_through_north_the_short_way() {
+        assert_eq!(azimuth_delta(359.0, 1.0), 2.0);
+        assert_eq!(azimuth_delta(1.0, 359.0), -2.0);
+        assert_eq!(azimuth_delta(90.0, 270.0), -180.0);
+
+        let sweep = [[355.0, 30.0], [359.0, 31.0], [1.0, 32.0], [5.0, 33.0]];
+        let segments = trail_segments(&sweep);
+        assert_eq!(segments.len(), 1);
+        let trail = &segments[0];
+        assert_eq!(trail.first(), Some(&[355.0, 30.0]));
+        assert_eq!(trail.last(), Some(&[5.0, 33.0]));
+        // Every point stays near north; none swings round through south
+        for pair in trail.windows(2) {
+            assert!(azimuth_delta(pair[0][0], pair[1][0]).abs() <= TRAIL_STEP_DEG);
+            assert!(pair[1][0] >= 355.0 || pair[1][0] <= 5.0, "{:?}", pair[1]);
+        }
+        assert!(trail.iter().all(|p| (0.0..360.0).contains(&p[0])));
+
+        // A jump is a break, and a lone sample draws nothing
+        let jumpy = [[10.0, 40.0], [12.0, 40.0], [200.0, 10.0], [f64::NAN, 5.0], [50.0, 5.0]];
+        let segments = trail_segments(&jumpy);
+        assert_eq!(segments, [vec![[10.0, 40.0], [12.0, 40.0]]]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Per-satellite CSV time series for antenna and multipath studies: one
+//! row per satellite per GSV cycle, or per GSV cycle after each fix the
+//! decimation keeps, one file per reading session.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::export::Decimator;
+use crate::logfile::{self, LogFile};
+use crate::satellite::Satellite;
+
+const HEADER: &str = "timestamp,prn,constellation,azimuth,elevation,snr";
+
+pub struct SatelliteLog {
+    pub path: PathBuf,
+    file: LogFile,
+    pub rows: u64,
+    decimator: Decimator,
+    // Whether the last fix's cycle is kept; `None` before the first fix,
+    // when each GSV cycle is decided on its own
+    fix_kept: Option<bool>,
+    synced: Instant,
+}
+
+impl SatelliteLog {
+    /// Creates `sats_<label>_<unix time>.csv` in `dir` and writes the header.
+    pub fn create(dir: &Path, label: &str, now: f64, decimator: Decimator) -> Result<Self, String> {
+        let label = logfile::file_name_part(label);
+        let path = dir.join(format!("sats_{label}_{}.csv", now as u64));
+        let file = LogFile::create(path.clone())?;
+        file.append(format!("{HEADER}\n"))?;
+        Ok(Self {
+            path,
+            file,
+            rows: 0,
+            decimator,
+            fix_kept: None,
+            synced: Instant::now(),
+        })
+    }
+
+    /// Starts the cycle of a fix at UTC `utc`, as its GGA gives it; the
+    /// satellites appended until the next are written if it is kept.
+    pub fn fix(&mut self, time: f64, utc: Option<&str>) {
+        self.fix_kept = Some(self.decimator.keep_fix(time, utc));
+    }
+
+    /// Appends one row per satellite, flushing so the file can be followed.
+    /// The rows are only formatted here; the writing is done off the
+    /// caller's thread.
+    pub fn append(&mut self, time: f64, satellites: &[Satellite]) -> Result<(), String> {
+        let keep = match self.fix_kept {
+            Some(keep) => keep,
+            None => self.decimator.keep(time),
+        };
+        if !keep {
+            return Ok(());
+        }
+        let rows: String = satellites.iter().map(|sat| row(time, sat) + "\n").collect();
+        self.file.append(rows)?;
+        self.file.flush()?;
+        self.rows += satellites.len() as u64;
+        Ok(())
+    }
+
+    /// Syncs the rows written so far to disk once `interval` has passed
+    /// since the last sync.
+    pub fn sync_if_due(&mut self, interval: Duration) -> Result<(), String> {
+        if self.synced.elapsed() < interval {
+            return Ok(());
+        }
+        self.synced = Instant::now();
+        self.file.sync()
+    }
+}
+
+fn row(time: f64, sat: &Satellite) -> String {
+    format!(
+        "{time:.3},{},{},{:.0},{:.0},{}",
+        sat.id,
+        sat.constellation.name(),
+        sat.longitude,
+        sat.latitude,
+        sat.strength
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{Decimation, DecimationMode};
+    use crate::parser;
+
+    #[test]
+    fn writes_header_and_rows() {
+        let sats = parser::parse_gsv("$GPGSV,1,1,01,12,45,270,38*4A").unwrap();
+        let dir = std::env::temp_dir();
+        let per_second = Decimation {
+            mode: DecimationMode::PerSecond,
+            ..Default::default()
+        };
+        let decimator = per_second.decimator();
+        let mut log = SatelliteLog::create(&dir, "test", 1_700_000_000.0, decimator).unwrap();
+        log.append(1_700_000_000.5, &sats).unwrap();
+        log.append(1_700_000_000.9, &sats).unwrap();
+        // From the first fix on, whole fix cycles: the GPS and combined
+        // GGA of the same time are one
+        log.fix(1_700_000_001.0, Some("123519.00"));
+        log.fix(1_700_000_001.1, Some("123519.00"));
+        log.append(1_700_000_001.2, &sats).unwrap();
+        log.fix(1_700_000_001.5, Some("123519.50"));
+        log.append(1_700_000_001.6, &sats).unwrap();
+        log.file.wait();
+        let text = std::fs::read_to_string(&log.path).unwrap();
+        let _ = std::fs::remove_file(&log.path);
+
+        let rows = ["1700000000.500", "1700000001.200"].map(|t| format!("{t},12,GPS,270,45,38\n"));
+        assert_eq!(text, format!("{HEADER}\n{}", rows.concat()));
+    }
+}
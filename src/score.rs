@@ -0,0 +1,192 @@
+//! One 0–100 signal quality score, for users who would rather watch a
+//! single number than read HDOP and SNR. Four components each rate 0 to 1
+//! and the score is their weighted average:
+//!
+//! - satellites used, rising linearly to full at `FULL_SATELLITES`
+//! - mean SNR of the used satellites, from `SNR_FLOOR` to `SNR_FULL` dB-Hz
+//! - HDOP, full at `HDOP_BEST` or better and nothing at `HDOP_WORST`
+//! - fix type, the GGA quality's precision rank against RTK fixed
+//!
+//! The weights are relative, so only their proportions matter. With no
+//! fix, HDOP rates nothing whatever the receiver reports.
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::GgaFix;
+use crate::quality::quality_rank;
+use crate::satellite::Satellite;
+
+pub const FULL_SATELLITES: f32 = 12.0;
+pub const SNR_FLOOR: f32 = 20.0;
+pub const SNR_FULL: f32 = 45.0;
+pub const HDOP_BEST: f32 = 1.0;
+pub const HDOP_WORST: f32 = 10.0;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreWeights {
+    pub satellites: f32,
+    pub snr: f32,
+    pub hdop: f32,
+    pub fix: f32,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            satellites: 1.0,
+            snr: 1.0,
+            hdop: 1.0,
+            fix: 1.0,
+        }
+    }
+}
+
+pub struct ScoreComponent {
+    /// What was rated, e.g. "HDOP 1.2"
+    pub value: String,
+    /// 0 to 1
+    pub rating: f32,
+    pub weight: f32,
+}
+
+pub struct Score {
+    pub components: [ScoreComponent; 4],
+    pub total: f32,
+}
+
+impl ScoreComponent {
+    /// Points this component adds to the total out of 100.
+    pub fn points(&self, total_weight: f32) -> f32 {
+        if total_weight > 0.0 {
+            100.0 * self.rating * self.weight / total_weight
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Score {
+    fn total_weight(&self) -> f32 {
+        self.components.iter().map(|c| c.weight).sum()
+    }
+
+    /// A line per component, e.g. "HDOP 1.2: 25 of 25 points".
+    pub fn breakdown(&self) -> String {
+        let total_weight = self.total_weight();
+        self.components
+            .iter()
+            .map(|c| {
+                let most = if total_weight > 0.0 { 100.0 * c.weight / total_weight } else { 0.0 };
+                format!("{}: {:.0} of {most:.0} points", c.value, c.points(total_weight))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Rates `fix` and the satellites in view. When the receiver doesn't
+/// mark which satellites are used, the mean SNR is over all that are
+/// tracked.
+pub fn score(weights: &ScoreWeights, fix: &GgaFix, satellites: &[Satellite]) -> Score {
+    let tracked: Vec<f32> = satellites
+        .iter()
+        .filter(|s| s.strength > 0)
+        .map(|s| f32::from(s.strength))
+        .collect();
+    let used: Vec<f32> = satellites
+        .iter()
+        .filter(|s| s.used_in_fix && s.strength > 0)
+        .map(|s| f32::from(s.strength))
+        .collect();
+    let snrs = if satellites.iter().any(|s| s.used_in_fix) { used } else { tracked };
+    let mean_snr = (!snrs.is_empty()).then(|| snrs.iter().sum::<f32>() / snrs.len() as f32);
+    let hdop = fix.hdop.filter(|_| fix.has_position()).map(|h| h as f32);
+    let ramp = |value: f32, zero: f32, full: f32| ((value - zero) / (full - zero)).clamp(0.0, 1.0);
+
+    let components = [
+        ScoreComponent {
+            value: format!("{} satellites used", fix.satellites_used),
+            rating: ramp(f32::from(fix.satellites_used), 0.0, FULL_SATELLITES),
+            weight: weights.satellites,
+        },
+        ScoreComponent {
+            value: match mean_snr {
+                Some(snr) => format!("Mean SNR {snr:.0} dB-Hz"),
+                None => "No SNR".to_string(),
+            },
+            rating: mean_snr.map_or(0.0, |snr| ramp(snr, SNR_FLOOR, SNR_FULL)),
+            weight: weights.snr,
+        },
+        ScoreComponent {
+            value: match hdop {
+                Some(hdop) => format!("HDOP {hdop:.1}"),
+                None => "No HDOP".to_string(),
+            },
+            rating: hdop.map_or(0.0, |h| ramp(h, HDOP_WORST, HDOP_BEST)),
+            weight: weights.hdop,
+        },
+        ScoreComponent {
+            value: fix.quality_name().to_string(),
+            rating: f32::from(quality_rank(fix.quality)) / f32::from(quality_rank(4)),
+            weight: weights.fix,
+        },
+    ];
+    let mut score = Score {
+        components,
+        total: 0.0,
+    };
+    let total_weight = score.total_weight();
+    score.total = score.components.iter().map(|c| c.points(total_weight)).sum();
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn satellite(strength: u8, used_in_fix: bool) -> Satellite {
+        Satellite {
+            strength,
+            used_in_fix,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn weighs_the_components() {
+        let fix = GgaFix {
+            latitude: 48.1,
+            longitude: 11.5,
+            quality: 1,
+            satellites_used: 3,
+            hdop: Some(1.0),
+            ..Default::default()
+        };
+        // The unused 45 dB-Hz satellite is left out of the mean
+        let satellites = [satellite(30, true), satellite(30, true), satellite(45, false)];
+        let result = score(&ScoreWeights::default(), &fix, &satellites);
+        let ratings = result.components.each_ref().map(|c| c.rating);
+        assert_eq!(ratings, [0.25, 0.4, 1.0, 2.0 / 6.0]);
+        assert!((result.total - 49.583).abs() < 0.01);
+        assert!(result.breakdown().starts_with("3 satellites used: 6 of 25 points\n"));
+
+        // Only HDOP counts
+        let weights = ScoreWeights {
+            satellites: 0.0,
+            snr: 0.0,
+            fix: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(score(&weights, &fix, &satellites).total, 100.0);
+
+        // No fix, no flags: HDOP is ignored and all tracked satellites count
+        let none = GgaFix {
+            hdop: Some(1.0),
+            ..Default::default()
+        };
+        let result = score(&ScoreWeights::default(), &none, &[satellite(45, false)]);
+        assert_eq!(result.total, 25.0);
+        assert_eq!(score(&ScoreWeights { hdop: 0.0, ..weights }, &fix, &[]).total, 0.0);
+    }
+}
@@ -0,0 +1,144 @@
+//! The bounded, timestamped sample buffer behind the graphs: each push
+//! drops the oldest samples past the cap, and the history can also be
+//! cut to a time span, so every graph is bounded the same way. Clones
+//! share the samples, so the UI's per-frame copies cost nothing until the
+//! original is next written to.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeSeries<T = f64> {
+    /// Samples kept at most
+    cap: usize,
+    /// Unix time and value, oldest first
+    samples: Arc<VecDeque<(f64, T)>>,
+}
+
+impl<T> TimeSeries<T> {
+    pub fn new(cap: usize) -> Self {
+        Self {
+            cap,
+            samples: Arc::new(VecDeque::new()),
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(f64, T)> + '_ {
+        self.samples.iter()
+    }
+
+    /// The samples from `time` on.
+    pub fn since(&self, time: f64) -> impl Iterator<Item = &(f64, T)> + '_ {
+        self.iter().filter(move |(t, _)| *t >= time)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn last(&self) -> Option<&(f64, T)> {
+        self.samples.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+impl<T: Clone> TimeSeries<T> {
+    /// Appends a sample, dropping the oldest once past the cap.
+    pub fn push(&mut self, time: f64, value: T) {
+        Arc::make_mut(&mut self.samples).push_back((time, value));
+        self.trim();
+    }
+
+    /// Changes the cap, dropping the oldest samples that no longer fit.
+    pub fn set_cap(&mut self, cap: usize) {
+        self.cap = cap;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        let excess = self.samples.len().saturating_sub(self.cap);
+        if excess > 0 {
+            Arc::make_mut(&mut self.samples).drain(..excess);
+        }
+    }
+
+    /// Drops the samples older than `time`.
+    pub fn trim_before(&mut self, time: f64) {
+        let excess = self.samples.iter().take_while(|(t, _)| *t < time).count();
+        if excess > 0 {
+            Arc::make_mut(&mut self.samples).drain(..excess);
+        }
+    }
+
+    /// Keeps only the `span` seconds behind the newest sample.
+    pub fn trim_to_span(&mut self, span: f64) {
+        if let Some(newest) = self.last().map(|(t, _)| *t) {
+            self.trim_before(newest - span);
+        }
+    }
+}
+
+impl<T: Copy + Into<f64>> TimeSeries<T> {
+    pub fn min(&self) -> Option<f64> {
+        self.values().map(|v| (*v).into()).reduce(f64::min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.values().map(|v| (*v).into()).reduce(f64::max)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        let sum: f64 = self.values().map(|v| (*v).into()).sum();
+        (!self.is_empty()).then(|| sum / self.len() as f64)
+    }
+}
+
+/// Unbounded by count, for series cut to a time span instead.
+impl<T> Default for TimeSeries<T> {
+    fn default() -> Self {
+        Self::new(usize::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded_by_cap_and_span() {
+        let mut series = TimeSeries::new(3);
+        for (time, snr) in [(1.0, 30u8), (2.0, 42), (3.0, 36), (4.0, 39)] {
+            series.push(time, snr);
+        }
+        assert_eq!(series.values().copied().collect::<Vec<_>>(), [42, 36, 39]);
+        assert_eq!(series.min(), Some(36.0));
+        assert_eq!(series.max(), Some(42.0));
+        assert_eq!(series.mean(), Some(39.0));
+        assert_eq!(series.since(3.0).count(), 2);
+
+        series.set_cap(2);
+        assert_eq!(series.iter().next(), Some(&(3.0, 36)));
+        series.trim_to_span(0.5);
+        assert_eq!(series.len(), 1);
+        series.trim_before(10.0);
+        assert!(series.is_empty());
+        assert_eq!(series.mean(), None);
+
+        let mut unbounded = TimeSeries::default();
+        (0..1000).for_each(|n| unbounded.push(f64::from(n), n));
+        assert_eq!(unbounded.len(), 1000);
+
+        // A copy keeps its samples while the original moves on
+        let copy = unbounded.clone();
+        unbounded.trim_before(500.0);
+        assert_eq!((copy.len(), unbounded.len()), (1000, 500));
+        assert_eq!(unbounded.iter().next(), Some(&(500.0, 500)));
+    }
+}
@@ -0,0 +1,213 @@
+//! JSON session files: the settings, log, fix history and satellites of
+//! every source, so a capture can be reviewed offline.
+
+use std::collections::HashMap;
+use std::fs::{self, File, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::records::SessionRecords;
+use crate::satellite::Satellite;
+use crate::settings::Settings;
+use crate::{unix_now, FixRecord, LogEntry, SourceState, Waypoint};
+
+/// Bump when the layout changes incompatibly; older files must keep loading.
+pub const SESSION_VERSION: u32 = 1;
+/// Fixes per source kept in the crash recovery file, bounding its size.
+pub const RECOVERY_FIXES: usize = 2_000;
+
+/// Start of every instance's recovery file name, which the process ID
+/// completes so two instances don't overwrite each other's.
+const RECOVERY_PREFIX: &str = "rust_nmea_recovery";
+
+/// Where this instance mirrors its live session until a clean exit
+/// removes it.
+pub fn recovery_path() -> PathBuf {
+    std::env::temp_dir().join(format!("{RECOVERY_PREFIX}_{}.json", std::process::id()))
+}
+
+/// Held while an instance runs, so others can tell its recovery file from
+/// one a crash left behind: the OS releases the lock however it exits.
+pub struct RecoveryLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl RecoveryLock {
+    pub fn acquire() -> Option<Self> {
+        let path = recovery_path().with_extension("lock");
+        let file = File::create(&path).ok()?;
+        file.try_lock().ok()?;
+        Some(Self { path, _file: file })
+    }
+}
+
+impl Drop for RecoveryLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The newest recovery file whose instance is no longer running.
+pub fn orphaned_recovery() -> Option<PathBuf> {
+    let own = recovery_path();
+    let running = |path: &Path| {
+        File::open(path.with_extension("lock"))
+            .is_ok_and(|lock| matches!(lock.try_lock(), Err(TryLockError::WouldBlock)))
+    };
+    fs::read_dir(std::env::temp_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            name.starts_with(RECOVERY_PREFIX) && name.ends_with(".json")
+        })
+        .filter(|path| *path != own && !running(path))
+        .max_by_key(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+}
+
+/// Removes a recovery file no longer on offer, with its instance's lock.
+pub fn remove_recovery(path: &Path) {
+    let _ = fs::remove_file(path);
+    let _ = fs::remove_file(path.with_extension("lock"));
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    pub exported_at: f64,
+    pub settings: Settings,
+    pub sources: Vec<SourceSession>,
+    #[serde(default)]
+    pub waypoints: Vec<Waypoint>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SourceSession {
+    pub label: String,
+    pub port: Option<String>,
+    pub baud_rate: u32,
+    pub log: Vec<LogEntry>,
+    pub fixes: Vec<FixRecord>,
+    pub satellites: Vec<Satellite>,
+    // Satellites among those missing from the latest cycles but held over,
+    // with how many cycles each has been missed for
+    #[serde(default)]
+    pub held_satellites: HashMap<String, u32>,
+    #[serde(default)]
+    pub records: SessionRecords,
+    // User notes, including those the log cap has dropped
+    #[serde(default)]
+    pub notes: Vec<LogEntry>,
+}
+
+impl SourceSession {
+    pub fn capture(source: &SourceState) -> Self {
+        Self {
+            label: source.label.clone(),
+            port: source.selected_port.clone(),
+            baud_rate: source.baud_rate,
+            log: source.nmea_log.to_vec(),
+            fixes: source.fix_history.iter().cloned().collect(),
+            satellites: source.satellites.clone(),
+            held_satellites: source.held_satellites.clone(),
+            records: source.records.clone(),
+            notes: source.notes.clone(),
+        }
+    }
+
+    /// Keeps only the newest `max_fixes` fixes.
+    pub fn truncated(mut self, max_fixes: usize) -> Self {
+        let excess = self.fixes.len().saturating_sub(max_fixes);
+        self.fixes.drain(..excess);
+        self
+    }
+
+    /// Rebuilds a stopped source holding the recorded data.
+    pub fn restore(self) -> SourceState {
+        let mut source = SourceState::new(&self.label);
+        source.selected_port = self.port;
+        source.baud_rate = self.baud_rate;
+        source.nmea_log = Arc::new(self.log);
+        source.fix = self.fixes.last().map(|r| r.fix.clone());
+        source.fix_history = Arc::new(self.fixes.into());
+        source.satellites = self.satellites;
+        source.held_satellites = self.held_satellites;
+        source.records = self.records;
+        source.notes = self.notes;
+        source
+    }
+}
+
+impl Session {
+    pub fn new(settings: Settings, sources: Vec<SourceSession>, waypoints: Vec<Waypoint>) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            exported_at: unix_now(),
+            settings,
+            sources,
+            waypoints,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Writes the recovery file compactly, via a temporary file synced to
+    /// disk first so a crash or power cut mid-write leaves the previous
+    /// copy intact.
+    pub fn save_recovery(&self) -> Result<(), String> {
+        let path = recovery_path();
+        let partial = path.with_extension("json.partial");
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::File::create(&partial)
+            .and_then(|mut file| {
+                file.write_all(json.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|()| fs::rename(&partial, &path))
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let session: Session =
+            serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+
+        if session.version > SESSION_VERSION {
+            return Err(format!(
+                "{}: session version {} is newer than supported version {SESSION_VERSION}",
+                path.display(),
+                session.version
+            ));
+        }
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_recovery_files_left_by_a_crash_are_offered() {
+        let dir = std::env::temp_dir();
+        let crashed = dir.join(format!("{RECOVERY_PREFIX}_test_crashed.json"));
+        let running = dir.join(format!("{RECOVERY_PREFIX}_test_running.json"));
+        fs::write(&crashed, "{}").unwrap();
+        let lock = File::create(running.with_extension("lock")).unwrap();
+        lock.try_lock().unwrap();
+        fs::write(&running, "{}").unwrap();
+
+        assert_eq!(orphaned_recovery(), Some(crashed.clone()));
+        remove_recovery(&crashed);
+        drop(lock);
+        remove_recovery(&running);
+    }
+}
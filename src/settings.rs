@@ -0,0 +1,693 @@
+//! User preferences persisted between runs through eframe's storage.
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alarms::{AlarmThresholds, NotifyPolicy};
+use crate::autostart::AutoStart;
+use crate::buffers::BufferCaps;
+use crate::events::EventTriggers;
+use crate::export::{Decimation, LogClock, LogFormat};
+use crate::geo::{BearingUnit, DistanceMode};
+use crate::palette::Palette;
+use crate::parser::{GsvMapping, FEET_TO_METERS};
+use crate::pipeline::SentenceFilter;
+use crate::profiles::Profile;
+use crate::repeater::RepeaterConfig;
+use crate::score::ScoreWeights;
+use crate::survey::SurveyConfig;
+
+/// A sector of the horizon with its own minimum elevation, e.g. a
+/// building blocking the view between two azimuths.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MaskSector {
+    pub from_azimuth: f64,
+    pub to_azimuth: f64,
+    pub min_elevation: f64,
+}
+
+impl MaskSector {
+    /// Whether `azimuth` falls in the sector, which may wrap through north.
+    fn contains(&self, azimuth: f64) -> bool {
+        let az = azimuth.rem_euclid(360.0);
+        let (from, to) = (self.from_azimuth.rem_euclid(360.0), self.to_azimuth.rem_euclid(360.0));
+        if from <= to {
+            (from..=to).contains(&az)
+        } else {
+            az >= from || az <= to
+        }
+    }
+}
+
+/// Local horizon obstructions shaded on the sky map.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ElevationMask {
+    pub min_elevation: f64,
+    pub sectors: Vec<MaskSector>,
+}
+
+impl ElevationMask {
+    /// Lowest unobstructed elevation at `azimuth`.
+    pub fn limit_at(&self, azimuth: f64) -> f64 {
+        self.sectors
+            .iter()
+            .filter(|s| s.contains(azimuth))
+            .map(|s| s.min_elevation)
+            .fold(self.min_elevation, f64::max)
+    }
+
+    pub fn is_masked(&self, azimuth: f64, elevation: f64) -> bool {
+        elevation < self.limit_at(azimuth)
+    }
+}
+
+/// Serial handshaking, for adapters that drop or garble data at high
+/// baud rates without it.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowControl {
+    #[default]
+    None,
+    Hardware,
+    Software,
+}
+
+impl FlowControl {
+    pub const ALL: [FlowControl; 3] = [
+        FlowControl::None,
+        FlowControl::Hardware,
+        FlowControl::Software,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlowControl::None => "None",
+            FlowControl::Hardware => "Hardware (RTS/CTS)",
+            FlowControl::Software => "Software (XON/XOFF)",
+        }
+    }
+
+    pub fn to_serialport(self) -> serialport::FlowControl {
+        match self {
+            FlowControl::None => serialport::FlowControl::None,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+            FlowControl::Software => serialport::FlowControl::Software,
+        }
+    }
+}
+
+/// How a side-by-side replay lines its captures up.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayAlign {
+    #[default]
+    Elapsed,
+    Timestamps,
+}
+
+impl ReplayAlign {
+    pub const ALL: [ReplayAlign; 2] = [ReplayAlign::Elapsed, ReplayAlign::Timestamps];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReplayAlign::Elapsed => "From the start",
+            ReplayAlign::Timestamps => "By timestamps",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ReplayAlign::Elapsed => {
+                "Start every file at once, for captures taken at different times"
+            }
+            ReplayAlign::Timestamps => {
+                "Skip ahead in the files that started earlier so the same UTC moment plays \
+                 together, for captures taken side by side"
+            }
+        }
+    }
+}
+
+/// How elevation maps to distance from the sky map centre, as a fraction
+/// of the horizon radius.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkyProjection {
+    #[default]
+    Linear,
+    Stereographic,
+    EqualArea,
+}
+
+impl SkyProjection {
+    pub const ALL: [SkyProjection; 3] = [
+        SkyProjection::Linear,
+        SkyProjection::Stereographic,
+        SkyProjection::EqualArea,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SkyProjection::Linear => "Linear",
+            SkyProjection::Stereographic => "Stereographic",
+            SkyProjection::EqualArea => "Equal-area",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            SkyProjection::Linear => "Radius proportional to zenith angle, (90 − el) / 90",
+            SkyProjection::Stereographic => {
+                "Preserves shapes; spreads out satellites near the horizon"
+            }
+            SkyProjection::EqualArea => "Equal sky areas cover equal map areas",
+        }
+    }
+
+    /// Distance from the centre for `elevation` degrees: 0 at the zenith,
+    /// 1 on the horizon.
+    pub fn radius(&self, elevation: f64) -> f64 {
+        let zenith = (90.0 - elevation.clamp(0.0, 90.0)).to_radians();
+        match self {
+            SkyProjection::Linear => zenith / std::f64::consts::FRAC_PI_2,
+            SkyProjection::Stereographic => (zenith / 2.0).tan(),
+            SkyProjection::EqualArea => (zenith / 2.0).sin() * std::f64::consts::SQRT_2,
+        }
+    }
+
+    /// Inverse of [`radius`](Self::radius): the elevation drawn at
+    /// `radius`, or `None` beyond the horizon.
+    pub fn elevation(&self, radius: f64) -> Option<f64> {
+        if !(0.0..=1.0 + 1e-9).contains(&radius) {
+            return None;
+        }
+        let zenith = match self {
+            SkyProjection::Linear => radius * std::f64::consts::FRAC_PI_2,
+            SkyProjection::Stereographic => 2.0 * radius.atan(),
+            SkyProjection::EqualArea => {
+                2.0 * (radius / std::f64::consts::SQRT_2).min(1.0).asin()
+            }
+        };
+        Some((90.0 - zenith.to_degrees()).clamp(0.0, 90.0))
+    }
+}
+
+/// Which view the floating satellite panel shows.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkyPanelView {
+    /// Per-satellite C/N0 bars in the style of u-center
+    #[default]
+    Bars,
+    Polar,
+}
+
+/// Window corner the floating satellite panel is pinned to.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PanelCorner {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl PanelCorner {
+    pub const ALL: [PanelCorner; 4] = [
+        PanelCorner::TopLeft,
+        PanelCorner::TopRight,
+        PanelCorner::BottomLeft,
+        PanelCorner::BottomRight,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PanelCorner::TopLeft => "Top left",
+            PanelCorner::TopRight => "Top right",
+            PanelCorner::BottomLeft => "Bottom left",
+            PanelCorner::BottomRight => "Bottom right",
+        }
+    }
+}
+
+/// Unit signal strengths are shown in.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnrUnit {
+    #[default]
+    DbHz,
+    /// Percentage of the full scale, capped at 100
+    Percent,
+}
+
+impl SnrUnit {
+    pub const ALL: [SnrUnit; 2] = [SnrUnit::DbHz, SnrUnit::Percent];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SnrUnit::DbHz => "dB-Hz",
+            SnrUnit::Percent => "%",
+        }
+    }
+}
+
+/// How signal strengths are presented. They are kept in dB-Hz either way.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnrDisplay {
+    pub unit: SnrUnit,
+    /// dB-Hz shown as 100%
+    pub full_scale: u8,
+}
+
+impl Default for SnrDisplay {
+    fn default() -> Self {
+        Self {
+            unit: SnrUnit::DbHz,
+            full_scale: 50,
+        }
+    }
+}
+
+impl SnrDisplay {
+    /// `db_hz` in the display unit, for charts.
+    pub fn value(&self, db_hz: f64) -> f64 {
+        match self.unit {
+            SnrUnit::DbHz => db_hz,
+            SnrUnit::Percent => (db_hz * 100.0 / f64::from(self.full_scale.max(1))).min(100.0),
+        }
+    }
+
+    /// A strength for tables: "38", or "76%".
+    pub fn number(&self, db_hz: f64) -> String {
+        match self.unit {
+            SnrUnit::DbHz => format!("{db_hz:.0}"),
+            SnrUnit::Percent => format!("{:.0}%", self.value(db_hz)),
+        }
+    }
+
+    /// A strength for text: "38 dB-Hz", or "76%".
+    pub fn text(&self, db_hz: f64) -> String {
+        match self.unit {
+            SnrUnit::DbHz => format!("{db_hz:.0} dB-Hz"),
+            SnrUnit::Percent => self.number(db_hz),
+        }
+    }
+
+    /// e.g. "SNR (dB-Hz)", for chart axes.
+    pub fn axis_label(&self) -> String {
+        format!("SNR ({})", self.unit.name())
+    }
+}
+
+/// Unit altitudes are shown in. They are kept, and exported, in metres.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AltitudeUnit {
+    #[default]
+    Meters,
+    Feet,
+}
+
+impl AltitudeUnit {
+    pub const ALL: [AltitudeUnit; 2] = [AltitudeUnit::Meters, AltitudeUnit::Feet];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AltitudeUnit::Meters => "Metres",
+            AltitudeUnit::Feet => "Feet",
+        }
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            AltitudeUnit::Meters => "m",
+            AltitudeUnit::Feet => "ft",
+        }
+    }
+
+    pub fn value(&self, meters: f64) -> f64 {
+        match self {
+            AltitudeUnit::Meters => meters,
+            AltitudeUnit::Feet => meters / FEET_TO_METERS,
+        }
+    }
+
+    /// e.g. "545.4 m", or "1789.4 ft".
+    pub fn format(&self, meters: f64, decimals: usize) -> String {
+        format!("{:.decimals$} {}", self.value(meters), self.suffix())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub watchdog_timeout_secs: f32,
+    /// Serial read timeout. The reader only sees the stop flag between
+    /// reads, so this bounds how long Stop takes to release the port.
+    pub read_timeout_ms: u64,
+    /// Handshaking on serial ports, applied when a reader starts
+    pub flow_control: FlowControl,
+    /// Start reading a matching device as soon as it appears
+    pub auto_start: AutoStart,
+    /// Drop bytes ahead of the first `$` or UBX sync after connecting or
+    /// a reset, so startup noise doesn't corrupt the first sentence
+    pub skip_leading_garbage: bool,
+    /// Retry at 9600 8N1 without flow control when a port refuses the
+    /// chosen settings
+    pub open_fallback: bool,
+    /// The sentence inspector lists unknown sentences' fields by index
+    pub inspector_field_indices: bool,
+    /// Only the big fix readout is shown, for reading from a distance
+    pub big_readout: bool,
+    pub altitude_unit: AltitudeUnit,
+    /// Saved connection profiles, in the order they were first saved
+    pub profiles: Vec<Profile>,
+    pub elevation_mask: ElevationMask,
+    /// Show a moving average of SNR instead of the raw value
+    pub smooth_snr: bool,
+    /// Keep each satellite in its own row of the table, in the order they
+    /// were acquired, instead of sorting by a column
+    pub stable_satellite_rows: bool,
+    /// Receiver consistency warnings in the status bar
+    pub diagnostics: bool,
+    /// Reader thread health panel for debugging
+    pub debug_panel: bool,
+    /// Satellites below this SNR are hidden; 0 shows all
+    pub min_snr: u8,
+    /// Rotate the sky map so the current course points up
+    pub sky_heading_up: bool,
+    /// Trace each satellite's path across the sky map
+    pub sky_trails: bool,
+    /// Mirror the sky map east/west, as seen looking up at the sky
+    pub sky_flip_east_west: bool,
+    /// Ring each satellite on the sky map by how much it improves the
+    /// geometry, see [`crate::dop`]
+    pub sky_geometry: bool,
+    /// Show only the satellites used in the fix, per GSA, in the list,
+    /// sky map and signal charts
+    pub used_only: bool,
+    pub sky_projection: SkyProjection,
+    /// Latitude/longitude decimals shown and exported; values are stored
+    /// at full precision
+    pub coordinate_decimals: usize,
+    /// Keep showing the last position, marked stale, when the fix drops;
+    /// otherwise blank it and break the track
+    pub hold_last_fix: bool,
+    /// Colour the track by the fix quality of each stretch rather than by
+    /// source
+    pub track_by_quality: bool,
+    /// Re-send one source's lines to local TCP clients and/or a second
+    /// serial port; an empty port name sends to no serial port
+    pub repeater_enabled: bool,
+    pub repeater_source: usize,
+    pub repeater_tcp: bool,
+    pub repeater_tcp_port: u16,
+    pub repeater_serial_port: String,
+    pub repeater_baud: u32,
+    pub repeater_forward_invalid: bool,
+    /// Local `/status` JSON endpoint, used with the `http` feature
+    pub http_enabled: bool,
+    pub http_port: u16,
+    /// Raster map tiles behind the track plot
+    pub map_tiles: bool,
+    pub tile_url: String,
+    /// Per-satellite CSV time series, written into `satellite_csv_dir`
+    pub satellite_csv: bool,
+    pub satellite_csv_dir: String,
+    /// Every received sentence appended to a file in `autosave_dir`
+    pub autosave_log: bool,
+    pub autosave_dir: String,
+    /// How often the auto-save log and satellite CSV are synced to disk
+    /// and, no more often than every 30 s, the recovery file rewritten,
+    /// seconds
+    pub flush_secs: u32,
+    pub parsed_sentences: SentenceFilter,
+    /// Where non-standard receivers put the values in GSV blocks
+    pub gsv_mapping: GsvMapping,
+    pub sky_panel: SkyPanelView,
+    /// Whether the floating satellite panel shows, the corner it is pinned
+    /// to and its distance in from that corner, points
+    pub sky_panel_visible: bool,
+    pub sky_panel_corner: PanelCorner,
+    pub sky_panel_offset: [f32; 2],
+    /// Dashed reference line on the C/N0 bars, dB-Hz
+    pub cn0_threshold: u8,
+    /// Gridlines every 10 dB-Hz (or percent) on the Signal chart
+    pub snr_grid: bool,
+    /// Distribution of SNR under the Signal chart
+    pub snr_histogram: bool,
+    pub snr_display: SnrDisplay,
+    /// Usable-signal line on the Signal chart, dB-Hz
+    pub snr_threshold_line: bool,
+    pub snr_threshold: u8,
+    /// Draw the Signal chart's line at the minimum SNR filter instead
+    pub snr_threshold_from_filter: bool,
+    pub score_weights: ScoreWeights,
+    /// Path model for the trip distance and point-to-point readout
+    pub distance_mode: DistanceMode,
+    pub bearing_unit: BearingUnit,
+    /// `[latitude, longitude]` of the go-to target
+    pub go_to: Option<[f64; 2]>,
+    /// Group the Signal chart and summary by frequency band rather than
+    /// constellation, for multi-frequency receivers
+    pub group_by_band: bool,
+    /// SVN/slot labels next to GPS and GLONASS PRNs in the detail panel
+    pub satellite_names: bool,
+    /// Sky map marker radius, points, and label size factor; unset in
+    /// settings saved before they could be changed
+    pub sky_marker_radius: Option<f32>,
+    pub sky_label_scale: Option<f32>,
+    /// Format the GPS Stream's Save writes, and the clock its timestamps
+    /// come from
+    pub log_format: LogFormat,
+    pub log_clock: LogClock,
+    /// Cycles kept by the saved log, track export and satellite CSV
+    pub decimation: Decimation,
+    /// Lines kept in each source's NMEA log before the oldest are dropped
+    pub max_log_lines: usize,
+    /// Use `low_memory_caps` for the session buffers, log included
+    pub low_memory: bool,
+    pub low_memory_caps: BufferCaps,
+    /// Keys of satellites shown whatever their SNR, highlighted and with
+    /// SNR history kept while out of view
+    pub favorite_satellites: BTreeSet<String>,
+    /// Conditions that capture a freeze-frame event
+    pub event_triggers: EventTriggers,
+    /// Quality thresholds that raise an alarm while violated
+    pub alarm_thresholds: AlarmThresholds,
+    /// Throttling and quiet hours for the attention alarms call for
+    pub alarm_notify: NotifyPolicy,
+    /// Window and fix screening for the next survey-in
+    pub survey: SurveyConfig,
+    /// How replaying both sources' captures together lines them up
+    pub compare_align: ReplayAlign,
+    /// Seconds without a GSV cycle before the satellites are cleared,
+    /// independent of how long a fix is held
+    pub satellite_timeout_secs: f32,
+    /// Cycles and seconds a satellite missing from GSV stays listed,
+    /// dimmed, before it is removed; either running out removes it
+    pub loss_grace_cycles: u32,
+    pub loss_grace_secs: f32,
+    /// Colors of the constellations, SNR scale, fix qualities and graphs
+    pub palette: Palette,
+    /// The first-run guide was finished or skipped. Configs saved before
+    /// the guide existed count as done, their owners needing no guide.
+    #[serde(default = "saved_before_onboarding")]
+    pub onboarding_done: bool,
+}
+
+fn saved_before_onboarding() -> bool {
+    true
+}
+
+impl Settings {
+    pub fn satellite_timeout(&self) -> Duration {
+        Duration::from_secs_f32(self.satellite_timeout_secs)
+    }
+
+    /// The caps the session buffers are held to; low-memory mode never
+    /// keeps more log than the log lines setting.
+    pub fn buffer_caps(&self) -> BufferCaps {
+        if self.low_memory {
+            BufferCaps {
+                log_lines: self.low_memory_caps.log_lines.min(self.max_log_lines),
+                ..self.low_memory_caps
+            }
+        } else {
+            BufferCaps::full(self.max_log_lines)
+        }
+    }
+
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_secs(u64::from(self.flush_secs.max(1)))
+    }
+
+    /// Where the Signal chart's threshold line sits, if it is shown.
+    pub fn snr_threshold_db(&self) -> Option<u8> {
+        if !self.snr_threshold_line {
+            None
+        } else if self.snr_threshold_from_filter {
+            Some(self.min_snr)
+        } else {
+            Some(self.snr_threshold)
+        }
+    }
+
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_millis(self.read_timeout_ms)
+    }
+
+    /// What the repeater should run with, or `None` for it to be off.
+    pub fn repeater_config(&self) -> Option<RepeaterConfig> {
+        let serial_port = Some(self.repeater_serial_port.trim().to_string())
+            .filter(|p| !p.is_empty());
+        let tcp_port = self.repeater_tcp.then_some(self.repeater_tcp_port);
+        let wanted = self.repeater_enabled && (tcp_port.is_some() || serial_port.is_some());
+        wanted.then_some(RepeaterConfig {
+            tcp_port,
+            serial_port,
+            baud_rate: self.repeater_baud,
+            forward_invalid: self.repeater_forward_invalid,
+        })
+    }
+
+    /// Fills in unset sky map sizes. Points already scale with the
+    /// display's pixels per point, so the defaults hold on high-DPI ones.
+    pub fn default_sky_sizes(&mut self) {
+        self.sky_marker_radius.get_or_insert(3.0);
+        self.sky_label_scale.get_or_insert(1.0);
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            watchdog_timeout_secs: 5.0,
+            read_timeout_ms: 1000,
+            flow_control: FlowControl::None,
+            auto_start: AutoStart::default(),
+            skip_leading_garbage: true,
+            open_fallback: true,
+            inspector_field_indices: false,
+            big_readout: false,
+            altitude_unit: AltitudeUnit::Meters,
+            profiles: Vec::new(),
+            elevation_mask: ElevationMask::default(),
+            smooth_snr: false,
+            stable_satellite_rows: false,
+            diagnostics: false,
+            debug_panel: false,
+            min_snr: 0,
+            sky_heading_up: false,
+            sky_trails: false,
+            sky_flip_east_west: false,
+            sky_geometry: false,
+            used_only: false,
+            sky_projection: SkyProjection::default(),
+            coordinate_decimals: 6,
+            hold_last_fix: true,
+            track_by_quality: false,
+            repeater_enabled: false,
+            repeater_source: 0,
+            repeater_tcp: true,
+            // The usual port for NMEA over TCP
+            repeater_tcp_port: 10110,
+            repeater_serial_port: String::new(),
+            repeater_baud: 4800,
+            repeater_forward_invalid: false,
+            http_enabled: false,
+            http_port: 8080,
+            map_tiles: false,
+            tile_url: crate::tiles::DEFAULT_URL.to_string(),
+            satellite_csv: false,
+            satellite_csv_dir: ".".to_string(),
+            autosave_log: false,
+            autosave_dir: ".".to_string(),
+            flush_secs: 30,
+            parsed_sentences: SentenceFilter::default(),
+            gsv_mapping: GsvMapping::STANDARD,
+            sky_panel: SkyPanelView::default(),
+            sky_panel_visible: true,
+            sky_panel_corner: PanelCorner::default(),
+            sky_panel_offset: [10.0, 10.0],
+            cn0_threshold: 30,
+            snr_grid: true,
+            snr_histogram: false,
+            snr_display: SnrDisplay::default(),
+            snr_threshold_line: true,
+            snr_threshold: 35,
+            snr_threshold_from_filter: false,
+            score_weights: ScoreWeights::default(),
+            distance_mode: DistanceMode::default(),
+            bearing_unit: BearingUnit::default(),
+            go_to: None,
+            group_by_band: false,
+            satellite_names: false,
+            sky_marker_radius: Some(3.0),
+            sky_label_scale: Some(1.0),
+            log_format: LogFormat::Raw,
+            log_clock: LogClock::System,
+            decimation: Decimation::default(),
+            max_log_lines: crate::LOG_LEN,
+            low_memory: false,
+            low_memory_caps: BufferCaps::LOW_MEMORY,
+            favorite_satellites: BTreeSet::new(),
+            event_triggers: EventTriggers::default(),
+            alarm_thresholds: AlarmThresholds::default(),
+            alarm_notify: NotifyPolicy::default(),
+            survey: SurveyConfig::default(),
+            compare_align: ReplayAlign::default(),
+            satellite_timeout_secs: 5.0,
+            loss_grace_cycles: 2,
+            loss_grace_secs: 5.0,
+            palette: Palette::Default,
+            onboarding_done: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projections_map_zenith_and_horizon() {
+        for projection in SkyProjection::ALL {
+            assert!(projection.radius(90.0).abs() < 1e-12);
+            assert!((projection.radius(0.0) - 1.0).abs() < 1e-12);
+        }
+        assert!((SkyProjection::Linear.radius(45.0) - 0.5).abs() < 1e-12);
+        // Stereographic leaves more of the map to low satellites, equal-area less
+        assert!(SkyProjection::Stereographic.radius(45.0) < 0.5);
+        assert!(SkyProjection::EqualArea.radius(45.0) > 0.5);
+    }
+
+    #[test]
+    fn snr_shown_as_a_capped_percentage() {
+        let mut display = SnrDisplay::default();
+        assert_eq!(display.text(38.0), "38 dB-Hz");
+        assert_eq!(display.number(38.0), "38");
+        display.unit = SnrUnit::Percent;
+        assert_eq!(display.text(38.0), "76%");
+        assert_eq!(display.value(55.0), 100.0);
+        display.full_scale = 40;
+        assert_eq!(display.value(30.0), 75.0);
+    }
+
+    #[test]
+    fn only_a_new_config_shows_the_guide() {
+        assert!(!Settings::default().onboarding_done);
+        let saved: Settings = serde_json::from_str(r#"{"min_snr": 10}"#).unwrap();
+        assert!(saved.onboarding_done);
+        assert_eq!(saved.min_snr, 10);
+    }
+
+    #[test]
+    fn projection_elevation_inverts_radius() {
+        for projection in SkyProjection::ALL {
+            for elevation in [0.0, 10.0, 45.0, 80.0, 90.0] {
+                let back = projection.elevation(projection.radius(elevation)).unwrap();
+                assert!((back - elevation).abs() < 1e-9);
+            }
+            assert_eq!(projection.elevation(1.1), None);
+        }
+    }
+}
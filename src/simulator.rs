@@ -0,0 +1,256 @@
+//! Built-in receiver simulator: a deterministic stream of GGA, RMC, GSA
+//! and GSV sentences for a receiver moving in a straight line under a
+//! sky of satellites that rise and set.
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::checksum;
+
+/// Metres per degree of latitude, close enough for a simulated track.
+const METRES_PER_DEGREE: f64 = 111_320.0;
+/// Kept as a whole 1 Hz epoch so every display path sees complete cycles.
+pub const EPOCH_SECS: f64 = 1.0;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimConfig {
+    pub satellites: usize,
+    /// Standard deviation of the horizontal position noise, in metres.
+    pub noise_m: f64,
+    pub speed_mps: f64,
+    pub heading_deg: f64,
+    pub start_latitude: f64,
+    pub start_longitude: f64,
+    pub seed: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            satellites: 12,
+            noise_m: 2.0,
+            speed_mps: 5.0,
+            heading_deg: 45.0,
+            start_latitude: 40.6795,
+            start_longitude: -74.2347,
+            seed: 1,
+        }
+    }
+}
+
+/// Small xorshift generator so runs are reproducible without extra crates.
+struct Rng(u64);
+
+impl Rng {
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Approximately normal, via the sum of uniforms.
+    fn gaussian(&mut self) -> f64 {
+        (0..6).map(|_| self.next_f64()).sum::<f64>() - 3.0
+    }
+}
+
+struct SimSatellite {
+    prn: u32,
+    /// Orbit phase and rate drive the elevation through rise and set.
+    phase: f64,
+    rate: f64,
+    azimuth: f64,
+    azimuth_rate: f64,
+}
+
+pub struct Simulator {
+    config: SimConfig,
+    rng: Rng,
+    satellites: Vec<SimSatellite>,
+    elapsed: f64,
+}
+
+impl Simulator {
+    pub fn new(config: SimConfig) -> Self {
+        let mut rng = Rng(config.seed.max(1));
+        let satellites = (0..config.satellites.clamp(1, 32))
+            .map(|i| SimSatellite {
+                prn: i as u32 + 1,
+                phase: rng.next_f64() * std::f64::consts::TAU,
+                rate: 0.002 + rng.next_f64() * 0.004,
+                azimuth: rng.next_f64() * 360.0,
+                azimuth_rate: 0.05 + rng.next_f64() * 0.1,
+            })
+            .collect();
+
+        Self {
+            config,
+            rng,
+            satellites,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Produces the sentences of the next epoch and advances time.
+    pub fn next_epoch(&mut self) -> Vec<String> {
+        let t = self.elapsed;
+        self.elapsed += EPOCH_SECS;
+
+        // Sky: (prn, elevation, azimuth, snr) for satellites above the horizon
+        let mut visible = Vec::new();
+        for sat in &self.satellites {
+            let elevation = 90.0 * (sat.phase + sat.rate * t).sin();
+            // Below one degree the rounded GSV field would read as 0
+            if elevation < 1.0 {
+                continue;
+            }
+            let azimuth = (sat.azimuth + sat.azimuth_rate * t).rem_euclid(360.0);
+            let snr = (20.0 + elevation * 0.35 + self.rng.gaussian() * 2.0).clamp(0.0, 55.0);
+            visible.push((sat.prn, elevation, azimuth, snr.round() as u32));
+        }
+
+        // Position along the heading, plus noise
+        let distance = self.config.speed_mps * t;
+        let heading = self.config.heading_deg.to_radians();
+        let north = distance * heading.cos() + self.rng.gaussian() * self.config.noise_m;
+        let east = distance * heading.sin() + self.rng.gaussian() * self.config.noise_m;
+        let latitude = self.config.start_latitude + north / METRES_PER_DEGREE;
+        let longitude = self.config.start_longitude
+            + east / (METRES_PER_DEGREE * latitude.to_radians().cos());
+
+        let used: Vec<u32> = visible
+            .iter()
+            .filter(|(_, el, _, snr)| *el > 10.0 && *snr > 25)
+            .map(|(prn, ..)| *prn)
+            .take(12)
+            .collect();
+        let quality = if used.len() >= 4 { 1 } else { 0 };
+        let hdop = if used.is_empty() { 99.9 } else { 4.0 / (used.len() as f64).sqrt() };
+
+        let time = utc_time(t);
+        let (lat, ns) = nmea_coordinate(latitude, 2, 'N', 'S');
+        let (lon, ew) = nmea_coordinate(longitude, 3, 'E', 'W');
+
+        let mut sentences = vec![
+            sentence(&format!(
+                "GPGGA,{time},{lat},{ns},{lon},{ew},{quality},{:02},{hdop:.1},{:.1},M,-34.0,M,,",
+                used.len(),
+                15.0 + self.rng.gaussian() * self.config.noise_m
+            )),
+            sentence(&format!(
+                "GPRMC,{time},{},{lat},{ns},{lon},{ew},{:.1},{:.1},010124,,,A",
+                if quality > 0 { 'A' } else { 'V' },
+                self.config.speed_mps * 1.943_844,
+                self.config.heading_deg
+            )),
+        ];
+
+        let used_fields: Vec<String> = (0..12)
+            .map(|i| used.get(i).map_or(String::new(), |prn| format!("{prn:02}")))
+            .collect();
+        sentences.push(sentence(&format!(
+            "GPGSA,A,{},{},{:.1},{hdop:.1},{:.1}",
+            if quality > 0 { 3 } else { 1 },
+            used_fields.join(","),
+            hdop * 1.5,
+            hdop * 1.2
+        )));
+
+        let total = visible.len().div_ceil(4).max(1);
+        for (n, chunk) in visible.chunks(4).enumerate() {
+            let blocks: Vec<String> = chunk
+                .iter()
+                .map(|(prn, el, az, snr)| format!("{prn:02},{el:.0},{az:03.0},{snr:02}"))
+                .collect();
+            sentences.push(sentence(&format!(
+                "GPGSV,{total},{},{:02},{}",
+                n + 1,
+                visible.len(),
+                blocks.join(",")
+            )));
+        }
+
+        sentences
+    }
+}
+
+fn sentence(body: &str) -> String {
+    format!("${body}*{:02X}", checksum(body))
+}
+
+/// `hhmmss.ss` for `t` seconds after a fixed noon start.
+fn utc_time(t: f64) -> String {
+    let secs = 12.0 * 3600.0 + t;
+    let whole = secs as u64;
+    format!(
+        "{:02}{:02}{:02}.00",
+        (whole / 3600) % 24,
+        (whole / 60) % 60,
+        whole % 60
+    )
+}
+
+/// Formats signed degrees as NMEA `(d)ddmm.mmmm` plus hemisphere.
+fn nmea_coordinate(value: f64, degree_digits: usize, pos: char, neg: char) -> (String, char) {
+    let abs = value.abs();
+    let degrees = abs.trunc();
+    let minutes = (abs - degrees) * 60.0;
+    let text = format!("{:0width$}{:07.4}", degrees as u32, minutes, width = degree_digits);
+    (text, if value < 0.0 { neg } else { pos })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_gga, parse_gsa_prns, parse_gsv, verify_checksum};
+
+    #[test]
+    fn epochs_are_valid_and_deterministic() {
+        let mut a = Simulator::new(SimConfig::default());
+        let mut b = Simulator::new(SimConfig::default());
+
+        for _ in 0..120 {
+            let epoch = a.next_epoch();
+            assert_eq!(epoch, b.next_epoch());
+            assert!(epoch.iter().all(|s| verify_checksum(s)), "{epoch:?}");
+        }
+    }
+
+    #[test]
+    fn epoch_parses_back() {
+        let config = SimConfig {
+            noise_m: 0.0,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulator::new(config.clone());
+        let epoch = sim.next_epoch();
+
+        let fix = epoch.iter().find_map(|s| parse_gga(s)).unwrap();
+        assert!((fix.latitude - config.start_latitude).abs() < 1e-6);
+        assert!((fix.longitude - config.start_longitude).abs() < 1e-6);
+
+        let sats: Vec<_> = epoch.iter().filter_map(|s| parse_gsv(s)).flatten().collect();
+        assert!(!sats.is_empty());
+        assert!(sats.iter().all(|s| s.latitude > 0.0 && s.latitude <= 90.0));
+
+        let used = epoch.iter().find_map(|s| parse_gsa_prns(s)).unwrap();
+        assert_eq!(used.len(), fix.satellites_used as usize);
+    }
+
+    #[test]
+    fn receiver_moves_along_heading() {
+        let config = SimConfig {
+            noise_m: 0.0,
+            heading_deg: 0.0,
+            speed_mps: 10.0,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulator::new(config);
+        let first = sim.next_epoch().iter().find_map(|s| parse_gga(s)).unwrap();
+        let second = sim.next_epoch().iter().find_map(|s| parse_gga(s)).unwrap();
+
+        let moved_m = (second.latitude - first.latitude) * METRES_PER_DEGREE;
+        assert!((moved_m - 10.0).abs() < 0.25, "{moved_m}");
+    }
+}
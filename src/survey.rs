@@ -0,0 +1,245 @@
+//! Survey-in: averaging a stationary receiver's fixes over a set time or
+//! number of fixes into one coordinate, for placing a base station or
+//! marking a point. Fixes above an HDOP limit are rejected and the rest
+//! weighted by `1/HDOP²`, so the better-geometry fixes count for more.
+
+use serde::{Deserialize, Serialize};
+
+use crate::conditions;
+use crate::geo::{self, Scatter};
+use crate::parser::GgaFix;
+
+/// What ends the averaging window.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SurveyStop {
+    #[default]
+    Duration,
+    Count,
+}
+
+impl SurveyStop {
+    pub const ALL: [SurveyStop; 2] = [SurveyStop::Duration, SurveyStop::Count];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SurveyStop::Duration => "Seconds",
+            SurveyStop::Count => "Fixes",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SurveyConfig {
+    pub stop: SurveyStop,
+    pub duration_secs: u32,
+    pub count: u32,
+    /// Fixes with a higher HDOP are left out of the average
+    pub max_hdop: f64,
+    pub weight_by_hdop: bool,
+}
+
+impl Default for SurveyConfig {
+    fn default() -> Self {
+        Self {
+            stop: SurveyStop::Duration,
+            duration_secs: 300,
+            count: 300,
+            max_hdop: 2.5,
+            weight_by_hdop: true,
+        }
+    }
+}
+
+struct Sample {
+    position: [f64; 2],
+    altitude: Option<f64>,
+    weight: f64,
+}
+
+/// The averaged coordinate and how much the fixes behind it spread.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurveyResult {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+    pub fixes: usize,
+    /// Standard deviations of the fixes about the mean, metres
+    pub std_east: f64,
+    pub std_north: f64,
+    pub std_horizontal: f64,
+    pub std_vertical: Option<f64>,
+}
+
+pub struct Survey {
+    /// Fixed when the survey starts; later setting changes do not apply
+    pub config: SurveyConfig,
+    pub started: f64,
+    samples: Vec<Sample>,
+    pub rejected: usize,
+    /// Set once the window is complete or the survey is stopped early,
+    /// freezing the result
+    finished: bool,
+    result: Option<SurveyResult>,
+}
+
+impl Survey {
+    pub fn start(config: SurveyConfig, time: f64) -> Self {
+        Self {
+            config,
+            started: time,
+            samples: Vec::new(),
+            rejected: 0,
+            finished: false,
+            result: None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.finished
+    }
+
+    /// Adds a fix taken at `time`, returning whether it completed the
+    /// window and froze the result.
+    pub fn push(&mut self, time: f64, fix: &GgaFix) -> bool {
+        if self.is_done() {
+            return false;
+        }
+        let too_imprecise = conditions::high_hdop(fix, self.config.max_hdop).is_some();
+        if !fix.has_position() || too_imprecise {
+            self.rejected += 1;
+        } else {
+            let hdop = fix.hdop.unwrap_or(1.0);
+            self.samples.push(Sample {
+                position: [fix.latitude, fix.longitude],
+                altitude: fix.altitude,
+                weight: if self.config.weight_by_hdop { 1.0 / hdop.max(0.1).powi(2) } else { 1.0 },
+            });
+        }
+        if self.progress(time) >= 1.0 {
+            self.finish();
+        }
+        self.is_done()
+    }
+
+    /// How far through the window, 0 to 1.
+    pub fn progress(&self, time: f64) -> f32 {
+        let fraction = match self.config.stop {
+            SurveyStop::Duration => (time - self.started) / f64::from(self.config.duration_secs),
+            SurveyStop::Count => self.samples.len() as f64 / f64::from(self.config.count),
+        };
+        fraction.clamp(0.0, 1.0) as f32
+    }
+
+    /// Freezes the average of the fixes so far, ending the survey early
+    /// when the window is not yet complete.
+    pub fn finish(&mut self) {
+        if !self.finished {
+            self.result = self.estimate();
+            self.finished = true;
+        }
+    }
+
+    /// The frozen result, or the running average while still surveying.
+    /// Needs two accepted fixes for a spread.
+    pub fn estimate(&self) -> Option<SurveyResult> {
+        if self.finished {
+            return self.result.clone();
+        }
+        if self.samples.len() < 2 {
+            return None;
+        }
+        let total: f64 = self.samples.iter().map(|s| s.weight).sum();
+        let mean = [0, 1].map(|axis| {
+            self.samples.iter().map(|s| s.position[axis] * s.weight).sum::<f64>() / total
+        });
+        let offsets: Vec<[f64; 2]> = self
+            .samples
+            .iter()
+            .map(|s| geo::local_offset_m(mean, s.position))
+            .collect();
+        let scatter = Scatter::of(&offsets)?;
+
+        let heights: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .filter_map(|s| s.altitude.map(|a| (a, s.weight)))
+            .collect();
+        let height_total: f64 = heights.iter().map(|(_, w)| w).sum();
+        let altitude = (!heights.is_empty())
+            .then(|| heights.iter().map(|(a, w)| a * w).sum::<f64>() / height_total);
+        let std_vertical = altitude.filter(|_| heights.len() >= 2).map(|mean| {
+            let squares: f64 = heights.iter().map(|(a, _)| (a - mean).powi(2)).sum();
+            (squares / (heights.len() - 1) as f64).sqrt()
+        });
+
+        Some(SurveyResult {
+            latitude: mean[0],
+            longitude: mean[1],
+            altitude,
+            fixes: self.samples.len(),
+            std_east: scatter.std_east,
+            std_north: scatter.std_north,
+            std_horizontal: scatter.horizontal(),
+            std_vertical,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(latitude: f64, altitude: f64, hdop: f64) -> GgaFix {
+        GgaFix {
+            latitude,
+            altitude: Some(altitude),
+            ..conditions::fix(1, 8, hdop)
+        }
+    }
+
+    #[test]
+    fn weights_by_hdop_and_rejects_poor_fixes() {
+        let config = SurveyConfig {
+            stop: SurveyStop::Count,
+            count: 3,
+            ..Default::default()
+        };
+        let mut survey = Survey::start(config, 0.0);
+        assert!(!survey.push(1.0, &fix(48.0, 500.0, 1.0)));
+        // Rejected: above the HDOP limit, or no position
+        assert!(!survey.push(2.0, &fix(49.0, 900.0, 9.0)));
+        assert!(!survey.push(3.0, &GgaFix::default()));
+        assert!(!survey.push(4.0, &fix(48.0001, 510.0, 2.0)));
+        assert_eq!(survey.progress(4.0), 2.0 / 3.0);
+        assert!(survey.push(5.0, &fix(48.0, 500.0, 1.0)));
+
+        let result = survey.estimate().unwrap();
+        assert_eq!((result.fixes, survey.rejected), (3, 2));
+        // HDOP 2 counts a quarter as much as HDOP 1
+        assert!((result.latitude - (48.0 + 0.0001 * 0.25 / 2.25)).abs() < 1e-12);
+        assert!((result.altitude.unwrap() - (500.0 + 10.0 * 0.25 / 2.25)).abs() < 1e-9);
+        assert!(result.std_north > 5.0 && result.std_north < 7.0);
+        assert!(result.std_east < 1e-6);
+
+        // Frozen: later fixes change nothing
+        assert!(!survey.push(6.0, &fix(48.5, 0.0, 1.0)));
+        assert_eq!(survey.estimate(), Some(result));
+    }
+
+    #[test]
+    fn duration_window_and_early_finish() {
+        let mut survey = Survey::start(SurveyConfig::default(), 100.0);
+        assert_eq!(survey.progress(250.0), 0.5);
+        survey.push(101.0, &fix(48.0, 500.0, 1.0));
+        survey.finish();
+        // One fix has no spread to report
+        assert!(survey.is_done());
+        assert_eq!(survey.estimate(), None);
+
+        let mut survey = Survey::start(SurveyConfig::default(), 100.0);
+        survey.push(101.0, &fix(48.0, 500.0, 1.0));
+        assert!(survey.push(400.0, &fix(48.0, 500.0, 1.0)));
+        assert_eq!(survey.estimate().unwrap().fixes, 2);
+    }
+}
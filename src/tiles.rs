@@ -0,0 +1,312 @@
+//! Slippy-map raster tiles drawn behind the track plot.
+//!
+//! Tiles are fetched on a background thread and cached on disk as
+//! `<cache>/<server>/<z>/<x>/<y>.png`, `<server>` a hash of the URL
+//! template, so a map seen once keeps working offline and switching
+//! servers doesn't show the previous one's tiles. Only tiles that decode
+//! are cached.
+//! Downloads are spaced out and identify the program, as the OpenStreetMap
+//! tile usage policy asks. `https://` URLs are fetched through the system's
+//! `curl`, which ships with Windows 10 and later, macOS and most Linux
+//! installs, rather than bundling a TLS stack.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+
+/// Default URL template, `{z}`/`{x}`/`{y}` being substituted.
+pub const DEFAULT_URL: &str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+pub const ATTRIBUTION: &str = "Map data © OpenStreetMap contributors";
+const USER_AGENT: &str = concat!("RUST_NMEA_PARSER/", env!("CARGO_PKG_VERSION"));
+/// Least time between two downloads; cached tiles are not held up.
+const FETCH_INTERVAL: Duration = Duration::from_millis(250);
+const FETCH_TIMEOUT_SECS: u64 = 10;
+/// Upper bound on tiles requested for one view.
+const MAX_VISIBLE_TILES: usize = 36;
+const MAX_ZOOM: u32 = 19;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TileKey {
+    pub z: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileKey {
+    /// Longitude/latitude bounds as `([west, south], [east, north])`.
+    pub fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let n = (1u64 << self.z) as f64;
+        let lon = |x: f64| x / n * 360.0 - 180.0;
+        let lat = |y: f64| (PI * (1.0 - 2.0 * y / n)).sinh().atan().to_degrees();
+        let (x, y) = (self.x as f64, self.y as f64);
+        ([lon(x), lat(y + 1.0)], [lon(x + 1.0), lat(y)])
+    }
+}
+
+/// Web Mercator tile column/row holding a point at zoom `z`.
+pub fn tile_at(lon: f64, lat: f64, z: u32) -> (u32, u32) {
+    let n = (1u64 << z) as f64;
+    let lat = lat.clamp(-85.0511, 85.0511).to_radians();
+    let x = (lon + 180.0) / 360.0 * n;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+    let clamp = |v: f64| v.floor().clamp(0.0, n - 1.0) as u32;
+    (clamp(x), clamp(y))
+}
+
+/// Tiles covering a lon/lat view, at a zoom giving a few tiles across.
+pub fn visible_tiles(min: [f64; 2], max: [f64; 2]) -> Vec<TileKey> {
+    let width = (max[0] - min[0]).max(1e-6);
+    let z = ((360.0 / width * 3.0).log2().floor() as u32).min(MAX_ZOOM);
+
+    let (x0, y0) = tile_at(min[0], max[1], z);
+    let (x1, y1) = tile_at(max[0], min[1], z);
+    let mut tiles = Vec::new();
+    for x in x0..=x1 {
+        for y in y0..=y1 {
+            tiles.push(TileKey { z, x, y });
+            if tiles.len() >= MAX_VISIBLE_TILES {
+                return tiles;
+            }
+        }
+    }
+    tiles
+}
+
+enum Slot {
+    Pending,
+    Ready(egui::TextureHandle),
+    Failed,
+}
+
+/// Tile textures for the UI plus the channel to the fetch thread.
+pub struct TileLayer {
+    pub url_template: String,
+    slots: HashMap<TileKey, Slot>,
+    requests: Sender<TileKey>,
+    results: Receiver<(TileKey, Result<egui::ColorImage, String>)>,
+    pub last_error: Option<String>,
+}
+
+impl TileLayer {
+    pub fn new(url_template: &str) -> Self {
+        let (requests, request_rx) = mpsc::channel::<TileKey>();
+        let (result_tx, results) = mpsc::channel();
+        let url = url_template.to_string();
+        let cache = cache_dir(url_template);
+
+        thread::spawn(move || {
+            let mut last_fetch = None;
+            for key in request_rx {
+                let image = load_or_fetch(&cache, &url, key, &mut last_fetch);
+                if result_tx.send((key, image)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            url_template: url_template.to_string(),
+            slots: HashMap::new(),
+            requests,
+            results,
+            last_error: None,
+        }
+    }
+
+    /// Loaded tiles for the view, queueing any not yet requested.
+    pub fn tiles(
+        &mut self,
+        ctx: &egui::Context,
+        min: [f64; 2],
+        max: [f64; 2],
+    ) -> Vec<(TileKey, egui::TextureId)> {
+        for (key, image) in self.results.try_iter() {
+            let slot = match image {
+                Ok(image) => {
+                    let name = format!("tile-{}-{}-{}", key.z, key.x, key.y);
+                    Slot::Ready(ctx.load_texture(name, image, egui::TextureOptions::LINEAR))
+                }
+                Err(e) => {
+                    self.last_error = Some(e);
+                    Slot::Failed
+                }
+            };
+            self.slots.insert(key, slot);
+        }
+
+        let mut ready = Vec::new();
+        for key in visible_tiles(min, max) {
+            match self.slots.get(&key) {
+                Some(Slot::Ready(texture)) => ready.push((key, texture.id())),
+                Some(Slot::Pending | Slot::Failed) => {}
+                None => {
+                    self.slots.insert(key, Slot::Pending);
+                    let _ = self.requests.send(key);
+                }
+            }
+        }
+        ready
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.slots.values().any(|s| matches!(s, Slot::Pending))
+    }
+}
+
+/// The tile cache of one URL template.
+fn cache_dir(url_template: &str) -> PathBuf {
+    // FNV-1a, which unlike std's hasher stays the same across releases
+    let hash = url_template
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| (h ^ u64::from(b)).wrapping_mul(0x100_0000_01b3));
+    std::env::temp_dir().join("rust_nmea_tiles").join(format!("{hash:016x}"))
+}
+
+/// A tile from the cache, or else downloaded and, once it decodes,
+/// cached; a cached file that doesn't decode is fetched again.
+fn load_or_fetch(
+    cache: &Path,
+    url: &str,
+    key: TileKey,
+    last_fetch: &mut Option<Instant>,
+) -> Result<egui::ColorImage, String> {
+    let path: PathBuf = cache
+        .join(key.z.to_string())
+        .join(key.x.to_string())
+        .join(format!("{}.png", key.y));
+    if let Some(image) = fs::read(&path).ok().and_then(|bytes| decode(&bytes).ok()) {
+        return Ok(image);
+    }
+
+    let url = url
+        .replace("{z}", &key.z.to_string())
+        .replace("{x}", &key.x.to_string())
+        .replace("{y}", &key.y.to_string());
+    if let Some(wait) = last_fetch.and_then(|t| FETCH_INTERVAL.checked_sub(t.elapsed())) {
+        thread::sleep(wait);
+    }
+    *last_fetch = Some(Instant::now());
+    let bytes = if url.starts_with("https://") { curl_get(&url)? } else { http_get(&url)? };
+    let image = decode(&bytes).map_err(|e| format!("{url}: {e}"))?;
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir).and_then(|()| fs::write(&path, &bytes));
+    }
+    Ok(image)
+}
+
+fn decode(bytes: &[u8]) -> Result<egui::ColorImage, String> {
+    let image = image::load_from_memory(bytes).map_err(|e| format!("Bad tile image: {e}"))?;
+    let rgba = image.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, &rgba))
+}
+
+/// Minimal HTTP/1.0 GET for `http://host[:port]/path`; 1.0 keeps servers
+/// from chunking the body.
+fn http_get(url: &str) -> Result<Vec<u8>, String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("{url}: only http:// and https:// tile servers are supported"))?;
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{host}:80")
+    };
+
+    let fail = |e: std::io::Error| format!("{url}: {e}");
+    let mut stream = TcpStream::connect(&address).map_err(fail)?;
+    stream.set_read_timeout(Some(Duration::from_secs(FETCH_TIMEOUT_SECS))).map_err(fail)?;
+    write!(
+        stream,
+        "GET {} HTTP/1.0\r\nHost: {host}\r\nUser-Agent: {USER_AGENT}\r\n\
+         Connection: close\r\n\r\n",
+        if path.is_empty() { "/" } else { path }
+    )
+    .map_err(fail)?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).map_err(fail)?;
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| format!("{url}: malformed response"))?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let status = head.lines().next().unwrap_or("");
+    if !status.contains(" 200 ") {
+        return Err(format!("{url}: {status}"));
+    }
+    Ok(response[split + 4..].to_vec())
+}
+
+/// GET through `curl`, for `https://` servers.
+fn curl_get(url: &str) -> Result<Vec<u8>, String> {
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--max-time", &FETCH_TIMEOUT_SECS.to_string()])
+        .args(["--user-agent", USER_AGENT, url]);
+    // Without a console window flashing up for each tile
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("{url}: could not run curl for HTTPS: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("{url}: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_bounds_contain_their_point() {
+        let (lon, lat) = (-74.2347, 40.6795);
+        let (x, y) = tile_at(lon, lat, 15);
+        let ([west, south], [east, north]) = TileKey { z: 15, x, y }.bounds();
+
+        assert!((west..east).contains(&lon));
+        assert!((south..north).contains(&lat));
+        assert_eq!(tile_at(0.0, 0.0, 1), (1, 1));
+    }
+
+    #[test]
+    fn only_decodable_tiles_are_cached_per_server() {
+        assert_ne!(cache_dir(DEFAULT_URL), cache_dir("http://localhost/{z}/{x}/{y}.png"));
+        assert_eq!(cache_dir(DEFAULT_URL), cache_dir(DEFAULT_URL));
+
+        // A server answering 200 with an error page instead of a tile
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            stream.write_all(b"HTTP/1.0 200 OK\r\n\r\n<html>rate limited</html>").unwrap();
+        });
+        let cache = std::env::temp_dir().join("rust_nmea_tiles_test");
+        let _ = fs::remove_dir_all(&cache);
+        let url = format!("http://127.0.0.1:{port}/{{z}}/{{x}}/{{y}}.png");
+        let key = TileKey { z: 1, x: 0, y: 1 };
+        let error = load_or_fetch(&cache, &url, key, &mut None).unwrap_err();
+        assert!(error.contains("Bad tile image"), "{error}");
+        assert!(!cache.join("1").join("0").join("1.png").exists());
+        server.join().unwrap();
+    }
+}
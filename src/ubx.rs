@@ -0,0 +1,254 @@
+//! u-blox UBX binary frames: reassembly from a byte stream and decoding
+//! of the few messages the UI shows.
+
+use crate::commands::{DynamicModel, UBX_NAV5_LEN};
+use crate::parser::{AntennaStatus, DeviceInfo, GpsTime};
+
+const SYNC: [u8; 2] = [0xB5, 0x62];
+/// Sync, class, id and the two length bytes.
+const HEADER_LEN: usize = 6;
+const CHECKSUM_LEN: usize = 2;
+/// Frames claiming more payload than this are treated as a false sync.
+const MAX_PAYLOAD: usize = 4096;
+
+const CLASS_NAV: u8 = 0x01;
+const NAV_TIMEGPS: u8 = 0x20;
+const CLASS_MON: u8 = 0x0A;
+const MON_HW: u8 = 0x09;
+pub const MON_VER: u8 = 0x04;
+const CLASS_CFG: u8 = 0x06;
+pub const CFG_NAV5: u8 = 0x24;
+/// UBX-MON-VER: software version, hardware version, then extension
+/// strings such as `MOD=NEO-M8N` and `FWVER=SPG 3.01`.
+const MON_VER_SW_LEN: usize = 30;
+const MON_VER_HW_LEN: usize = 10;
+const MON_VER_EXT_LEN: usize = 30;
+/// Offset of `aStatus` in the UBX-MON-HW payload.
+const MON_HW_ANTENNA_STATUS: usize = 20;
+
+pub struct UbxFrame {
+    pub class: u8,
+    pub id: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Buffers bytes until whole, checksum-valid frames are available.
+#[derive(Default)]
+pub struct UbxDecoder {
+    buf: Vec<u8>,
+}
+
+impl UbxDecoder {
+    /// No partial frame is waiting for more bytes.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn push(&mut self, data: &[u8]) -> Vec<UbxFrame> {
+        self.buf.extend_from_slice(data);
+        let mut frames = Vec::new();
+
+        loop {
+            // Drop anything before the next sync pair
+            match self.buf.windows(2).position(|w| w == SYNC) {
+                Some(start) => {
+                    self.buf.drain(..start);
+                }
+                None => {
+                    let keep = usize::from(self.buf.last() == Some(&SYNC[0]));
+                    self.buf.drain(..self.buf.len() - keep);
+                    return frames;
+                }
+            }
+            if self.buf.len() < HEADER_LEN {
+                return frames;
+            }
+
+            let len = u16::from_le_bytes([self.buf[4], self.buf[5]]) as usize;
+            if len > MAX_PAYLOAD {
+                self.buf.drain(..SYNC.len());
+                continue;
+            }
+            let total = HEADER_LEN + len + CHECKSUM_LEN;
+            if self.buf.len() < total {
+                return frames;
+            }
+
+            let frame: Vec<u8> = self.buf.drain(..total).collect();
+            let (ck_a, ck_b) = fletcher(&frame[2..HEADER_LEN + len]);
+            if [ck_a, ck_b] == frame[HEADER_LEN + len..] {
+                frames.push(UbxFrame {
+                    class: frame[2],
+                    id: frame[3],
+                    payload: frame[HEADER_LEN..HEADER_LEN + len].to_vec(),
+                });
+            }
+        }
+    }
+}
+
+fn fletcher(data: &[u8]) -> (u8, u8) {
+    data.iter().fold((0u8, 0u8), |(a, b), byte| {
+        let a = a.wrapping_add(*byte);
+        (a, b.wrapping_add(a))
+    })
+}
+
+impl UbxFrame {
+    /// Decodes UBX-NAV-TIMEGPS, keeping only the fields flagged valid.
+    pub fn gps_time(&self) -> Option<GpsTime> {
+        if self.class != CLASS_NAV || self.id != NAV_TIMEGPS || self.payload.len() < 16 {
+            return None;
+        }
+        let p = &self.payload;
+        let itow_ms = u32::from_le_bytes([p[0], p[1], p[2], p[3]]);
+        let ftow_ns = i32::from_le_bytes([p[4], p[5], p[6], p[7]]);
+        let week = i16::from_le_bytes([p[8], p[9]]);
+        let leap = p[10] as i8;
+        let valid = p[11];
+
+        Some(GpsTime {
+            tow_secs: (valid & 0x01 != 0).then(|| itow_ms as f64 / 1e3 + ftow_ns as f64 / 1e9),
+            week: (valid & 0x02 != 0).then_some(week as u16),
+            leap_seconds: (valid & 0x04 != 0).then_some(leap as i32),
+        })
+    }
+
+    /// Antenna supervisor state from UBX-MON-HW; `None` while it is
+    /// initialising or unknown.
+    pub fn antenna_status(&self) -> Option<AntennaStatus> {
+        if self.class != CLASS_MON || self.id != MON_HW {
+            return None;
+        }
+        match self.payload.get(MON_HW_ANTENNA_STATUS)? {
+            2 => Some(AntennaStatus::Ok),
+            3 => Some(AntennaStatus::Short),
+            4 => Some(AntennaStatus::Open),
+            _ => None,
+        }
+    }
+
+    /// Receiver model and firmware from UBX-MON-VER, preferring the
+    /// `MOD=` and `FWVER=` extensions over the raw version strings.
+    pub fn device_info(&self) -> Option<DeviceInfo> {
+        if self.class != CLASS_MON || self.id != MON_VER {
+            return None;
+        }
+        let text = |bytes: &[u8]| {
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+        };
+        let software = text(self.payload.get(..MON_VER_SW_LEN)?);
+        let hardware = text(self.payload.get(MON_VER_SW_LEN..MON_VER_SW_LEN + MON_VER_HW_LEN)?);
+        let extensions: Vec<String> = self.payload[MON_VER_SW_LEN + MON_VER_HW_LEN..]
+            .chunks(MON_VER_EXT_LEN)
+            .map(text)
+            .collect();
+        let extension = |key: &str| extensions.iter().find_map(|e| e.strip_prefix(key));
+
+        Some(DeviceInfo {
+            model: format!("u-blox {}", extension("MOD=").unwrap_or(&hardware)),
+            firmware: extension("FWVER=").unwrap_or(&software).to_string(),
+        })
+    }
+
+    /// Dynamic model from a UBX-CFG-NAV5 poll reply; the empty poll
+    /// itself, echoed back, has no payload to read.
+    pub fn dynamic_model(&self) -> Option<DynamicModel> {
+        if self.class != CLASS_CFG || self.id != CFG_NAV5 || self.payload.len() < UBX_NAV5_LEN {
+            return None;
+        }
+        DynamicModel::from_code(self.payload[2])
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "class 0x{:02X} id 0x{:02X}, {} byte payload",
+            self.class,
+            self.id,
+            self.payload.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::ubx_frame;
+
+    #[test]
+    fn reassembles_split_frames_and_decodes_timegps() {
+        let mut payload = Vec::new();
+        payload.extend(345_600_000u32.to_le_bytes());
+        payload.extend(500_000_000i32.to_le_bytes());
+        payload.extend(2_330i16.to_le_bytes());
+        payload.extend([18, 0x07]);
+        payload.extend(10u32.to_le_bytes());
+        let frame = ubx_frame(CLASS_NAV, NAV_TIMEGPS, &payload);
+
+        let mut stream = vec![0x00, b'$'];
+        stream.extend(&frame);
+        let mut decoder = UbxDecoder::default();
+        assert!(decoder.push(&stream[..9]).is_empty());
+        let frames = decoder.push(&stream[9..]);
+
+        assert_eq!(frames.len(), 1);
+        let time = frames[0].gps_time().unwrap();
+        assert_eq!(time.week, Some(2_330));
+        assert_eq!(time.tow_secs, Some(345_600.5));
+        assert_eq!(time.leap_seconds, Some(18));
+    }
+
+    #[test]
+    fn mon_hw_antenna_short() {
+        let mut payload = vec![0; 60];
+        payload[MON_HW_ANTENNA_STATUS] = 3;
+        let frames = UbxDecoder::default().push(&ubx_frame(CLASS_MON, MON_HW, &payload));
+        assert_eq!(frames[0].antenna_status(), Some(AntennaStatus::Short));
+        assert_eq!(frames[0].gps_time(), None);
+    }
+
+    #[test]
+    fn mon_ver_identifies_the_receiver() {
+        let field = |text: &str, len: usize| {
+            let mut bytes = text.as_bytes().to_vec();
+            bytes.resize(len, 0);
+            bytes
+        };
+        let mut payload = field("ROM CORE 3.01 (107888)", MON_VER_SW_LEN);
+        payload.extend(field("00080000", MON_VER_HW_LEN));
+        payload.extend(field("FWVER=SPG 3.01", MON_VER_EXT_LEN));
+        payload.extend(field("PROTVER=18.00", MON_VER_EXT_LEN));
+        payload.extend(field("MOD=NEO-M8N-0", MON_VER_EXT_LEN));
+        let frames = UbxDecoder::default().push(&ubx_frame(CLASS_MON, MON_VER, &payload));
+        let info = frames[0].device_info().unwrap();
+        assert_eq!(info.model, "u-blox NEO-M8N-0");
+        assert_eq!(info.firmware, "SPG 3.01");
+
+        // Older firmware without extensions
+        let mut payload = field("7.03 (45969)", MON_VER_SW_LEN);
+        payload.extend(field("00040007", MON_VER_HW_LEN));
+        let frames = UbxDecoder::default().push(&ubx_frame(CLASS_MON, MON_VER, &payload));
+        let info = frames[0].device_info().unwrap();
+        assert_eq!(info.model, "u-blox 00040007");
+        assert_eq!(info.firmware, "7.03 (45969)");
+    }
+
+    #[test]
+    fn cfg_nav5_reports_the_dynamic_model() {
+        let mut payload = vec![0xFF, 0xFF, 4, 3];
+        payload.resize(UBX_NAV5_LEN, 0);
+        let frames = UbxDecoder::default().push(&ubx_frame(CLASS_CFG, CFG_NAV5, &payload));
+        assert_eq!(frames[0].dynamic_model(), Some(DynamicModel::Automotive));
+        // The poll itself, echoed, says nothing
+        let frames = UbxDecoder::default().push(&ubx_frame(CLASS_CFG, CFG_NAV5, &[]));
+        assert_eq!(frames[0].dynamic_model(), None);
+    }
+
+    #[test]
+    fn drops_frames_with_bad_checksums() {
+        let mut frame = ubx_frame(0x06, 0x01, &[0xF0, 0x03, 0x00]);
+        *frame.last_mut().unwrap() ^= 0xFF;
+        assert!(UbxDecoder::default().push(&frame).is_empty());
+    }
+}